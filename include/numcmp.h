@@ -0,0 +1,55 @@
+/* C interface to numcmp. Link against the numcmp shared library built by
+ * `cargo build --release --lib`.
+ *
+ * Options are the command-line flags of `numcmp compare`, one argument per
+ * string, e.g. {"--iterations", "1000", "--seed", "1"}. On failure the
+ * functions return NULL or a non-zero exit code as the command line would,
+ * and numcmp_last_error() describes the error. */
+
+#ifndef NUMCMP_H
+#define NUMCMP_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct {
+    char *name;
+    double baseline;
+    double target;
+    double p_value;
+    /* -1 if the target improved, 1 if it regressed, 0 if unchanged. */
+    int verdict;
+} NumcmpEstimate;
+
+typedef struct {
+    NumcmpEstimate *estimates;
+    size_t len;
+} NumcmpResults;
+
+/* Returns the results as printed by --format json, to be released with
+ * numcmp_string_free, or NULL on failure. */
+char *numcmp_compare_json(const double *baseline, size_t baseline_len,
+                          const double *target, size_t target_len,
+                          const char *const *options, size_t options_len);
+
+/* Fills in the bootstrap result of each estimator, to be released with
+ * numcmp_results_free. Returns 0 on success. */
+int numcmp_compare(const double *baseline, size_t baseline_len,
+                   const double *target, size_t target_len,
+                   const char *const *options, size_t options_len,
+                   NumcmpResults *out);
+
+/* The last error on the calling thread, valid until the next call. */
+const char *numcmp_last_error(void);
+
+void numcmp_string_free(char *s);
+void numcmp_results_free(NumcmpResults *results);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,72 @@
+// JavaScript bindings for numcmp compiled to WebAssembly:
+//
+//   cargo build --release --lib --target wasm32-unknown-unknown
+//
+//   const numcmp = await load(fetch("numcmp.wasm"));
+//   const result = numcmp.compare(baseline, target, { iterations: 2000, seed: 1 });
+//
+// Options are the command-line flags without the leading dashes, in
+// camelCase or kebab-case; `true` passes a flag without a value and arrays
+// repeat it.
+
+function optionArgs(opts) {
+  const args = [];
+  for (const [key, value] of Object.entries(opts || {})) {
+    const flag = "--" + key.replace(/[A-Z]/g, (c) => "-" + c.toLowerCase());
+    for (const v of Array.isArray(value) ? value : [value]) {
+      if (v === false || v === null || v === undefined) continue;
+      args.push(flag);
+      if (v !== true) args.push(String(v));
+    }
+  }
+  return args;
+}
+
+export async function load(source) {
+  let memory;
+  const imports = {
+    env: {
+      numcmp_random(ptr, len) {
+        crypto.getRandomValues(new Uint8Array(memory.buffer, ptr, len));
+      },
+    },
+  };
+  const { instance } = await (source instanceof Promise || source instanceof Response
+    ? WebAssembly.instantiateStreaming(source, imports)
+    : WebAssembly.instantiate(source, imports));
+  const wasm = instance.exports;
+  memory = wasm.memory;
+
+  function copyIn(bytes) {
+    const ptr = wasm.numcmp_alloc(bytes.byteLength);
+    new Uint8Array(memory.buffer, ptr, bytes.byteLength).set(
+      new Uint8Array(bytes.buffer, bytes.byteOffset, bytes.byteLength)
+    );
+    return ptr;
+  }
+
+  // Compares two Float64Arrays and returns the parsed JSON results; throws
+  // with numcmp's error message on failure.
+  function compare(baseline, target, opts) {
+    const options = new TextEncoder().encode(optionArgs(opts).join("\0"));
+    const inputs = [Float64Array.from(baseline), Float64Array.from(target), options];
+    const ptrs = inputs.map(copyIn);
+    let status;
+    try {
+      status = wasm.numcmp_compare(
+        ptrs[0], inputs[0].length, ptrs[1], inputs[1].length, ptrs[2], inputs[2].length
+      );
+    } finally {
+      inputs.forEach((input, i) => wasm.numcmp_free(ptrs[i], input.byteLength));
+    }
+    const output = new TextDecoder().decode(
+      new Uint8Array(memory.buffer, wasm.numcmp_result_ptr(), wasm.numcmp_result_len())
+    );
+    if (status !== 0) {
+      throw new Error("numcmp: " + output);
+    }
+    return JSON.parse(output);
+  }
+
+  return { compare };
+}
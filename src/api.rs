@@ -0,0 +1,240 @@
+// Comparisons of in-memory samples with the results as JSON, for programs
+// that embed numcmp instead of running the command line.
+
+use crate::sample;
+use crate::{comparison_json, comparison_results, CompareArgs, Error, Verdict};
+use clap::Parser;
+use std::future::Future;
+use std::sync::mpsc::Receiver;
+
+#[derive(Debug, Parser)]
+#[command(name = "numcmp", no_binary_name = true)]
+struct Options {
+    #[command(flatten)]
+    compare: CompareArgs,
+}
+
+fn sample(role: &str, values: &[f64]) -> Result<sample::Sample, Error> {
+    if values.is_empty() {
+        return Err(Error::EmptySample(format!("no {} values", role)));
+    }
+    if let Some(x) = values.iter().find(|x| !x.is_finite()) {
+        return Err(Error::InvalidInput(format!(
+            "{} value {} is not finite",
+            role, x
+        )));
+    }
+    Ok(sample::Sample::new(values.to_vec()))
+}
+
+// A sample for compare_samples, made with a SampleBuilder.
+#[derive(Debug, Clone)]
+pub struct Sample(sample::Sample);
+
+impl Sample {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    // Always false: a SampleBuilder refuses to build an empty sample.
+    pub fn is_empty(&self) -> bool {
+        self.0.len() == 0
+    }
+}
+
+// Collects measurements as they are made, so that a service can compare
+// them in process without writing them to files first. Values are checked
+// when the sample is built.
+#[derive(Debug, Clone, Default)]
+pub struct SampleBuilder {
+    values: Vec<f64>,
+    // Present once a weighted value is pushed, with weight one for the
+    // values before it.
+    weights: Option<Vec<f64>>,
+}
+
+impl SampleBuilder {
+    pub fn new() -> SampleBuilder {
+        SampleBuilder::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> SampleBuilder {
+        SampleBuilder {
+            values: Vec::with_capacity(capacity),
+            weights: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn push(&mut self, x: f64) {
+        self.values.push(x);
+        if let Some(weights) = &mut self.weights {
+            weights.push(1.0);
+        }
+    }
+
+    pub fn push_weighted(&mut self, x: f64, weight: f64) {
+        let n = self.values.len();
+        self.weights
+            .get_or_insert_with(|| vec![1.0; n])
+            .push(weight);
+        self.values.push(x);
+    }
+
+    // Pushes the values sent on a channel, e.g. by measuring threads,
+    // until every sender is gone.
+    pub fn push_from_channel(&mut self, values: &Receiver<f64>) {
+        for x in values.iter() {
+            self.push(x);
+        }
+    }
+
+    // Pushes the values an asynchronous source yields until it yields None:
+    // `next` is called for each, e.g. || stream.next() for a futures Stream,
+    // so that any async runtime will do.
+    pub async fn push_from_async<F>(&mut self, mut next: impl FnMut() -> F)
+    where
+        F: Future<Output = Option<f64>>,
+    {
+        while let Some(x) = next().await {
+            self.push(x);
+        }
+    }
+
+    pub fn build(self) -> Result<Sample, Error> {
+        if self.values.is_empty() {
+            return Err(Error::EmptySample("no values".to_string()));
+        }
+        if let Some(x) = self.values.iter().find(|x| !x.is_finite()) {
+            return Err(Error::InvalidInput(format!("value {} is not finite", x)));
+        }
+        match self.weights {
+            Some(weights) => {
+                let sample =
+                    sample::Sample::weighted(self.values.into_iter().zip(weights).collect())?;
+                if sample.len() == 0 {
+                    return Err(Error::EmptySample(
+                        "no values of positive weight".to_string(),
+                    ));
+                }
+                Ok(Sample(sample))
+            }
+            None => Ok(Sample(sample::Sample::new(self.values))),
+        }
+    }
+}
+
+impl Extend<f64> for SampleBuilder {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, values: I) {
+        for x in values {
+            self.push(x);
+        }
+    }
+}
+
+// The bootstrap result for one estimator.
+#[derive(Debug, Clone)]
+pub struct Estimate {
+    pub name: String,
+    pub baseline: f64,
+    pub target: f64,
+    pub p_value: f64,
+    // -1 if the target improved, 1 if it regressed, 0 if unchanged.
+    pub verdict: i32,
+}
+
+fn parse(options: &[&str]) -> Result<CompareArgs, Error> {
+    #[allow(unused_mut)]
+    let mut args = Options::try_parse_from(options)
+        .map_err(|e| Error::Usage(e.to_string()))?
+        .compare;
+    // Browsers run WebAssembly on one thread.
+    #[cfg(target_arch = "wasm32")]
+    {
+        args.threads = Some(1);
+    }
+    Ok(args)
+}
+
+// Runs `f`, turning a panic into an error. Entry points called from other
+// languages go through this: unwinding out of them aborts the host.
+pub fn catch_panic<T>(f: impl FnOnce() -> Result<T, Error>) -> Result<T, Error> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "panic".to_string());
+        Err(Error::Internal(message))
+    })
+}
+
+// Compares two samples with options as on the command line, e.g.
+// ["--iterations", "1000", "--seed", "1"], and returns what --format json
+// would print.
+pub fn compare_json(baseline: &[f64], target: &[f64], options: &[&str]) -> Result<String, Error> {
+    let args = parse(options)?;
+    comparison_json(
+        &args,
+        &sample("baseline", baseline)?,
+        &sample("target", target)?,
+    )
+}
+
+// Compares two samples like compare_json, returning the result of each
+// estimator.
+pub fn compare(baseline: &[f64], target: &[f64], options: &[&str]) -> Result<Vec<Estimate>, Error> {
+    estimates(
+        &sample("baseline", baseline)?,
+        &sample("target", target)?,
+        options,
+    )
+}
+
+// Compares samples made with SampleBuilder like compare_json.
+pub fn compare_samples_json(
+    baseline: &Sample,
+    target: &Sample,
+    options: &[&str],
+) -> Result<String, Error> {
+    comparison_json(&parse(options)?, &baseline.0, &target.0)
+}
+
+// Compares samples made with SampleBuilder like compare.
+pub fn compare_samples(
+    baseline: &Sample,
+    target: &Sample,
+    options: &[&str],
+) -> Result<Vec<Estimate>, Error> {
+    estimates(&baseline.0, &target.0, options)
+}
+
+fn estimates(
+    baseline: &sample::Sample,
+    target: &sample::Sample,
+    options: &[&str],
+) -> Result<Vec<Estimate>, Error> {
+    let args = parse(options)?;
+    let results = comparison_results(&args, baseline, target)?;
+    Ok(results
+        .iter()
+        .map(|r| Estimate {
+            name: r.name.clone(),
+            baseline: r.full_baseline_estimator,
+            target: r.target_estimator,
+            p_value: r.p_value(),
+            verdict: match r.verdict(args.alpha) {
+                Verdict::Improved => -1,
+                Verdict::Unchanged | Verdict::WithinNoise => 0,
+                Verdict::Regressed => 1,
+            },
+        })
+        .collect())
+}
@@ -0,0 +1,327 @@
+// Reading a numeric column from Arrow IPC files and streams: the
+// flatbuffer-encoded schema and record batch metadata, and the column's
+// value buffers in each batch.
+
+// A message describing why the column could not be read.
+type Result<T> = std::result::Result<T, String>;
+
+const MAGIC: &[u8] = b"ARROW1";
+const CONTINUATION: u32 = 0xffff_ffff;
+
+// Message header types.
+const SCHEMA: u8 = 1;
+const DICTIONARY_BATCH: u8 = 2;
+const RECORD_BATCH: u8 = 3;
+
+// Field types.
+const TYPE_NULL: u8 = 1;
+const TYPE_INT: u8 = 2;
+const TYPE_FLOATING_POINT: u8 = 3;
+const TYPE_STRUCT: u8 = 13;
+const TYPE_UNION: u8 = 14;
+const TYPE_FIXED_SIZE_LIST: u8 = 16;
+
+// Position of a flatbuffer table in the buffer.
+#[derive(Clone, Copy)]
+struct Table<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+fn corrupt() -> String {
+    "corrupt Arrow IPC data".to_string()
+}
+
+fn read<const N: usize>(buf: &[u8], pos: usize) -> Result<[u8; N]> {
+    buf.get(pos..pos + N)
+        .map(|b| b.try_into().expect("N bytes"))
+        .ok_or_else(corrupt)
+}
+
+fn u32_at(buf: &[u8], pos: usize) -> Result<u32> {
+    Ok(u32::from_le_bytes(read(buf, pos)?))
+}
+
+fn i64_at(buf: &[u8], pos: usize) -> Result<i64> {
+    Ok(i64::from_le_bytes(read(buf, pos)?))
+}
+
+impl<'a> Table<'a> {
+    fn root(buf: &'a [u8]) -> Result<Table<'a>> {
+        Ok(Table {
+            buf,
+            pos: u32_at(buf, 0)? as usize,
+        })
+    }
+
+    // Where field `id` is stored, if it is present.
+    fn field(&self, id: usize) -> Result<Option<usize>> {
+        let soffset = i32::from_le_bytes(read(self.buf, self.pos)?) as isize;
+        let vtable = (self.pos as isize - soffset) as usize;
+        let vtable_size = u16::from_le_bytes(read(self.buf, vtable)?) as usize;
+        let entry = 4 + 2 * id;
+        if entry + 2 > vtable_size {
+            return Ok(None);
+        }
+        match u16::from_le_bytes(read(self.buf, vtable + entry)?) {
+            0 => Ok(None),
+            offset => Ok(Some(self.pos + offset as usize)),
+        }
+    }
+
+    fn u8(&self, id: usize, default: u8) -> Result<u8> {
+        match self.field(id)? {
+            Some(pos) => Ok(read::<1>(self.buf, pos)?[0]),
+            None => Ok(default),
+        }
+    }
+
+    fn i16(&self, id: usize, default: i16) -> Result<i16> {
+        match self.field(id)? {
+            Some(pos) => Ok(i16::from_le_bytes(read(self.buf, pos)?)),
+            None => Ok(default),
+        }
+    }
+
+    fn i32(&self, id: usize, default: i32) -> Result<i32> {
+        match self.field(id)? {
+            Some(pos) => Ok(i32::from_le_bytes(read(self.buf, pos)?)),
+            None => Ok(default),
+        }
+    }
+
+    fn i64(&self, id: usize, default: i64) -> Result<i64> {
+        match self.field(id)? {
+            Some(pos) => i64_at(self.buf, pos),
+            None => Ok(default),
+        }
+    }
+
+    // Follows the offset stored in field `id`.
+    fn indirect(&self, id: usize) -> Result<Option<usize>> {
+        match self.field(id)? {
+            Some(pos) => Ok(Some(pos + u32_at(self.buf, pos)? as usize)),
+            None => Ok(None),
+        }
+    }
+
+    fn table(&self, id: usize) -> Result<Option<Table<'a>>> {
+        Ok(self.indirect(id)?.map(|pos| Table { buf: self.buf, pos }))
+    }
+
+    fn string(&self, id: usize) -> Result<String> {
+        match self.indirect(id)? {
+            Some(pos) => {
+                let len = u32_at(self.buf, pos)? as usize;
+                let bytes = self.buf.get(pos + 4..pos + 4 + len).ok_or_else(corrupt)?;
+                Ok(String::from_utf8_lossy(bytes).into_owned())
+            }
+            None => Ok(String::new()),
+        }
+    }
+
+    // The length and position of the first element of vector field `id`.
+    fn vector(&self, id: usize) -> Result<(usize, usize)> {
+        match self.indirect(id)? {
+            Some(pos) => Ok((u32_at(self.buf, pos)? as usize, pos + 4)),
+            None => Ok((0, 0)),
+        }
+    }
+
+    fn tables(&self, id: usize) -> Result<Vec<Table<'a>>> {
+        let (len, start) = self.vector(id)?;
+        (0..len)
+            .map(|i| {
+                let pos = start + 4 * i;
+                Ok(Table {
+                    buf: self.buf,
+                    pos: pos + u32_at(self.buf, pos)? as usize,
+                })
+            })
+            .collect()
+    }
+
+    // A vector of structs made of two longs, such as FieldNode and Buffer.
+    fn pairs(&self, id: usize) -> Result<Vec<(i64, i64)>> {
+        let (len, start) = self.vector(id)?;
+        (0..len)
+            .map(|i| {
+                let pos = start + 16 * i;
+                Ok((i64_at(self.buf, pos)?, i64_at(self.buf, pos + 8)?))
+            })
+            .collect()
+    }
+}
+
+// How the values of the column are stored.
+#[derive(Clone, Copy)]
+enum Values {
+    Int { bytes: usize, signed: bool },
+    Float { bytes: usize },
+}
+
+// The selected column: its node and first buffer in each record batch.
+struct Column {
+    node: usize,
+    buffer: usize,
+    values: Values,
+}
+
+// The number of buffers of a field and of its children, in the order they
+// appear in a record batch.
+fn buffer_count(field: &Table) -> Result<(usize, usize)> {
+    let name = field.string(0)?;
+    // Dictionary-encoded columns hold only indices into the dictionary.
+    if field.field(4)?.is_some() {
+        return Ok((1, 2));
+    }
+    let own = match field.u8(2, 0)? {
+        TYPE_NULL => 0,
+        TYPE_STRUCT | TYPE_FIXED_SIZE_LIST => 1,
+        TYPE_UNION => return Err(format!("union column {:?} is not supported", name)),
+        // Binary, Utf8 and their large variants have offsets and data.
+        4 | 5 | 19 | 20 => 3,
+        t if t > 21 => return Err(format!("column {:?} has an unsupported type", name)),
+        _ => 2,
+    };
+    let (mut nodes, mut buffers) = (1, own);
+    for child in field.tables(5)? {
+        let (n, b) = buffer_count(&child)?;
+        nodes += n;
+        buffers += b;
+    }
+    Ok((nodes, buffers))
+}
+
+fn find_column(schema: &Table, name: &str) -> Result<Column> {
+    let (mut node, mut buffer) = (0, 0);
+    for field in schema.tables(1)? {
+        if field.string(0)? == name {
+            if field.field(4)?.is_some() {
+                return Err(format!(
+                    "dictionary-encoded column {:?} is not supported",
+                    name
+                ));
+            }
+            let ty = field.table(3)?.ok_or_else(corrupt)?;
+            let values = match field.u8(2, 0)? {
+                TYPE_INT => Values::Int {
+                    bytes: (ty.i32(0, 0)? / 8) as usize,
+                    signed: ty.u8(1, 0)? != 0,
+                },
+                TYPE_FLOATING_POINT => match ty.i16(0, 0)? {
+                    1 => Values::Float { bytes: 4 },
+                    2 => Values::Float { bytes: 8 },
+                    _ => return Err(format!("column {:?} has half-precision floats", name)),
+                },
+                _ => return Err(format!("column {:?} is not numeric", name)),
+            };
+            return Ok(Column {
+                node,
+                buffer,
+                values,
+            });
+        }
+        let (n, b) = buffer_count(&field)?;
+        node += n;
+        buffer += b;
+    }
+    Err(format!("no column named {:?}", name))
+}
+
+fn value(bytes: &[u8], values: Values) -> f64 {
+    match values {
+        Values::Float { bytes: 4 } => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        Values::Float { .. } => f64::from_le_bytes(bytes.try_into().unwrap()),
+        Values::Int { signed, .. } => {
+            let mut raw = [0u8; 8];
+            raw[..bytes.len()].copy_from_slice(bytes);
+            let x = u64::from_le_bytes(raw);
+            let shift = 64 - 8 * bytes.len() as u32;
+            if signed {
+                (((x << shift) as i64) >> shift) as f64
+            } else {
+                x as f64
+            }
+        }
+    }
+}
+
+// Appends the non-null values of the column in one record batch to `out`,
+// returning how many were null.
+fn read_batch(batch: &Table, body: &[u8], column: &Column, out: &mut Vec<f64>) -> Result<usize> {
+    if batch.field(3)?.is_some() {
+        return Err("compressed Arrow IPC record batches are not supported".to_string());
+    }
+    let nodes = batch.pairs(1)?;
+    let buffers = batch.pairs(2)?;
+    let (length, null_count) = *nodes.get(column.node).ok_or_else(corrupt)?;
+    let slice = |(offset, len): (i64, i64)| {
+        body.get(offset as usize..(offset + len) as usize)
+            .ok_or_else(corrupt)
+    };
+    let validity = slice(*buffers.get(column.buffer).ok_or_else(corrupt)?)?;
+    let data = slice(*buffers.get(column.buffer + 1).ok_or_else(corrupt)?)?;
+    let width = match column.values {
+        Values::Int { bytes, .. } | Values::Float { bytes } => bytes,
+    };
+    if !(1..=8).contains(&width) || data.len() < length as usize * width {
+        return Err(corrupt());
+    }
+    let mut nulls = 0;
+    for i in 0..length as usize {
+        let valid = null_count == 0
+            || validity.is_empty()
+            || validity.get(i / 8).is_some_and(|b| b >> (i % 8) & 1 == 1);
+        if valid {
+            out.push(value(&data[i * width..(i + 1) * width], column.values));
+        } else {
+            nulls += 1;
+        }
+    }
+    Ok(nulls)
+}
+
+// Reads the named column from an Arrow IPC file or stream, returning its
+// non-null values and how many values were null.
+pub fn read_column(data: &[u8], name: &str) -> Result<(Vec<f64>, usize)> {
+    let mut pos = if data.starts_with(MAGIC) { 8 } else { 0 };
+    let mut column = None;
+    let mut values = Vec::new();
+    let mut nulls = 0;
+    while pos + 4 <= data.len() {
+        let mut size = u32_at(data, pos)?;
+        pos += 4;
+        if size == CONTINUATION {
+            size = u32_at(data, pos)?;
+            pos += 4;
+        }
+        if size == 0 {
+            break;
+        }
+        let metadata = data
+            .get(pos..pos + size as usize)
+            .ok_or_else(|| "not an Arrow IPC file or stream".to_string())?;
+        pos += size as usize;
+        let message = Table::root(metadata)?;
+        let body_len = message.i64(3, 0)? as usize;
+        let body = data.get(pos..pos + body_len).ok_or_else(corrupt)?;
+        pos += body_len;
+        let header = message.table(2)?.ok_or_else(corrupt)?;
+        match message.u8(1, 0)? {
+            SCHEMA => column = Some(find_column(&header, name)?),
+            RECORD_BATCH => {
+                let column = column
+                    .as_ref()
+                    .ok_or_else(|| "record batch before the schema".to_string())?;
+                nulls += read_batch(&header, body, column, &mut values)?;
+            }
+            DICTIONARY_BATCH => {}
+            _ => return Err("unsupported Arrow IPC message".to_string()),
+        }
+    }
+    if column.is_none() {
+        return Err("not an Arrow IPC file or stream".to_string());
+    }
+    Ok((values, nulls))
+}
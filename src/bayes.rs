@@ -0,0 +1,132 @@
+// Bayesian bootstrap comparison: both samples are reweighted with flat
+// Dirichlet weights, giving a posterior for the difference in each estimator.
+
+use crate::estimator::Estimator;
+use crate::metadata::Labels;
+use crate::rng::Seed;
+use crate::sample::Sample;
+use crate::units::Units;
+use crate::{get_quantile, json, Error};
+use rand::Rng;
+
+pub struct BayesResult {
+    pub name: String,
+    pub baseline_estimator: f64,
+    pub target_estimator: f64,
+    // Posterior probability that the target's estimator exceeds the baseline's.
+    pub prob_target_greater: f64,
+    pub credible_level: f64,
+    pub diff_low: f64,
+    pub diff_high: f64,
+}
+
+// Sets the weights of a sorted sample to a draw from Dirichlet(1, ..., 1),
+// scaled by any weights the sample originally carried.
+fn draw_weights(rng: &mut impl Rng, original: Option<&[f64]>, weights: &mut [f64]) {
+    let mut total = 0.0;
+    for (i, w) in weights.iter_mut().enumerate() {
+        let u: f64 = rng.gen();
+        let e = -(1.0 - u).ln();
+        *w = e * original.map_or(1.0, |ws| ws[i]);
+        total += *w;
+    }
+    for w in weights.iter_mut() {
+        *w /= total;
+    }
+}
+
+fn reweightable(sample: &Sample) -> Sample {
+    Sample {
+        values: sample.values.clone(),
+        weights: Some(vec![0.0; sample.len()]),
+        input_order: Vec::new(),
+        strata: None,
+    }
+}
+
+pub fn bayesian_bootstrap(
+    iterations: u64,
+    seed: Seed,
+    baseline: &Sample,
+    target: &Sample,
+    estimators: &[Box<dyn Estimator>],
+    credible_level: f64,
+) -> Result<Vec<BayesResult>, Error> {
+    let mut rng = seed.analysis_stream("bayes");
+    let mut baseline_w = reweightable(baseline);
+    let mut target_w = reweightable(target);
+    let mut diffs: Vec<Vec<f64>> = vec![Vec::with_capacity(iterations as usize); estimators.len()];
+
+    for _ in 0..iterations {
+        draw_weights(
+            &mut rng,
+            baseline.weights.as_deref(),
+            baseline_w.weights.as_mut().expect("reweightable"),
+        );
+        draw_weights(
+            &mut rng,
+            target.weights.as_deref(),
+            target_w.weights.as_mut().expect("reweightable"),
+        );
+        for (est, d) in estimators.iter().zip(diffs.iter_mut()) {
+            d.push(est.estimate(&target_w)? - est.estimate(&baseline_w)?);
+        }
+    }
+
+    let tail = (1.0 - credible_level) / 2.0;
+    let mut results = Vec::new();
+    for (est, mut d) in estimators.iter().zip(diffs) {
+        d.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let greater = d.iter().filter(|x| **x > 0.0).count();
+        results.push(BayesResult {
+            name: est.name().to_string(),
+            baseline_estimator: est.estimate(baseline)?,
+            target_estimator: est.estimate(target)?,
+            prob_target_greater: (greater as f64) / (d.len() as f64),
+            credible_level,
+            diff_low: get_quantile(&d, tail)?,
+            diff_high: get_quantile(&d, 1.0 - tail)?,
+        });
+    }
+    Ok(results)
+}
+
+pub fn print_text(results: &[BayesResult], units: &Units, labels: &Labels) {
+    println!("=== Bayesian comparison ===");
+    for r in results.iter() {
+        println!(
+            "{}: {} to {}, P({} > {}) = {}, {}% credible interval for difference [{}, {}]",
+            r.name,
+            units.format(r.baseline_estimator),
+            units.format(r.target_estimator),
+            labels.target,
+            labels.baseline,
+            r.prob_target_greater,
+            100.0 * r.credible_level,
+            units.format(r.diff_low),
+            units.format(r.diff_high)
+        );
+    }
+}
+
+pub fn results_json(metadata_json: String, results: &[BayesResult]) -> String {
+    let items: Vec<String> = results
+        .iter()
+        .map(|r| {
+            json::object(&[
+                ("estimator", json::string(&r.name)),
+                ("baseline", json::number(r.baseline_estimator)),
+                ("target", json::number(r.target_estimator)),
+                ("prob_target_greater", json::number(r.prob_target_greater)),
+                ("credible_level", json::number(r.credible_level)),
+                ("diff_low", json::number(r.diff_low)),
+                ("diff_high", json::number(r.diff_high)),
+            ])
+        })
+        .collect();
+    json::object(&[
+        ("metadata", metadata_json),
+        ("method", json::string("bayes")),
+        ("comparison", json::array(&items)),
+    ])
+}
@@ -0,0 +1,65 @@
+// An on-disk cache of simulation results, so that re-running a comparison
+// with the same inputs, seed and options returns without simulating again.
+// Entries are files of whitespace-separated numbers named by their key.
+
+use crate::sha256;
+use std::io::Write;
+use std::path::PathBuf;
+
+const HEADER: &str = "numcmp-cache 1";
+
+// $NUMCMP_CACHE_DIR, or numcmp under $XDG_CACHE_HOME or ~/.cache.
+fn dir() -> Option<PathBuf> {
+    let var = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+    if let Some(dir) = var("NUMCMP_CACHE_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    match var("XDG_CACHE_HOME") {
+        Some(cache) => Some(PathBuf::from(cache).join("numcmp")),
+        None => var("HOME").map(|home| PathBuf::from(home).join(".cache").join("numcmp")),
+    }
+}
+
+// Hashes the parts of a key, each prefixed by its length so that different
+// splits of the same bytes give different keys.
+pub fn key(parts: &[&[u8]]) -> String {
+    let mut data = Vec::new();
+    for part in parts {
+        data.extend_from_slice(&(part.len() as u64).to_le_bytes());
+        data.extend_from_slice(part);
+    }
+    sha256::hex_digest(&data)
+}
+
+pub fn load(key: &str) -> Option<Vec<Vec<f64>>> {
+    let text = std::fs::read_to_string(dir()?.join(key)).ok()?;
+    let mut lines = text.lines();
+    if lines.next()? != HEADER {
+        return None;
+    }
+    lines
+        .map(|line| line.split_whitespace().map(|x| x.parse().ok()).collect())
+        .collect()
+}
+
+// Stores an entry, ignoring failures: the cache only saves time.
+pub fn store(key: &str, rows: &[Vec<f64>]) {
+    let Some(dir) = dir() else {
+        return;
+    };
+    let write = || -> std::io::Result<()> {
+        std::fs::create_dir_all(&dir)?;
+        // Written under a temporary name and renamed, so concurrent runs
+        // never read a partial entry.
+        let partial = dir.join(format!("{}.{}.tmp", key, std::process::id()));
+        let mut out = std::io::BufWriter::new(std::fs::File::create(&partial)?);
+        writeln!(out, "{}", HEADER)?;
+        for row in rows.iter() {
+            let items: Vec<String> = row.iter().map(|x| x.to_string()).collect();
+            writeln!(out, "{}", items.join(" "))?;
+        }
+        out.into_inner().map_err(|e| e.into_error())?;
+        std::fs::rename(&partial, dir.join(key))
+    };
+    let _ = write();
+}
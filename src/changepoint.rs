@@ -0,0 +1,165 @@
+// Detecting shifts in level within one series of measurements in the order
+// they were taken, by binary segmentation: the most likely split of a
+// segment is kept if a permutation test finds it significant, and both
+// halves are then searched in turn.
+
+use crate::input::{self, InputArgs};
+use crate::rng::{self, Seed};
+use crate::table::{Layout, Table};
+use crate::units::format_number;
+use crate::Error;
+use rand::seq::SliceRandom;
+use std::path::Path;
+
+// The split of xs into xs[..k] and xs[k..], each at least `min_size` long,
+// with the largest standardized difference in means
+// sqrt(k (n - k) / n) |mean before - mean after|, and that difference.
+fn best_split(xs: &[f64], min_size: usize) -> Option<(usize, f64)> {
+    let n = xs.len();
+    if n < 2 * min_size {
+        return None;
+    }
+    let total: f64 = xs.iter().sum();
+    let mut before = 0.0;
+    let mut best: Option<(usize, f64)> = None;
+    for (i, x) in xs.iter().enumerate().take(n - min_size) {
+        before += x;
+        let k = i + 1;
+        if k < min_size {
+            continue;
+        }
+        let (kf, nf) = (k as f64, n as f64);
+        let diff = before / kf - (total - before) / (nf - kf);
+        let stat = (kf * (nf - kf) / nf).sqrt() * diff.abs();
+        if best.is_none_or(|(_, s)| stat > s) {
+            best = Some((k, stat));
+        }
+    }
+    best
+}
+
+struct Change {
+    // Index of the first value after the change.
+    index: usize,
+    p_value: f64,
+}
+
+// Splits xs[start..end] at significant changes, appending them to `changes`.
+#[allow(clippy::too_many_arguments)]
+fn segment(
+    xs: &[f64],
+    start: usize,
+    end: usize,
+    depth: u64,
+    min_size: usize,
+    iterations: u64,
+    seed: Seed,
+    alpha: f64,
+    changes: &mut Vec<Change>,
+) {
+    let part = &xs[start..end];
+    let (k, observed) = match best_split(part, min_size) {
+        Some(split) => split,
+        None => return,
+    };
+    // Each segment draws from its own stream, so that its halves do not
+    // repeat its shuffles.
+    let mut rng = seed.stream(rng::derive_stream(&[start as u64, end as u64, depth]));
+    let mut shuffled = part.to_vec();
+    let mut extreme = 0;
+    for _ in 0..iterations {
+        shuffled.shuffle(&mut rng);
+        let (_, stat) = best_split(&shuffled, min_size).expect("same length");
+        if stat >= observed {
+            extreme += 1;
+        }
+    }
+    // Counting the observed split as one of the permutations keeps the
+    // p-value above zero, as a permutation test's should be.
+    let p_value = (extreme + 1) as f64 / (iterations + 1) as f64;
+    if p_value >= alpha {
+        return;
+    }
+    changes.push(Change {
+        index: start + k,
+        p_value,
+    });
+    segment(
+        xs,
+        start,
+        start + k,
+        depth + 1,
+        min_size,
+        iterations,
+        seed,
+        alpha,
+        changes,
+    );
+    segment(
+        xs,
+        start + k,
+        end,
+        depth + 1,
+        min_size,
+        iterations,
+        seed,
+        alpha,
+        changes,
+    );
+}
+
+fn mean(xs: &[f64]) -> f64 {
+    xs.iter().sum::<f64>() / xs.len() as f64
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn changepoint(
+    filename: &Path,
+    input: &InputArgs,
+    min_size: usize,
+    iterations: u64,
+    seed: Seed,
+    alpha: f64,
+    layout: Layout,
+) -> Result<(), Error> {
+    let sample = input::read_sample(filename, input)?;
+    if sample.weights.is_some() {
+        return Err(Error::Usage(
+            "change points need a series of unweighted values".to_string(),
+        ));
+    }
+    let xs = &sample.input_order;
+    let mut changes = Vec::new();
+    segment(
+        xs,
+        0,
+        xs.len(),
+        0,
+        min_size,
+        iterations,
+        seed,
+        alpha,
+        &mut changes,
+    );
+    changes.sort_by_key(|c| c.index);
+
+    println!("=== Change points ===");
+    if changes.is_empty() {
+        println!("No significant change in level at alpha={}.", alpha);
+        return Ok(());
+    }
+    let mut bounds = vec![0];
+    bounds.extend(changes.iter().map(|c| c.index));
+    bounds.push(xs.len());
+    let mut table = Table::new(&["at value", "mean before", "mean after", "p-value"]);
+    for (i, change) in changes.iter().enumerate() {
+        table.row(vec![
+            (change.index + 1).to_string(),
+            format_number(mean(&xs[bounds[i]..bounds[i + 1]])),
+            format_number(mean(&xs[bounds[i + 1]..bounds[i + 2]])),
+            format!("{:.4}", change.p_value),
+        ]);
+    }
+    print!("{}", table.render(layout));
+    Ok(())
+}
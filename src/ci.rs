@@ -0,0 +1,192 @@
+// Confidence intervals for the difference target - baseline in each
+// estimator, from a bootstrap that resamples both samples, usually in the
+// simulation's own resampling pass.
+
+use crate::estimator::Estimator;
+use crate::resample::{BootstrapMethod, Resampler};
+use crate::rng::Seed;
+use crate::sample::Sample;
+use crate::{get_quantile, Error};
+use clap::ValueEnum;
+use rand::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum CiMethod {
+    /// Quantiles of the bootstrapped differences
+    Percentile,
+    /// Bootstrap-t, with standard errors from an inner bootstrap
+    Studentized,
+}
+
+// Inner resamples per outer replicate for the studentized interval.
+const INNER_ITERATIONS: usize = 50;
+
+fn std_dev(xs: &[f64]) -> f64 {
+    let n = xs.len() as f64;
+    let mean = xs.iter().sum::<f64>() / n;
+    (xs.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1.0)).sqrt()
+}
+
+pub fn draw(rng: &mut impl Rng, resampler: &Resampler, from: &Sample, out: &mut Sample) {
+    resampler.resample(rng, from, from.size(), out);
+    if out.weights.is_none() {
+        out.values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    }
+}
+
+// Bootstrap estimates of the standard error of the difference between two
+// resamples, for each estimator.
+fn inner_std_errors(
+    rng: &mut impl Rng,
+    baseline: &Sample,
+    target: &Sample,
+    estimators: &[Box<dyn Estimator>],
+) -> Result<Vec<f64>, Error> {
+    let baseline_resampler = Resampler::new(BootstrapMethod::Iid, None, None, None, baseline)?;
+    let target_resampler = Resampler::new(BootstrapMethod::Iid, None, None, None, target)?;
+    let mut b = Sample::default();
+    let mut t = Sample::default();
+    let mut diffs = vec![Vec::with_capacity(INNER_ITERATIONS); estimators.len()];
+    for _ in 0..INNER_ITERATIONS {
+        draw(rng, &baseline_resampler, baseline, &mut b);
+        draw(rng, &target_resampler, target, &mut t);
+        for (est, d) in estimators.iter().zip(diffs.iter_mut()) {
+            d.push(est.estimate(&t)? - est.estimate(&b)?);
+        }
+    }
+    Ok(diffs.iter().map(|d| std_dev(d)).collect())
+}
+
+// Values one replicate adds per estimator: the difference, and for the
+// studentized interval its inner standard error.
+pub fn values_per_replicate(method: CiMethod) -> usize {
+    match method {
+        CiMethod::Percentile => 1,
+        CiMethod::Studentized => 2,
+    }
+}
+
+// Appends the differences between resamples `t` and `b` for each estimator,
+// followed for the studentized interval by their standard errors.
+pub fn replicate(
+    rng: &mut impl Rng,
+    method: CiMethod,
+    b: &Sample,
+    t: &Sample,
+    estimators: &[Box<dyn Estimator>],
+    out: &mut Vec<f64>,
+) -> Result<(), Error> {
+    for est in estimators.iter() {
+        out.push(est.estimate(t)? - est.estimate(b)?);
+    }
+    if method == CiMethod::Studentized {
+        out.extend(inner_std_errors(rng, b, t, estimators)?);
+    }
+    Ok(())
+}
+
+// Collects replicates laid out as by replicate() into differences and
+// standard errors per estimator.
+pub struct Replicates {
+    diffs: Vec<Vec<f64>>,
+    std_errors: Vec<Vec<f64>>,
+}
+
+impl Replicates {
+    pub fn new(estimators: usize) -> Replicates {
+        Replicates {
+            diffs: vec![Vec::new(); estimators],
+            std_errors: vec![Vec::new(); estimators],
+        }
+    }
+
+    pub fn push(&mut self, values: &[f64]) {
+        let k = self.diffs.len();
+        for (i, d) in values[..k].iter().enumerate() {
+            self.diffs[i].push(*d);
+        }
+        for (i, se) in values[k..].iter().enumerate() {
+            self.std_errors[i].push(*se);
+        }
+    }
+
+    // Returns (difference, low, high) per estimator at the given confidence
+    // level, around the observed differences.
+    pub fn intervals(
+        self,
+        method: CiMethod,
+        observed: &[f64],
+        level: f64,
+    ) -> Result<Vec<(f64, f64, f64)>, Error> {
+        let tail = (1.0 - level) / 2.0;
+        let mut intervals = Vec::new();
+        for ((observed, mut diffs), std_errors) in observed
+            .iter()
+            .copied()
+            .zip(self.diffs)
+            .zip(self.std_errors)
+        {
+            // Replicates with no spread (e.g. the min of a small sample)
+            // carry no information about the pivot.
+            let mut pivots: Vec<f64> = diffs
+                .iter()
+                .zip(std_errors.iter())
+                .filter(|(_, se)| **se > 0.0)
+                .map(|(diff, se)| (diff - observed) / se)
+                .collect();
+            diffs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let (lo, hi) = match method {
+                CiMethod::Percentile => (
+                    get_quantile(&diffs, tail)?,
+                    get_quantile(&diffs, 1.0 - tail)?,
+                ),
+                CiMethod::Studentized if pivots.is_empty() => (observed, observed),
+                CiMethod::Studentized => {
+                    pivots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                    let se = std_dev(&diffs);
+                    (
+                        observed - get_quantile(&pivots, 1.0 - tail)? * se,
+                        observed - get_quantile(&pivots, tail)? * se,
+                    )
+                }
+            };
+            intervals.push((observed, lo, hi));
+        }
+        Ok(intervals)
+    }
+}
+
+// Returns (difference, low, high) per estimator at the given confidence
+// level, from a pass of its own. The simulation draws these replicates
+// alongside the null distribution; this is for when it has no resampling
+// pass, as with exact permutations.
+pub fn difference_intervals(
+    method: CiMethod,
+    iterations: u64,
+    seed: Seed,
+    baseline: &Sample,
+    target: &Sample,
+    estimators: &[Box<dyn Estimator>],
+    level: f64,
+) -> Result<Vec<(f64, f64, f64)>, Error> {
+    let baseline_resampler = Resampler::new(BootstrapMethod::Iid, None, None, None, baseline)?;
+    let target_resampler = Resampler::new(BootstrapMethod::Iid, None, None, None, target)?;
+    let mut rng = seed.analysis_stream("difference intervals");
+    let mut b = Sample::default();
+    let mut t = Sample::default();
+
+    let observed = estimators
+        .iter()
+        .map(|est| Ok(est.estimate(target)? - est.estimate(baseline)?))
+        .collect::<Result<Vec<f64>, Error>>()?;
+    let mut replicates = Replicates::new(estimators.len());
+    let mut values = Vec::new();
+    for _ in 0..iterations {
+        draw(&mut rng, &baseline_resampler, baseline, &mut b);
+        draw(&mut rng, &target_resampler, target, &mut t);
+        values.clear();
+        replicate(&mut rng, method, &b, &t, estimators, &mut values)?;
+        replicates.push(&values);
+    }
+    replicates.intervals(method, &observed, level)
+}
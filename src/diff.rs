@@ -0,0 +1,203 @@
+// `numcmp diff`: how a comparison changed between two runs, read from their
+// --format json results without the raw data, e.g. for successive runs of a
+// benchmark gate.
+
+use crate::json::{self, Value};
+use crate::table::{Layout, Table};
+use crate::units::format_number;
+use crate::{fetch, Error};
+use std::path::Path;
+
+struct Estimate {
+    name: String,
+    delta: f64,
+    p_value: f64,
+    verdict: String,
+}
+
+// One comparison of a result file; --all-columns writes one per column.
+struct Comparison {
+    column: Option<String>,
+    timestamp: String,
+    iterations: f64,
+    alpha: f64,
+    counts: (f64, f64),
+    estimates: Vec<Estimate>,
+}
+
+impl Comparison {
+    // A p-value as the result showed it: a zero only means that it is below
+    // what the iterations can resolve.
+    fn p_value_text(&self, p_value: f64) -> String {
+        match p_value {
+            0.0 => format!("< {}", 1.0 / self.iterations),
+            p => p.to_string(),
+        }
+    }
+}
+
+fn comparison(path: &Path, value: &Value) -> Result<Comparison, Error> {
+    let invalid = |what: &str| {
+        Error::InvalidInput(format!(
+            "{} is not a numcmp JSON result: no {}",
+            path.display(),
+            what
+        ))
+    };
+    let number = |v: &Value, key: &str| match v.get_path(key) {
+        Some(Value::Number(x)) => Ok(*x),
+        _ => Err(invalid(key)),
+    };
+    let string = |v: &Value, key: &str| match v.get_path(key) {
+        Some(Value::String(s)) => Some(s.clone()),
+        _ => None,
+    };
+    let Some(Value::Array(items)) = value.get_path("comparison") else {
+        return Err(invalid("comparison"));
+    };
+    let estimates = items
+        .iter()
+        .map(|item| {
+            Ok(Estimate {
+                name: string(item, "estimator").ok_or_else(|| invalid("estimator"))?,
+                delta: number(item, "delta")?,
+                p_value: number(item, "p_value")?,
+                verdict: string(item, "verdict").ok_or_else(|| invalid("verdict"))?,
+            })
+        })
+        .collect::<Result<Vec<Estimate>, Error>>()?;
+    Ok(Comparison {
+        column: string(value, "metadata.column"),
+        timestamp: string(value, "metadata.timestamp").unwrap_or_default(),
+        iterations: number(value, "metadata.iterations")?,
+        alpha: number(value, "alpha")?,
+        counts: (
+            number(value, "baseline.count")?,
+            number(value, "target.count")?,
+        ),
+        estimates,
+    })
+}
+
+// The comparisons of a result file: a single JSON document, or one per
+// line as --all-columns writes them.
+fn read(path: &Path) -> Result<Vec<Comparison>, Error> {
+    let text = std::fs::read_to_string(fetch::local(path)?).map_err(|e| Error::open(path, e))?;
+    let documents = match json::parse(&text) {
+        Ok(value) => vec![value],
+        Err(_) => text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(i, line)| {
+                json::parse(line).map_err(|e| {
+                    Error::InvalidInput(format!("{}: document {}: {}", path.display(), i + 1, e))
+                })
+            })
+            .collect::<Result<Vec<Value>, Error>>()?,
+    };
+    documents.iter().map(|v| comparison(path, v)).collect()
+}
+
+fn print_comparison(old: &Comparison, new: &Comparison, layout: Layout) {
+    println!(
+        "Old: {}, {} iterations, alpha {}, {} baseline and {} target values",
+        old.timestamp, old.iterations, old.alpha, old.counts.0, old.counts.1
+    );
+    println!(
+        "New: {}, {} iterations, alpha {}, {} baseline and {} target values",
+        new.timestamp, new.iterations, new.alpha, new.counts.0, new.counts.1
+    );
+    let mut table = Table::new(&[
+        "estimator",
+        "old delta",
+        "new delta",
+        "change",
+        "old p-value",
+        "new p-value",
+        "verdict",
+    ]);
+    let mut changed = Vec::new();
+    let missing = || "-".to_string();
+    for o in old.estimates.iter() {
+        let Some(n) = new.estimates.iter().find(|n| n.name == o.name) else {
+            table.row(vec![
+                o.name.clone(),
+                format_number(o.delta),
+                missing(),
+                missing(),
+                old.p_value_text(o.p_value),
+                missing(),
+                format!("{} -> (dropped)", o.verdict),
+            ]);
+            continue;
+        };
+        let verdict = if o.verdict == n.verdict {
+            n.verdict.clone()
+        } else {
+            changed.push(format!("{} {} -> {}", o.name, o.verdict, n.verdict));
+            format!("{} -> {}", o.verdict, n.verdict)
+        };
+        table.row(vec![
+            o.name.clone(),
+            format_number(o.delta),
+            format_number(n.delta),
+            format_number(n.delta - o.delta),
+            old.p_value_text(o.p_value),
+            new.p_value_text(n.p_value),
+            verdict,
+        ]);
+    }
+    for n in new.estimates.iter() {
+        if !old.estimates.iter().any(|o| o.name == n.name) {
+            table.row(vec![
+                n.name.clone(),
+                missing(),
+                format_number(n.delta),
+                missing(),
+                missing(),
+                new.p_value_text(n.p_value),
+                format!("(added) -> {}", n.verdict),
+            ]);
+        }
+    }
+    print!("{}", table.render(layout));
+    if changed.is_empty() {
+        println!("No estimator changed verdict.");
+    } else {
+        println!("Verdicts changed: {}.", changed.join(", "));
+    }
+}
+
+// Returns whether any estimator became regressed.
+pub fn diff(old: &Path, new: &Path, layout: Layout) -> Result<bool, Error> {
+    let old = read(old)?;
+    let new = read(new)?;
+    let pairs: Vec<(&Comparison, &Comparison)> = new
+        .iter()
+        .filter_map(|n| Some((old.iter().find(|o| o.column == n.column)?, n)))
+        .collect();
+    if pairs.is_empty() {
+        return Err(Error::InvalidInput(
+            "the results have no column in common".to_string(),
+        ));
+    }
+    let mut regressed = false;
+    for (i, (o, n)) in pairs.into_iter().enumerate() {
+        if let Some(column) = &n.column {
+            if i > 0 {
+                println!();
+            }
+            println!("##### Column {} #####", column);
+        }
+        print_comparison(o, n, layout);
+        regressed |= n.estimates.iter().any(|e| {
+            e.verdict == "regressed"
+                && !o
+                    .estimates
+                    .iter()
+                    .any(|o| o.name == e.name && o.verdict == "regressed")
+        });
+    }
+    Ok(regressed)
+}
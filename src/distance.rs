@@ -0,0 +1,164 @@
+// Distances between the two distributions as a whole, with percentile
+// bootstrap confidence intervals from resampling both samples.
+
+use crate::ci;
+use crate::resample::{BootstrapMethod, Resampler};
+use crate::rng::Seed;
+use crate::sample::Sample;
+use crate::{get_quantile, Error};
+
+pub struct Distance {
+    pub name: &'static str,
+    pub value: f64,
+    pub interval: (f64, f64),
+    // Whether the value is a pure number rather than in the data's units.
+    pub dimensionless: bool,
+}
+
+fn weight(sample: &Sample, i: usize) -> f64 {
+    sample.weights.as_ref().map_or(1.0, |ws| ws[i])
+}
+
+// The 1-Wasserstein (earth mover's) distance: the area between the two
+// empirical CDFs, in the units of the data.
+pub fn wasserstein(a: &Sample, b: &Sample) -> f64 {
+    let (total_a, total_b) = (a.total_weight(), b.total_weight());
+    let (mut i, mut j) = (0, 0);
+    let (mut cdf_a, mut cdf_b) = (0.0f64, 0.0f64);
+    let mut prev: Option<f64> = None;
+    let mut area = 0.0;
+    while i < a.len() || j < b.len() {
+        let x = match (a.values.get(i), b.values.get(j)) {
+            (Some(x), Some(y)) => x.min(*y),
+            (Some(x), None) | (None, Some(x)) => *x,
+            (None, None) => unreachable!(),
+        };
+        if let Some(prev) = prev {
+            area += (x - prev) * (cdf_a - cdf_b).abs();
+        }
+        while a.values.get(i) == Some(&x) {
+            cdf_a += weight(a, i) / total_a;
+            i += 1;
+        }
+        while b.values.get(j) == Some(&x) {
+            cdf_b += weight(b, j) / total_b;
+            j += 1;
+        }
+        prev = Some(x);
+    }
+    area
+}
+
+// Shared histogram bins over the range of both samples, the square root of
+// their combined size in number, within limits.
+pub struct Bins {
+    lo: f64,
+    width: f64,
+    count: usize,
+}
+
+const MIN_BINS: usize = 10;
+const MAX_BINS: usize = 100;
+
+impl Bins {
+    pub fn new(a: &Sample, b: &Sample) -> Bins {
+        let first = |s: &Sample| s.values.first().copied().unwrap_or(f64::INFINITY);
+        let last = |s: &Sample| s.values.last().copied().unwrap_or(f64::NEG_INFINITY);
+        let lo = first(a).min(first(b));
+        let hi = last(a).max(last(b));
+        let count = ((a.size() + b.size()) as f64)
+            .sqrt()
+            .ceil()
+            .clamp(MIN_BINS as f64, MAX_BINS as f64) as usize;
+        let width = if hi > lo {
+            (hi - lo) / count as f64
+        } else {
+            1.0
+        };
+        Bins { lo, width, count }
+    }
+
+    // The fraction of the sample's weight in each bin; values outside the
+    // range go into the first or last bin.
+    fn fractions(&self, sample: &Sample) -> Vec<f64> {
+        let mut fractions = vec![0.0; self.count];
+        let total = sample.total_weight();
+        for (i, x) in sample.values.iter().enumerate() {
+            let bin = ((x - self.lo) / self.width).max(0.0) as usize;
+            fractions[bin.min(self.count - 1)] += weight(sample, i) / total;
+        }
+        fractions
+    }
+}
+
+// The Jensen-Shannon divergence of the samples' histograms, in bits: 0 for
+// identical distributions and 1 for ones that do not overlap at all.
+pub fn jensen_shannon(a: &Sample, b: &Sample, bins: &Bins) -> f64 {
+    let (p, q) = (bins.fractions(a), bins.fractions(b));
+    let kl = |x: f64, m: f64| if x > 0.0 { x * (x / m).log2() } else { 0.0 };
+    p.iter()
+        .zip(q.iter())
+        .map(|(p, q)| {
+            let m = (p + q) / 2.0;
+            (kl(*p, m) + kl(*q, m)) / 2.0
+        })
+        .sum()
+}
+
+// The probability of superiority (Vargha and Delaney's A12): the chance
+// that a random target value exceeds a random baseline value, counting ties
+// as half. 0.5 means neither tends to be larger.
+pub fn superiority(baseline: &Sample, target: &Sample) -> f64 {
+    let (mut below, mut tied) = (0.0, 0.0);
+    let (mut i, mut k) = (0, 0);
+    let mut sum = 0.0;
+    for (j, y) in target.values.iter().enumerate() {
+        if j == 0 || target.values[j - 1] != *y {
+            while baseline.values.get(i).is_some_and(|x| x < y) {
+                below += weight(baseline, i);
+                i += 1;
+            }
+            k = k.max(i);
+            tied = 0.0;
+            while baseline.values.get(k).is_some_and(|x| x == y) {
+                tied += weight(baseline, k);
+                k += 1;
+            }
+        }
+        sum += weight(target, j) * (below + tied / 2.0);
+    }
+    sum / (baseline.total_weight() * target.total_weight())
+}
+
+// The observed distance with a percentile interval. Plug-in distances are
+// biased upwards by sampling noise, so the interval is not a test of
+// whether the distributions differ.
+pub fn with_interval(
+    name: &'static str,
+    distance: &dyn Fn(&Sample, &Sample) -> f64,
+    iterations: u64,
+    seed: Seed,
+    baseline: &Sample,
+    target: &Sample,
+    level: f64,
+) -> Result<Distance, Error> {
+    let baseline_resampler = Resampler::new(BootstrapMethod::Iid, None, None, None, baseline)?;
+    let target_resampler = Resampler::new(BootstrapMethod::Iid, None, None, None, target)?;
+    let mut rng = seed.analysis_stream("distances");
+    let mut b = Sample::default();
+    let mut t = Sample::default();
+    let mut sims = Vec::new();
+    for _ in 0..iterations {
+        ci::draw(&mut rng, &baseline_resampler, baseline, &mut b);
+        ci::draw(&mut rng, &target_resampler, target, &mut t);
+        sims.push(distance(&b, &t));
+    }
+    sims.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let tail = (1.0 - level) / 2.0;
+    Ok(Distance {
+        name,
+        value: distance(baseline, target),
+        interval: (get_quantile(&sims, tail)?, get_quantile(&sims, 1.0 - tail)?),
+        dimensionless: false,
+    })
+}
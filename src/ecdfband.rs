@@ -0,0 +1,118 @@
+// Simultaneous bootstrap confidence bands for the baseline's empirical CDF,
+// with the target's ECDF set against them: wherever the target leaves the
+// band, its distribution differs from the baseline's there.
+
+use crate::ci;
+use crate::resample::{BootstrapMethod, Resampler};
+use crate::rng::Seed;
+use crate::sample::Sample;
+use crate::{get_quantile, Error};
+
+pub struct EcdfBand {
+    // The distinct values of both samples, ascending.
+    pub xs: Vec<f64>,
+    // The ECDFs of both samples at xs.
+    pub baseline: Vec<f64>,
+    pub target: Vec<f64>,
+    // The band is the baseline ECDF plus or minus this.
+    pub half_width: f64,
+}
+
+impl EcdfBand {
+    pub fn band(&self, i: usize) -> (f64, f64) {
+        (
+            (self.baseline[i] - self.half_width).max(0.0),
+            (self.baseline[i] + self.half_width).min(1.0),
+        )
+    }
+
+    // Whether the target ECDF is above (true) or below (false) the band at
+    // xs[i], if it is outside it. Above means more of the target is at most
+    // xs[i], so the target is lower there.
+    pub fn outside(&self, i: usize) -> Option<bool> {
+        let (lo, hi) = self.band(i);
+        match self.target[i] {
+            t if t > hi => Some(true),
+            t if t < lo => Some(false),
+            _ => None,
+        }
+    }
+}
+
+// The ECDF of a sample at each of the ascending points.
+fn ecdf_at(sample: &Sample, points: &[f64]) -> Vec<f64> {
+    let total = sample.total_weight();
+    let mut cumulative = 0.0;
+    let mut j = 0;
+    points
+        .iter()
+        .map(|x| {
+            while j < sample.values.len() && sample.values[j] <= *x {
+                cumulative += sample.weights.as_ref().map_or(1.0, |ws| ws[j]);
+                j += 1;
+            }
+            cumulative / total
+        })
+        .collect()
+}
+
+// The half-width is the `level` quantile of the largest distance between the
+// ECDF of a bootstrap resample of the baseline and the baseline's own, so
+// that the band holds at all values at once with that confidence.
+pub fn ecdf_band(
+    iterations: u64,
+    seed: Seed,
+    baseline: &Sample,
+    target: &Sample,
+    level: f64,
+) -> Result<EcdfBand, Error> {
+    let resampler = Resampler::new(BootstrapMethod::Iid, None, None, None, baseline)?;
+    let mut rng = seed.analysis_stream("ecdf band");
+    let mut b = Sample::default();
+
+    let mut xs: Vec<f64> = baseline
+        .values
+        .iter()
+        .chain(&target.values)
+        .copied()
+        .collect();
+    xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    xs.dedup();
+    let observed = ecdf_at(baseline, &xs);
+    let mut maxima = Vec::with_capacity(iterations as usize);
+    for _ in 0..iterations {
+        ci::draw(&mut rng, &resampler, baseline, &mut b);
+        let distance = ecdf_at(&b, &xs)
+            .iter()
+            .zip(observed.iter())
+            .map(|(f, g)| (f - g).abs())
+            .fold(0.0, f64::max);
+        maxima.push(distance);
+    }
+    maxima.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    Ok(EcdfBand {
+        target: ecdf_at(target, &xs),
+        baseline: observed,
+        half_width: get_quantile(&maxima, level)?,
+        xs,
+    })
+}
+
+// Ranges of values where the target is outside the band, e.g.
+// "12 to 15 (down)" where the target is lower.
+pub fn outside_ranges(band: &EcdfBand, show: impl Fn(f64) -> String) -> Vec<String> {
+    let indices: Vec<usize> = (0..band.xs.len()).collect();
+    indices
+        .chunk_by(|a, b| band.outside(*a) == band.outside(*b))
+        .filter_map(|run| {
+            let above = band.outside(run[0])?;
+            let direction = if above { "down" } else { "up" };
+            let (first, last) = (band.xs[run[0]], band.xs[run[run.len() - 1]]);
+            Some(match run.len() {
+                1 => format!("{} ({})", show(first), direction),
+                _ => format!("{} to {} ({})", show(first), show(last), direction),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,102 @@
+// The machine a run happened on, for --capture-env: enough to judge whether
+// two runs were measured in comparable conditions. Each item is read where
+// the platform offers it and left out otherwise.
+
+use crate::json;
+
+pub struct Environment {
+    pub hostname: Option<String>,
+    pub cpu_model: Option<String>,
+    pub cores: usize,
+    // The cpufreq scaling governor of the first CPU, e.g. "performance".
+    pub governor: Option<String>,
+    // The 1, 5 and 15 minute load averages.
+    pub load_average: Option<[f64; 3]>,
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    let text = std::fs::read_to_string(path).ok()?;
+    Some(text.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+#[cfg(unix)]
+fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: gethostname writes at most buf.len() bytes into buf.
+    if unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) } != 0 {
+        return None;
+    }
+    let end = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    Some(String::from_utf8_lossy(&buf[..end]).into_owned()).filter(|s| !s.is_empty())
+}
+
+#[cfg(not(unix))]
+fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+// The first model line of /proc/cpuinfo; ARM kernels call it differently.
+fn cpu_model() -> Option<String> {
+    let text = std::fs::read_to_string("/proc/cpuinfo").ok()?;
+    text.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        matches!(key.trim(), "model name" | "Hardware" | "cpu model")
+            .then(|| value.trim().to_string())
+    })
+}
+
+fn load_average() -> Option<[f64; 3]> {
+    let text = read_trimmed("/proc/loadavg")?;
+    let mut fields = text.split_whitespace().map(|x| x.parse().ok());
+    Some([fields.next()??, fields.next()??, fields.next()??])
+}
+
+pub fn capture() -> Environment {
+    Environment {
+        hostname: hostname(),
+        cpu_model: cpu_model(),
+        cores: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        governor: read_trimmed("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor"),
+        load_average: load_average(),
+    }
+}
+
+impl Environment {
+    // Label and value of each item captured, for reports.
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let mut rows = Vec::new();
+        if let Some(hostname) = &self.hostname {
+            rows.push(("Hostname", hostname.clone()));
+        }
+        if let Some(model) = &self.cpu_model {
+            rows.push(("CPU model", model.clone()));
+        }
+        rows.push(("CPU cores", self.cores.to_string()));
+        if let Some(governor) = &self.governor {
+            rows.push(("CPU governor", governor.clone()));
+        }
+        if let Some([one, five, fifteen]) = self.load_average {
+            rows.push(("Load average", format!("{} {} {}", one, five, fifteen)));
+        }
+        rows
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut fields = Vec::new();
+        if let Some(hostname) = &self.hostname {
+            fields.push(("hostname", json::string(hostname)));
+        }
+        if let Some(model) = &self.cpu_model {
+            fields.push(("cpu_model", json::string(model)));
+        }
+        fields.push(("cores", self.cores.to_string()));
+        if let Some(governor) = &self.governor {
+            fields.push(("governor", json::string(governor)));
+        }
+        if let Some(load) = self.load_average {
+            let load: Vec<String> = load.iter().map(|x| json::number(*x)).collect();
+            fields.push(("load_average", json::array(&load)));
+        }
+        json::object(&fields)
+    }
+}
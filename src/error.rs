@@ -0,0 +1,97 @@
+// Errors reported by numcmp. Each kind exits with its own status, following
+// the BSD sysexits conventions; status 2 is left to regressions and usage
+// errors detected by the argument parser.
+
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum Error {
+    // An input file could not be opened.
+    Open {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Io(std::io::Error),
+    // A value on a line of an input file could not be parsed as a number.
+    Parse {
+        path: PathBuf,
+        line: usize,
+        content: String,
+        token: String,
+    },
+    // Input that was read but cannot be used, e.g. a missing CSV column.
+    InvalidInput(String),
+    EmptySample(String),
+    InvalidQuantile(f64),
+    // Option values that are invalid or cannot be combined.
+    Usage(String),
+    // A WebAssembly plugin that is malformed or trapped.
+    Plugin(String),
+    // A panic caught at an entry point called from another language.
+    Internal(String),
+}
+
+impl Error {
+    pub fn open(path: &Path, source: std::io::Error) -> Error {
+        Error::Open {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Usage(_) => 64,
+            Error::Parse { .. } | Error::InvalidInput(_) | Error::EmptySample(_) => 65,
+            Error::Open { .. } => 66,
+            Error::InvalidQuantile(_) | Error::Plugin(_) | Error::Internal(_) => 70,
+            Error::Io(_) => 74,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Open { path, source } => write!(f, "cannot open {}: {}", path.display(), source),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Parse {
+                path,
+                line,
+                content,
+                token,
+            } => write!(
+                f,
+                "{}:{}: cannot parse {:?} as a number in line {:?}",
+                path.display(),
+                line,
+                token,
+                content
+            ),
+            Error::InvalidInput(msg) => write!(f, "invalid input: {}", msg),
+            Error::EmptySample(msg) => write!(f, "empty sample: {}", msg),
+            Error::InvalidQuantile(q) => {
+                write!(f, "quantile parameter q={} is out of range [0,1]", q)
+            }
+            Error::Usage(msg) => write!(f, "{}", msg),
+            Error::Plugin(msg) => write!(f, "plugin error: {}", msg),
+            Error::Internal(msg) => write!(f, "internal error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Open { source, .. } => Some(source),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        Error::Io(e)
+    }
+}
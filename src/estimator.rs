@@ -0,0 +1,536 @@
+// Statistics computed on each sample and compared between the baseline and
+// the target.
+
+use crate::sample::Sample;
+use crate::stats::incomplete_beta;
+use crate::units::format_number;
+use crate::Error;
+use clap::ValueEnum;
+use std::cell::RefCell;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Direction {
+    /// An increase is a regression (e.g. latency)
+    #[value(name = "lower")]
+    LowerIsBetter,
+    /// A decrease is a regression (e.g. throughput)
+    #[value(name = "higher")]
+    HigherIsBetter,
+}
+
+pub trait Estimator {
+    fn name(&self) -> &str;
+
+    fn direction(&self) -> Direction {
+        Direction::LowerIsBetter
+    }
+
+    // Whether estimate() relies on the values being sorted. Simulations skip
+    // sorting resamples when no estimator needs it.
+    fn requires_sorted(&self) -> bool {
+        true
+    }
+
+    // Whether the estimator converges at the usual 1/sqrt(n) rate on
+    // samples of size n, as the rescaling of the subsampling bootstrap
+    // assumes. Extremes converge at a rate that depends on the tail.
+    fn sqrt_n_rate(&self, _n: usize) -> bool {
+        true
+    }
+
+    fn estimate(&self, xs: &Sample) -> Result<f64, Error>;
+}
+
+pub struct Mean;
+
+impl Estimator for Mean {
+    fn name(&self) -> &str {
+        "avg"
+    }
+
+    fn requires_sorted(&self) -> bool {
+        false
+    }
+
+    fn estimate(&self, xs: &Sample) -> Result<f64, Error> {
+        Ok(xs.mean())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum QuantileEstimator {
+    /// The nearest values, interpolated as chosen by --quantile-method
+    Linear,
+    /// Harrell-Davis: a Beta-weighted average of all values, more efficient for small samples
+    HarrellDavis,
+}
+
+// The sample quantile definitions of Hyndman and Fan (1996), numbered as in
+// R's quantile(type = ...).
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum QuantileMethod {
+    /// Inverse of the empirical CDF (SAS definition 3)
+    #[value(name = "1")]
+    Type1,
+    /// Inverse of the empirical CDF, averaging at discontinuities (SAS definition 5)
+    #[value(name = "2")]
+    Type2,
+    /// The nearest value, ties to the even one (SAS definition 2)
+    #[value(name = "3")]
+    Type3,
+    /// Linear interpolation of the empirical CDF
+    #[value(name = "4")]
+    Type4,
+    /// Piecewise linear with knots at the midpoints of the steps
+    #[value(name = "5")]
+    Type5,
+    /// Linear, with p(k) = k / (n + 1) (Minitab, SPSS, NumPy "weibull")
+    #[value(name = "6")]
+    Type6,
+    /// Linear, with p(k) = (k - 1) / (n - 1) (R, NumPy and pandas default)
+    #[value(name = "7")]
+    Type7,
+    /// Linear, approximately median-unbiased
+    #[value(name = "8")]
+    Type8,
+    /// Linear, approximately unbiased for normal data
+    #[value(name = "9")]
+    Type9,
+}
+
+impl QuantileMethod {
+    // The quantile of sorted values as (1 - g) x[j] + g x[j+1], with x
+    // indexed from 1 and clamped to the sample.
+    pub fn quantile(self, sorted: &[f64], q: f64) -> Result<f64, Error> {
+        if sorted.is_empty() {
+            return Err(Error::EmptySample(
+                "cannot take a quantile of no values".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&q) {
+            return Err(Error::InvalidQuantile(q));
+        }
+        let n = sorted.len() as f64;
+        let m = match self {
+            QuantileMethod::Type1 | QuantileMethod::Type2 | QuantileMethod::Type4 => 0.0,
+            QuantileMethod::Type3 => -0.5,
+            QuantileMethod::Type5 => 0.5,
+            QuantileMethod::Type6 => q,
+            QuantileMethod::Type7 => 1.0 - q,
+            QuantileMethod::Type8 => (q + 1.0) / 3.0,
+            QuantileMethod::Type9 => q / 4.0 + 3.0 / 8.0,
+        };
+        let h = n * q + m;
+        let j = h.floor();
+        let fraction = h - j;
+        let g = match self {
+            QuantileMethod::Type1 if fraction == 0.0 => 0.0,
+            QuantileMethod::Type2 if fraction == 0.0 => 0.5,
+            QuantileMethod::Type3 if fraction == 0.0 && j % 2.0 == 0.0 => 0.0,
+            QuantileMethod::Type1 | QuantileMethod::Type2 | QuantileMethod::Type3 => 1.0,
+            _ => fraction,
+        };
+        let x = |k: f64| sorted[(k.clamp(1.0, n) as usize) - 1];
+        Ok(if g == 0.0 {
+            x(j)
+        } else {
+            (1.0 - g) * x(j) + g * x(j + 1.0)
+        })
+    }
+}
+
+// How the quantile estimators compute their values.
+#[derive(Debug, Clone, Copy)]
+pub struct QuantileOptions {
+    pub estimator: QuantileEstimator,
+    pub method: QuantileMethod,
+}
+
+impl Default for QuantileOptions {
+    fn default() -> QuantileOptions {
+        QuantileOptions {
+            estimator: QuantileEstimator::Linear,
+            method: QuantileMethod::Type7,
+        }
+    }
+}
+
+pub struct Quantile {
+    name: String,
+    q: f64,
+    options: QuantileOptions,
+    // Harrell-Davis weights for unweighted samples depend only on the
+    // sample size, so the last ones are kept: (n, first index, weights).
+    weights: RefCell<Option<(usize, usize, Vec<f64>)>>,
+}
+
+// Weights below this are left out of Harrell-Davis estimates.
+const NEGLIGIBLE_WEIGHT: f64 = 1e-12;
+
+impl Quantile {
+    pub fn new(name: &str, q: f64, options: QuantileOptions) -> Quantile {
+        Quantile {
+            name: name.to_string(),
+            q,
+            options,
+            weights: RefCell::new(None),
+        }
+    }
+
+    // The weight of the value covering the fraction (lo, hi] of the sample
+    // is the Beta((n+1)q, (n+1)(1-q)) probability of that interval.
+    fn beta_weight(&self, n: usize, lo: f64, hi: f64) -> f64 {
+        let a = (n as f64 + 1.0) * self.q;
+        let b = (n as f64 + 1.0) * (1.0 - self.q);
+        incomplete_beta(a, b, hi.min(1.0)) - incomplete_beta(a, b, lo.min(1.0))
+    }
+
+    fn harrell_davis(&self, xs: &Sample) -> Result<f64, Error> {
+        if xs.len() == 0 {
+            return Err(Error::EmptySample(
+                "cannot take a quantile of no values".to_string(),
+            ));
+        }
+        if let Some(ws) = &xs.weights {
+            let total = xs.total_weight();
+            let n = xs.size();
+            let mut cumulative = 0.0;
+            let mut sum = 0.0;
+            for (x, w) in xs.values.iter().zip(ws.iter()) {
+                let lo = cumulative / total;
+                cumulative += w;
+                sum += x * self.beta_weight(n, lo, cumulative / total);
+            }
+            return Ok(sum);
+        }
+        let n = xs.len();
+        let mut cache = self.weights.borrow_mut();
+        if cache.as_ref().is_none_or(|(m, _, _)| *m != n) {
+            let weights: Vec<f64> = (0..n)
+                .map(|i| self.beta_weight(n, i as f64 / n as f64, (i + 1) as f64 / n as f64))
+                .collect();
+            let first = weights
+                .iter()
+                .position(|w| *w > NEGLIGIBLE_WEIGHT)
+                .unwrap_or(0);
+            let last = weights
+                .iter()
+                .rposition(|w| *w > NEGLIGIBLE_WEIGHT)
+                .unwrap_or(n - 1);
+            *cache = Some((n, first, weights[first..=last].to_vec()));
+        }
+        let (_, first, weights) = cache.as_ref().expect("filled above");
+        Ok(xs.values[*first..]
+            .iter()
+            .zip(weights.iter())
+            .map(|(x, w)| x * w)
+            .sum())
+    }
+}
+
+impl Estimator for Quantile {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    // With only a few values beyond it a quantile behaves like the extreme.
+    fn sqrt_n_rate(&self, n: usize) -> bool {
+        const MIN_BEYOND: f64 = 3.0;
+        self.q.min(1.0 - self.q) * n as f64 >= MIN_BEYOND
+    }
+
+    fn estimate(&self, xs: &Sample) -> Result<f64, Error> {
+        // The Beta weights degenerate at the extremes, which remain the
+        // smallest and largest values.
+        match self.options {
+            QuantileOptions {
+                estimator: QuantileEstimator::HarrellDavis,
+                ..
+            } if self.q > 0.0 && self.q < 1.0 => self.harrell_davis(xs),
+            QuantileOptions {
+                method: QuantileMethod::Type7,
+                ..
+            } => xs.quantile(self.q),
+            // The other definitions count values, so they cannot honour
+            // weights such as the Bayesian bootstrap's.
+            QuantileOptions { method, .. } if xs.weights.is_none() => {
+                method.quantile(&xs.values, self.q)
+            }
+            QuantileOptions { .. } => Err(Error::Usage(
+                "--quantile-method and --discrete need unweighted samples".to_string(),
+            )),
+        }
+    }
+}
+
+// A level strictly between 0 and 1 for --quantile and --cte.
+pub fn parse_level(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(q) if q > 0.0 && q < 1.0 => Ok(q),
+        _ => Err(format!("expected a number between 0 and 1, got {:?}", s)),
+    }
+}
+
+// A positive bin width for --mode.
+pub fn parse_width(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(w) if w > 0.0 && w.is_finite() => Ok(w),
+        _ => Err(format!("expected a positive number, got {:?}", s)),
+    }
+}
+
+// Parses NAME:DIRECTION for --estimator, e.g. "p99:lower".
+pub fn parse_directed(s: &str) -> Result<(String, Direction), String> {
+    let (name, direction) = s
+        .rsplit_once(':')
+        .ok_or_else(|| format!("expected NAME:higher or NAME:lower, got {:?}", s))?;
+    let direction = Direction::from_str(direction.trim(), true)?;
+    Ok((name.trim().to_string(), direction))
+}
+
+// The level as a percentage for estimator names, e.g. 0.999 -> "99.9".
+pub fn percent_name(q: f64) -> String {
+    format_number(q * 100.0)
+}
+
+// The mean of the values above the given quantile, i.e. of the highest
+// (1 - level) of the total weight, counting the straddling value in part.
+pub fn tail_mean(xs: &Sample, level: f64) -> Result<f64, Error> {
+    if !(0.0..1.0).contains(&level) {
+        return Err(Error::InvalidQuantile(level));
+    }
+    if xs.len() == 0 {
+        return Err(Error::EmptySample(
+            "cannot take the tail mean of no values".to_string(),
+        ));
+    }
+    let lo = level * xs.total_weight();
+    let mut cumulative = 0.0;
+    let mut sum = 0.0;
+    let mut kept = 0.0;
+    for (i, x) in xs.values.iter().enumerate() {
+        let w = xs.weights.as_ref().map_or(1.0, |ws| ws[i]);
+        let start = cumulative;
+        cumulative += w;
+        let overlap = cumulative - start.max(lo);
+        if overlap > 0.0 {
+            sum += overlap * x;
+            kept += overlap;
+        }
+    }
+    Ok(sum / kept)
+}
+
+// Conditional tail expectation (expected shortfall): how bad the worst
+// (1 - level) of the values are on average.
+pub struct TailMean {
+    name: String,
+    level: f64,
+}
+
+impl TailMean {
+    pub fn new(level: f64) -> TailMean {
+        TailMean {
+            name: format!("cte{}", percent_name(level)),
+            level,
+        }
+    }
+}
+
+impl Estimator for TailMean {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn estimate(&self, xs: &Sample) -> Result<f64, Error> {
+        tail_mean(xs, self.level)
+    }
+}
+
+// The most common value, to a bin width: the centre of the bin, of those
+// starting at multiples of the width, that holds the most weight. For
+// multimodal distributions it tells which mode the values mostly fall in,
+// which neither the mean nor the median does.
+pub struct BinnedMode {
+    name: String,
+    width: f64,
+}
+
+impl BinnedMode {
+    pub fn new(width: f64) -> BinnedMode {
+        BinnedMode {
+            name: format!("mode{}", format_number(width)),
+            width,
+        }
+    }
+}
+
+impl Estimator for BinnedMode {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    // The mode of a density converges at the cube root rate.
+    fn sqrt_n_rate(&self, _n: usize) -> bool {
+        false
+    }
+
+    fn estimate(&self, xs: &Sample) -> Result<f64, Error> {
+        if xs.len() == 0 {
+            return Err(Error::EmptySample(
+                "cannot take the mode of no values".to_string(),
+            ));
+        }
+        // The values are sorted, so each bin is a run of them; the first
+        // of the fullest bins wins.
+        let mut best = (f64::NAN, 0.0);
+        let mut current = (f64::NAN, 0.0);
+        for (i, x) in xs.values.iter().enumerate() {
+            let w = xs.weights.as_ref().map_or(1.0, |ws| ws[i]);
+            let bin = (x / self.width).floor();
+            if bin == current.0 {
+                current.1 += w;
+            } else {
+                current = (bin, w);
+            }
+            if current.1 > best.1 {
+                best = current;
+            }
+        }
+        Ok((best.0 + 0.5) * self.width)
+    }
+}
+
+// The skewness and excess kurtosis, from the central moments with weights
+// as frequencies. A sample with no spread has no shape to speak of, and
+// gets zero for both.
+pub fn shape(xs: &Sample) -> (f64, f64) {
+    let mean = xs.mean();
+    let total = xs.total_weight();
+    let (mut m2, mut m3, mut m4) = (0.0, 0.0, 0.0);
+    for (i, x) in xs.values.iter().enumerate() {
+        let w = xs.weights.as_ref().map_or(1.0, |ws| ws[i]);
+        let d = x - mean;
+        m2 += w * d * d;
+        m3 += w * d * d * d;
+        m4 += w * d * d * d * d;
+    }
+    let (m2, m3, m4) = (m2 / total, m3 / total, m4 / total);
+    if m2 <= 0.0 {
+        return (0.0, 0.0);
+    }
+    (m3 / m2.powf(1.5), m4 / (m2 * m2) - 3.0)
+}
+
+pub struct Skewness;
+
+impl Estimator for Skewness {
+    fn name(&self) -> &str {
+        "skew"
+    }
+
+    fn requires_sorted(&self) -> bool {
+        false
+    }
+
+    fn estimate(&self, xs: &Sample) -> Result<f64, Error> {
+        Ok(shape(xs).0)
+    }
+}
+
+pub struct Kurtosis;
+
+impl Estimator for Kurtosis {
+    fn name(&self) -> &str {
+        "kurt"
+    }
+
+    fn requires_sorted(&self) -> bool {
+        false
+    }
+
+    fn estimate(&self, xs: &Sample) -> Result<f64, Error> {
+        Ok(shape(xs).1)
+    }
+}
+
+// Overrides the direction of another estimator.
+pub struct Directed {
+    pub inner: Box<dyn Estimator>,
+    pub direction: Direction,
+}
+
+impl Estimator for Directed {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    fn requires_sorted(&self) -> bool {
+        self.inner.requires_sorted()
+    }
+
+    fn sqrt_n_rate(&self, n: usize) -> bool {
+        self.inner.sqrt_n_rate(n)
+    }
+
+    fn estimate(&self, xs: &Sample) -> Result<f64, Error> {
+        self.inner.estimate(xs)
+    }
+}
+
+pub fn default_estimators(options: QuantileOptions) -> Vec<Box<dyn Estimator>> {
+    vec![
+        Box::new(Mean),
+        Box::new(Quantile::new("min", 0.0, options)),
+        Box::new(Quantile::new("p50", 0.5, options)),
+        Box::new(Quantile::new("p75", 0.75, options)),
+        Box::new(Quantile::new("p90", 0.9, options)),
+        Box::new(Quantile::new("p95", 0.95, options)),
+        Box::new(Quantile::new("p99", 0.99, options)),
+        Box::new(Quantile::new("max", 1.0, options)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quantile(method: QuantileMethod) -> Quantile {
+        let options = QuantileOptions {
+            method,
+            ..QuantileOptions::default()
+        };
+        Quantile::new("p50", 0.5, options)
+    }
+
+    #[test]
+    fn quantile_methods_follow_r() {
+        let xs = Sample::new(vec![1.0, 2.0, 3.0, 4.0]);
+        for (method, expected) in [
+            (QuantileMethod::Type1, 2.0),
+            (QuantileMethod::Type2, 2.5),
+            (QuantileMethod::Type5, 2.5),
+            (QuantileMethod::Type7, 2.5),
+        ] {
+            assert_eq!(quantile(method).estimate(&xs).unwrap(), expected);
+        }
+    }
+
+    // Weights such as the Bayesian bootstrap's must not be dropped in
+    // favour of an unweighted answer.
+    #[test]
+    fn weighted_samples_need_the_default_method() {
+        let xs = Sample::weighted(vec![(1.0, 0.1), (2.0, 0.1), (3.0, 0.8)]).unwrap();
+        // Between 2 and 3 rather than the unweighted median of 2.
+        let median = quantile(QuantileMethod::Type7).estimate(&xs).unwrap();
+        assert!((median - (2.0 + 0.175 / 0.45)).abs() < 1e-12, "{}", median);
+        assert!(matches!(
+            quantile(QuantileMethod::Type5).estimate(&xs),
+            Err(Error::Usage(_))
+        ));
+    }
+}
@@ -0,0 +1,505 @@
+// A small expression language for custom estimators, e.g.
+// "quantile(0.995) - quantile(0.5)", "trimmed_mean(0.05)" or "cte(0.99)".
+//
+//   expr    := term (('+' | '-') term)*
+//   term    := unary (('*' | '/') unary)*
+//   unary   := '-' unary | primary
+//   primary := number | name | name '(' [expr (',' expr)*] ')' | '(' expr ')'
+
+use crate::estimator::{shape, tail_mean, Estimator};
+use crate::sample::Sample;
+use crate::Error;
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(f64),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+// A parsed expression together with its source text, which names the
+// estimator.
+#[derive(Debug, Clone)]
+pub struct Expression {
+    pub source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Symbol(char),
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = s.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_digit()
+                    || chars[i] == '.'
+                    || chars[i] == 'e'
+                    || chars[i] == 'E'
+                    || ((chars[i] == '-' || chars[i] == '+')
+                        && (chars[i - 1] == 'e' || chars[i - 1] == 'E')))
+            {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let x = text
+                .parse()
+                .map_err(|_| format!("invalid number {:?}", text))?;
+            tokens.push(Token::Number(x));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+        } else if "+-*/(),".contains(c) {
+            tokens.push(Token::Symbol(c));
+            i += 1;
+        } else {
+            return Err(format!("unexpected character {:?}", c));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(format!("expected {:?}", symbol))
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol(c)) if *c == '+' || *c == '-' => *c,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol(c)) if *c == '*' || *c == '/' => *c,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.tokens.get(self.pos).cloned() {
+            Some(Token::Number(x)) => {
+                self.pos += 1;
+                Ok(Expr::Number(x))
+            }
+            Some(Token::Name(name)) => {
+                self.pos += 1;
+                let mut args = Vec::new();
+                if self.eat('(') && !self.eat(')') {
+                    loop {
+                        args.push(self.expr()?);
+                        if self.eat(')') {
+                            break;
+                        }
+                        if !self.eat(',') {
+                            return Err("expected ',' or ')'".to_string());
+                        }
+                    }
+                }
+                check_call(&name, args.len())?;
+                Ok(Expr::Call(name, args))
+            }
+            Some(Token::Symbol('(')) => {
+                self.pos += 1;
+                let e = self.expr()?;
+                self.expect(')')?;
+                Ok(e)
+            }
+            Some(t) => Err(format!("unexpected {:?}", t)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+// Functions and the number of arguments they take.
+const FUNCTIONS: &[(&str, usize)] = &[
+    ("count", 0),
+    ("sum", 0),
+    ("mean", 0),
+    ("median", 0),
+    ("min", 0),
+    ("max", 0),
+    ("stddev", 0),
+    ("var", 0),
+    ("iqr", 0),
+    ("skewness", 0),
+    ("kurtosis", 0),
+    ("quantile", 1),
+    ("trimmed_mean", 1),
+    ("cte", 1),
+    ("abs", 1),
+    ("sqrt", 1),
+    ("log", 1),
+    ("exp", 1),
+];
+
+fn check_call(name: &str, arity: usize) -> Result<(), String> {
+    match FUNCTIONS.iter().find(|(n, _)| *n == name) {
+        Some((_, expected)) if *expected == arity => Ok(()),
+        Some((_, expected)) => Err(format!(
+            "{} takes {} argument(s), got {}",
+            name, expected, arity
+        )),
+        None => Err(format!("unknown function {:?}", name)),
+    }
+}
+
+pub fn parse(s: &str) -> Result<Expression, String> {
+    let mut parser = Parser {
+        tokens: tokenize(s)?,
+        pos: 0,
+    };
+    let expr = parser.expr()?;
+    if parser.pos != parser.tokens.len() {
+        return Err(format!("unexpected {:?}", parser.tokens[parser.pos]));
+    }
+    Ok(Expression {
+        source: s.trim().to_string(),
+        expr,
+    })
+}
+
+fn weights(xs: &Sample) -> Vec<f64> {
+    match &xs.weights {
+        Some(ws) => ws.clone(),
+        None => vec![1.0; xs.len()],
+    }
+}
+
+// Weights are treated as frequencies, so unweighted samples get the usual
+// n - 1 denominator.
+fn variance(xs: &Sample) -> f64 {
+    let mean = xs.mean();
+    let total = xs.total_weight();
+    let ss: f64 = xs
+        .values
+        .iter()
+        .zip(weights(xs))
+        .map(|(x, w)| w * (x - mean) * (x - mean))
+        .sum();
+    ss / (total - 1.0)
+}
+
+// Mean after removing the given fraction of the total weight from each end.
+fn trimmed_mean(xs: &Sample, fraction: f64) -> Result<f64, Error> {
+    if !(0.0..0.5).contains(&fraction) {
+        return Err(Error::Usage(format!(
+            "trimmed_mean fraction {} is out of range [0, 0.5)",
+            fraction
+        )));
+    }
+    let total = xs.total_weight();
+    let lo = fraction * total;
+    let hi = total - lo;
+    let mut cumulative = 0.0;
+    let mut sum = 0.0;
+    let mut kept = 0.0;
+    for (x, w) in xs.values.iter().zip(weights(xs)) {
+        let start = cumulative;
+        cumulative += w;
+        let overlap = cumulative.min(hi) - start.max(lo);
+        if overlap > 0.0 {
+            sum += overlap * x;
+            kept += overlap;
+        }
+    }
+    Ok(sum / kept)
+}
+
+impl Expr {
+    fn eval(&self, xs: &Sample) -> Result<f64, Error> {
+        match self {
+            Expr::Number(x) => Ok(*x),
+            Expr::Neg(e) => Ok(-e.eval(xs)?),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(xs)?, b.eval(xs)?);
+                Ok(match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    _ => a / b,
+                })
+            }
+            Expr::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|a| a.eval(xs))
+                    .collect::<Result<Vec<f64>, Error>>()?;
+                match name.as_str() {
+                    "count" => Ok(xs.total_weight()),
+                    "sum" => Ok(xs.mean() * xs.total_weight()),
+                    "mean" => Ok(xs.mean()),
+                    "median" => xs.quantile(0.5),
+                    "min" => xs.quantile(0.0),
+                    "max" => xs.quantile(1.0),
+                    "stddev" => Ok(variance(xs).sqrt()),
+                    "var" => Ok(variance(xs)),
+                    "iqr" => Ok(xs.quantile(0.75)? - xs.quantile(0.25)?),
+                    "skewness" => Ok(shape(xs).0),
+                    "kurtosis" => Ok(shape(xs).1),
+                    "quantile" => xs.quantile(args[0]),
+                    "trimmed_mean" => trimmed_mean(xs, args[0]),
+                    "cte" => tail_mean(xs, args[0]),
+                    "abs" => Ok(args[0].abs()),
+                    "sqrt" => Ok(args[0].sqrt()),
+                    "log" => Ok(args[0].ln()),
+                    "exp" => Ok(args[0].exp()),
+                    _ => unreachable!("functions are checked when parsing"),
+                }
+            }
+        }
+    }
+}
+
+impl Estimator for Expression {
+    fn name(&self) -> &str {
+        &self.source
+    }
+
+    // Resamples can take an expression outside its domain, e.g. sqrt of a
+    // negative difference, so every result is checked.
+    fn estimate(&self, xs: &Sample) -> Result<f64, Error> {
+        let x = self.expr.eval(xs)?;
+        if !x.is_finite() {
+            return Err(Error::InvalidInput(format!(
+                "expression {:?} evaluates to {}",
+                self.source, x
+            )));
+        }
+        Ok(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, values: &[f64]) -> Result<f64, Error> {
+        parse(source)
+            .expect("valid expression")
+            .estimate(&Sample::new(values.to_vec()))
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-9 * b.abs().max(1.0)
+    }
+
+    const ONE_TO_FIVE: &[f64] = &[1.0, 2.0, 3.0, 4.0, 5.0];
+
+    #[test]
+    fn tokenizes_numbers_with_exponents() {
+        assert_eq!(
+            tokenize("1.5e3 + 2E-2").unwrap(),
+            vec![
+                Token::Number(1500.0),
+                Token::Symbol('+'),
+                Token::Number(0.02)
+            ]
+        );
+        assert_eq!(tokenize("1.2.3").unwrap_err(), "invalid number \"1.2.3\"");
+        assert_eq!(
+            tokenize("mean % 2").unwrap_err(),
+            "unexpected character '%'"
+        );
+    }
+
+    #[test]
+    fn multiplication_binds_tighter_than_addition() {
+        assert_eq!(eval("1 + 2 * 3", ONE_TO_FIVE).unwrap(), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", ONE_TO_FIVE).unwrap(), 9.0);
+        assert_eq!(eval("2 * 3 - 4 / 2", ONE_TO_FIVE).unwrap(), 4.0);
+    }
+
+    #[test]
+    fn operators_associate_to_the_left() {
+        assert_eq!(eval("10 - 4 - 3", ONE_TO_FIVE).unwrap(), 3.0);
+        assert_eq!(eval("16 / 4 / 2", ONE_TO_FIVE).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(eval("-3 + 5", ONE_TO_FIVE).unwrap(), 2.0);
+        assert_eq!(eval("--3", ONE_TO_FIVE).unwrap(), 3.0);
+        assert_eq!(eval("2 * -mean", ONE_TO_FIVE).unwrap(), -6.0);
+    }
+
+    #[test]
+    fn functions_check_their_arity() {
+        assert_eq!(
+            parse("quantile()").unwrap_err(),
+            "quantile takes 1 argument(s), got 0"
+        );
+        assert_eq!(
+            parse("mean(1)").unwrap_err(),
+            "mean takes 0 argument(s), got 1"
+        );
+        assert_eq!(
+            parse("cte(0.9, 0.99)").unwrap_err(),
+            "cte takes 1 argument(s), got 2"
+        );
+        // A name without parentheses is a call with no arguments.
+        assert!(parse("mean").is_ok());
+        assert!(parse("mean()").is_ok());
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert_eq!(
+            parse("frobnicate").unwrap_err(),
+            "unknown function \"frobnicate\""
+        );
+        assert_eq!(parse("1 +").unwrap_err(), "unexpected end of expression");
+        assert_eq!(parse("(1 + 2").unwrap_err(), "expected ')'");
+        assert_eq!(
+            parse("quantile(0.5 0.9)").unwrap_err(),
+            "expected ',' or ')'"
+        );
+        assert_eq!(parse("1 2").unwrap_err(), "unexpected Number(2.0)");
+        assert_eq!(parse(")").unwrap_err(), "unexpected Symbol(')')");
+        assert_eq!(parse("").unwrap_err(), "unexpected end of expression");
+    }
+
+    #[test]
+    fn names_the_estimator_by_its_trimmed_source() {
+        assert_eq!(parse(" max - min ").unwrap().name(), "max - min");
+    }
+
+    #[test]
+    fn sample_functions() {
+        let cases = [
+            ("count", 5.0),
+            ("sum", 15.0),
+            ("mean", 3.0),
+            ("median", 3.0),
+            ("min", 1.0),
+            ("max", 5.0),
+            ("var", 2.5),
+            ("stddev", 2.5f64.sqrt()),
+            ("iqr", 2.0),
+            ("skewness", 0.0),
+            ("quantile(0.25)", 2.0),
+            ("quantile(0.9)", 4.6),
+            ("trimmed_mean(0.2)", 3.0),
+        ];
+        for (source, expected) in cases {
+            let x = eval(source, ONE_TO_FIVE).unwrap();
+            assert!(
+                close(x, expected),
+                "{} = {}, expected {}",
+                source,
+                x,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn trimmed_mean_removes_weight_from_both_ends() {
+        let x = eval("trimmed_mean(0.1)", &[0.0, 1.0, 2.0, 3.0, 100.0]).unwrap();
+        // Half of each extreme value's weight is trimmed.
+        assert!(close(x, (0.5 * 0.0 + 1.0 + 2.0 + 3.0 + 0.5 * 100.0) / 4.0));
+        assert!(matches!(
+            eval("trimmed_mean(0.5)", ONE_TO_FIVE),
+            Err(Error::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn weighted_samples_count_weights_as_frequencies() {
+        let weighted = Sample::weighted(vec![(1.0, 2.0), (4.0, 1.0)]).unwrap();
+        let expr = |source: &str| parse(source).unwrap().estimate(&weighted).unwrap();
+        assert_eq!(expr("count"), 3.0);
+        assert_eq!(expr("sum"), 6.0);
+        assert_eq!(expr("mean"), 2.0);
+        assert!(close(expr("var"), 3.0));
+    }
+
+    #[test]
+    fn scalar_functions() {
+        assert_eq!(eval("abs(-2)", ONE_TO_FIVE).unwrap(), 2.0);
+        assert_eq!(eval("sqrt(16)", ONE_TO_FIVE).unwrap(), 4.0);
+        assert!(close(eval("log(exp(2))", ONE_TO_FIVE).unwrap(), 2.0));
+        assert!(close(
+            eval("quantile(0.99) - quantile(0.5)", ONE_TO_FIVE).unwrap(),
+            1.96
+        ));
+    }
+
+    #[test]
+    fn non_finite_results_are_errors() {
+        for source in ["sqrt(mean - 100)", "1 / (max - 5)", "log(min - 1)"] {
+            match eval(source, ONE_TO_FIVE) {
+                Err(Error::InvalidInput(msg)) => assert!(msg.contains(source), "{}", msg),
+                other => panic!("{}: expected an error, got {:?}", source, other),
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_range_quantiles_are_errors() {
+        assert!(matches!(
+            eval("quantile(1.5)", ONE_TO_FIVE),
+            Err(Error::InvalidQuantile(_))
+        ));
+        assert!(matches!(
+            eval("cte(1)", ONE_TO_FIVE),
+            Err(Error::InvalidQuantile(_))
+        ));
+    }
+}
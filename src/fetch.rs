@@ -0,0 +1,150 @@
+// Inputs that are not local files: samples given as http:// or https://
+// URLs, downloaded with curl, and parts of a --split-marker input. Both are
+// kept in temporary files that are removed when numcmp exits.
+
+use crate::Error;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub fn is_url(path: &Path) -> bool {
+    let s = path.to_string_lossy();
+    s.starts_with("http://") || s.starts_with("https://")
+}
+
+// The downloads so far, by URL, so that each URL is fetched once per run.
+#[cfg(feature = "http")]
+static DOWNLOADS: std::sync::Mutex<Vec<(PathBuf, PathBuf)>> = std::sync::Mutex::new(Vec::new());
+
+// Staged inputs by the name they are read under, with their files.
+static STAGED: std::sync::Mutex<Vec<(PathBuf, PathBuf)>> = std::sync::Mutex::new(Vec::new());
+
+// Creates a temporary file that only the user can read, under a random name
+// so that another user cannot create it first.
+fn temp_file() -> Result<(PathBuf, File), Error> {
+    loop {
+        let path = std::env::temp_dir().join(format!(
+            "numcmp-{}-{:016x}",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+// Makes `data` readable as an input named `name`.
+pub fn stage(name: &Path, data: &[u8]) -> Result<(), Error> {
+    let (path, mut file) = temp_file()?;
+    STAGED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push((name.to_path_buf(), path));
+    file.write_all(data)?;
+    Ok(())
+}
+
+// A local file holding the data at `path`: the path itself, for a URL a
+// downloaded copy, and for a staged input its temporary file.
+pub fn local(path: &Path) -> Result<Cow<'_, Path>, Error> {
+    let staged = STAGED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((_, file)) = staged.iter().find(|(name, _)| name == path) {
+        return Ok(Cow::Owned(file.clone()));
+    }
+    drop(staged);
+    if !is_url(path) {
+        return Ok(Cow::Borrowed(path));
+    }
+    download(path).map(Cow::Owned)
+}
+
+#[cfg(feature = "http")]
+fn download(url: &Path) -> Result<PathBuf, Error> {
+    let mut downloads = DOWNLOADS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((_, file)) = downloads.iter().find(|(u, _)| u == url) {
+        return Ok(file.clone());
+    }
+    let (file, out) = temp_file()?;
+    let output = std::process::Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", "--"])
+        .arg(url)
+        .stdout(out)
+        .output()
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&file);
+            Error::open(
+                url,
+                std::io::Error::new(e.kind(), format!("cannot run curl to download: {}", e)),
+            )
+        })?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&file);
+        return Err(Error::open(
+            url,
+            std::io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        ));
+    }
+    downloads.push((url.to_path_buf(), file.clone()));
+    Ok(file)
+}
+
+#[cfg(not(feature = "http"))]
+fn download(url: &Path) -> Result<PathBuf, Error> {
+    Err(Error::Usage(format!(
+        "cannot read {}: numcmp was built without the http feature",
+        url.display()
+    )))
+}
+
+// Removes the downloaded and staged files when dropped, including when
+// numcmp unwinds from a panic.
+pub struct Cleanup;
+
+impl Drop for Cleanup {
+    fn drop(&mut self) {
+        cleanup();
+    }
+}
+
+// Removes the downloaded and staged files.
+fn cleanup() {
+    for (_, file) in STAGED.lock().unwrap_or_else(|e| e.into_inner()).drain(..) {
+        let _ = std::fs::remove_file(file);
+    }
+    #[cfg(feature = "http")]
+    for (_, file) in DOWNLOADS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .drain(..)
+    {
+        let _ = std::fs::remove_file(file);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temporary_files_are_new_and_private() {
+        let (a, _) = temp_file().unwrap();
+        let (b, _) = temp_file().unwrap();
+        assert_ne!(a, b);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&a).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_file(a).unwrap();
+        std::fs::remove_file(b).unwrap();
+    }
+}
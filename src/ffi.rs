@@ -0,0 +1,200 @@
+// C interface for benchmark harnesses in other languages, declared in
+// include/numcmp.h. Results come back either as a JSON string or as an
+// array of structs; both are owned by numcmp and must be released with the
+// matching free function. On failure the message is available from
+// numcmp_last_error on the same thread.
+
+use crate::api;
+use crate::Error;
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::ptr;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(e: &Error) {
+    let message = CString::new(e.to_string().replace('\0', " ")).expect("no NUL bytes");
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+#[repr(C)]
+pub struct NumcmpEstimate {
+    name: *mut c_char,
+    baseline: f64,
+    target: f64,
+    p_value: f64,
+    verdict: c_int,
+}
+
+#[repr(C)]
+pub struct NumcmpResults {
+    estimates: *mut NumcmpEstimate,
+    len: usize,
+}
+
+// The arguments shared by both entry points, checked and converted.
+struct Inputs<'a> {
+    baseline: &'a [f64],
+    target: &'a [f64],
+    options: Vec<&'a str>,
+}
+
+unsafe fn values<'a>(ptr: *const f64, len: usize, role: &str) -> Result<&'a [f64], Error> {
+    match (ptr.is_null(), len) {
+        (_, 0) => Ok(&[]),
+        (true, _) => Err(Error::Usage(format!("{} is NULL", role))),
+        (false, len) => Ok(std::slice::from_raw_parts(ptr, len)),
+    }
+}
+
+unsafe fn inputs<'a>(
+    baseline: *const f64,
+    baseline_len: usize,
+    target: *const f64,
+    target_len: usize,
+    options: *const *const c_char,
+    options_len: usize,
+) -> Result<Inputs<'a>, Error> {
+    let mut parsed = Vec::with_capacity(options_len);
+    if options_len > 0 {
+        if options.is_null() {
+            return Err(Error::Usage("options is NULL".to_string()));
+        }
+        for &option in std::slice::from_raw_parts(options, options_len) {
+            if option.is_null() {
+                return Err(Error::Usage("option is NULL".to_string()));
+            }
+            let option = CStr::from_ptr(option)
+                .to_str()
+                .map_err(|_| Error::Usage("option is not UTF-8".to_string()))?;
+            parsed.push(option);
+        }
+    }
+    Ok(Inputs {
+        baseline: values(baseline, baseline_len, "baseline")?,
+        target: values(target, target_len, "target")?,
+        options: parsed,
+    })
+}
+
+/// # Safety
+/// The arrays must hold the given number of elements, and each option must
+/// be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn numcmp_compare_json(
+    baseline: *const f64,
+    baseline_len: usize,
+    target: *const f64,
+    target_len: usize,
+    options: *const *const c_char,
+    options_len: usize,
+) -> *mut c_char {
+    let result = api::catch_panic(|| {
+        let i = inputs(
+            baseline,
+            baseline_len,
+            target,
+            target_len,
+            options,
+            options_len,
+        )?;
+        let json = api::compare_json(i.baseline, i.target, &i.options)?;
+        Ok(CString::new(json).expect("JSON has no NUL bytes"))
+    });
+    match result {
+        Ok(json) => json.into_raw(),
+        Err(e) => {
+            set_error(&e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+/// As numcmp_compare_json, and `out` must point to a NumcmpResults.
+#[no_mangle]
+pub unsafe extern "C" fn numcmp_compare(
+    baseline: *const f64,
+    baseline_len: usize,
+    target: *const f64,
+    target_len: usize,
+    options: *const *const c_char,
+    options_len: usize,
+    out: *mut NumcmpResults,
+) -> c_int {
+    let result = api::catch_panic(|| {
+        let i = inputs(
+            baseline,
+            baseline_len,
+            target,
+            target_len,
+            options,
+            options_len,
+        )?;
+        api::compare(i.baseline, i.target, &i.options)
+    });
+    let estimates = match result {
+        Ok(estimates) => estimates,
+        Err(e) => {
+            set_error(&e);
+            return e.exit_code();
+        }
+    };
+    let estimates: Box<[NumcmpEstimate]> = estimates
+        .into_iter()
+        .map(|e| NumcmpEstimate {
+            name: CString::new(e.name.replace('\0', " "))
+                .expect("no NUL bytes")
+                .into_raw(),
+            baseline: e.baseline,
+            target: e.target,
+            p_value: e.p_value,
+            verdict: e.verdict,
+        })
+        .collect();
+    let len = estimates.len();
+    *out = NumcmpResults {
+        estimates: Box::into_raw(estimates) as *mut NumcmpEstimate,
+        len,
+    };
+    0
+}
+
+// The message of the last error on this thread, valid until the next call;
+// NULL if there was none.
+#[no_mangle]
+pub extern "C" fn numcmp_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+/// # Safety
+/// `s` must come from numcmp_compare_json, or be NULL.
+#[no_mangle]
+pub unsafe extern "C" fn numcmp_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// # Safety
+/// `results` must have been filled in by numcmp_compare, or be NULL.
+#[no_mangle]
+pub unsafe extern "C" fn numcmp_results_free(results: *mut NumcmpResults) {
+    if results.is_null() || (*results).estimates.is_null() {
+        return;
+    }
+    let estimates = std::slice::from_raw_parts_mut((*results).estimates, (*results).len);
+    let estimates = Box::from_raw(estimates as *mut [NumcmpEstimate]);
+    for estimate in estimates.iter() {
+        drop(CString::from_raw(estimate.name));
+    }
+    *results = NumcmpResults {
+        estimates: ptr::null_mut(),
+        len: 0,
+    };
+}
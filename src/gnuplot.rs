@@ -0,0 +1,178 @@
+// Exports plot data files plus a gnuplot script that reproduces the plots.
+
+use crate::metadata::Labels;
+use crate::plots::{file_stem, HISTOGRAM_BINS};
+use crate::units::Units;
+use crate::{Error, EstimatorResult};
+use std::fmt::Write as _;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+fn with_suffix(prefix: &Path, suffix: &str) -> PathBuf {
+    let mut s = prefix.as_os_str().to_owned();
+    s.push(suffix);
+    PathBuf::from(s)
+}
+
+// The script refers to its data files by name, relative to its own directory.
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn write_sample(path: &Path, sorted: &[f64]) -> Result<(), Error> {
+    let mut out = BufWriter::new(std::fs::File::create(path)?);
+    writeln!(out, "# value\tecdf")?;
+    let n = sorted.len() as f64;
+    for (i, x) in sorted.iter().enumerate() {
+        writeln!(out, "{}\t{}", x, ((i + 1) as f64) / n)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn write_bootstrap(
+    path: &Path,
+    results: &[EstimatorResult],
+    distributions: &[Vec<f64>],
+) -> Result<(), Error> {
+    let mut out = BufWriter::new(std::fs::File::create(path)?);
+    let names: Vec<&str> = results.iter().map(|r| r.name.as_str()).collect();
+    writeln!(out, "# {}", names.join("\t"))?;
+    let rows = distributions.iter().map(|d| d.len()).min().unwrap_or(0);
+    for i in 0..rows {
+        let row: Vec<String> = distributions.iter().map(|d| d[i].to_string()).collect();
+        writeln!(out, "{}", row.join("\t"))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn bin_width(values: &[&[f64]]) -> f64 {
+    let lo = values
+        .iter()
+        .flat_map(|v| v.iter())
+        .fold(f64::INFINITY, |a, b| a.min(*b));
+    let hi = values
+        .iter()
+        .flat_map(|v| v.iter())
+        .fold(f64::NEG_INFINITY, |a, b| a.max(*b));
+    if hi > lo {
+        (hi - lo) / (HISTOGRAM_BINS as f64)
+    } else {
+        1.0
+    }
+}
+
+pub fn export(
+    prefix: &Path,
+    baseline: &[f64],
+    target: &[f64],
+    results: &[EstimatorResult],
+    distributions: &[Vec<f64>],
+    units: &Units,
+    labels: &Labels,
+) -> Result<(), Error> {
+    let baseline_path = with_suffix(prefix, "-baseline.dat");
+    let target_path = with_suffix(prefix, "-target.dat");
+    let bootstrap_path = with_suffix(prefix, "-bootstrap.dat");
+    let script_path = with_suffix(prefix, ".gp");
+    let stem = file_name(prefix);
+
+    write_sample(&baseline_path, baseline)?;
+    write_sample(&target_path, target)?;
+    write_bootstrap(&bootstrap_path, results, distributions)?;
+
+    let baseline_file = quote(&file_name(&baseline_path));
+    let target_file = quote(&file_name(&target_path));
+    let bootstrap_file = quote(&file_name(&bootstrap_path));
+
+    let mut gp = String::new();
+    let _ = writeln!(gp, "# Generated by numcmp.");
+    let _ = writeln!(
+        gp,
+        "# Run from this directory: gnuplot {}",
+        file_name(&script_path)
+    );
+    let _ = writeln!(gp, "set terminal svg size 640,320 dynamic");
+    let _ = writeln!(gp, "set style fill transparent solid 0.35");
+    let _ = writeln!(gp, "set key top right");
+    if let Some(unit) = &units.unit {
+        let _ = writeln!(gp, "set xlabel {}", quote(unit));
+    }
+    let _ = writeln!(gp);
+
+    let _ = writeln!(gp, "binwidth = {}", bin_width(&[baseline, target]));
+    let _ = writeln!(gp, "bin(x) = binwidth * floor(x / binwidth)");
+    let _ = writeln!(gp, "set output {}", quote(&format!("{}-density.svg", stem)));
+    let _ = writeln!(gp, "set title 'Density'");
+    let _ = writeln!(
+        gp,
+        "plot {} using (bin($1)):(1.0 / ({} * binwidth)) smooth frequency with boxes title {}, \\",
+        baseline_file,
+        baseline.len(),
+        quote(&labels.baseline)
+    );
+    let _ = writeln!(
+        gp,
+        "     {} using (bin($1)):(1.0 / ({} * binwidth)) smooth frequency with boxes title {}",
+        target_file,
+        target.len(),
+        quote(&labels.target)
+    );
+    let _ = writeln!(gp);
+
+    let _ = writeln!(gp, "set output {}", quote(&format!("{}-ecdf.svg", stem)));
+    let _ = writeln!(gp, "set title 'ECDF'");
+    let _ = writeln!(gp, "set yrange [0:1]");
+    let _ = writeln!(
+        gp,
+        "plot {} using 1:2 with steps title {}, {} using 1:2 with steps title {}",
+        baseline_file,
+        quote(&labels.baseline),
+        target_file,
+        quote(&labels.target)
+    );
+    let _ = writeln!(gp, "set autoscale y");
+
+    for (column, (result, sims)) in results.iter().zip(distributions.iter()).enumerate() {
+        let _ = writeln!(gp);
+        let _ = writeln!(gp, "binwidth = {}", bin_width(&[sims]));
+        let _ = writeln!(
+            gp,
+            "set output {}",
+            quote(&format!(
+                "{}-bootstrap-{}.svg",
+                stem,
+                file_stem(&result.name)
+            ))
+        );
+        let _ = writeln!(gp, "set title {}", quote(&result.name));
+        let _ = writeln!(
+            gp,
+            "set arrow 1 from first {}, graph 0 to first {}, graph 1 nohead dashtype 2 linecolor rgb '#2ca02c'",
+            result.full_baseline_estimator, result.full_baseline_estimator
+        );
+        let _ = writeln!(
+            gp,
+            "set arrow 2 from first {}, graph 0 to first {}, graph 1 nohead dashtype 2 linecolor rgb '#ff7f0e'",
+            result.target_estimator, result.target_estimator
+        );
+        let _ = writeln!(
+            gp,
+            "plot {} using (bin(${})):(1.0 / ({} * binwidth)) smooth frequency with boxes title 'simulated'",
+            bootstrap_file,
+            column + 1,
+            sims.len().max(1)
+        );
+        let _ = writeln!(gp, "unset arrow");
+    }
+
+    std::fs::write(script_path, gp)?;
+    Ok(())
+}
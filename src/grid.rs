@@ -0,0 +1,90 @@
+// A grid of percentiles of both samples for --percentile-grid, written as
+// CSV or JSON for dashboards that plot percentile curves.
+
+use crate::estimator::{Estimator, Quantile, QuantileOptions};
+use crate::sample::Sample;
+use crate::table::{Layout, Table};
+use crate::units::Units;
+use crate::{json, Error};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// A percentage from 0 to 100 for --percentile-grid.
+pub fn parse_percent(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(p) if (0.0..=100.0).contains(&p) => Ok(p),
+        _ => Err(format!("expected a percentage from 0 to 100, got {:?}", s)),
+    }
+}
+
+pub struct GridPoint {
+    pub percent: f64,
+    pub baseline: f64,
+    pub target: f64,
+}
+
+pub fn percentile_grid(
+    baseline: &Sample,
+    target: &Sample,
+    percents: &[f64],
+    options: QuantileOptions,
+) -> Result<Vec<GridPoint>, Error> {
+    percents
+        .iter()
+        .map(|percent| {
+            let quantile = Quantile::new("", percent / 100.0, options);
+            Ok(GridPoint {
+                percent: *percent,
+                baseline: quantile.estimate(baseline)?,
+                target: quantile.estimate(target)?,
+            })
+        })
+        .collect()
+}
+
+pub fn table(grid: &[GridPoint], units: &Units, labels: (&str, &str), layout: Layout) -> String {
+    let mut table = Table::new(&["percentile", labels.0, labels.1, "delta"]);
+    for p in grid.iter() {
+        table.row(vec![
+            p.percent.to_string(),
+            units.format(p.baseline),
+            units.format(p.target),
+            units.format(p.target - p.baseline),
+        ]);
+    }
+    table.render(layout)
+}
+
+pub fn write_csv(path: &Path, grid: &[GridPoint]) -> Result<(), Error> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "percentile,baseline,target,delta")?;
+    for p in grid.iter() {
+        writeln!(
+            out,
+            "{},{},{},{}",
+            p.percent,
+            p.baseline,
+            p.target,
+            p.target - p.baseline
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+pub fn write_json(path: &Path, grid: &[GridPoint]) -> Result<(), Error> {
+    let items: Vec<String> = grid
+        .iter()
+        .map(|p| {
+            json::object(&[
+                ("percentile", json::number(p.percent)),
+                ("baseline", json::number(p.baseline)),
+                ("target", json::number(p.target)),
+                ("delta", json::number(p.target - p.baseline)),
+            ])
+        })
+        .collect();
+    std::fs::write(path, json::array(&items) + "\n")?;
+    Ok(())
+}
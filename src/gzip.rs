@@ -0,0 +1,469 @@
+// Decompression of gzip files (RFC 1952) and the DEFLATE streams inside
+// them (RFC 1951), for reading compressed measurement dumps.
+
+// A message describing why the data could not be decompressed.
+type Result<T> = std::result::Result<T, String>;
+
+pub const MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// Base lengths and extra bits of length codes 257..285.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+// Base distances and extra bits of distance codes 0..29.
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// The order in which code length code lengths are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const CORRUPT: &str = "corrupt gzip data";
+
+// Reads bits least significant first, as DEFLATE packs them.
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u32,
+}
+
+impl Bits<'_> {
+    fn bits(&mut self, n: u32) -> Result<u32> {
+        let mut value = 0;
+        for i in 0..n {
+            let byte = *self.data.get(self.pos).ok_or(CORRUPT)?;
+            value |= (((byte >> self.bit) & 1) as u32) << i;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+        Ok(value)
+    }
+
+    fn align(&mut self) {
+        if self.bit > 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+}
+
+// A canonical Huffman code, as counts of codes per length and the symbols
+// in code order.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; 16];
+        for l in lengths {
+            counts[*l as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, l) in lengths.iter().enumerate() {
+            if *l > 0 {
+                symbols[offsets[*l as usize] as usize] = symbol as u16;
+                offsets[*l as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= bits.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(CORRUPT.to_string())
+    }
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_codes(bits: &mut Bits) -> Result<(Huffman, Huffman)> {
+    let nlen = bits.bits(5)? as usize + 257;
+    let ndist = bits.bits(5)? as usize + 1;
+    let ncode = bits.bits(4)? as usize + 4;
+    let mut code_lengths = [0u8; 19];
+    for i in CODE_LENGTH_ORDER.iter().take(ncode) {
+        code_lengths[*i] = bits.bits(3)? as u8;
+    }
+    let code = Huffman::new(&code_lengths);
+    let mut lengths = Vec::with_capacity(nlen + ndist);
+    while lengths.len() < nlen + ndist {
+        let (value, repeat) = match code.decode(bits)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => (*lengths.last().ok_or(CORRUPT)?, 3 + bits.bits(2)?),
+            17 => (0, 3 + bits.bits(3)?),
+            18 => (0, 11 + bits.bits(7)?),
+            _ => return Err(CORRUPT.to_string()),
+        };
+        for _ in 0..repeat {
+            lengths.push(value);
+        }
+    }
+    if lengths.len() > nlen + ndist {
+        return Err(CORRUPT.to_string());
+    }
+    Ok((
+        Huffman::new(&lengths[..nlen]),
+        Huffman::new(&lengths[nlen..]),
+    ))
+}
+
+fn inflate_block(
+    bits: &mut Bits,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<()> {
+    loop {
+        let symbol = literals.decode(bits)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let i = symbol - 257;
+                let length = LENGTH_BASE[i] as usize + bits.bits(LENGTH_EXTRA[i] as u32)? as usize;
+                let d = distances.decode(bits)? as usize;
+                if d >= 30 {
+                    return Err(CORRUPT.to_string());
+                }
+                let distance =
+                    DISTANCE_BASE[d] as usize + bits.bits(DISTANCE_EXTRA[d] as u32)? as usize;
+                if distance > out.len() {
+                    return Err(CORRUPT.to_string());
+                }
+                let start = out.len() - distance;
+                for k in 0..length {
+                    out.push(out[start + k]);
+                }
+            }
+            _ => return Err(CORRUPT.to_string()),
+        }
+    }
+}
+
+// Inflates one DEFLATE stream, returning the position just after it.
+fn inflate(data: &[u8], out: &mut Vec<u8>) -> Result<usize> {
+    let mut bits = Bits {
+        data,
+        pos: 0,
+        bit: 0,
+    };
+    loop {
+        let last = bits.bits(1)? == 1;
+        match bits.bits(2)? {
+            0 => {
+                bits.align();
+                let header = data.get(bits.pos..bits.pos + 4).ok_or(CORRUPT)?;
+                let len = u16::from_le_bytes([header[0], header[1]]) as usize;
+                let nlen = u16::from_le_bytes([header[2], header[3]]) as usize;
+                if len != !nlen & 0xffff {
+                    return Err(CORRUPT.to_string());
+                }
+                bits.pos += 4;
+                out.extend_from_slice(data.get(bits.pos..bits.pos + len).ok_or(CORRUPT)?);
+                bits.pos += len;
+            }
+            1 => {
+                let (literals, distances) = fixed_codes();
+                inflate_block(&mut bits, out, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(&mut bits)?;
+                inflate_block(&mut bits, out, &literals, &distances)?;
+            }
+            _ => return Err(CORRUPT.to_string()),
+        }
+        if last {
+            bits.align();
+            return Ok(bits.pos);
+        }
+    }
+}
+
+// CRC-32 as used by gzip, to check the decompressed data.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+// Decompresses a gzip file, which may hold several members back to back.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    const FHCRC: u8 = 2;
+    const FEXTRA: u8 = 4;
+    const FNAME: u8 = 8;
+    const FCOMMENT: u8 = 16;
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let member = pos;
+        let header = data.get(pos..pos + 10).ok_or(CORRUPT)?;
+        if header[..2] != MAGIC || header[2] != 8 {
+            return Err("not gzip data compressed with DEFLATE".to_string());
+        }
+        let flags = header[3];
+        pos += 10;
+        if flags & FEXTRA != 0 {
+            let len = data.get(pos..pos + 2).ok_or(CORRUPT)?;
+            pos += 2 + u16::from_le_bytes([len[0], len[1]]) as usize;
+        }
+        for flag in [FNAME, FCOMMENT] {
+            if flags & flag != 0 {
+                let end = data[pos.min(data.len())..]
+                    .iter()
+                    .position(|b| *b == 0)
+                    .ok_or(CORRUPT)?;
+                pos += end + 1;
+            }
+        }
+        // The header checksum is the low half of the CRC-32 of the header.
+        if flags & FHCRC != 0 {
+            let crc = data.get(pos..pos + 2).ok_or(CORRUPT)?;
+            if u16::from_le_bytes([crc[0], crc[1]]) as u32 != crc32(&data[member..pos]) & 0xffff {
+                return Err("gzip header checksum mismatch".to_string());
+            }
+            pos += 2;
+        }
+        let start = out.len();
+        pos += inflate(data.get(pos..).ok_or(CORRUPT)?, &mut out)?;
+        let trailer = data.get(pos..pos + 8).ok_or(CORRUPT)?;
+        let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        if crc != crc32(&out[start..]) {
+            return Err("gzip checksum mismatch".to_string());
+        }
+        pos += 8;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "hello hello hello gzip\n" as one fixed Huffman block.
+    const FIXED: &[u8] = &[
+        0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x22, 0xd3, 0xab, 0x32, 0x0b, 0xb8, 0x00,
+    ];
+
+    // The text of dynamic_text() as one dynamic Huffman block.
+    const DYNAMIC: &[u8] = &[
+        0x0d, 0x8f, 0xb9, 0x0d, 0x00, 0x41, 0x10, 0xc2, 0x72, 0x8a, 0x41, 0xcb, 0x30, 0x6f, 0xff,
+        0x8d, 0xdd, 0xa5, 0x96, 0xb0, 0xf0, 0xe3, 0x83, 0x78, 0x3a, 0x04, 0xd7, 0x0b, 0x73, 0x6a,
+        0x90, 0xec, 0x69, 0x14, 0xeb, 0x0a, 0xcd, 0x52, 0x62, 0x98, 0x36, 0x96, 0xae, 0xc0, 0x31,
+        0x46, 0xd0, 0xa3, 0xee, 0x1f, 0x8b, 0x7a, 0x07, 0x05, 0x63, 0x21, 0xf3, 0x72, 0xa0, 0xe4,
+        0x76, 0x43, 0xc5, 0xd9, 0x82, 0x9a, 0xf3, 0x12, 0x1a, 0x76, 0x18, 0x5a, 0x56, 0x06, 0x74,
+        0xcc, 0x16, 0xe2, 0xd1, 0xfb, 0x10, 0x62, 0xdc, 0x7f, 0xe1, 0x97, 0x68, 0x11, 0xa6, 0x3c,
+        0x88, 0x64, 0x35, 0xa2, 0x78, 0x53, 0x88, 0xe6, 0x5e, 0x22, 0x86, 0x2b, 0x23, 0x96, 0xe3,
+        0x40, 0x1c, 0xbb, 0x04, 0x3f, 0xd6, 0x3c, 0x58, 0xcc, 0x3d, 0x38, 0x98, 0xef, 0x0f, 0x31,
+        0x1d, 0x03, 0x27, 0x23, 0x1b, 0x2e, 0xaa, 0x0b, 0xfe, 0x2d, 0x09, 0x0f, 0x0d, 0x2f, 0x2f,
+        0x02, 0xbe, 0x9f, 0x08, 0x1f,
+    ];
+
+    fn dynamic_text() -> Vec<u8> {
+        (0..40)
+            .map(|i| format!("{}.{}\n", i, i * 7919 % 1000))
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    // Uncompressed data as stored blocks of at most `block` bytes.
+    fn stored(data: &[u8], block: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut chunks: Vec<&[u8]> = data.chunks(block).collect();
+        if chunks.is_empty() {
+            chunks.push(data);
+        }
+        for (i, chunk) in chunks.iter().enumerate() {
+            out.push((i + 1 == chunks.len()) as u8);
+            out.extend((chunk.len() as u16).to_le_bytes());
+            out.extend((!(chunk.len() as u16)).to_le_bytes());
+            out.extend_from_slice(chunk);
+        }
+        out
+    }
+
+    // A gzip member around a DEFLATE stream, with the optional header fields
+    // given as (flag, bytes).
+    fn member(deflate: &[u8], text: &[u8], fields: &[(u8, &[u8])], hcrc: bool) -> Vec<u8> {
+        let flags = fields.iter().map(|f| f.0).sum::<u8>() | if hcrc { 2 } else { 0 };
+        let mut out = vec![0x1f, 0x8b, 8, flags, 0, 0, 0, 0, 0, 3];
+        for (_, bytes) in fields {
+            out.extend_from_slice(bytes);
+        }
+        if hcrc {
+            out.extend((crc32(&out) as u16).to_le_bytes());
+        }
+        out.extend_from_slice(deflate);
+        out.extend(crc32(text).to_le_bytes());
+        out.extend((text.len() as u32).to_le_bytes());
+        out
+    }
+
+    fn gzip(deflate: &[u8], text: &[u8]) -> Vec<u8> {
+        member(deflate, text, &[], false)
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn stored_blocks() {
+        let text = b"1.5\n2.25\n3.125\n";
+        assert_eq!(decompress(&gzip(&stored(text, 1000), text)).unwrap(), text);
+        assert_eq!(decompress(&gzip(&stored(text, 4), text)).unwrap(), text);
+        assert_eq!(decompress(&gzip(&stored(b"", 1), b"")).unwrap(), b"");
+        let mut mismatched = stored(text, 1000);
+        mismatched[3] ^= 1;
+        assert_eq!(
+            decompress(&gzip(&mismatched, text)).unwrap_err(),
+            "corrupt gzip data"
+        );
+    }
+
+    #[test]
+    fn fixed_block() {
+        let text = b"hello hello hello gzip\n";
+        assert_eq!(decompress(&gzip(FIXED, text)).unwrap(), text);
+    }
+
+    #[test]
+    fn dynamic_block() {
+        let text = dynamic_text();
+        assert_eq!(decompress(&gzip(DYNAMIC, &text)).unwrap(), text);
+    }
+
+    #[test]
+    fn members_are_concatenated() {
+        let text = dynamic_text();
+        let mut data = gzip(FIXED, b"hello hello hello gzip\n");
+        data.extend(gzip(DYNAMIC, &text));
+        data.extend(gzip(&stored(b"end\n", 100), b"end\n"));
+        let mut expected = b"hello hello hello gzip\n".to_vec();
+        expected.extend(&text);
+        expected.extend(b"end\n");
+        assert_eq!(decompress(&data).unwrap(), expected);
+    }
+
+    #[test]
+    fn optional_header_fields() {
+        let text = b"hello hello hello gzip\n";
+        let extra: &[u8] = &[4, 0, b'A', b'B', 0, 0];
+        let fields = [
+            (4, extra),
+            (8, &b"data.txt\0"[..]),
+            (16, &b"a comment\0"[..]),
+        ];
+        for n in 0..=fields.len() {
+            for hcrc in [false, true] {
+                let data = member(FIXED, text, &fields[..n], hcrc);
+                assert_eq!(decompress(&data).unwrap(), text, "{} fields", n);
+            }
+        }
+        let mut data = member(FIXED, text, &fields, true);
+        data[12] ^= 1;
+        assert_eq!(
+            decompress(&data).unwrap_err(),
+            "gzip header checksum mismatch"
+        );
+    }
+
+    #[test]
+    fn rejects_other_data() {
+        assert_eq!(
+            decompress(b"1.5\n2.5\n3.5\n").unwrap_err(),
+            "not gzip data compressed with DEFLATE"
+        );
+        let mut data = gzip(FIXED, b"hello hello hello gzip\n");
+        data[2] = 0;
+        assert!(decompress(&data).is_err());
+        let mut data = gzip(FIXED, b"hello hello hello gzip\n");
+        let n = data.len();
+        data[n - 8] ^= 1;
+        assert_eq!(decompress(&data).unwrap_err(), "gzip checksum mismatch");
+        // Block type 3 is reserved.
+        assert!(decompress(&gzip(&[0x07], b"")).is_err());
+    }
+
+    #[test]
+    fn truncated_input_is_an_error() {
+        let text = dynamic_text();
+        let extra: &[u8] = &[2, 0, 1, 2];
+        let fields = [(4, extra), (8, &b"name\0"[..])];
+        for data in [
+            gzip(DYNAMIC, &text),
+            gzip(&stored(&text, 100), &text),
+            member(FIXED, b"hello hello hello gzip\n", &fields, true),
+        ] {
+            for n in 1..data.len() {
+                assert!(decompress(&data[..n]).is_err(), "prefix of {} bytes", n);
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_input_does_not_panic() {
+        let text = dynamic_text();
+        let data = gzip(DYNAMIC, &text);
+        for i in 0..data.len() {
+            for bit in 0..8 {
+                let mut corrupt = data.clone();
+                corrupt[i] ^= 1 << bit;
+                let _ = decompress(&corrupt);
+            }
+        }
+        // Pseudo-random DEFLATE streams behind a valid header.
+        let mut state = 1u32;
+        for _ in 0..2000 {
+            let mut data = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3];
+            for _ in 0..64 {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                data.push((state >> 16) as u8);
+            }
+            assert!(decompress(&data).is_err());
+        }
+    }
+}
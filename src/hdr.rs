@@ -0,0 +1,63 @@
+// A sketch in the manner of HDR histograms for --hdr-digits: values are
+// counted in buckets whose width is a fixed fraction of their magnitude, so
+// every value is known to the given number of significant decimal digits
+// while memory depends only on the range of the values, not their number.
+
+use std::collections::BTreeMap;
+
+pub struct Histogram {
+    digits: u32,
+    // Counts by bucket: the sign, the decimal exponent and the leading
+    // `digits` digits of the values in it.
+    counts: BTreeMap<(bool, i32, i64), f64>,
+    // Zeros, infinities and NaN, which have no magnitude to bucket by, are
+    // kept exactly.
+    exact: Vec<(f64, f64)>,
+}
+
+impl Histogram {
+    pub fn new(digits: u32) -> Histogram {
+        Histogram {
+            digits,
+            counts: BTreeMap::new(),
+            exact: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, x: f64, weight: f64) {
+        if x == 0.0 || !x.is_finite() {
+            match self.exact.iter_mut().find(|(y, _)| y.total_cmp(&x).is_eq()) {
+                Some((_, count)) => *count += weight,
+                None => self.exact.push((x, weight)),
+            }
+            return;
+        }
+        let mut exponent = x.abs().log10().floor() as i32 - (self.digits as i32 - 1);
+        let mut leading = (x.abs() / 10f64.powi(exponent)).floor() as i64;
+        // log10 rounds, so the leading digits can come out one short or
+        // one over.
+        let limit = 10i64.pow(self.digits);
+        if leading >= limit {
+            leading /= 10;
+            exponent += 1;
+        } else if leading < limit / 10 {
+            leading = (x.abs() / 10f64.powi(exponent - 1)).floor() as i64;
+            exponent -= 1;
+        }
+        *self
+            .counts
+            .entry((x < 0.0, exponent, leading))
+            .or_insert(0.0) += weight;
+    }
+
+    // Each non-empty bucket as the midpoint of its range and its count.
+    pub fn buckets(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.counts
+            .iter()
+            .map(|((negative, exponent, leading), count)| {
+                let x = (*leading as f64 + 0.5) * 10f64.powi(*exponent);
+                (if *negative { -x } else { x }, *count)
+            })
+            .chain(self.exact.iter().copied())
+    }
+}
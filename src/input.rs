@@ -0,0 +1,1289 @@
+// Reading samples from plain text and CSV files.
+
+#[cfg(feature = "arrow")]
+use crate::arrow;
+use crate::fetch;
+use crate::gzip;
+use crate::hdr;
+use crate::json;
+#[cfg(feature = "parquet")]
+use crate::parquet;
+use crate::regex::Regex;
+use crate::sample::Sample;
+use crate::tdigest::TDigest;
+use crate::Error;
+use clap::{Args, ValueEnum};
+use std::fs::File;
+use std::io::{BufRead, Cursor, Read};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Debug, Clone, Args)]
+pub struct InputArgs {
+    /// Read CSV input (with a header row) and take values from this column
+    #[arg(long = "column", value_name = "NAME")]
+    pub column: Option<String>,
+
+    /// CSV column holding a weight for each value
+    #[arg(long = "weight-column", value_name = "NAME", requires = "column")]
+    pub weight_column: Option<String>,
+
+    /// CSV column naming the stratum of each value (machine, day, test case); resampling stays within strata
+    #[arg(
+        long = "strata-column",
+        value_name = "NAME",
+        requires = "column",
+        conflicts_with_all = ["weight_column", "baseline_group"]
+    )]
+    pub strata_column: Option<String>,
+
+    /// Read both samples from one file of group,value rows; this group is the baseline
+    #[arg(
+        long = "baseline-group",
+        value_name = "GROUP",
+        requires = "target_group"
+    )]
+    pub baseline_group: Option<String>,
+
+    /// Group whose rows form the target sample
+    #[arg(
+        long = "target-group",
+        value_name = "GROUP",
+        requires = "baseline_group"
+    )]
+    pub target_group: Option<String>,
+
+    /// CSV column holding the group label (with --column)
+    #[arg(long = "group-column", value_name = "NAME", default_value = "group")]
+    pub group_column: String,
+
+    /// How input files are compressed
+    #[arg(long = "compression", value_enum, default_value = "auto")]
+    pub compression: Compression,
+
+    /// Layout of each line of plain input, or arrow or parquet for columnar files (with --column)
+    #[arg(long = "input-format", value_enum, default_value = "plain")]
+    pub input_format: InputFormat,
+
+    /// Dotted path to the value in each line of JSON Lines input, e.g. metrics.latency_ms
+    #[arg(long = "path", value_name = "PATH", conflicts_with = "column")]
+    pub json_path: Option<String>,
+
+    /// Take the value from this whitespace-separated field of each line (1-based)
+    #[arg(
+        long = "field",
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        conflicts_with_all = ["column", "input_format"]
+    )]
+    pub field: Option<usize>,
+
+    /// Take the value from the capture group of this regex, e.g. 'took (\d+\.\d+)ms'; other lines are ignored
+    #[arg(
+        long = "extract",
+        value_name = "REGEX",
+        value_parser = parse_extract,
+        conflicts_with_all = ["column", "field", "input_format"]
+    )]
+    pub extract: Option<Regex>,
+
+    /// Read numbers written with a decimal comma, e.g. 3,14
+    #[arg(long = "decimal-comma")]
+    pub decimal_comma: bool,
+
+    /// Ignore this thousands separator in numbers, e.g. '.' with --decimal-comma
+    #[arg(long = "thousands-separator", value_name = "CHAR")]
+    pub thousands_separator: Option<char>,
+
+    /// Skip lines that cannot be parsed instead of failing, with a warning
+    #[arg(long = "skip-bad-lines")]
+    pub skip_bad_lines: bool,
+
+    /// Fail anyway once more than this many lines of a file have been skipped
+    #[arg(long = "max-bad-lines", value_name = "N", requires = "skip_bad_lines")]
+    pub max_bad_lines: Option<usize>,
+
+    /// Treat these tokens as missing values and drop them with a count, e.g. --na-values NA,null, (a trailing comma adds the empty string)
+    #[arg(long = "na-values", value_name = "TOKENS", value_delimiter = ',')]
+    pub na_values: Vec<String>,
+
+    /// Drop this many values from the start of each file (or group), e.g. warmup iterations
+    #[arg(long = "skip-first", value_name = "N")]
+    pub skip_first: Option<usize>,
+
+    /// Drop this percentage of the values from the start of each file (or group)
+    #[arg(
+        long = "skip-first-pct",
+        value_name = "P",
+        value_parser = parse_percentage,
+        conflicts_with = "skip_first"
+    )]
+    pub skip_first_pct: Option<f64>,
+
+    /// Multiply every value by this factor, e.g. 0.001 to read nanoseconds as microseconds
+    #[arg(long = "scale", value_name = "A", default_value = "1", value_parser = parse_scale, allow_negative_numbers = true)]
+    pub scale: f64,
+
+    /// Add this to every value after scaling
+    #[arg(
+        long = "offset",
+        value_name = "B",
+        default_value = "0",
+        allow_negative_numbers = true
+    )]
+    pub offset: f64,
+
+    /// Drop values below this bound, e.g. sentinel values such as -1 (compared before --scale and --offset)
+    #[arg(long = "min", value_name = "X", allow_negative_numbers = true)]
+    pub min: Option<f64>,
+
+    /// Drop values above this bound
+    #[arg(long = "max", value_name = "X", allow_negative_numbers = true)]
+    pub max: Option<f64>,
+
+    /// What to do with values outside --min and --max
+    #[arg(long = "out-of-range", value_enum, default_value = "drop")]
+    pub out_of_range: OutOfRange,
+
+    /// Summarize each sample in a sketch as it is read, using memory independent of the sample size; simulations resample the sketch
+    #[arg(
+        long = "sketch",
+        value_enum,
+        conflicts_with_all = ["skip_first", "skip_first_pct", "strata_column"]
+    )]
+    pub sketch: Option<Sketch>,
+
+    /// Significant digits kept by --sketch hdr (default 3); implies --sketch hdr
+    #[arg(
+        long = "hdr-digits",
+        value_name = "DIGITS",
+        value_parser = clap::value_parser!(u32).range(1..=6),
+        conflicts_with_all = ["skip_first", "skip_first_pct", "strata_column"]
+    )]
+    pub hdr_digits: Option<u32>,
+
+    /// Compression of --sketch tdigest: roughly the number of centroids kept
+    #[arg(
+        long = "tdigest-compression",
+        value_name = "DELTA",
+        default_value = "200",
+        value_parser = clap::value_parser!(u32).range(10..)
+    )]
+    pub tdigest_compression: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Sketch {
+    /// Buckets a fixed fraction of their magnitude wide, as in HDR histograms (see --hdr-digits)
+    Hdr,
+    /// A t-digest, most accurate in the tails (see --tdigest-compression)
+    Tdigest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutOfRange {
+    /// Leave them out of the sample, with a warning giving how many
+    Drop,
+    /// Fail on the first one
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Compression {
+    /// Detect gzip and zstd from the start of each file
+    Auto,
+    None,
+    Gzip,
+    /// Decompressed with the zstd command, which must be installed
+    Zstd,
+}
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum InputFormat {
+    /// A value per line, optionally followed by its weight
+    Plain,
+    /// A value and the number of times it occurred per line, for pre-aggregated data
+    Counted,
+    /// Histogram buckets: an upper bound and the number of values in the bucket per line
+    Buckets,
+    /// Cumulative histogram buckets as exported by Prometheus: an upper bound (le, +Inf allowed) and the number of values up to it per line
+    Prometheus,
+    /// An Arrow IPC file or stream, taking the numeric column named by --column
+    Arrow,
+    /// A Parquet file, taking the numeric column named by --column
+    Parquet,
+    /// A JSON object per line, taking the number at --path
+    Jsonl,
+}
+
+// Each bucket is represented by this many evenly spaced points sharing its
+// count, so quantiles interpolate linearly within the bucket.
+const POINTS_PER_BUCKET: usize = 16;
+
+// Turns histogram buckets of (upper bound, count) into weighted values. The
+// first bucket starts at zero (or at its bound, if that is negative), and an
+// unbounded last bucket is placed at the highest finite bound.
+fn expand_buckets(
+    path: &Path,
+    mut buckets: Vec<(f64, f64)>,
+    cumulative: bool,
+) -> Result<Observations, Error> {
+    buckets.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    if cumulative {
+        let mut previous = 0.0;
+        for bucket in buckets.iter_mut() {
+            let count = bucket.1 - previous;
+            if count < 0.0 {
+                return Err(Error::InvalidInput(format!(
+                    "{}: cumulative bucket counts decrease at le={}",
+                    path.display(),
+                    bucket.0
+                )));
+            }
+            previous = bucket.1;
+            bucket.1 = count;
+        }
+    }
+    let mut values = Vec::new();
+    let mut weights = Vec::new();
+    let mut lower = buckets.first().map_or(0.0, |b| b.0.min(0.0));
+    for (upper, count) in buckets {
+        if upper.is_infinite() {
+            values.push(lower);
+            weights.push(count);
+            continue;
+        }
+        // Whole counts per point keep the weights frequencies.
+        let k = POINTS_PER_BUCKET as f64;
+        for i in 0..POINTS_PER_BUCKET {
+            let t = (i as f64 + 0.5) / k;
+            values.push(lower + t * (upper - lower));
+            weights.push(((i + 1) as f64 * count / k).floor() - (i as f64 * count / k).floor());
+        }
+        lower = upper;
+    }
+    Ok(Observations {
+        values,
+        weights: Some(weights),
+        strata: None,
+    })
+}
+
+fn parse_extract(s: &str) -> Result<Regex, String> {
+    let regex = Regex::new(s)?;
+    if regex.groups() != 1 {
+        return Err(format!(
+            "expected exactly one capture group, found {}",
+            regex.groups()
+        ));
+    }
+    Ok(regex)
+}
+
+// Values in input order, with weights if the input provided any.
+pub struct Observations {
+    pub values: Vec<f64>,
+    pub weights: Option<Vec<f64>>,
+    // The stratum of each value, with --strata-column.
+    pub strata: Option<Vec<String>>,
+}
+
+impl Observations {
+    pub fn into_sample(self) -> Result<Sample, Error> {
+        match (self.weights, self.strata) {
+            (Some(ws), _) => Sample::weighted(self.values.into_iter().zip(ws).collect()),
+            (None, Some(strata)) => Ok(Sample::stratified(self.values, strata)),
+            (None, None) => Ok(Sample::new(self.values)),
+        }
+    }
+}
+
+// Splits a CSV line into fields, honouring double-quoted fields.
+pub fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+// Opens an input file, decompressing it whole if it is compressed.
+fn open(path: &Path, args: &InputArgs) -> Result<Box<dyn Read>, Error> {
+    let local = fetch::local(path)?;
+    let mut file = File::open(&local).map_err(|e| Error::open(path, e))?;
+    // The bytes read to detect the compression, which pipes cannot seek
+    // back over.
+    let mut magic = Vec::with_capacity(ZSTD_MAGIC.len());
+    let compression = match args.compression {
+        Compression::Auto => {
+            (&mut file)
+                .take(ZSTD_MAGIC.len() as u64)
+                .read_to_end(&mut magic)?;
+            if magic.starts_with(&gzip::MAGIC) {
+                Compression::Gzip
+            } else if magic == ZSTD_MAGIC {
+                Compression::Zstd
+            } else {
+                Compression::None
+            }
+        }
+        compression => compression,
+    };
+    let corrupt = |msg: String| Error::InvalidInput(format!("{}: {}", path.display(), msg));
+    let mut file = Cursor::new(magic).chain(file);
+    match compression {
+        Compression::Auto | Compression::None => Ok(Box::new(file)),
+        Compression::Gzip => {
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+            Ok(Box::new(Cursor::new(
+                gzip::decompress(&data).map_err(corrupt)?,
+            )))
+        }
+        Compression::Zstd => {
+            // zstd reads the file itself, so it cannot be a pipe.
+            let output = Command::new("zstd")
+                .args(["-dc", "--"])
+                .arg(&*local)
+                .output()
+                .map_err(|e| corrupt(format!("cannot run zstd to decompress: {}", e)))?;
+            if !output.status.success() {
+                return Err(corrupt(format!(
+                    "zstd failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+            Ok(Box::new(Cursor::new(output.stdout)))
+        }
+    }
+}
+
+fn find_column(header: &[String], name: &str) -> Result<usize, Error> {
+    header
+        .iter()
+        .position(|h| h.trim() == name)
+        .ok_or_else(|| Error::InvalidInput(format!("no column named {:?}", name)))
+}
+
+// How numbers are written in the input.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NumberFormat {
+    pub decimal_comma: bool,
+    pub thousands_separator: Option<char>,
+}
+
+fn parse_percentage(s: &str) -> Result<f64, String> {
+    match s.trim().trim_end_matches('%').parse::<f64>() {
+        Ok(p) if (0.0..100.0).contains(&p) => Ok(p),
+        _ => Err(format!("expected a percentage from 0 to 100, got {:?}", s)),
+    }
+}
+
+fn parse_scale(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(a) if a.is_finite() && a != 0.0 => Ok(a),
+        _ => Err(format!("scale must be a non-zero number, got {:?}", s)),
+    }
+}
+
+impl InputArgs {
+    // How many of `n` values in measurement order --skip-first or
+    // --skip-first-pct drops.
+    fn skipped(&self, n: usize) -> Result<usize, Error> {
+        let skip = match (self.skip_first, self.skip_first_pct) {
+            (Some(count), _) => count.min(n),
+            (None, Some(pct)) => (pct / 100.0 * n as f64).floor() as usize,
+            (None, None) => return Ok(0),
+        };
+        if skip > 0
+            && self.column.is_none()
+            && !matches!(self.input_format, InputFormat::Plain | InputFormat::Jsonl)
+        {
+            return Err(Error::Usage(
+                "--skip-first needs values in measurement order, not aggregated input".to_string(),
+            ));
+        }
+        Ok(skip)
+    }
+
+    // Applies --scale and --offset to a value as read.
+    fn transform(&self, x: f64) -> f64 {
+        self.scale * x + self.offset
+    }
+
+    fn number_format(&self) -> NumberFormat {
+        NumberFormat {
+            decimal_comma: self.decimal_comma,
+            thousands_separator: self.thousands_separator,
+        }
+    }
+}
+
+// A line of an input file, kept to point at the offending row in errors.
+pub struct Line<'a> {
+    pub path: &'a Path,
+    pub number: usize,
+    pub text: &'a str,
+    pub format: NumberFormat,
+}
+
+// Parses a plain number or a clock-style duration such as 1:23.456 (MM:SS)
+// or 01:02:03 (HH:MM:SS), which is converted to seconds.
+fn parse_number(token: &str) -> Option<f64> {
+    if let Ok(x) = token.parse() {
+        return Some(x);
+    }
+    let parts: Vec<&str> = token.split(':').collect();
+    if !(2..=3).contains(&parts.len()) {
+        return None;
+    }
+    let (seconds, rest) = parts.split_last()?;
+    let mut total = 0.0;
+    for (i, part) in rest.iter().enumerate() {
+        if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let x: f64 = part.parse().ok()?;
+        if i > 0 && x >= 60.0 {
+            return None;
+        }
+        total = total * 60.0 + x;
+    }
+    if !seconds.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let seconds: f64 = seconds.parse().ok()?;
+    if seconds >= 60.0 {
+        return None;
+    }
+    Some(total * 60.0 + seconds)
+}
+
+impl Line<'_> {
+    pub fn parse(&self, token: &str) -> Result<f64, Error> {
+        let mut normalized = token.trim().to_string();
+        if let Some(sep) = self.format.thousands_separator {
+            normalized.retain(|c| c != sep);
+        }
+        if self.format.decimal_comma {
+            normalized = normalized.replace(',', ".");
+        }
+        parse_number(&normalized).ok_or_else(|| Error::Parse {
+            path: self.path.to_path_buf(),
+            line: self.number,
+            content: self.text.to_string(),
+            token: token.trim().to_string(),
+        })
+    }
+
+    fn invalid(&self, msg: String) -> Error {
+        Error::InvalidInput(format!("{}:{}: {}", self.path.display(), self.number, msg))
+    }
+}
+
+// Tracks the lines of one file skipped with --skip-bad-lines, and the
+// values dropped as missing with --na-values.
+struct BadLines<'a> {
+    path: &'a Path,
+    skip: bool,
+    max: Option<usize>,
+    skipped: usize,
+    first: Option<Error>,
+    na_values: &'a [String],
+    missing: usize,
+}
+
+impl BadLines<'_> {
+    fn new<'a>(path: &'a Path, args: &'a InputArgs) -> BadLines<'a> {
+        BadLines {
+            path,
+            skip: args.skip_bad_lines,
+            max: args.max_bad_lines,
+            skipped: 0,
+            first: None,
+            na_values: &args.na_values,
+            missing: 0,
+        }
+    }
+
+    // Whether a token is one of --na-values, counting it if so.
+    fn is_missing(&mut self, token: &str) -> bool {
+        let missing = self.na_values.iter().any(|na| na == token.trim());
+        self.missing += missing as usize;
+        missing
+    }
+
+    // Passes through the result of parsing one line, turning an error into
+    // None when the line may be skipped.
+    fn check<T>(&mut self, result: Result<T, Error>) -> Result<Option<T>, Error> {
+        match result {
+            Ok(x) => Ok(Some(x)),
+            Err(e) if self.skip => {
+                self.skipped += 1;
+                if let Some(max) = self.max {
+                    if self.skipped > max {
+                        return Err(Error::InvalidInput(format!(
+                            "more than {} bad lines in {}; the last one was: {}",
+                            max,
+                            self.path.display(),
+                            e
+                        )));
+                    }
+                }
+                self.first.get_or_insert(e);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn finish(self) {
+        if self.missing > 0 {
+            tracing::warn!(
+                "dropped {} missing value(s) from {}",
+                self.missing,
+                self.path.display()
+            );
+        }
+        if let Some(first) = self.first {
+            tracing::warn!(
+                "skipped {} bad line(s) in {}; the first one was: {}",
+                self.skipped,
+                self.path.display(),
+                first
+            );
+        }
+    }
+}
+
+// Each line holds a value, optionally followed by its weight (or its count
+// with --input-format counted), unless --field or --extract picks the value
+// out of a longer line. Calls `f` with each value and weight.
+fn for_each_plain_value(
+    path: &Path,
+    args: &InputArgs,
+    mut f: impl FnMut(f64, Option<f64>),
+) -> Result<(), Error> {
+    let mut bad_lines = BadLines::new(path, args);
+    for (i, text) in std::io::BufReader::new(open(path, args)?)
+        .lines()
+        .enumerate()
+    {
+        let text = text?;
+        let line = Line {
+            path,
+            number: i + 1,
+            text: &text,
+            format: args.number_format(),
+        };
+        let parsed = (|| {
+            if let Some(regex) = &args.extract {
+                return match regex.captures(&text) {
+                    Some(caps) if bad_lines.is_missing(caps[1].unwrap_or("")) => Ok(None),
+                    Some(caps) => Ok(Some((line.parse(caps[1].unwrap_or(""))?, None))),
+                    None => Ok(None),
+                };
+            }
+            if let Some(field) = args.field {
+                return match text.split_whitespace().nth(field - 1) {
+                    Some(token) if bad_lines.is_missing(token) => Ok(None),
+                    Some(token) => Ok(Some((line.parse(token)?, None))),
+                    None => Err(line.invalid(format!("no field {} in {:?}", field, text))),
+                };
+            }
+            if args.input_format != InputFormat::Plain {
+                let tokens: Vec<&str> = text.split_whitespace().collect();
+                if tokens
+                    .first()
+                    .is_some_and(|token| bad_lines.is_missing(token))
+                {
+                    return Ok(None);
+                }
+                if tokens.len() != 2 {
+                    return Err(
+                        line.invalid(format!("expected a value and a count, got {:?}", text))
+                    );
+                }
+                let count = line.parse(tokens[1])?;
+                if count < 0.0 || count.fract() != 0.0 {
+                    return Err(line.invalid(format!("invalid count {:?}", tokens[1])));
+                }
+                return Ok(Some((line.parse(tokens[0])?, Some(count))));
+            }
+            let mut tokens = text.split_whitespace();
+            let token = tokens.next().unwrap_or("");
+            if bad_lines.is_missing(token) {
+                return Ok(None);
+            }
+            let x = line.parse(token)?;
+            let w = tokens.next().map(|token| line.parse(token)).transpose()?;
+            if tokens.next().is_some() {
+                return Err(line.invalid(format!(
+                    "expected a value and an optional weight, got {:?}",
+                    text
+                )));
+            }
+            Ok(Some((x, w)))
+        })();
+        if let Some((x, w)) = bad_lines.check(parsed)?.flatten() {
+            f(x, w);
+        }
+    }
+    bad_lines.finish();
+    Ok(())
+}
+
+// Counts become weights, which estimators treat as frequencies.
+fn read_plain(path: &Path, args: &InputArgs) -> Result<Observations, Error> {
+    let mut values = Vec::new();
+    let mut weights = Vec::new();
+    let mut weighted = false;
+    for_each_plain_value(path, args, |x, w| {
+        weighted |= w.is_some();
+        values.push(x);
+        weights.push(w.unwrap_or(1.0));
+    })?;
+    match args.input_format {
+        InputFormat::Buckets | InputFormat::Prometheus => expand_buckets(
+            path,
+            values.into_iter().zip(weights).collect(),
+            args.input_format == InputFormat::Prometheus,
+        ),
+        _ => Ok(Observations {
+            values,
+            weights: if weighted { Some(weights) } else { None },
+            strata: None,
+        }),
+    }
+}
+
+// Calls `f` with the requested columns of every data row of a CSV file with
+// a header row. Rows that are short of columns or that `f` fails on are bad
+// lines.
+fn for_each_csv_row(
+    path: &Path,
+    names: &[&str],
+    args: &InputArgs,
+    mut f: impl FnMut(&Line, &[String]) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let mut lines = std::io::BufReader::new(open(path, args)?).lines();
+    let header = match lines.next() {
+        Some(line) => split_csv_line(&line?),
+        None => {
+            return Err(Error::InvalidInput(format!(
+                "{}: CSV input has no header row",
+                path.display()
+            )))
+        }
+    };
+    let indices = names
+        .iter()
+        .map(|name| find_column(&header, name))
+        .collect::<Result<Vec<usize>, Error>>()?;
+
+    let mut row = Vec::with_capacity(indices.len());
+    let mut bad_lines = BadLines::new(path, args);
+    for (i, text) in lines.enumerate() {
+        let text = text?;
+        if text.is_empty() {
+            continue;
+        }
+        let line = Line {
+            path,
+            number: i + 2,
+            text: &text,
+            format: args.number_format(),
+        };
+        let mut fields = split_csv_line(&text);
+        row.clear();
+        if indices.iter().any(|i| {
+            fields
+                .get(*i)
+                .is_some_and(|field| bad_lines.is_missing(field))
+        }) {
+            continue;
+        }
+        let result = indices
+            .iter()
+            .try_for_each(|i| match fields.get_mut(*i) {
+                Some(field) => {
+                    row.push(std::mem::take(field));
+                    Ok(())
+                }
+                None => Err(line.invalid(format!("missing column {}", i + 1))),
+            })
+            .and_then(|()| f(&line, &row));
+        bad_lines.check(result)?;
+    }
+    bad_lines.finish();
+    Ok(())
+}
+
+fn read_csv(
+    path: &Path,
+    column: &str,
+    weight_column: Option<&str>,
+    args: &InputArgs,
+) -> Result<Observations, Error> {
+    let mut names = vec![column];
+    names.extend(weight_column);
+    names.extend(args.strata_column.as_deref());
+    let mut values = Vec::new();
+    let mut weights = Vec::new();
+    let mut strata = Vec::new();
+    for_each_csv_row(path, &names, args, |line, row| {
+        let x = line.parse(&row[0])?;
+        if weight_column.is_some() {
+            weights.push(line.parse(&row[1])?);
+        }
+        if args.strata_column.is_some() {
+            strata.push(row[row.len() - 1].trim().to_string());
+        }
+        values.push(x);
+        Ok(())
+    })?;
+    Ok(Observations {
+        values,
+        weights: weight_column.map(|_| weights),
+        strata: args.strata_column.as_ref().map(|_| strata),
+    })
+}
+
+// The columns of a CSV file with a header row whose first non-missing
+// value is a number, for --all-columns. Weight and strata columns are left
+// out.
+pub fn numeric_columns(path: &Path, args: &InputArgs) -> Result<Vec<String>, Error> {
+    let mut lines = std::io::BufReader::new(open(path, args)?).lines();
+    let header = match lines.next() {
+        Some(line) => split_csv_line(&line?),
+        None => {
+            return Err(Error::InvalidInput(format!(
+                "{}: CSV input has no header row",
+                path.display()
+            )))
+        }
+    };
+    // Whether each column is numeric, once its first value has been seen.
+    let mut numeric: Vec<Option<bool>> = vec![None; header.len()];
+    let mut bad_lines = BadLines::new(path, args);
+    for (i, text) in lines.enumerate() {
+        if numeric.iter().all(Option::is_some) {
+            break;
+        }
+        let text = text?;
+        let line = Line {
+            path,
+            number: i + 2,
+            text: &text,
+            format: args.number_format(),
+        };
+        for (field, numeric) in split_csv_line(&text).iter().zip(numeric.iter_mut()) {
+            if numeric.is_none() && !field.trim().is_empty() && !bad_lines.is_missing(field) {
+                *numeric = Some(line.parse(field).is_ok());
+            }
+        }
+    }
+    let excluded = [args.weight_column.as_deref(), args.strata_column.as_deref()];
+    Ok(header
+        .into_iter()
+        .zip(numeric)
+        .filter(|(name, numeric)| {
+            *numeric == Some(true) && !excluded.contains(&Some(name.as_str()))
+        })
+        .map(|(name, _)| name)
+        .collect())
+}
+
+// Rows of (group, value, weight) from either a CSV file with named columns
+// or plain group,value lines.
+fn read_grouped_rows(path: &Path, args: &InputArgs) -> Result<Vec<(String, f64, f64)>, Error> {
+    let mut rows = Vec::new();
+    match &args.column {
+        Some(column) => {
+            let mut names = vec![args.group_column.as_str(), column.as_str()];
+            names.extend(args.weight_column.as_deref());
+            for_each_csv_row(path, &names, args, |line, row| {
+                let w = match row.get(2) {
+                    Some(w) => line.parse(w)?,
+                    None => 1.0,
+                };
+                rows.push((row[0].trim().to_string(), line.parse(&row[1])?, w));
+                Ok(())
+            })?;
+        }
+        None => {
+            let lines = std::io::BufReader::new(open(path, args)?).lines();
+            let mut bad_lines = BadLines::new(path, args);
+            for (i, text) in lines.enumerate() {
+                let text = text?;
+                if text.is_empty() {
+                    continue;
+                }
+                let line = Line {
+                    path,
+                    number: i + 1,
+                    text: &text,
+                    format: args.number_format(),
+                };
+                let fields = split_csv_line(&text);
+                if fields.len() == 2 && bad_lines.is_missing(&fields[1]) {
+                    continue;
+                }
+                let parsed = if fields.len() != 2 {
+                    Err(line.invalid(format!("expected a group,value row, got {:?}", text)))
+                } else {
+                    line.parse(&fields[1])
+                };
+                let value = match parsed {
+                    // Tolerate a header row such as "group,value".
+                    Err(_) if i == 0 && fields.len() == 2 => continue,
+                    parsed => bad_lines.check(parsed)?,
+                };
+                if let Some(value) = value {
+                    rows.push((fields[0].trim().to_string(), value, 1.0));
+                }
+            }
+            bad_lines.finish();
+        }
+    }
+    Ok(rows)
+}
+
+// Splits one grouped file into the baseline and target samples.
+pub fn read_grouped(
+    path: &Path,
+    args: &InputArgs,
+    baseline_group: &str,
+    target_group: &str,
+) -> Result<(Observations, Observations), Error> {
+    if matches!(
+        args.input_format,
+        InputFormat::Arrow | InputFormat::Parquet | InputFormat::Jsonl
+    ) {
+        return Err(Error::Usage(
+            "grouped input must be group,value lines or CSV".to_string(),
+        ));
+    }
+    let mut rows = read_grouped_rows(path, args)?;
+    let values: Vec<f64> = rows.iter().map(|r| r.1).collect();
+    let mut keep = within_bounds(path, &values, args)?.into_iter();
+    rows.retain(|_| keep.next().expect("a flag per row"));
+    for row in rows.iter_mut() {
+        row.1 = args.transform(row.1);
+    }
+    let weighted = args.weight_column.is_some();
+    let select = |group: &str| -> Result<Observations, Error> {
+        let selected: Vec<&(String, f64, f64)> = rows.iter().filter(|r| r.0 == group).collect();
+        if selected.is_empty() {
+            return Err(Error::InvalidInput(format!("no rows in group {:?}", group)));
+        }
+        let selected = &selected[args.skipped(selected.len())?..];
+        if selected.is_empty() {
+            return Err(Error::InvalidInput(format!(
+                "no rows left in group {:?} after --skip-first",
+                group
+            )));
+        }
+        Observations {
+            values: selected.iter().map(|r| r.1).collect(),
+            weights: if weighted {
+                Some(selected.iter().map(|r| r.2).collect())
+            } else {
+                None
+            },
+            strata: None,
+        }
+        .sketch(args)
+    };
+    Ok((select(baseline_group)?, select(target_group)?))
+}
+
+// The values allowed by --min and --max.
+fn bounds(args: &InputArgs) -> Result<RangeInclusive<f64>, Error> {
+    let range = args.min.unwrap_or(f64::NEG_INFINITY)..=args.max.unwrap_or(f64::INFINITY);
+    if range.is_empty() {
+        return Err(Error::Usage(
+            "--min must not be greater than --max".to_string(),
+        ));
+    }
+    Ok(range)
+}
+
+// Fails on `first`, the first value outside the range, with --out-of-range
+// error, and otherwise warns about how many were dropped.
+fn check_out_of_range(
+    path: &Path,
+    args: &InputArgs,
+    range: &RangeInclusive<f64>,
+    dropped: usize,
+    first: Option<f64>,
+) -> Result<(), Error> {
+    let Some(x) = first else {
+        return Ok(());
+    };
+    let bounds = format!("[{}, {}]", range.start(), range.end());
+    if args.out_of_range == OutOfRange::Error {
+        return Err(Error::InvalidInput(format!(
+            "{}: value {} is outside {}",
+            path.display(),
+            x,
+            bounds
+        )));
+    }
+    tracing::warn!(
+        "dropped {} value(s) outside {} from {}",
+        dropped,
+        bounds,
+        path.display()
+    );
+    Ok(())
+}
+
+// NaN is no value at all rather than one outside the bounds, so it is
+// dropped whatever --min, --max and --out-of-range say.
+fn warn_nan(path: &Path, nans: usize) {
+    if nans > 0 {
+        tracing::warn!("dropped {} NaN value(s) from {}", nans, path.display());
+    }
+}
+
+// Which of the values read from `path` are within --min and --max, failing
+// on the first one outside with --out-of-range error and otherwise warning
+// about how many will be dropped.
+fn within_bounds(path: &Path, values: &[f64], args: &InputArgs) -> Result<Vec<bool>, Error> {
+    let range = bounds(args)?;
+    let keep: Vec<bool> = values.iter().map(|x| range.contains(x)).collect();
+    let nans = values.iter().filter(|x| x.is_nan()).count();
+    let dropped = keep.iter().filter(|k| !**k).count() - nans;
+    let first = values
+        .iter()
+        .find(|x| !x.is_nan() && !range.contains(x))
+        .copied();
+    check_out_of_range(path, args, &range, dropped, first)?;
+    warn_nan(path, nans);
+    Ok(keep)
+}
+
+// A sketch values are counted into as they are read, with --sketch.
+enum Sketcher {
+    Hdr(hdr::Histogram),
+    TDigest(TDigest),
+}
+
+impl Sketcher {
+    fn record(&mut self, x: f64, weight: f64) {
+        match self {
+            Sketcher::Hdr(histogram) => histogram.record(x, weight),
+            Sketcher::TDigest(digest) => digest.record(x, weight),
+        }
+    }
+
+    // The buckets or centroids of the sketch as weighted values.
+    fn into_observations(self) -> Observations {
+        let (values, weights) = match self {
+            Sketcher::Hdr(histogram) => histogram.buckets().unzip(),
+            Sketcher::TDigest(digest) => digest.centroids().into_iter().unzip(),
+        };
+        Observations {
+            values,
+            weights: Some(weights),
+            strata: None,
+        }
+    }
+}
+
+impl InputArgs {
+    // A new sketch of the kind asked for, if any.
+    fn sketcher(&self) -> Result<Option<Sketcher>, Error> {
+        Ok(match (self.sketch, self.hdr_digits) {
+            (Some(Sketch::Tdigest), Some(_)) => {
+                return Err(Error::Usage(
+                    "--hdr-digits applies to --sketch hdr, not tdigest".to_string(),
+                ))
+            }
+            (Some(Sketch::Tdigest), None) => Some(Sketcher::TDigest(TDigest::new(
+                self.tdigest_compression as f64,
+            ))),
+            (Some(Sketch::Hdr), digits) | (None, digits @ Some(_)) => {
+                Some(Sketcher::Hdr(hdr::Histogram::new(digits.unwrap_or(3))))
+            }
+            (None, None) => None,
+        })
+    }
+}
+
+// Plain or counted input counted straight into a sketch, so that memory
+// does not grow with the number of values. --min, --max, --scale and
+// --offset apply to each value before it is counted.
+fn read_plain_sketch(
+    path: &Path,
+    args: &InputArgs,
+    mut sketch: Sketcher,
+) -> Result<Observations, Error> {
+    let range = bounds(args)?;
+    let mut dropped = 0;
+    let mut nans = 0;
+    let mut first = None;
+    for_each_plain_value(path, args, |x, w| {
+        if range.contains(&x) {
+            sketch.record(args.transform(x), w.unwrap_or(1.0));
+        } else if x.is_nan() {
+            nans += 1;
+        } else {
+            dropped += 1;
+            first.get_or_insert(x);
+        }
+    })?;
+    check_out_of_range(path, args, &range, dropped, first)?;
+    warn_nan(path, nans);
+    Ok(sketch.into_observations())
+}
+
+impl Observations {
+    // Counts the observations into a sketch with --sketch, for input that
+    // is not read straight into one.
+    fn sketch(self, args: &InputArgs) -> Result<Observations, Error> {
+        let Some(mut sketch) = args.sketcher()? else {
+            return Ok(self);
+        };
+        match &self.weights {
+            Some(ws) => self
+                .values
+                .iter()
+                .zip(ws)
+                .for_each(|(x, w)| sketch.record(*x, *w)),
+            None => self.values.iter().for_each(|x| sketch.record(*x, 1.0)),
+        }
+        Ok(sketch.into_observations())
+    }
+}
+
+fn filter<T: Clone>(xs: &[T], keep: &[bool]) -> Vec<T> {
+    xs.iter()
+        .zip(keep)
+        .filter(|(_, k)| **k)
+        .map(|(x, _)| x.clone())
+        .collect()
+}
+
+#[cfg(feature = "arrow")]
+fn read_arrow_column(data: &[u8], column: &str) -> Result<(Vec<f64>, usize), Error> {
+    arrow::read_column(data, column).map_err(Error::InvalidInput)
+}
+
+#[cfg(not(feature = "arrow"))]
+fn read_arrow_column(_data: &[u8], _column: &str) -> Result<(Vec<f64>, usize), Error> {
+    Err(Error::Usage(
+        "cannot read Arrow input: numcmp was built without the arrow feature".to_string(),
+    ))
+}
+
+#[cfg(feature = "parquet")]
+fn read_parquet_column(data: &[u8], column: &str) -> Result<(Vec<f64>, usize), Error> {
+    parquet::read_column(data, column).map_err(Error::InvalidInput)
+}
+
+#[cfg(not(feature = "parquet"))]
+fn read_parquet_column(_data: &[u8], _column: &str) -> Result<(Vec<f64>, usize), Error> {
+    Err(Error::Usage(
+        "cannot read Parquet input: numcmp was built without the parquet feature".to_string(),
+    ))
+}
+
+// Reads the named column of an Arrow or Parquet file.
+fn read_columnar(path: &Path, column: &str, args: &InputArgs) -> Result<Observations, Error> {
+    if args.weight_column.is_some() || args.strata_column.is_some() {
+        return Err(Error::Usage(
+            "--weight-column and --strata-column are not supported for Arrow or Parquet input"
+                .to_string(),
+        ));
+    }
+    let mut data = Vec::new();
+    open(path, args)?.read_to_end(&mut data)?;
+    let read = if args.input_format == InputFormat::Parquet {
+        read_parquet_column(&data, column)
+    } else {
+        read_arrow_column(&data, column)
+    };
+    let (values, nulls) = read.map_err(|e| match e {
+        Error::InvalidInput(e) => Error::InvalidInput(format!("{}: {}", path.display(), e)),
+        e => e,
+    })?;
+    if nulls > 0 {
+        tracing::warn!("dropped {} null value(s) from {}", nulls, path.display());
+    }
+    Ok(Observations {
+        values,
+        weights: None,
+        strata: None,
+    })
+}
+
+// Each line is a JSON value holding a number, or a string with a number, at
+// the given path. Nulls and --na-values are dropped as missing.
+fn read_jsonl(path: &Path, json_path: &str, args: &InputArgs) -> Result<Observations, Error> {
+    let mut values = Vec::new();
+    let mut bad_lines = BadLines::new(path, args);
+    for (i, text) in std::io::BufReader::new(open(path, args)?)
+        .lines()
+        .enumerate()
+    {
+        let text = text?;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let line = Line {
+            path,
+            number: i + 1,
+            text: &text,
+            format: args.number_format(),
+        };
+        let parsed = (|| {
+            let value = json::parse(&text).map_err(|e| line.invalid(e))?;
+            match value.get_path(json_path) {
+                Some(json::Value::Number(x)) => Ok(Some(*x)),
+                Some(json::Value::Null) => {
+                    bad_lines.missing += 1;
+                    Ok(None)
+                }
+                Some(json::Value::String(s)) if bad_lines.is_missing(s) => Ok(None),
+                Some(json::Value::String(s)) => Ok(Some(line.parse(s)?)),
+                Some(_) => Err(line.invalid(format!("{} is not a number", json_path))),
+                None => Err(line.invalid(format!("no {} in the line", json_path))),
+            }
+        })();
+        if let Some(x) = bad_lines.check(parsed)?.flatten() {
+            values.push(x);
+        }
+    }
+    bad_lines.finish();
+    Ok(Observations {
+        values,
+        weights: None,
+        strata: None,
+    })
+}
+
+pub fn read_observations(path: &Path, args: &InputArgs) -> Result<Observations, Error> {
+    if let (Some(sketch), None, InputFormat::Plain | InputFormat::Counted) =
+        (args.sketcher()?, &args.column, args.input_format)
+    {
+        return read_plain_sketch(path, args, sketch);
+    }
+    let obs =
+        match (&args.column, args.input_format) {
+            (None, InputFormat::Jsonl) => match &args.json_path {
+                Some(json_path) => read_jsonl(path, json_path, args)?,
+                None => {
+                    return Err(Error::Usage(
+                        "--input-format jsonl needs --path".to_string(),
+                    ))
+                }
+            },
+            (Some(column), InputFormat::Arrow | InputFormat::Parquet) => {
+                read_columnar(path, column, args)?
+            }
+            (None, InputFormat::Arrow) => {
+                return Err(Error::Usage(
+                    "--input-format arrow needs --column".to_string(),
+                ))
+            }
+            (None, InputFormat::Parquet) => {
+                return Err(Error::Usage(
+                    "--input-format parquet needs --column".to_string(),
+                ))
+            }
+            (Some(column), InputFormat::Plain) => {
+                read_csv(path, column, args.weight_column.as_deref(), args)?
+            }
+            (Some(_), _) => return Err(Error::Usage(
+                "--column reads CSV, Arrow or Parquet input, not --input-format counted, buckets or jsonl"
+                    .to_string(),
+            )),
+            (None, _) if args.json_path.is_some() => {
+                return Err(Error::Usage(
+                    "--path needs --input-format jsonl".to_string(),
+                ))
+            }
+            (None, _) => read_plain(path, args)?,
+        };
+    let skip = args.skipped(obs.values.len())?;
+    let obs = Observations {
+        values: obs.values[skip..].to_vec(),
+        weights: obs.weights.map(|ws| ws[skip..].to_vec()),
+        strata: obs.strata.map(|ss| ss[skip..].to_vec()),
+    };
+    let keep = within_bounds(path, &obs.values, args)?;
+    Observations {
+        values: filter(&obs.values, &keep)
+            .into_iter()
+            .map(|x| args.transform(x))
+            .collect(),
+        weights: obs.weights.map(|ws| filter(&ws, &keep)),
+        strata: obs.strata.map(|ss| filter(&ss, &keep)),
+    }
+    .sketch(args)
+}
+
+// Splits one input, a file or stdin when `path` is "-", into the baseline
+// before the line holding only `marker` and the target after it. The parts
+// are staged under names such as "stdin (baseline)" and returned.
+pub fn split_input(
+    path: &Path,
+    marker: &str,
+    args: &InputArgs,
+) -> Result<(PathBuf, PathBuf), Error> {
+    let mut text = String::new();
+    let source = if path.as_os_str() == "-" {
+        std::io::stdin().read_to_string(&mut text)?;
+        "stdin".to_string()
+    } else {
+        open(path, args)?.read_to_string(&mut text)?;
+        path.display().to_string()
+    };
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let markers: Vec<usize> = (0..lines.len())
+        .filter(|i| lines[*i].trim() == marker)
+        .collect();
+    let split = match markers[..] {
+        [i] => i,
+        [] => {
+            return Err(Error::InvalidInput(format!(
+                "{}: no line {:?} between the baseline and the target",
+                source, marker
+            )))
+        }
+        [_, i, ..] => {
+            return Err(Error::InvalidInput(format!(
+                "{}: line {} repeats the split marker {:?}",
+                source,
+                i + 1,
+                marker
+            )))
+        }
+    };
+    let baseline = PathBuf::from(format!("{} (baseline)", source));
+    let target = PathBuf::from(format!("{} (target)", source));
+    fetch::stage(&baseline, lines[..split].concat().as_bytes())?;
+    fetch::stage(&target, lines[split + 1..].concat().as_bytes())?;
+    Ok((baseline, target))
+}
+
+pub fn read_sample(path: &Path, args: &InputArgs) -> Result<Sample, Error> {
+    read_observations(path, args)?.into_sample()
+}
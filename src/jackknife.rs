@@ -0,0 +1,125 @@
+// Jackknife standard errors and bias estimates from leave-one-out samples.
+
+use crate::estimator::Estimator;
+use crate::metadata::Labels;
+use crate::sample::Sample;
+use crate::units::Units;
+use crate::{json, Error};
+
+pub struct Jackknife {
+    pub estimate: f64,
+    pub std_error: f64,
+    pub bias: f64,
+}
+
+pub struct JackknifeResult {
+    pub name: String,
+    pub baseline: Jackknife,
+    pub target: Jackknife,
+}
+
+// The estimator evaluated on each sample with one observation left out.
+pub fn leave_one_out(sample: &Sample, estimator: &dyn Estimator) -> Result<Vec<f64>, Error> {
+    let n = sample.len();
+    let mut loo = Sample {
+        values: Vec::with_capacity(n),
+        weights: sample.weights.as_ref().map(|_| Vec::with_capacity(n)),
+        input_order: Vec::new(),
+        strata: None,
+    };
+    let mut estimates = Vec::with_capacity(n);
+    for i in 0..n {
+        loo.values.clear();
+        loo.values.extend_from_slice(&sample.values[..i]);
+        loo.values.extend_from_slice(&sample.values[i + 1..]);
+        if let (Some(ws), Some(loo_ws)) = (&sample.weights, loo.weights.as_mut()) {
+            loo_ws.clear();
+            loo_ws.extend_from_slice(&ws[..i]);
+            loo_ws.extend_from_slice(&ws[i + 1..]);
+        }
+        estimates.push(estimator.estimate(&loo)?);
+    }
+    Ok(estimates)
+}
+
+pub fn jackknife(sample: &Sample, estimator: &dyn Estimator) -> Result<Jackknife, Error> {
+    if sample.len() < 2 {
+        return Err(Error::EmptySample(
+            "the jackknife needs at least two values per sample".to_string(),
+        ));
+    }
+    let estimate = estimator.estimate(sample)?;
+    let loo = leave_one_out(sample, estimator)?;
+    let n = loo.len() as f64;
+    let mean = loo.iter().sum::<f64>() / n;
+    let ss: f64 = loo.iter().map(|x| (x - mean) * (x - mean)).sum();
+    Ok(Jackknife {
+        estimate,
+        std_error: ((n - 1.0) / n * ss).sqrt(),
+        bias: (n - 1.0) * (mean - estimate),
+    })
+}
+
+pub fn jackknife_all(
+    baseline: &Sample,
+    target: &Sample,
+    estimators: &[Box<dyn Estimator>],
+) -> Result<Vec<JackknifeResult>, Error> {
+    estimators
+        .iter()
+        .map(|est| {
+            Ok(JackknifeResult {
+                name: est.name().to_string(),
+                baseline: jackknife(baseline, est.as_ref())?,
+                target: jackknife(target, est.as_ref())?,
+            })
+        })
+        .collect()
+}
+
+pub fn print_text(results: &[JackknifeResult], units: &Units, labels: &Labels) {
+    println!("=== Jackknife ===");
+    for r in results.iter() {
+        let diff_se = (r.baseline.std_error.powi(2) + r.target.std_error.powi(2)).sqrt();
+        println!(
+            "{}: {} {} (s.e. {}, bias {}), {} {} (s.e. {}, bias {}), difference {} (s.e. {})",
+            r.name,
+            labels.baseline,
+            units.format(r.baseline.estimate),
+            units.format(r.baseline.std_error),
+            units.format(r.baseline.bias),
+            labels.target,
+            units.format(r.target.estimate),
+            units.format(r.target.std_error),
+            units.format(r.target.bias),
+            units.format(r.target.estimate - r.baseline.estimate),
+            units.format(diff_se)
+        );
+    }
+}
+
+fn jackknife_json(j: &Jackknife) -> String {
+    json::object(&[
+        ("estimate", json::number(j.estimate)),
+        ("std_error", json::number(j.std_error)),
+        ("bias", json::number(j.bias)),
+    ])
+}
+
+pub fn results_json(metadata_json: String, results: &[JackknifeResult]) -> String {
+    let items: Vec<String> = results
+        .iter()
+        .map(|r| {
+            json::object(&[
+                ("estimator", json::string(&r.name)),
+                ("baseline", jackknife_json(&r.baseline)),
+                ("target", jackknife_json(&r.target)),
+            ])
+        })
+        .collect();
+    json::object(&[
+        ("metadata", metadata_json),
+        ("method", json::string("jackknife")),
+        ("comparison", json::array(&items)),
+    ])
+}
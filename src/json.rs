@@ -0,0 +1,416 @@
+// Minimal JSON serialization helpers, enough for numcmp's own output, and a
+// parser for reading values out of JSON input.
+
+pub fn string(s: &str) -> String {
+    let mut rv = String::with_capacity(s.len() + 2);
+    rv.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => rv.push_str("\\\""),
+            '\\' => rv.push_str("\\\\"),
+            '\n' => rv.push_str("\\n"),
+            '\r' => rv.push_str("\\r"),
+            '\t' => rv.push_str("\\t"),
+            c if (c as u32) < 0x20 => rv.push_str(&format!("\\u{:04x}", c as u32)),
+            c => rv.push(c),
+        }
+    }
+    rv.push('"');
+    rv
+}
+
+// JSON has no representation for NaN or infinities.
+pub fn number(x: f64) -> String {
+    if x.is_finite() {
+        format!("{}", x)
+    } else {
+        "null".to_string()
+    }
+}
+
+// Builds an object from already-serialized values.
+pub fn object(fields: &[(&str, String)]) -> String {
+    let items: Vec<String> = fields
+        .iter()
+        .map(|(k, v)| format!("{}:{}", string(k), v))
+        .collect();
+    format!("{{{}}}", items.join(","))
+}
+
+pub fn array(items: &[String]) -> String {
+    format!("[{}]", items.join(","))
+}
+
+// A parsed JSON value. Objects keep their keys in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    // Looks up a dotted path such as "metrics.latency_ms"; numeric parts
+    // index into arrays.
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        path.split('.').try_fold(self, |value, key| match value {
+            Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+            _ => None,
+        })
+    }
+}
+
+// Arrays and objects nest at most this deep, so that hostile input cannot
+// exhaust the stack.
+const MAX_DEPTH: usize = 512;
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &str) -> String {
+        format!("{} at offset {}", msg, self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn expect(&mut self, literal: &str) -> Result<(), String> {
+        if self.text[self.pos..].starts_with(literal) {
+            self.pos += literal.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {}", literal)))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("too deeply nested"));
+        }
+        self.depth += 1;
+        let value = self.scalar_or_nested();
+        self.depth -= 1;
+        value
+    }
+
+    fn scalar_or_nested(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Value::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    self.expect(":")?;
+                    fields.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(Value::Object(fields));
+                        }
+                        _ => return Err(self.error("expected , or }")),
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Value::Array(items));
+                        }
+                        _ => return Err(self.error("expected , or ]")),
+                    }
+                }
+            }
+            Some(b'"') => Ok(Value::String(self.string()?)),
+            Some(b't') => self.expect("true").map(|_| Value::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| Value::Bool(false)),
+            Some(b'n') => self.expect("null").map(|_| Value::Null),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.pos;
+                while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.peek() {
+                    self.pos += 1;
+                }
+                self.text[start..self.pos]
+                    .parse()
+                    .map(Value::Number)
+                    .map_err(|_| self.error("invalid number"))
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect("\"")?;
+        let mut rv = String::new();
+        let mut chars = self.text[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(rv);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('u') => {
+                            let mut code = hex4(chars.as_str())
+                                .ok_or_else(|| self.error("invalid \\u escape"))?;
+                            chars.nth(3);
+                            // A high surrogate joins the low surrogate escaped
+                            // after it; unpaired ones cannot be represented.
+                            if (0xd800..0xdc00).contains(&code) {
+                                let low = chars.as_str().strip_prefix("\\u").and_then(hex4);
+                                if let Some(low @ 0xdc00..=0xdfff) = low {
+                                    chars.nth(5);
+                                    code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                                }
+                            }
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        Some(c) => c,
+                        None => break,
+                    };
+                    rv.push(escaped);
+                }
+                c => rv.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+}
+
+// The value of the four hex digits at the start of `s`.
+fn hex4(s: &str) -> Option<u32> {
+    let digits = s.get(..4)?;
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(digits, 16).ok()
+}
+
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        text,
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string_value(text: &str) -> String {
+        match parse(text).unwrap() {
+            Value::String(s) => s,
+            v => panic!("not a string: {:?}", v),
+        }
+    }
+
+    fn number_value(text: &str) -> f64 {
+        match parse(text).unwrap() {
+            Value::Number(x) => x,
+            v => panic!("not a number: {:?}", v),
+        }
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(parse("null"), Ok(Value::Null));
+        assert_eq!(parse(" true "), Ok(Value::Bool(true)));
+        assert_eq!(parse("\tfalse\r\n"), Ok(Value::Bool(false)));
+        assert_eq!(parse("nul"), Err("expected null at offset 0".to_string()));
+        assert_eq!(
+            parse("True"),
+            Err("expected a value at offset 0".to_string())
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(number_value("0"), 0.0);
+        assert_eq!(number_value("-12"), -12.0);
+        assert_eq!(number_value("3.25"), 3.25);
+        assert_eq!(number_value("1e3"), 1000.0);
+        assert_eq!(number_value("1E+3"), 1000.0);
+        assert_eq!(number_value("2.5e-3"), 0.0025);
+        assert_eq!(number_value("-0.5E2"), -50.0);
+        assert_eq!(number_value("1e400"), f64::INFINITY);
+        assert_eq!(
+            number_value("123456789012345678901234567890"),
+            1.2345678901234568e29
+        );
+        for bad in ["-", "1e", "1.2.3", "--1", "1e+-2"] {
+            let offset = bad.len();
+            let message = format!("invalid number at offset {}", offset);
+            assert_eq!(parse(bad), Err(message), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(string_value(r#""plain""#), "plain");
+        assert_eq!(string_value(r#""""#), "");
+        assert_eq!(
+            string_value(r#""a\"b\\c\/d\n\t\r\b\f""#),
+            "a\"b\\c/d\n\t\r\u{8}\u{c}"
+        );
+        assert_eq!(string_value(r#""\u0041\u00e9\u20AC""#), "Aé€");
+        assert_eq!(string_value("\"µs ✓\""), "µs ✓");
+        assert_eq!(
+            parse(r#""\u12""#),
+            Err("invalid \\u escape at offset 1".to_string())
+        );
+        assert!(parse(r#""\u+123""#).is_err());
+        assert_eq!(
+            parse(r#""abc"#),
+            Err("unterminated string at offset 1".to_string())
+        );
+        assert!(parse(r#""abc\"#).is_err());
+    }
+
+    #[test]
+    fn surrogate_pairs() {
+        assert_eq!(string_value(r#""\ud83d\ude00""#), "😀");
+        assert_eq!(string_value(r#""x\uD834\uDD1Ey""#), "x𝄞y");
+        // Unpaired surrogates have no character of their own.
+        assert_eq!(string_value(r#""\ud83d""#), "\u{fffd}");
+        assert_eq!(string_value(r#""\ude00a""#), "\u{fffd}a");
+        assert_eq!(string_value(r#""\ud83dA""#), "\u{fffd}A");
+        assert_eq!(string_value(r#""\ud83d\n""#), "\u{fffd}\n");
+    }
+
+    #[test]
+    fn serialized_strings_parse_back() {
+        for s in [
+            "",
+            "a\"b",
+            "back\\slash",
+            "line\nbreak",
+            "\u{1}\u{1f}",
+            "😀 µs",
+        ] {
+            assert_eq!(string_value(&string(s)), s);
+        }
+        assert_eq!(number(1.5), "1.5");
+        assert_eq!(number(f64::NAN), "null");
+    }
+
+    #[test]
+    fn nesting() {
+        let value = parse(r#"{"a": [1, {"b": [true, null]}, []], "c": {}, "d": "x"}"#).unwrap();
+        assert_eq!(value.get_path("a.0"), Some(&Value::Number(1.0)));
+        assert_eq!(value.get_path("a.1.b.0"), Some(&Value::Bool(true)));
+        assert_eq!(value.get_path("a.1.b.1"), Some(&Value::Null));
+        assert_eq!(value.get_path("a.2"), Some(&Value::Array(vec![])));
+        assert_eq!(value.get_path("c"), Some(&Value::Object(vec![])));
+        assert_eq!(value.get_path("a.3"), None);
+        assert_eq!(value.get_path("d.0"), None);
+        assert_eq!(value.get_path("e"), None);
+        // Keys keep their order.
+        let Value::Object(fields) = parse(r#"{"z": 1, "a": 2}"#).unwrap() else {
+            panic!("not an object");
+        };
+        assert_eq!(fields[0].0, "z");
+        let deep = format!("{}1{}", "[".repeat(100), "]".repeat(100));
+        assert!(parse(&deep).is_ok());
+        let hostile = "[".repeat(100000);
+        assert_eq!(
+            parse(&hostile),
+            Err("too deeply nested at offset 512".to_string())
+        );
+    }
+
+    #[test]
+    fn trailing_garbage() {
+        assert_eq!(
+            parse("1 2"),
+            Err("trailing characters at offset 2".to_string())
+        );
+        assert_eq!(
+            parse("{} x"),
+            Err("trailing characters at offset 3".to_string())
+        );
+        assert!(parse("[1],").is_err());
+        assert!(parse(r#""a" "b""#).is_err());
+        assert!(parse("truex").is_err());
+    }
+
+    #[test]
+    fn malformed_containers() {
+        assert_eq!(
+            parse("[1 2]"),
+            Err("expected , or ] at offset 3".to_string())
+        );
+        assert_eq!(
+            parse("[1,]"),
+            Err("expected a value at offset 3".to_string())
+        );
+        assert_eq!(
+            parse(r#"{"a" 1}"#),
+            Err("expected : at offset 5".to_string())
+        );
+        assert_eq!(
+            parse(r#"{"a":1 "b":2}"#),
+            Err("expected , or } at offset 7".to_string())
+        );
+        assert!(parse("{a:1}").is_err());
+        assert!(parse(r#"{"a":1,}"#).is_err());
+    }
+
+    #[test]
+    fn truncated_input() {
+        let text = r#"{"a": [1.5, -2e3, "xé😀", true, null], "b": {"c": false}}"#;
+        assert!(parse(text).is_ok());
+        for (n, _) in text.char_indices() {
+            assert!(parse(&text[..n]).is_err(), "prefix {:?}", &text[..n]);
+        }
+        assert_eq!(parse(""), Err("expected a value at offset 0".to_string()));
+        assert_eq!(parse("  "), Err("expected a value at offset 2".to_string()));
+    }
+}
@@ -0,0 +1,85 @@
+// Gaussian kernel density estimates, a smooth alternative to histograms for
+// continuous data such as latencies.
+
+use crate::sample::Sample;
+
+// Points at which densities are evaluated for plotting.
+pub const GRID_POINTS: usize = 200;
+
+// How far beyond the data the density is drawn, in bandwidths.
+const TAIL_BANDWIDTHS: f64 = 3.0;
+
+pub fn parse_bandwidth(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(h) if h.is_finite() && h > 0.0 => Ok(h),
+        _ => Err(format!("bandwidth must be a positive number, got {:?}", s)),
+    }
+}
+
+// Silverman's rule of thumb, 0.9 min(sd, IQR / 1.34) n^(-1/5). Constant
+// samples get a bandwidth relative to their value so they still show up as
+// a narrow peak.
+pub fn silverman_bandwidth(sample: &Sample) -> f64 {
+    let mean = sample.mean();
+    let squares: f64 = match &sample.weights {
+        Some(ws) => sample
+            .values
+            .iter()
+            .zip(ws.iter())
+            .map(|(x, w)| w * (x - mean).powi(2))
+            .sum(),
+        None => sample.values.iter().map(|x| (x - mean).powi(2)).sum(),
+    };
+    let sd = (squares / sample.total_weight()).sqrt();
+    let iqr = match (sample.quantile(0.75), sample.quantile(0.25)) {
+        (Ok(q3), Ok(q1)) => q3 - q1,
+        _ => 0.0,
+    };
+    let spread = if iqr > 0.0 { sd.min(iqr / 1.34) } else { sd };
+    let h = 0.9 * spread * (sample.size() as f64).powf(-0.2);
+    if h > 0.0 && h.is_finite() {
+        h
+    } else if mean != 0.0 && mean.is_finite() {
+        mean.abs() * 0.01
+    } else {
+        1.0
+    }
+}
+
+// The density at each of `xs`.
+pub fn density(sample: &Sample, bandwidth: f64, xs: &[f64]) -> Vec<f64> {
+    let norm = 1.0 / (sample.total_weight() * bandwidth * (2.0 * std::f64::consts::PI).sqrt());
+    xs.iter()
+        .map(|x| {
+            let kernel = |v: &f64| (-0.5 * ((x - v) / bandwidth).powi(2)).exp();
+            let sum: f64 = match &sample.weights {
+                Some(ws) => sample
+                    .values
+                    .iter()
+                    .zip(ws.iter())
+                    .map(|(v, w)| w * kernel(v))
+                    .sum(),
+                None => sample.values.iter().map(kernel).sum(),
+            };
+            sum * norm
+        })
+        .collect()
+}
+
+// Evenly spaced points covering every sample and its kernel tails.
+pub fn grid(samples: &[(&Sample, f64)], points: usize) -> Vec<f64> {
+    let mut lo = f64::INFINITY;
+    let mut hi = f64::NEG_INFINITY;
+    for (sample, bandwidth) in samples.iter() {
+        if let (Some(first), Some(last)) = (sample.values.first(), sample.values.last()) {
+            lo = lo.min(first - TAIL_BANDWIDTHS * bandwidth);
+            hi = hi.max(last + TAIL_BANDWIDTHS * bandwidth);
+        }
+    }
+    if !lo.is_finite() || hi <= lo {
+        return Vec::new();
+    }
+    (0..points)
+        .map(|i| lo + (hi - lo) * (i as f64) / ((points - 1) as f64))
+        .collect()
+}
@@ -0,0 +1,3873 @@
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use rand::Rng;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering};
+use std::sync::mpsc;
+use std::time::Instant;
+
+use distance::Distance;
+pub use error::Error;
+use estimator::{
+    default_estimators, BinnedMode, Directed, Direction, Estimator, Kurtosis, Quantile,
+    QuantileEstimator, QuantileMethod, QuantileOptions, Skewness, TailMean,
+};
+use input::{Compression, InputArgs};
+use margin::{Equivalence, Margin};
+use metadata::{InputInfo, Labels, RunMetadata};
+use resample::{BootstrapMethod, Resampler};
+use rng::{RngKind, Seed};
+use sample::Sample;
+use table::{Layout, Table};
+use units::{format_change, DisplayUnit, Notation, Precision, Units};
+
+pub mod api;
+#[cfg(feature = "arrow")]
+mod arrow;
+mod bayes;
+mod cache;
+mod changepoint;
+mod ci;
+mod diff;
+mod distance;
+mod ecdfband;
+mod environment;
+mod error;
+mod estimator;
+mod expr;
+mod fetch;
+#[cfg(not(target_arch = "wasm32"))]
+mod ffi;
+mod gnuplot;
+mod grid;
+mod gzip;
+mod hdr;
+mod input;
+mod jackknife;
+mod json;
+mod kde;
+mod log;
+mod margin;
+mod merge;
+mod metadata;
+mod modality;
+mod omnibus;
+mod output;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod plots;
+mod power;
+#[cfg(feature = "python")]
+mod python;
+mod regex;
+mod replicates;
+mod report;
+mod resample;
+mod rng;
+mod runner;
+mod sample;
+mod sanity;
+mod selftest;
+mod sequential;
+mod sha256;
+mod shift;
+mod stats;
+mod store;
+mod svg;
+mod table;
+mod tdigest;
+mod template;
+mod textplot;
+mod tui;
+mod units;
+mod wasm;
+mod watch;
+#[cfg(target_arch = "wasm32")]
+mod web;
+mod windows;
+
+#[derive(Debug, Parser)]
+#[command(name = "numcmp")]
+#[command(about = "Compare two numeric samples using bootstrapping and simulation")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// File with baseline numbers
+    #[arg(value_name = "BASELINE", required_unless_present = "split_marker")]
+    baseline_filename: Option<PathBuf>,
+
+    /// File with numbers under test
+    #[arg(
+        value_name = "TARGET",
+        required_unless_present_any = ["baseline_group", "split_marker"],
+        conflicts_with = "baseline_group"
+    )]
+    target_filename: Option<PathBuf>,
+
+    /// Read both samples from BASELINE, or stdin if it is "-" or not given, split at the line MARKER: the baseline before it and the target after
+    #[arg(
+        long = "split-marker",
+        value_name = "MARKER",
+        allow_hyphen_values = true,
+        conflicts_with_all = ["target_filename", "baseline_group"]
+    )]
+    split_marker: Option<String>,
+
+    /// Compare every numeric column that both CSV files have, one after the other (text and json output)
+    #[arg(
+        long = "all-columns",
+        requires = "target_filename",
+        conflicts_with_all = [
+            "column",
+            "split_marker",
+            "emit_iterations",
+            "iterations_csv",
+            "report",
+            "plot_dir",
+            "export_gnuplot",
+            "dump_bootstrap",
+            "percentile_grid_csv",
+            "percentile_grid_json"
+        ]
+    )]
+    all_columns: bool,
+
+    /// Rerun the comparison whenever BASELINE or TARGET changes, until interrupted
+    #[arg(long = "watch", conflicts_with = "split_marker")]
+    watch: bool,
+
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    compare: CompareArgs,
+
+    /// Which diagnostics to log to stderr
+    #[arg(long = "log-level", value_enum, default_value = "warn", global = true)]
+    log_level: log::Level,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Store a sample in a results database under a label
+    Record {
+        /// Results database file (SQLite, created if missing; needs the sqlite3 command)
+        #[arg(long = "db", value_name = "FILE")]
+        db: PathBuf,
+
+        /// Label to store the sample under, e.g. a version number
+        #[arg(long = "label")]
+        label: String,
+
+        /// File with numbers to record
+        #[arg(value_name = "FILE")]
+        filename: PathBuf,
+
+        #[command(flatten)]
+        input: InputArgs,
+    },
+
+    /// Compare a sample against a labelled baseline from a results database
+    Check {
+        /// Results database file
+        #[arg(long = "db", value_name = "FILE")]
+        db: PathBuf,
+
+        /// Label of the recorded baseline to compare against
+        #[arg(long = "against", value_name = "LABEL")]
+        against: String,
+
+        /// File with numbers under test
+        #[arg(value_name = "TARGET")]
+        target_filename: PathBuf,
+
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        compare: Box<CompareArgs>,
+    },
+
+    /// Run a command repeatedly and record how long each run takes, optionally comparing against a baseline
+    Run {
+        /// Number of timed runs
+        #[arg(
+            short = 'n',
+            long = "runs",
+            value_name = "N",
+            default_value = "10",
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        runs: usize,
+
+        /// Untimed runs before the timed ones, e.g. to warm up caches
+        #[arg(long = "warmup", value_name = "N", default_value = "0")]
+        warmup: usize,
+
+        /// Which time to record for each run
+        #[arg(long = "measure", value_enum, default_value = "wall")]
+        measure: runner::Measure,
+
+        /// Write the times in seconds, one per line, to this file (default: stdout unless comparing)
+        #[arg(short = 'o', long = "output", value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Compare the times against the baseline numbers in this file
+        #[arg(long = "against", value_name = "BASELINE")]
+        against: Option<PathBuf>,
+
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        compare: Box<CompareArgs>,
+
+        /// The command to run and its arguments, after --
+        #[arg(value_name = "COMMAND", required = true, last = true)]
+        command: Vec<String>,
+    },
+
+    /// Time two commands in alternation, in random order, and compare the first (baseline) with the second (target)
+    Duel {
+        /// Number of timed runs of each command
+        #[arg(
+            short = 'n',
+            long = "runs",
+            value_name = "N",
+            default_value = "10",
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        runs: usize,
+
+        /// Untimed runs of each command before the timed ones
+        #[arg(long = "warmup", value_name = "N", default_value = "0")]
+        warmup: usize,
+
+        /// Which time to record for each run
+        #[arg(long = "measure", value_enum, default_value = "wall")]
+        measure: runner::Measure,
+
+        #[command(flatten)]
+        compare: Box<CompareArgs>,
+
+        /// The two commands, each after --
+        #[arg(value_name = "COMMANDS", required = true, last = true)]
+        commands: Vec<String>,
+    },
+
+    /// Explore a comparison in an interactive terminal view
+    Tui {
+        /// File with baseline numbers
+        #[arg(value_name = "BASELINE")]
+        baseline_filename: PathBuf,
+
+        /// File with numbers under test
+        #[arg(value_name = "TARGET")]
+        target_filename: PathBuf,
+
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        compare: Box<CompareArgs>,
+    },
+
+    /// Compare a target file that is still growing as its values arrive, spending alpha over the repeated looks
+    Follow {
+        /// File with baseline numbers
+        #[arg(value_name = "BASELINE")]
+        baseline_filename: PathBuf,
+
+        /// File with numbers under test, which may still be written to
+        #[arg(value_name = "TARGET")]
+        target_filename: PathBuf,
+
+        /// Number of target values the comparison is planned to end at
+        #[arg(
+            long = "planned",
+            value_name = "N",
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        planned: usize,
+
+        /// Look again after every N new target values
+        #[arg(
+            long = "look-every",
+            value_name = "N",
+            default_value = "10",
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        look_every: usize,
+
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        compare: Box<CompareArgs>,
+    },
+
+    /// Report how an estimator evolved across the runs in a results database
+    Trend {
+        /// Results database file
+        #[arg(long = "db", value_name = "FILE")]
+        db: PathBuf,
+
+        /// Name of the estimator to follow, e.g. p95
+        #[arg(long = "estimator", default_value = "p50")]
+        estimator: String,
+
+        /// Number of simulation iterations per comparison
+        #[arg(
+            short = 'i',
+            long = "iterations",
+            default_value = "10000",
+            value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..)
+        )]
+        iterations: u64,
+
+        /// Significance level for flagging changes and trends
+        #[arg(long = "alpha", default_value = "0.05")]
+        alpha: f64,
+
+        /// Seed for the random number generator (random if not given)
+        #[arg(long = "seed")]
+        seed: Option<u64>,
+    },
+
+    /// Concatenate the samples of several files, e.g. repeated benchmark runs, into one
+    Merge {
+        /// Files with numbers to merge
+        #[arg(value_name = "FILE", required = true)]
+        filenames: Vec<PathBuf>,
+
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// Write the merged sample to this file instead of standard output
+        #[arg(short = 'o', long = "output", value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Print the size, mean and median of each file to standard error
+        #[arg(long = "summaries")]
+        summaries: bool,
+
+        /// Significance level of the check that the files differ
+        #[arg(long = "alpha", default_value = "0.05")]
+        alpha: f64,
+    },
+
+    /// Test whether several samples, e.g. the variants of an experiment, differ at all
+    Groups {
+        /// Files with the numbers of each group
+        #[arg(value_name = "FILE", num_args = 2.., required = true)]
+        filenames: Vec<PathBuf>,
+
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// Number of permutations
+        #[arg(
+            short = 'i',
+            long = "iterations",
+            default_value = "10000",
+            value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..)
+        )]
+        iterations: u64,
+
+        /// Significance level of the test
+        #[arg(long = "alpha", default_value = "0.05")]
+        alpha: f64,
+
+        /// Seed for the random number generator (random if not given)
+        #[arg(long = "seed")]
+        seed: Option<u64>,
+
+        /// Random number generator for the permutations
+        #[arg(long = "rng", value_enum, default_value = "chacha")]
+        rng: RngKind,
+
+        /// How tables are printed in text output
+        #[arg(long = "layout", value_enum, default_value = "auto")]
+        layout: Layout,
+
+        /// Compare every pair of groups, printing a matrix per estimator
+        #[arg(long = "pairwise")]
+        pairwise: bool,
+
+        /// Write the pairwise comparisons as CSV to this file
+        #[arg(long = "pairwise-csv", value_name = "FILE")]
+        pairwise_csv: Option<PathBuf>,
+
+        /// Write the pairwise comparisons as JSON to this file
+        #[arg(long = "pairwise-json", value_name = "FILE")]
+        pairwise_json: Option<PathBuf>,
+    },
+
+    /// Find shifts in level within one series of measurements, in the order they were taken
+    Changepoint {
+        /// File with the series
+        #[arg(value_name = "FILE")]
+        filename: PathBuf,
+
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// Fewest values between change points
+        #[arg(
+            long = "min-segment",
+            value_name = "N",
+            default_value = "10",
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        min_segment: usize,
+
+        /// Number of permutations testing each change point
+        #[arg(
+            short = 'i',
+            long = "iterations",
+            default_value = "1000",
+            value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..)
+        )]
+        iterations: u64,
+
+        /// Significance level of each change point
+        #[arg(long = "alpha", default_value = "0.05")]
+        alpha: f64,
+
+        /// Seed for the random number generator (random if not given)
+        #[arg(long = "seed")]
+        seed: Option<u64>,
+
+        /// Random number generator for the permutations
+        #[arg(long = "rng", value_enum, default_value = "chacha")]
+        rng: RngKind,
+
+        /// How tables are printed in text output
+        #[arg(long = "layout", value_enum, default_value = "auto")]
+        layout: Layout,
+    },
+
+    /// Compare consecutive windows of one series against the first or previous window
+    Windows {
+        /// File with the series
+        #[arg(value_name = "FILE")]
+        filename: PathBuf,
+
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// Number of values per window
+        #[arg(
+            long = "window",
+            value_name = "N",
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        window: usize,
+
+        /// Which window each window is compared against
+        #[arg(long = "against", value_enum, default_value = "first")]
+        against: windows::Against,
+
+        /// Name of the estimator to compare, e.g. p95
+        #[arg(long = "estimator", default_value = "p50")]
+        estimator: String,
+
+        /// Number of simulation iterations per comparison
+        #[arg(
+            short = 'i',
+            long = "iterations",
+            default_value = "10000",
+            value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..)
+        )]
+        iterations: u64,
+
+        /// Significance level for flagging changes
+        #[arg(long = "alpha", default_value = "0.05")]
+        alpha: f64,
+
+        /// Seed for the random number generator (random if not given)
+        #[arg(long = "seed")]
+        seed: Option<u64>,
+
+        /// Random number generator for the simulations
+        #[arg(long = "rng", value_enum, default_value = "chacha")]
+        rng: RngKind,
+
+        /// How tables are printed in text output
+        #[arg(long = "layout", value_enum, default_value = "auto")]
+        layout: Layout,
+    },
+
+    /// Estimate the power to detect a regression of a given size at the baseline's sample size
+    Power {
+        /// File with baseline numbers
+        #[arg(value_name = "BASELINE")]
+        filename: PathBuf,
+
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// Size of the regression to detect (e.g. 5% or 0.5)
+        #[arg(long = "effect", value_name = "MARGIN", value_parser = margin::parse_margin)]
+        effect: Margin,
+
+        /// Significance level of the test
+        #[arg(long = "alpha", default_value = "0.05")]
+        alpha: f64,
+
+        /// Number of simulated comparisons
+        #[arg(
+            short = 'i',
+            long = "iterations",
+            default_value = "2000",
+            value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..)
+        )]
+        iterations: u64,
+
+        /// Seed for the random number generator (random if not given)
+        #[arg(long = "seed")]
+        seed: Option<u64>,
+
+        /// Number of measurements per side (default: the baseline's size)
+        #[arg(long = "size", value_name = "N")]
+        size: Option<usize>,
+    },
+
+    /// Estimate how many measurements per side are needed to detect a regression of a given size
+    Samplesize {
+        /// File with baseline numbers
+        #[arg(value_name = "BASELINE")]
+        filename: PathBuf,
+
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// Size of the regression to detect (e.g. 3% or 0.5)
+        #[arg(long = "effect", value_name = "MARGIN", value_parser = margin::parse_margin)]
+        effect: Margin,
+
+        /// Desired probability of detecting the regression
+        #[arg(long = "power", default_value = "0.8")]
+        power: f64,
+
+        /// Significance level of the test
+        #[arg(long = "alpha", default_value = "0.05")]
+        alpha: f64,
+
+        /// Number of simulated comparisons per candidate size
+        #[arg(
+            short = 'i',
+            long = "iterations",
+            default_value = "1000",
+            value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..)
+        )]
+        iterations: u64,
+
+        /// Seed for the random number generator (random if not given)
+        #[arg(long = "seed")]
+        seed: Option<u64>,
+
+        /// Largest size per side to consider
+        #[arg(long = "max-size", value_name = "N", default_value = "100000")]
+        max_size: usize,
+    },
+
+    /// Check calibration: compare samples from the same known distributions many times and report false-positive rates and interval coverage
+    Selftest {
+        /// Comparisons per distribution
+        #[arg(
+            long = "runs",
+            value_name = "N",
+            default_value = "200",
+            value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..)
+        )]
+        runs: u64,
+
+        /// Number of values per side in each comparison
+        #[arg(long = "size", value_name = "N", default_value = "50")]
+        size: usize,
+
+        #[command(flatten)]
+        compare: Box<CompareArgs>,
+    },
+
+    /// Print a shell completion script, e.g. numcmp completions bash > /etc/bash_completion.d/numcmp
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    /// Print a man page in roff format, e.g. numcmp mangen > /usr/share/man/man1/numcmp.1
+    Mangen,
+
+    /// Report how a comparison changed between two --format json results, e.g. successive runs of a benchmark gate; exit with status 2 if an estimator newly regressed
+    Diff {
+        /// Earlier result
+        #[arg(value_name = "OLD")]
+        old: PathBuf,
+
+        /// Later result
+        #[arg(value_name = "NEW")]
+        new: PathBuf,
+
+        /// How tables are printed
+        #[arg(long = "layout", value_enum, default_value = "auto")]
+        layout: Layout,
+    },
+}
+
+#[derive(Debug, Clone, Args)]
+struct CompareArgs {
+    /// Number of simulation iterations
+    #[arg(
+        short = 'i',
+        long = "iterations",
+        default_value = "10000",
+        value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..)
+    )]
+    iterations: u64,
+
+    /// Keep simulating until the Monte Carlo standard error of every p-value is below this
+    #[arg(long = "target-mc-error", value_name = "SE")]
+    target_mc_error: Option<f64>,
+
+    /// Upper limit on iterations with --target-mc-error
+    #[arg(
+        long = "max-iterations",
+        value_name = "N",
+        default_value = "1000000",
+        value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..)
+    )]
+    max_iterations: u64,
+
+    /// Repeat the simulation with this many seeds and report the spread of the p-values and verdicts
+    #[arg(
+        long = "replicates",
+        value_name = "K",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(2..)
+    )]
+    replicates: Option<usize>,
+
+    /// Significance level used to decide whether an estimator changed
+    #[arg(long = "alpha", default_value = "0.05")]
+    alpha: f64,
+
+    /// Treat the target as one look of a group-sequential design and say whether to stop collecting or continue
+    #[arg(long = "sequential", requires_all = ["max_n", "looks"])]
+    sequential: bool,
+
+    /// Largest target size of the --sequential design
+    #[arg(
+        long = "max-n",
+        value_name = "N",
+        requires = "sequential",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    max_n: Option<usize>,
+
+    /// Number of equally spaced looks of the --sequential design
+    #[arg(
+        long = "looks",
+        value_name = "K",
+        requires = "sequential",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    looks: Option<usize>,
+
+    /// How alpha is spent over the looks of --sequential and numcmp follow
+    #[arg(long = "spending", value_enum, default_value = "obrien-fleming")]
+    spending: sequential::Spending,
+
+    /// Alternative hypothesis whose tail gives the p-value (default: the direction the target moved)
+    #[arg(long = "alternative", value_enum)]
+    alternative: Option<Alternative>,
+
+    /// How simulated values equal to the target's count toward the p-value (default: split with --discrete, otherwise conservative)
+    #[arg(
+        long = "ties",
+        value_enum,
+        default_value = "conservative",
+        default_value_if("discrete", "true", Some("split"))
+    )]
+    ties: Ties,
+
+    /// Treat the data as integer-valued (e.g. whole milliseconds): split ties, use midpoint quantiles (type 2), report the probability of superiority, and show values without spurious decimals (not with --method bayes)
+    #[arg(long = "discrete")]
+    discrete: bool,
+
+    /// Test both samples for normality and advise when the mean is a poor summary
+    #[arg(long = "normality")]
+    normality: bool,
+
+    /// Report the autocorrelation of each sample in input order and its effective sample size
+    #[arg(long = "autocorrelation")]
+    autocorrelation: bool,
+
+    /// Report the Wasserstein (earth mover's) distance between the samples with a bootstrap CI
+    #[arg(long = "wasserstein")]
+    wasserstein: bool,
+
+    /// Report the Jensen-Shannon divergence between the samples' histograms with a bootstrap CI
+    #[arg(long = "js-divergence")]
+    js_divergence: bool,
+
+    /// Report the probability that a target value exceeds a baseline value (A12) with a bootstrap CI
+    #[arg(long = "superiority")]
+    superiority: bool,
+
+    /// Report the difference at each decile with simultaneous bootstrap bands (Doksum's shift function)
+    #[arg(long = "shift-function")]
+    shift_function: bool,
+
+    /// Draw the target ECDF against a simultaneous bootstrap band around the baseline ECDF, flagging where it leaves the band
+    #[arg(long = "ecdf-band")]
+    ecdf_band: bool,
+
+    /// Draw a quantile-quantile plot of the target against the baseline in the terminal
+    #[arg(long = "qq")]
+    qq: bool,
+
+    /// Draw kernel density estimates of both samples in the terminal
+    #[arg(long = "kde")]
+    kde: bool,
+
+    /// Kernel bandwidth for density plots (default: Silverman's rule of thumb for each sample)
+    #[arg(long = "bandwidth", value_name = "H", value_parser = kde::parse_bandwidth)]
+    bandwidth: Option<f64>,
+
+    /// How the quantile estimators (p50 to p99) are computed
+    #[arg(long = "quantile-estimator", value_enum, default_value = "linear")]
+    quantile_estimator: QuantileEstimator,
+
+    /// Sample quantile definition, numbered as R's quantile types 1 to 9 (unweighted samples, not with --method bayes; default: 2 with --discrete, otherwise 7)
+    #[arg(
+        long = "quantile-method",
+        value_enum,
+        value_name = "TYPE",
+        default_value = "7",
+        default_value_if("discrete", "true", Some("2"))
+    )]
+    quantile_method: QuantileMethod,
+
+    /// Which way is better: lower (e.g. latency) or higher (e.g. throughput)
+    #[arg(long = "direction", value_enum)]
+    direction: Option<Direction>,
+
+    /// Which way is better for one estimator, overriding --direction, e.g. p99:lower, or for every estimator of a column with --all-columns, e.g. throughput:higher (repeatable)
+    #[arg(
+        long = "estimator",
+        value_name = "NAME:DIRECTION",
+        value_parser = estimator::parse_directed
+    )]
+    directed: Vec<(String, Direction)>,
+
+    /// Print only one verdict line per estimator; exit with status 2 on regression
+    #[arg(short = 'q', long = "quiet", conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Skip the simulation and print only the summaries and the raw deltas, for a quick look
+    #[arg(long = "no-sim", conflicts_with = "quiet")]
+    no_sim: bool,
+
+    /// Print simulation diagnostics (ties, Monte Carlo error, seed, timings)
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+
+    /// Seed for the random number generator (random if not given)
+    #[arg(long = "seed")]
+    seed: Option<u64>,
+
+    /// Number of threads for the simulation (default: all cores); results do not depend on it
+    #[arg(
+        long = "threads",
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    threads: Option<usize>,
+
+    /// Always simulate, instead of reusing cached results of the same comparison with the same --seed
+    #[arg(long = "no-cache")]
+    no_cache: bool,
+
+    /// Random number generator for the simulations
+    #[arg(long = "rng", value_enum, default_value = "chacha")]
+    rng: RngKind,
+
+    /// Stream the simulated estimator values of every iteration to this file as NDJSON
+    #[arg(long = "emit-iterations", value_name = "FILE")]
+    emit_iterations: Option<PathBuf>,
+
+    /// Write the simulated estimator values to this file as CSV, one row per iteration
+    #[arg(long = "iterations-csv", value_name = "FILE")]
+    iterations_csv: Option<PathBuf>,
+
+    /// Write a standalone HTML report with tables and plots to this file
+    #[arg(long = "report", value_name = "FILE")]
+    report: Option<PathBuf>,
+
+    /// Write SVG plots (histogram, KDE, box plot, bootstrap distributions) into this directory
+    #[arg(long = "plot-dir", value_name = "DIR")]
+    plot_dir: Option<PathBuf>,
+
+    /// Write gnuplot data files and a script reproducing the plots, named with this prefix
+    #[arg(long = "export-gnuplot", value_name = "PREFIX")]
+    export_gnuplot: Option<PathBuf>,
+
+    /// Write the simulated values of each estimator into this directory, one file per estimator
+    #[arg(long = "dump-bootstrap", value_name = "DIR")]
+    dump_bootstrap: Option<PathBuf>,
+
+    /// Percentiles of both samples to list or export, e.g. 1,5,10,25,50,75,90,95,99
+    #[arg(
+        long = "percentile-grid",
+        value_name = "PERCENTS",
+        value_delimiter = ',',
+        value_parser = grid::parse_percent
+    )]
+    percentile_grid: Vec<f64>,
+
+    /// Write the --percentile-grid as CSV to this file instead of listing it
+    #[arg(
+        long = "percentile-grid-csv",
+        value_name = "FILE",
+        requires = "percentile_grid"
+    )]
+    percentile_grid_csv: Option<PathBuf>,
+
+    /// Write the --percentile-grid as JSON to this file instead of listing it
+    #[arg(
+        long = "percentile-grid-json",
+        value_name = "FILE",
+        requires = "percentile_grid"
+    )]
+    percentile_grid_json: Option<PathBuf>,
+
+    /// Record the hostname, CPU model, core count, CPU governor and load average in JSON output and reports
+    #[arg(long = "capture-env")]
+    capture_env: bool,
+
+    /// Output format
+    #[arg(long = "format", value_enum, default_value = "text")]
+    format: Format,
+
+    /// Render the results through this Handlebars-style template instead, with the --format json document as context
+    #[arg(long = "template", value_name = "FILE", conflicts_with_all = ["format", "quiet", "no_sim"])]
+    template: Option<PathBuf>,
+
+    /// Test whether the samples are equivalent within this margin (e.g. 2% or 0.5)
+    #[arg(long = "equivalence-margin", value_name = "MARGIN", value_parser = margin::parse_margin)]
+    equivalence_margin: Option<Margin>,
+
+    /// Only count a regression if the target is significantly worse than the baseline plus this margin
+    #[arg(long = "tolerate", value_name = "MARGIN", value_parser = margin::parse_margin)]
+    tolerate: Option<Margin>,
+
+    /// Call significant changes no larger than this (e.g. 2% or 0.5) "within noise" rather than improved or regressed, as Criterion.rs does; with --ci-method the whole interval must lie beyond it
+    #[arg(long = "noise-threshold", value_name = "MARGIN", value_parser = margin::parse_margin)]
+    noise_threshold: Option<Margin>,
+
+    /// Report the smallest regression each estimator could detect with 80% power
+    #[arg(long = "mde")]
+    mde: bool,
+
+    /// Also report a confidence interval for the difference in each estimator
+    #[arg(long = "ci-method", value_enum, value_name = "METHOD")]
+    ci_method: Option<ci::CiMethod>,
+
+    /// Add the quantile estimator for this level, e.g. 0.999 for p99.9 (repeatable)
+    #[arg(long = "quantile", value_name = "Q", value_parser = estimator::parse_level)]
+    quantiles: Vec<f64>,
+
+    /// Add the mean of the values above this quantile (conditional tail expectation), e.g. 0.99 for cte99 (repeatable)
+    #[arg(long = "cte", value_name = "LEVEL", value_parser = estimator::parse_level)]
+    ctes: Vec<f64>,
+
+    /// First compare two random halves of the baseline, warning if they differ significantly
+    #[arg(long = "ab-sanity")]
+    ab_sanity: bool,
+
+    /// Test each sample for multimodality with Silverman's bootstrap test, warning when one looks multimodal
+    #[arg(long = "modality")]
+    modality: bool,
+
+    /// Add the skewness and excess kurtosis estimators, skew and kurt
+    #[arg(long = "shape")]
+    shape: bool,
+
+    /// Add the mode of the values binned to this width, e.g. 0.5 for mode0.5 (repeatable)
+    #[arg(long = "mode", value_name = "WIDTH", value_parser = estimator::parse_width)]
+    modes: Vec<f64>,
+
+    /// Add a custom estimator, e.g. "quantile(0.995) - quantile(0.5)" (repeatable)
+    #[arg(long = "expr", value_name = "EXPR", value_parser = expr::parse)]
+    exprs: Vec<expr::Expression>,
+
+    /// Add an estimator from a WebAssembly module exporting memory and estimate(ptr, len) -> f64 (repeatable)
+    #[arg(long = "plugin", value_name = "FILE")]
+    plugins: Vec<PathBuf>,
+
+    /// Comparison method
+    #[arg(long = "method", value_enum, default_value = "bootstrap")]
+    method: Method,
+
+    /// How simulated samples are drawn from the baseline
+    #[arg(long = "bootstrap", value_enum, default_value = "iid")]
+    bootstrap: BootstrapMethod,
+
+    /// How the bootstrap simulates the null hypothesis of no difference
+    #[arg(long = "null", value_enum, default_value = "baseline")]
+    null: NullModel,
+
+    /// With --null permutation, enumerate every reassignment exactly when there are at most this many
+    #[arg(long = "exact-max", value_name = "N", default_value = "100000")]
+    exact_max: u64,
+
+    /// Block length for the block bootstrap (default: cube root of the baseline size)
+    #[arg(long = "block-size", value_name = "N")]
+    block_size: Option<usize>,
+
+    /// Mean block length for the stationary bootstrap (default: cube root of the baseline size)
+    #[arg(long = "mean-block-size", value_name = "N")]
+    mean_block_size: Option<f64>,
+
+    /// Subsample size for the subsampling bootstrap (default: n^(2/3) of the baseline)
+    #[arg(long = "m", value_name = "SIZE")]
+    subsample_size: Option<usize>,
+
+    /// Print values in text output as durations in this unit, taking the input to be seconds (or --unit)
+    #[arg(long = "display-unit", value_enum, value_name = "UNIT")]
+    display_unit: Option<DisplayUnit>,
+
+    /// Print values in text output with this many decimal places
+    #[arg(long = "precision", value_name = "N", conflicts_with = "sig_figs")]
+    precision: Option<usize>,
+
+    /// Print values in text output with this many significant digits
+    #[arg(
+        long = "sig-figs",
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=17)
+    )]
+    sig_figs: Option<usize>,
+
+    /// Print values in text output in fixed, scientific or engineering notation
+    #[arg(long = "notation", value_enum, default_value = "fixed")]
+    notation: Notation,
+
+    /// How tables are printed in text output
+    #[arg(long = "layout", value_enum, default_value = "auto")]
+    layout: Layout,
+
+    /// Unit of the input values, e.g. ms, used to label tables, plots and reports
+    #[arg(long = "unit", value_name = "UNIT")]
+    unit: Option<String>,
+
+    /// Name of the baseline in tables, plots and reports, e.g. v1.8
+    #[arg(
+        long = "baseline-label",
+        value_name = "LABEL",
+        default_value = "baseline"
+    )]
+    baseline_label: String,
+
+    /// Name of the target in tables, plots and reports, e.g. v1.9
+    #[arg(long = "target-label", value_name = "LABEL", default_value = "target")]
+    target_label: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+    #[value(name = "openmetrics")]
+    OpenMetrics,
+    Github,
+    Junit,
+    Tap,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Method {
+    /// Compare the target against the simulated distribution of the baseline
+    Bootstrap,
+    /// Bayesian bootstrap: posterior probability and credible interval of the difference
+    Bayes,
+    /// Jackknife standard errors and bias estimates (deterministic, no simulation)
+    Jackknife,
+}
+
+// Exit status used by pass/fail oriented modes when an estimator regressed.
+const REGRESSION_EXIT_CODE: i32 = 2;
+
+fn any_regressed(results: &[EstimatorResult], alpha: f64) -> bool {
+    results
+        .iter()
+        .any(|r| r.verdict(alpha) == Verdict::Regressed)
+}
+
+fn is_sorted(xs: &[f64]) -> bool {
+    for window in xs.windows(2) {
+        if window[0] > window[1] {
+            return false;
+        }
+    }
+    true
+}
+
+fn quantile_index(n: usize, q: f64) -> f64 {
+    // 2 items, quantile 0.5: index should be 0.5
+    // 3 items, quantile 1: index should be 1
+
+    ((n - 1) as f64) * q
+}
+
+fn get_quantile(sorted_numbers: &[f64], q: f64) -> Result<f64, Error> {
+    if sorted_numbers.is_empty() {
+        return Err(Error::EmptySample(
+            "cannot take a quantile of no values".to_string(),
+        ));
+    }
+
+    if !(0.0..=1.0).contains(&q) {
+        return Err(Error::InvalidQuantile(q));
+    }
+
+    debug_assert!(is_sorted(sorted_numbers));
+
+    if q == 0.0 {
+        return Ok(*sorted_numbers
+            .first()
+            .expect("vector was checked to be nonempty"));
+    }
+    if q == 1.0 {
+        return Ok(*sorted_numbers
+            .last()
+            .expect("vector was checked to be nonempty"));
+    }
+
+    let qi = quantile_index(sorted_numbers.len(), q);
+    let qf = qi.floor();
+    let i = qf as usize;
+
+    if (i as f64) == qi {
+        return Ok(sorted_numbers[i]);
+    }
+
+    let t = qi - qf;
+
+    assert!(sorted_numbers.len() >= (i + 2));
+
+    let x0 = sorted_numbers[i];
+    let x1 = sorted_numbers[i + 1];
+
+    Ok(x0 * (1.0 - t) + x1 * t)
+}
+
+fn summary_table(
+    baseline: &Sample,
+    target: &Sample,
+    estimators: &[Box<dyn Estimator>],
+    units: &Units,
+    labels: &Labels,
+) -> Result<Table, Error> {
+    let mut table = Table::new(&["", &labels.baseline, &labels.target, "delta", "change"]);
+    let (b, t) = (baseline.len(), target.len());
+    table.row(vec![
+        "Count".to_string(),
+        b.to_string(),
+        t.to_string(),
+        format!("{:+}", t as i64 - b as i64),
+        format_change(b as f64, t as f64),
+    ]);
+    if baseline.weights.is_some() || target.weights.is_some() {
+        let (b, t) = (baseline.total_weight(), target.total_weight());
+        table.row(vec![
+            "Total weight".to_string(),
+            b.to_string(),
+            t.to_string(),
+            format!("{:+}", t - b),
+            format_change(b, t),
+        ]);
+    }
+
+    for est in estimators.iter() {
+        let (b, t) = (est.estimate(baseline)?, est.estimate(target)?);
+        table.row(vec![
+            est.name().to_string(),
+            units.format(b),
+            units.format(t),
+            units.format(t - b),
+            format_change(b, t),
+        ]);
+    }
+
+    Ok(table)
+}
+
+#[derive(Debug)]
+struct EstimatorResult {
+    name: String,
+    full_baseline_estimator: f64,
+    target_estimator: f64,
+    sim_count: u64,
+    target_lt_sim_count: u64,
+    target_gt_sim_count: u64,
+    target_eq_sim_count: u64,
+    direction: Direction,
+    alternative: Option<Alternative>,
+    ties: Ties,
+    equivalence: Option<Equivalence>,
+    // Set with --tolerate; replaces p_value() when deciding on a regression.
+    tolerated_p_value: Option<f64>,
+    // Set with --noise-threshold, in the units of the data.
+    noise_threshold: Option<f64>,
+    minimum_detectable_effect: Option<f64>,
+    // (1 - alpha) confidence interval for target - baseline, with --ci-method.
+    difference_interval: Option<(f64, f64)>,
+}
+
+// Ties between the target estimator and simulated values are common with
+// discrete data, such as whole milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Ties {
+    /// Count them as at least as extreme as the target
+    Conservative,
+    /// Count them half (the mid-p-value)
+    Split,
+    /// Count them as less extreme than the target
+    Anticonservative,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Alternative {
+    /// The target estimator is lower than the baseline's
+    Less,
+    /// The target estimator is higher than the baseline's
+    Greater,
+    /// The target estimator differs from the baseline's, in either direction
+    TwoSided,
+}
+
+// Normal quantile for the 95% Monte Carlo intervals on reported proportions.
+const MC_INTERVAL_Z: f64 = 1.96;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Verdict {
+    Improved,
+    Regressed,
+    Unchanged,
+    // Significant, but not beyond --noise-threshold.
+    WithinNoise,
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Verdict::Improved => "improved",
+            Verdict::Regressed => "regressed",
+            Verdict::Unchanged => "unchanged",
+            Verdict::WithinNoise => "within noise",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl EstimatorResult {
+    // The fraction of simulated baseline estimators at least as extreme as
+    // the target estimator in the tail given by --alternative (by default,
+    // the direction the target moved), and the factor the p-value is that
+    // fraction times. Ties count as --ties says.
+    fn p_value_tail(&self) -> (f64, f64) {
+        let n = self.sim_count as f64;
+        let ties = self.target_eq_sim_count as f64
+            * match self.ties {
+                Ties::Conservative => 0.0,
+                Ties::Split => 0.5,
+                Ties::Anticonservative => 1.0,
+            };
+        let upper = ((self.sim_count - self.target_gt_sim_count) as f64 - ties) / n;
+        let lower = ((self.sim_count - self.target_lt_sim_count) as f64 - ties) / n;
+        match self.alternative {
+            Some(Alternative::Greater) => (upper, 1.0),
+            Some(Alternative::Less) => (lower, 1.0),
+            Some(Alternative::TwoSided) => (upper.min(lower), 2.0),
+            None if self.target_estimator > self.full_baseline_estimator => (upper, 1.0),
+            None => (lower, 1.0),
+        }
+    }
+
+    fn p_value(&self) -> f64 {
+        let (tail, factor) = self.p_value_tail();
+        (tail * factor).min(1.0)
+    }
+
+    // The smallest p-value other than zero that the iterations can give.
+    fn p_value_floor(&self) -> f64 {
+        let (_, factor) = self.p_value_tail();
+        (factor / self.sim_count as f64).min(1.0)
+    }
+
+    // The p-value as shown to people: a zero only means that it is below
+    // the floor.
+    fn p_value_text(&self) -> String {
+        match self.p_value() {
+            0.0 => format!("< {}", self.p_value_floor()),
+            p => p.to_string(),
+        }
+    }
+
+    // 95% interval for the p-value reflecting only the Monte Carlo error of
+    // the finite number of iterations.
+    fn p_value_interval(&self) -> (f64, f64) {
+        let n = self.sim_count as f64;
+        let (tail, factor) = self.p_value_tail();
+        let (lo, hi) = stats::wilson_interval(tail * n, n, MC_INTERVAL_Z);
+        ((lo * factor).min(1.0), (hi * factor).min(1.0))
+    }
+
+    // The Monte Carlo error of the p-value for --target-mc-error: the
+    // half-width of its Wilson interval in standard errors. Unlike the plain
+    // standard error it is not zero while no simulated value is as extreme
+    // as the target.
+    fn p_value_mc_error(&self) -> f64 {
+        let (lo, hi) = self.p_value_interval();
+        (hi - lo) / (2.0 * MC_INTERVAL_Z)
+    }
+
+    fn p_value_std_error(&self) -> f64 {
+        let (tail, factor) = self.p_value_tail();
+        factor * (tail * (1.0 - tail) / (self.sim_count as f64)).sqrt()
+    }
+
+    fn is_worse(&self) -> bool {
+        match self.direction {
+            Direction::LowerIsBetter => self.target_estimator > self.full_baseline_estimator,
+            Direction::HigherIsBetter => self.target_estimator < self.full_baseline_estimator,
+        }
+    }
+
+    // Whether the change, or with --ci-method any part of its interval, is
+    // no larger than --noise-threshold.
+    fn within_noise(&self) -> bool {
+        let Some(threshold) = self.noise_threshold else {
+            return false;
+        };
+        let delta = self.target_estimator - self.full_baseline_estimator;
+        let (lo, hi) = self.difference_interval.unwrap_or((delta, delta));
+        if delta > 0.0 {
+            lo <= threshold
+        } else {
+            hi >= -threshold
+        }
+    }
+
+    fn verdict(&self, alpha: f64) -> Verdict {
+        let worse = self.is_worse();
+        let p = match self.tolerated_p_value {
+            Some(p) if worse => p,
+            _ => self.p_value(),
+        };
+        if self.target_estimator == self.full_baseline_estimator || p >= alpha {
+            Verdict::Unchanged
+        } else if self.within_noise() {
+            Verdict::WithinNoise
+        } else if worse {
+            Verdict::Regressed
+        } else {
+            Verdict::Improved
+        }
+    }
+}
+
+// How many iterations to simulate: a fixed number, or until the Monte Carlo
+// error of every p-value drops below a target. The errors are checked after
+// every chunk of iterations.
+#[derive(Debug, Clone, Copy)]
+enum Stopping {
+    Fixed(u64),
+    McError { target: f64, max_iterations: u64 },
+}
+
+impl Stopping {
+    fn from_args(args: &CompareArgs) -> Stopping {
+        match args.target_mc_error {
+            Some(target) => Stopping::McError {
+                target,
+                max_iterations: args.max_iterations,
+            },
+            None => Stopping::Fixed(args.iterations),
+        }
+    }
+
+    fn max_iterations(&self) -> u64 {
+        match self {
+            Stopping::Fixed(n) => *n,
+            Stopping::McError { max_iterations, .. } => *max_iterations,
+        }
+    }
+}
+
+// Called after each iteration with the simulated value of every estimator.
+type IterationCallback<'a> = dyn FnMut(u64, &[f64]) -> Result<(), Error> + 'a;
+
+// Builds a fresh set of estimators for a worker thread, since estimators
+// may keep state (caches, plugin instances) that cannot be shared.
+type EstimatorFactory<'a> = dyn Fn() -> Result<Vec<Box<dyn Estimator>>, Error> + Sync + 'a;
+
+// Simulates a chunk with the given estimators, and tallies a simulated chunk
+// returning whether to stop.
+type ChunkSimulator<'a> = dyn Fn(u64, &[Box<dyn Estimator>]) -> Result<Vec<f64>, Error> + Sync + 'a;
+type ChunkConsumer<'a> = dyn FnMut(u64, &[f64]) -> Result<bool, Error> + 'a;
+
+// Simulation on several threads, each with its own estimators.
+struct Parallel<'a> {
+    threads: usize,
+    estimators: &'a EstimatorFactory<'a>,
+}
+
+// Simulates one chunk of iterations from the chunk's own random stream,
+// returning the rescaled estimator values iteration by iteration.
+#[allow(clippy::too_many_arguments)]
+fn simulate_chunk(
+    seed: Seed,
+    chunk: u64,
+    iterations: u64,
+    resampler: &Resampler,
+    baseline: &Sample,
+    n: usize,
+    estimators: &[Box<dyn Estimator>],
+    baseline_estimates: &[f64],
+    draw: &Draw,
+    differences: Option<&Differences>,
+) -> Result<Vec<f64>, Error> {
+    let mut rng = seed.stream(chunk);
+    let mut resample = Sample::default();
+    let mut target_resample = Sample::default();
+    let mut pooled = Vec::new();
+    // The other null models keep the baseline's own size.
+    let n = match draw {
+        Draw::Baseline => n,
+        _ => baseline.size(),
+    };
+    resample.values.reserve_exact(n);
+    let sort = estimators.iter().any(|e| e.requires_sorted());
+    let sort_values = |out: &mut Sample| {
+        if sort && out.weights.is_none() {
+            out.values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        }
+    };
+
+    let stride = estimators.len() * (1 + differences.map_or(0, |d| d.values_per_iteration()));
+    let mut values = Vec::with_capacity((iterations as usize) * stride);
+    for _ in 0..iterations {
+        match draw {
+            Draw::Baseline => resampler.resample(&mut rng, baseline, n, &mut resample),
+            Draw::Aligned(aligned) => {
+                resampler.resample(&mut rng, baseline, n, &mut resample);
+                let m = aligned.target.size();
+                aligned
+                    .resampler
+                    .resample(&mut rng, aligned.target, m, &mut target_resample);
+            }
+            Draw::Permutation(values) => {
+                // A partial Fisher-Yates shuffle picks the target's values.
+                pooled.clone_from(values);
+                let m = pooled.len() - n;
+                for i in 0..m {
+                    let j = rng.gen_range(i..pooled.len());
+                    pooled.swap(i, j);
+                }
+                target_resample.values.clear();
+                target_resample.values.extend_from_slice(&pooled[..m]);
+                resample.values.clear();
+                resample.values.extend_from_slice(&pooled[m..]);
+            }
+        }
+        sort_values(&mut resample);
+        if !matches!(draw, Draw::Baseline) {
+            sort_values(&mut target_resample);
+        }
+        for (i, (est, full)) in estimators.iter().zip(baseline_estimates.iter()).enumerate() {
+            let estimate = est.estimate(&resample)?;
+            let b = resampler.rescale(estimate, *full, n);
+            values.push(match draw {
+                Draw::Baseline => b,
+                Draw::Aligned(aligned) => {
+                    let target_full = aligned.estimates[i];
+                    let m = aligned.target.size();
+                    let t =
+                        aligned
+                            .resampler
+                            .rescale(est.estimate(&target_resample)?, target_full, m);
+                    full + (t - target_full) - (b - full)
+                }
+                Draw::Permutation(_) => full + est.estimate(&target_resample)? - estimate,
+            });
+        }
+        if let Some(d) = differences {
+            if !d.share_baseline {
+                ci::draw(&mut rng, &d.baseline, baseline, &mut resample);
+            }
+            if !d.share_target {
+                ci::draw(&mut rng, &d.target, d.target_sample, &mut target_resample);
+            }
+            ci::replicate(
+                &mut rng,
+                d.method,
+                &resample,
+                &target_resample,
+                estimators,
+                &mut values,
+            )?;
+        }
+    }
+    Ok(values)
+}
+
+// Bootstrap replicates of target - baseline for --ci-method, drawn in the
+// same pass as the null distribution. Each iteration resamples both samples
+// with replacement at their own sizes, reusing the null model's resamples
+// where they are such draws instead of drawing again.
+struct Differences<'a> {
+    method: ci::CiMethod,
+    baseline: Resampler,
+    target: Resampler,
+    target_sample: &'a Sample,
+    share_baseline: bool,
+    share_target: bool,
+}
+
+impl Differences<'_> {
+    // Values each iteration adds per estimator after its null value.
+    fn values_per_iteration(&self) -> usize {
+        ci::values_per_replicate(self.method)
+    }
+}
+
+// The target side of a null-aligned bootstrap test, which resamples both
+// samples, each around its own estimate, so that the simulated difference
+// has the null hypothesis of no difference built in. Each simulated value
+// is the baseline estimate plus (T* - T) - (B* - B), to be compared with
+// the target estimate like a resampled baseline estimate.
+struct Aligned<'a> {
+    resampler: Resampler,
+    target: &'a Sample,
+    estimates: Vec<f64>,
+}
+
+// How simulate_chunk draws an iteration. A permutation reassigns the
+// pooled values to the two samples, giving the baseline estimate plus
+// T* - B*.
+enum Draw<'a> {
+    Baseline,
+    Aligned(Aligned<'a>),
+    Permutation(Vec<f64>),
+}
+
+// How simulate() is to simulate the null hypothesis of no difference.
+enum Null {
+    Baseline,
+    // Also resample the target with this resampler.
+    Aligned(Resampler),
+    // Reassign the pooled values, in every possible way if there are at
+    // most this many.
+    Permutation { exact_max: u64 },
+}
+
+// The number of ways to choose k of n items, if it fits in a u64.
+fn combinations(n: usize, k: usize) -> Option<u64> {
+    let k = k.min(n - k);
+    let mut c: u128 = 1;
+    for i in 0..k {
+        c = c * (n - i) as u128 / (i + 1) as u128;
+        if c > u64::MAX as u128 {
+            return None;
+        }
+    }
+    Some(c as u64)
+}
+
+// The simulated values of every reassignment of the sorted pooled values
+// to a target of size m and a baseline of the rest, in lexicographic order
+// of the target's positions.
+fn exact_permutations(
+    pooled: &[f64],
+    m: usize,
+    estimators: &[Box<dyn Estimator>],
+    baseline_estimates: &[f64],
+) -> Result<Vec<f64>, Error> {
+    let n = pooled.len();
+    let mut chosen: Vec<usize> = (0..m).collect();
+    let mut baseline = Sample::default();
+    let mut target = Sample::default();
+    let mut values = Vec::new();
+    loop {
+        baseline.values.clear();
+        target.values.clear();
+        let mut next = chosen.iter().peekable();
+        for (i, x) in pooled.iter().enumerate() {
+            if next.peek() == Some(&&i) {
+                next.next();
+                target.values.push(*x);
+            } else {
+                baseline.values.push(*x);
+            }
+        }
+        for (est, full) in estimators.iter().zip(baseline_estimates.iter()) {
+            values.push(full + est.estimate(&target)? - est.estimate(&baseline)?);
+        }
+        let mut i = m;
+        while i > 0 && chosen[i - 1] == n - m + i - 1 {
+            i -= 1;
+        }
+        if i == 0 {
+            return Ok(values);
+        }
+        chosen[i - 1] += 1;
+        for j in i..m {
+            chosen[j] = chosen[j - 1] + 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum NullModel {
+    /// Resample the baseline only, at the target's size
+    Baseline,
+    /// Resample both samples, each recentered on its own estimate
+    Aligned,
+    /// Reassign the pooled values to the two samples at random, or in every possible way for small samples (see --exact-max)
+    Permutation,
+}
+
+// Hands out chunks to worker threads and passes their values to `consume`
+// in chunk order until it returns true or the chunks run out.
+fn simulate_parallel(
+    parallel: &Parallel,
+    chunks: u64,
+    simulate: &ChunkSimulator,
+    consume: &mut ChunkConsumer,
+) -> Result<(), Error> {
+    let next = AtomicU64::new(0);
+    let stop = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        let (tx, rx) = mpsc::sync_channel(parallel.threads);
+        for _ in 0..parallel.threads {
+            let tx = tx.clone();
+            let (next, stop) = (&next, &stop);
+            scope.spawn(move || {
+                let estimators = match (parallel.estimators)() {
+                    Ok(estimators) => estimators,
+                    Err(e) => {
+                        let _ = tx.send((0, Err(e)));
+                        return;
+                    }
+                };
+                while !stop.load(AtomicOrdering::Relaxed) {
+                    let chunk = next.fetch_add(1, AtomicOrdering::Relaxed);
+                    if chunk >= chunks || tx.send((chunk, simulate(chunk, &estimators))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        let mut consume_in_order = || -> Result<(), Error> {
+            let mut pending = BTreeMap::new();
+            let mut expected = 0;
+            for (chunk, values) in rx.iter() {
+                pending.insert(chunk, values?);
+                while let Some(values) = pending.remove(&expected) {
+                    if consume(expected, &values)? {
+                        return Ok(());
+                    }
+                    expected += 1;
+                }
+            }
+            Ok(())
+        };
+        let outcome = consume_in_order();
+        // Workers blocked on a full channel give up once it is dropped.
+        stop.store(true, AtomicOrdering::Relaxed);
+        drop(rx);
+        outcome
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn simulate(
+    stopping: Stopping,
+    alternative: Option<Alternative>,
+    seed: Seed,
+    resampler: &Resampler,
+    baseline: &Sample,
+    target: &Sample,
+    estimators: &[Box<dyn Estimator>],
+    null: Null,
+    // --ci-method and the confidence level of the difference intervals.
+    intervals: Option<(ci::CiMethod, f64)>,
+    parallel: Option<Parallel>,
+    on_iteration: &mut IterationCallback,
+) -> Result<Vec<EstimatorResult>, Error> {
+    debug_assert!(is_sorted(&baseline.values));
+
+    let mut results: Vec<EstimatorResult> = Vec::new();
+
+    for est in estimators.iter() {
+        results.push(EstimatorResult {
+            name: est.name().to_string(),
+            full_baseline_estimator: est.estimate(baseline)?,
+            target_estimator: est.estimate(target)?,
+            sim_count: 0,
+            target_lt_sim_count: 0,
+            target_gt_sim_count: 0,
+            target_eq_sim_count: 0,
+            direction: est.direction(),
+            alternative,
+            ties: Ties::Conservative,
+            equivalence: None,
+            tolerated_p_value: None,
+            noise_threshold: None,
+            minimum_detectable_effect: None,
+            difference_interval: None,
+        });
+    }
+
+    let n = target.size();
+    let baseline_estimates: Vec<f64> = results.iter().map(|r| r.full_baseline_estimator).collect();
+    let pooled = || -> Result<Vec<f64>, Error> {
+        if baseline.weights.is_some() || target.weights.is_some() {
+            return Err(Error::Usage(
+                "permutations need unweighted samples".to_string(),
+            ));
+        }
+        let mut pooled = [baseline.values.as_slice(), target.values.as_slice()].concat();
+        pooled.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        Ok(pooled)
+    };
+    let differences = match intervals {
+        Some((method, _)) => Some(Differences {
+            method,
+            baseline: Resampler::new(BootstrapMethod::Iid, None, None, None, baseline)?,
+            target: Resampler::new(BootstrapMethod::Iid, None, None, None, target)?,
+            target_sample: target,
+            share_baseline: match &null {
+                Null::Baseline => n == baseline.size() && resampler.is_iid(),
+                Null::Aligned(_) => resampler.is_iid(),
+                Null::Permutation { .. } => false,
+            },
+            share_target: matches!(&null, Null::Aligned(r) if r.is_iid()),
+        }),
+        None => None,
+    };
+    let mut exact = None;
+    let draw = match null {
+        Null::Baseline => Draw::Baseline,
+        Null::Aligned(resampler) => Draw::Aligned(Aligned {
+            resampler,
+            target,
+            estimates: results.iter().map(|r| r.target_estimator).collect(),
+        }),
+        Null::Permutation { exact_max } => {
+            let pooled = pooled()?;
+            if combinations(pooled.len(), n).is_some_and(|c| c <= exact_max) {
+                exact = Some(exact_permutations(
+                    &pooled,
+                    n,
+                    estimators,
+                    &baseline_estimates,
+                )?);
+            }
+            Draw::Permutation(pooled)
+        }
+    };
+    let max_iterations = stopping.max_iterations();
+    let chunk_iterations =
+        |chunk: u64| (max_iterations - chunk * rng::CHUNK_SIZE).min(rng::CHUNK_SIZE);
+    let chunks = max_iterations.div_ceil(rng::CHUNK_SIZE);
+
+    // Exact permutations leave no resampling pass to share.
+    let differences = differences.filter(|_| exact.is_none());
+    let k = estimators.len();
+    let stride = (k * (1 + differences.as_ref().map_or(0, |d| d.values_per_iteration()))).max(1);
+    let mut replicates = ci::Replicates::new(k);
+
+    let mut consume = |chunk: u64, values: &[f64]| -> Result<bool, Error> {
+        for (i, iteration) in values.chunks(stride).enumerate() {
+            let (sim_vals, replicate) = iteration.split_at(k.min(iteration.len()));
+            if differences.is_some() {
+                replicates.push(replicate);
+            }
+            for (res, sim_val) in results.iter_mut().zip(sim_vals.iter()) {
+                res.sim_count += 1;
+
+                match res
+                    .target_estimator
+                    .partial_cmp(sim_val)
+                    .expect("estimator should not be NaN")
+                {
+                    Ordering::Less => {
+                        res.target_lt_sim_count += 1;
+                    }
+                    Ordering::Greater => {
+                        res.target_gt_sim_count += 1;
+                    }
+                    Ordering::Equal => {
+                        res.target_eq_sim_count += 1;
+                    }
+                }
+            }
+
+            on_iteration(chunk * rng::CHUNK_SIZE + i as u64, sim_vals)?;
+        }
+        if let Some(r) = results.first() {
+            tracing::debug!(
+                iterations = r.sim_count,
+                total = max_iterations,
+                "simulated"
+            );
+        }
+
+        // Chunks end on a multiple of the check interval.
+        Ok(match stopping {
+            Stopping::McError { target, .. } => {
+                results.iter().all(|r| r.p_value_mc_error() < target)
+            }
+            Stopping::Fixed(_) => false,
+        })
+    };
+
+    let simulate = |chunk: u64, estimators: &[Box<dyn Estimator>]| {
+        simulate_chunk(
+            seed,
+            chunk,
+            chunk_iterations(chunk),
+            resampler,
+            baseline,
+            n,
+            estimators,
+            &baseline_estimates,
+            &draw,
+            differences.as_ref(),
+        )
+    };
+    let exhaustive = exact.is_some();
+    if let Some(values) = exact {
+        let chunk_values = (rng::CHUNK_SIZE as usize) * stride;
+        for (chunk, values) in values.chunks(chunk_values).enumerate() {
+            consume(chunk as u64, values)?;
+        }
+    } else {
+        match parallel {
+            Some(parallel) if parallel.threads > 1 && chunks > 1 => {
+                simulate_parallel(&parallel, chunks, &simulate, &mut consume)?;
+            }
+            _ => {
+                for chunk in 0..chunks {
+                    if consume(chunk, &simulate(chunk, estimators)?)? {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some((method, level)) = intervals {
+        let observed: Vec<f64> = results
+            .iter()
+            .map(|r| r.target_estimator - r.full_baseline_estimator)
+            .collect();
+        let intervals = if exhaustive {
+            ci::difference_intervals(
+                method,
+                max_iterations,
+                seed,
+                baseline,
+                target,
+                estimators,
+                level,
+            )?
+        } else {
+            replicates.intervals(method, &observed, level)?
+        };
+        for (result, (_, lo, hi)) in results.iter_mut().zip(intervals) {
+            result.difference_interval = Some((lo, hi));
+        }
+    }
+    Ok(results)
+}
+
+fn write_iteration_ndjson(
+    out: &mut impl Write,
+    estimators: &[Box<dyn Estimator>],
+    iteration: u64,
+    sim_vals: &[f64],
+) -> Result<(), Error> {
+    write!(out, "{{\"iteration\":{},\"estimators\":{{", iteration)?;
+    for (i, (est, val)) in estimators.iter().zip(sim_vals.iter()).enumerate() {
+        if i > 0 {
+            write!(out, ",")?;
+        }
+        write!(out, "{}:{}", json::string(est.name()), json::number(*val))?;
+    }
+    writeln!(out, "}}}}")?;
+    Ok(())
+}
+
+// Quotes a CSV field when it contains a separator, quote or line break.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn write_iterations_csv_header(
+    out: &mut impl Write,
+    estimators: &[Box<dyn Estimator>],
+) -> Result<(), Error> {
+    write!(out, "iteration")?;
+    for est in estimators.iter() {
+        write!(out, ",{}", csv_field(est.name()))?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn write_iteration_csv(
+    out: &mut impl Write,
+    iteration: u64,
+    sim_vals: &[f64],
+) -> Result<(), Error> {
+    write!(out, "{}", iteration)?;
+    for val in sim_vals.iter() {
+        write!(out, ",{}", val)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn print_diagnostics(seed: u64, results: &[EstimatorResult], timings: &[(&str, f64)]) {
+    println!("=== Diagnostics ===");
+    println!("Seed:\t{}", seed);
+    for result in results.iter() {
+        let (lo, hi) = result.p_value_interval();
+        println!(
+            "{}: iterations {}, ties {}, p-value {} (MC s.e. {:.6}, 95% CI {:.6}-{:.6})",
+            result.name,
+            result.sim_count,
+            result.target_eq_sim_count,
+            result.p_value_text(),
+            result.p_value_std_error(),
+            lo,
+            hi
+        );
+    }
+    for (phase, secs) in timings.iter() {
+        println!("Time ({}):\t{:.3}s", phase, secs);
+    }
+}
+
+fn spread_verdict(spread: &stats::SpreadTest, alpha: f64) -> &'static str {
+    if spread.p_value >= alpha {
+        "unchanged"
+    } else if spread.spreads.1 > spread.spreads.0 {
+        "spread increased"
+    } else {
+        "spread decreased"
+    }
+}
+
+// Below this Anderson-Darling p-value a sample counts as strongly
+// non-normal.
+const NORMALITY_ADVISORY_P: f64 = 0.01;
+
+fn print_normality(baseline: &Sample, target: &Sample, labels: &Labels) {
+    println!("=== Normality (Anderson-Darling) ===");
+    let mut non_normal = false;
+    for (role, sample) in [(&labels.baseline, baseline), (&labels.target, target)] {
+        if sample.weights.is_some() {
+            println!("{}: not available for weighted samples", role);
+            continue;
+        }
+        match stats::anderson_darling(&sample.values) {
+            Some((a, p)) => {
+                println!("{}: A*² = {:.4}, p = {:.4}", role, a, p);
+                non_normal |= p < NORMALITY_ADVISORY_P;
+            }
+            None => println!("{}: needs at least 8 values with some spread", role),
+        }
+    }
+    if non_normal {
+        println!(
+            "Advisory: data strongly non-normal; rely on quantile/bootstrap results rather than the mean."
+        );
+    }
+}
+
+// Bootstrap iterations of --modality at most.
+const MODALITY_ITERATIONS: u64 = 1000;
+
+// Lags shown by --autocorrelation.
+const AUTOCORRELATION_LAGS: usize = 5;
+
+// Below this fraction of effective to actual sample size, the IID bootstrap
+// is warned against.
+const AUTOCORRELATION_WARNING_ESS: f64 = 0.5;
+
+// Whole-number samples averaging at least this many copies of each value
+// are pointed to --discrete.
+const DISCRETE_HINT_TIES: usize = 4;
+
+fn print_autocorrelation(baseline: &Sample, target: &Sample, labels: &Labels) {
+    println!("=== Autocorrelation (input order) ===");
+    for (role, sample) in [(&labels.baseline, baseline), (&labels.target, target)] {
+        if sample.weights.is_some() {
+            println!("{}: not available for weighted samples", role);
+            continue;
+        }
+        let xs = &sample.input_order;
+        let lags: Vec<String> = (1..=AUTOCORRELATION_LAGS.min(xs.len().saturating_sub(1)))
+            .map(|lag| format!("lag {} r={:.3}", lag, stats::autocorrelation(xs, lag)))
+            .collect();
+        println!(
+            "{}: {}; effective sample size {:.0} of {}",
+            role,
+            lags.join(", "),
+            stats::effective_sample_size(xs),
+            xs.len()
+        );
+    }
+}
+
+// Warns when a sample is autocorrelated enough that the IID bootstrap,
+// which treats every value as independent, overstates the evidence.
+fn warn_autocorrelation(args: &CompareArgs, baseline: &Sample, target: &Sample) {
+    if args.method != Method::Bootstrap || args.bootstrap != BootstrapMethod::Iid {
+        return;
+    }
+    for (role, sample) in [("baseline", baseline), ("target", target)] {
+        // Strata are often read in blocks, which looks like autocorrelation.
+        if sample.weights.is_some() || sample.strata.is_some() {
+            continue;
+        }
+        let n = sample.input_order.len();
+        let ess = stats::effective_sample_size(&sample.input_order);
+        if ess < AUTOCORRELATION_WARNING_ESS * n as f64 {
+            tracing::warn!(
+                "the {} is strongly autocorrelated (effective sample size {:.0} of {}); p-values assume independent values, consider --bootstrap block",
+                role, ess, n
+            );
+        }
+    }
+}
+
+// Rejects values that are not whole numbers under --discrete, and without
+// it suggests --discrete for integer samples with many ties.
+fn check_discrete(args: &CompareArgs, baseline: &Sample, target: &Sample) -> Result<(), Error> {
+    let fractional = [("baseline", baseline), ("target", target)]
+        .into_iter()
+        .find_map(|(role, sample)| {
+            sample
+                .values
+                .iter()
+                .find(|x| x.fract() != 0.0)
+                .map(|x| (role, *x))
+        });
+    match (args.discrete, fractional) {
+        (true, Some((role, x))) => Err(Error::InvalidInput(format!(
+            "--discrete needs whole numbers, but the {} has {}",
+            role, x
+        ))),
+        (false, None) => {
+            let distinct = |s: &Sample| s.values.windows(2).filter(|w| w[0] != w[1]).count() + 1;
+            let n = baseline.len() + target.len();
+            if distinct(baseline) + distinct(target) <= n / DISCRETE_HINT_TIES {
+                tracing::info!("the samples are whole numbers with many ties; consider --discrete");
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+// Warns when --alpha is finer than the iterations can resolve, suggesting
+// enough iterations for about ten simulated values beyond it.
+fn warn_p_value_floor(args: &CompareArgs, results: &[EstimatorResult]) {
+    let Some(r) = results
+        .iter()
+        .max_by(|a, b| a.p_value_floor().partial_cmp(&b.p_value_floor()).unwrap())
+    else {
+        return;
+    };
+    let floor = r.p_value_floor();
+    if args.alpha <= floor {
+        let (_, factor) = r.p_value_tail();
+        tracing::warn!(
+            "p-values cannot resolve alpha {}: with {} iterations the smallest above zero is {}; use at least --iterations {}",
+            args.alpha,
+            r.sim_count,
+            floor,
+            (10.0 * factor / args.alpha).ceil()
+        );
+    }
+}
+
+// Runs the simulation, streaming iterations to --emit-iterations if given.
+// The full simulated distribution of each estimator is kept only when some
+// output needs it; otherwise the returned distributions are empty.
+fn run_simulation(
+    args: &CompareArgs,
+    seed: Seed,
+    baseline: &Sample,
+    target: &Sample,
+    estimators: &[Box<dyn Estimator>],
+) -> Result<(Vec<EstimatorResult>, Vec<Vec<f64>>), Error> {
+    let keep_distributions = args.report.is_some()
+        || args.plot_dir.is_some()
+        || args.export_gnuplot.is_some()
+        || args.dump_bootstrap.is_some()
+        || args.equivalence_margin.is_some()
+        || args.tolerate.is_some()
+        || args.mde;
+    let mut distributions: Vec<Vec<f64>> = vec![Vec::new(); estimators.len()];
+    let mut ndjson = match &args.emit_iterations {
+        Some(path) => Some(BufWriter::new(File::create(path)?)),
+        None => None,
+    };
+    let mut csv = match &args.iterations_csv {
+        Some(path) => {
+            let mut out = BufWriter::new(File::create(path)?);
+            write_iterations_csv_header(&mut out, estimators)?;
+            Some(out)
+        }
+        None => None,
+    };
+
+    let resampler = match baseline.strata {
+        Some(_) => Resampler::stratified(baseline, target)?,
+        None => Resampler::new(
+            args.bootstrap,
+            args.block_size,
+            args.mean_block_size,
+            args.subsample_size,
+            baseline,
+        )?,
+    };
+    if let Resampler::Subsample { m } = resampler {
+        let names: Vec<&str> = estimators
+            .iter()
+            .filter(|e| !e.sqrt_n_rate(m))
+            .map(|e| e.name())
+            .collect();
+        if !names.is_empty() {
+            tracing::warn!(
+                "--bootstrap subsample rescales by sqrt(m/n), which assumes estimators converging at the sqrt(n) rate; with subsamples of {} values the p-values of {} are unreliable",
+                m,
+                names.join(", ")
+            );
+        }
+    }
+    let null = match args.null {
+        NullModel::Baseline => Null::Baseline,
+        NullModel::Aligned => Null::Aligned(Resampler::new(
+            args.bootstrap,
+            args.block_size,
+            args.mean_block_size,
+            args.subsample_size,
+            target,
+        )?),
+        NullModel::Permutation => Null::Permutation {
+            exact_max: args.exact_max,
+        },
+    };
+    let threads = args
+        .threads
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+    let cache_key = simulation_cache_key(args, seed, baseline, target, keep_distributions);
+    let cached = cache_key
+        .as_deref()
+        .and_then(cache::load)
+        .and_then(|rows| results_from_rows(args, estimators, baseline, target, rows));
+    let hit = cached.is_some();
+    let mut results = match cached {
+        Some((results, cached_distributions)) => {
+            distributions = cached_distributions;
+            results
+        }
+        None => simulate(
+            Stopping::from_args(args),
+            args.alternative,
+            seed,
+            &resampler,
+            baseline,
+            target,
+            estimators,
+            null,
+            args.ci_method.map(|method| (method, 1.0 - args.alpha)),
+            Some(Parallel {
+                threads,
+                estimators: &|| crate::estimators(args),
+            }),
+            &mut |iteration, sim_vals| {
+                if let Some(out) = ndjson.as_mut() {
+                    write_iteration_ndjson(out, estimators, iteration, sim_vals)?;
+                }
+                if let Some(out) = csv.as_mut() {
+                    write_iteration_csv(out, iteration, sim_vals)?;
+                }
+                if keep_distributions {
+                    for (dist, val) in distributions.iter_mut().zip(sim_vals.iter()) {
+                        dist.push(*val);
+                    }
+                }
+                Ok(())
+            },
+        )?,
+    };
+    if let (Some(key), false) = (&cache_key, hit) {
+        cache::store(key, &results_to_rows(&results, &distributions));
+    }
+
+    if let Some(mut out) = ndjson {
+        out.flush()?;
+    }
+    if let Some(mut out) = csv {
+        out.flush()?;
+    }
+    for result in results.iter_mut() {
+        result.ties = args.ties;
+    }
+    if let Some(margin) = args.equivalence_margin {
+        for (result, sims) in results.iter_mut().zip(distributions.iter()) {
+            result.equivalence = Some(margin::tost(result, sims, margin));
+        }
+    }
+    if let Some(margin) = args.noise_threshold {
+        for result in results.iter_mut() {
+            result.noise_threshold = Some(margin.amount(result.full_baseline_estimator));
+        }
+    }
+    if let Some(margin) = args.tolerate {
+        for (result, sims) in results.iter_mut().zip(distributions.iter()) {
+            result.tolerated_p_value = Some(margin::tolerated_p_value(result, sims, margin));
+        }
+    }
+    if args.mde {
+        for (result, sims) in results.iter_mut().zip(distributions.iter()) {
+            result.minimum_detectable_effect = Some(power::minimum_detectable_effect(
+                sims,
+                args.alpha,
+                power::MDE_POWER,
+            )?);
+        }
+    }
+    Ok((results, distributions))
+}
+
+// The key of a simulation in the result cache, or None if it must not be
+// cached: without --seed the run is not meant to be repeatable, and
+// per-iteration output needs the iterations to happen. Only the options
+// that change what is simulated are part of the key, so that output
+// options and --threads reuse the entry.
+fn simulation_cache_key(
+    args: &CompareArgs,
+    seed: Seed,
+    baseline: &Sample,
+    target: &Sample,
+    keep_distributions: bool,
+) -> Option<String> {
+    if args.no_cache
+        || args.seed.is_none()
+        || args.emit_iterations.is_some()
+        || args.iterations_csv.is_some()
+    {
+        return None;
+    }
+    let sample_bytes = |sample: &Sample| {
+        let mut bytes: Vec<u8> = sample.values.iter().flat_map(|x| x.to_le_bytes()).collect();
+        // Block and stationary bootstraps resample in input order, so the same
+        // values in another order are a different simulation.
+        for x in sample.input_order.iter() {
+            bytes.extend_from_slice(&x.to_le_bytes());
+        }
+        for w in sample.weights.iter().flatten() {
+            bytes.extend_from_slice(&w.to_le_bytes());
+        }
+        for stratum in sample.strata.iter().flatten() {
+            bytes.extend_from_slice(stratum.as_bytes());
+            bytes.push(0);
+        }
+        bytes
+    };
+    let estimators = format!(
+        "{:?} {:?} {:?} {:?} {} {:?} {:?} {:?} {:?} {:?}",
+        args.quantile_estimator,
+        args.quantile_method,
+        args.quantiles,
+        args.ctes,
+        args.shape,
+        args.modes,
+        args.exprs,
+        args.plugins,
+        args.direction,
+        args.directed
+    );
+    let simulation = format!(
+        "{:?} {} {} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+        seed,
+        args.iterations,
+        args.max_iterations,
+        args.target_mc_error,
+        args.bootstrap,
+        args.block_size,
+        args.mean_block_size,
+        args.subsample_size,
+        args.null,
+        args.exact_max,
+        args.alternative,
+        args.ties,
+        args.ci_method.map(|method| (method, args.alpha))
+    );
+    // A plugin may change without its path changing.
+    let mut plugins = Vec::new();
+    for path in args.plugins.iter() {
+        plugins.extend(std::fs::read(path).ok()?);
+    }
+    Some(cache::key(&[
+        env!("CARGO_PKG_VERSION").as_bytes(),
+        estimators.as_bytes(),
+        simulation.as_bytes(),
+        &[keep_distributions as u8],
+        &plugins,
+        &sample_bytes(baseline),
+        &sample_bytes(target),
+    ]))
+}
+
+// A cache entry: a row of estimates, counts and difference interval per
+// estimator, then the simulated distribution of each.
+fn results_to_rows(results: &[EstimatorResult], distributions: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let mut rows: Vec<Vec<f64>> = results
+        .iter()
+        .map(|r| {
+            vec![
+                r.full_baseline_estimator,
+                r.target_estimator,
+                r.sim_count as f64,
+                r.target_lt_sim_count as f64,
+                r.target_gt_sim_count as f64,
+                r.target_eq_sim_count as f64,
+                r.difference_interval.map_or(f64::NAN, |i| i.0),
+                r.difference_interval.map_or(f64::NAN, |i| i.1),
+            ]
+        })
+        .collect();
+    rows.extend(distributions.iter().cloned());
+    rows
+}
+
+// The results of a cached simulation, or None if the entry does not fit.
+fn results_from_rows(
+    args: &CompareArgs,
+    estimators: &[Box<dyn Estimator>],
+    baseline: &Sample,
+    target: &Sample,
+    rows: Vec<Vec<f64>>,
+) -> Option<(Vec<EstimatorResult>, Vec<Vec<f64>>)> {
+    if rows.len() != 2 * estimators.len() {
+        return None;
+    }
+    let (counts, distributions) = rows.split_at(estimators.len());
+    let mut results = Vec::with_capacity(estimators.len());
+    for (est, row) in estimators.iter().zip(counts.iter()) {
+        let [full_baseline_estimator, target_estimator, sim, lt, gt, eq, lo, hi] = row[..] else {
+            return None;
+        };
+        // Estimators are cheap next to the simulation; recomputing them
+        // guards against a stale entry.
+        if est.estimate(baseline).ok()?.to_bits() != full_baseline_estimator.to_bits()
+            || est.estimate(target).ok()?.to_bits() != target_estimator.to_bits()
+        {
+            return None;
+        }
+        results.push(EstimatorResult {
+            name: est.name().to_string(),
+            full_baseline_estimator,
+            target_estimator,
+            sim_count: sim as u64,
+            target_lt_sim_count: lt as u64,
+            target_gt_sim_count: gt as u64,
+            target_eq_sim_count: eq as u64,
+            direction: est.direction(),
+            alternative: args.alternative,
+            ties: args.ties,
+            equivalence: None,
+            tolerated_p_value: None,
+            noise_threshold: None,
+            minimum_detectable_effect: None,
+            difference_interval: (!lo.is_nan()).then_some((lo, hi)),
+        });
+    }
+    Some((results, distributions.to_vec()))
+}
+
+// One value per line, so the dumps can be fed back into numcmp.
+fn dump_bootstrap(
+    dir: &Path,
+    results: &[EstimatorResult],
+    distributions: &[Vec<f64>],
+) -> Result<(), Error> {
+    std::fs::create_dir_all(dir)?;
+    for (result, sims) in results.iter().zip(distributions.iter()) {
+        let path = dir.join(format!("{}.txt", plots::file_stem(&result.name)));
+        let mut out = BufWriter::new(File::create(path)?);
+        for x in sims.iter() {
+            writeln!(out, "{}", x)?;
+        }
+        out.flush()?;
+    }
+    Ok(())
+}
+
+fn write_outputs(
+    args: &CompareArgs,
+    units: &Units,
+    metadata: &RunMetadata,
+    baseline: &[f64],
+    target: &[f64],
+    results: &[EstimatorResult],
+    distributions: &[Vec<f64>],
+) -> Result<(), Error> {
+    if let Some(path) = &args.report {
+        report::write_html(
+            path,
+            metadata,
+            baseline,
+            target,
+            results,
+            distributions,
+            args.alpha,
+        )?;
+    }
+    if let Some(dir) = &args.plot_dir {
+        plots::write_plot_dir(
+            dir,
+            baseline,
+            target,
+            results,
+            distributions,
+            args.bandwidth,
+            units,
+            &metadata.labels,
+        )?;
+    }
+    if let Some(prefix) = &args.export_gnuplot {
+        gnuplot::export(
+            prefix,
+            baseline,
+            target,
+            results,
+            distributions,
+            units,
+            &metadata.labels,
+        )?;
+    }
+    if let Some(dir) = &args.dump_bootstrap {
+        dump_bootstrap(dir, results, distributions)?;
+    }
+    Ok(())
+}
+
+// The default estimators followed by any --expr and --plugin estimators,
+// all in the --direction given unless --estimator sets their own.
+fn estimators(args: &CompareArgs) -> Result<Vec<Box<dyn Estimator>>, Error> {
+    let options = QuantileOptions {
+        estimator: args.quantile_estimator,
+        method: args.quantile_method,
+    };
+    let mut estimators = default_estimators(options);
+    for q in args.quantiles.iter() {
+        let name = format!("p{}", estimator::percent_name(*q));
+        estimators.push(Box::new(Quantile::new(&name, *q, options)));
+    }
+    for level in args.ctes.iter() {
+        estimators.push(Box::new(TailMean::new(*level)));
+    }
+    if args.shape {
+        estimators.push(Box::new(Skewness));
+        estimators.push(Box::new(Kurtosis));
+    }
+    for width in args.modes.iter() {
+        estimators.push(Box::new(BinnedMode::new(*width)));
+    }
+    for e in args.exprs.iter().cloned() {
+        estimators.push(Box::new(e));
+    }
+    for path in args.plugins.iter() {
+        estimators.push(Box::new(wasm::Plugin::load(path)?));
+    }
+    if let Some(direction) = args.direction {
+        estimators = estimators
+            .into_iter()
+            .map(|inner| Box::new(Directed { inner, direction }) as Box<dyn Estimator>)
+            .collect();
+    }
+    for (name, direction) in args.directed.iter() {
+        let i = estimators
+            .iter()
+            .position(|e| e.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = estimators.iter().map(|e| e.name()).collect();
+                Error::Usage(format!(
+                    "--estimator {}: no estimator named {:?}; there are {}",
+                    name,
+                    name,
+                    names.join(", ")
+                ))
+            })?;
+        let inner = estimators.remove(i);
+        let direction = *direction;
+        estimators.insert(i, Box::new(Directed { inner, direction }));
+    }
+    Ok(estimators)
+}
+
+// The results of a bootstrap comparison, before they are presented.
+struct Analysis {
+    results: Vec<EstimatorResult>,
+    distributions: Vec<Vec<f64>>,
+    spread: Option<stats::SpreadTest>,
+    distances: Vec<Distance>,
+    superiority: Option<Distance>,
+}
+
+// Keeps the time a phase took for --verbose and logs it with --log-level
+// info.
+fn record_timing(timings: &mut Vec<(&'static str, f64)>, phase: &'static str, seconds: f64) {
+    tracing::info!(
+        phase = %phase,
+        seconds = %format!("{:.6}", seconds),
+        "phase finished"
+    );
+    timings.push((phase, seconds));
+}
+
+// Runs `f` and records how long it took, except on wasm32, which has no
+// clock.
+fn timed<T>(
+    timings: &mut Vec<(&'static str, f64)>,
+    phase: &'static str,
+    f: impl FnOnce() -> T,
+) -> T {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let start = Instant::now();
+        let result = f();
+        record_timing(timings, phase, start.elapsed().as_secs_f64());
+        result
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = (timings, phase);
+        f()
+    }
+}
+
+fn analyze(
+    args: &CompareArgs,
+    seed: Seed,
+    baseline: &Sample,
+    target: &Sample,
+    estimators: &[Box<dyn Estimator>],
+    timings: &mut Vec<(&'static str, f64)>,
+) -> Result<Analysis, Error> {
+    let (results, distributions) = timed(timings, "simulation", || {
+        run_simulation(args, seed, baseline, target, estimators)
+    })?;
+
+    // Brown-Forsythe needs the raw observations, so weighted samples are
+    // left out.
+    let spread = match (&baseline.weights, &target.weights) {
+        (None, None) => stats::brown_forsythe(&baseline.values, &target.values),
+        _ => None,
+    };
+
+    let mut distances = Vec::new();
+    if args.wasserstein || args.js_divergence {
+        distances = timed(timings, "distances", || {
+            distances_of(args, seed, baseline, target)
+        })?;
+    }
+
+    let mut superiority = None;
+    if args.superiority || args.discrete {
+        superiority = Some(timed(timings, "superiority", || {
+            distance::with_interval(
+                "superiority",
+                &distance::superiority,
+                args.iterations,
+                seed,
+                baseline,
+                target,
+                1.0 - args.alpha,
+            )
+        })?);
+    }
+
+    Ok(Analysis {
+        results,
+        distributions,
+        spread,
+        distances,
+        superiority,
+    })
+}
+
+fn distances_of(
+    args: &CompareArgs,
+    seed: Seed,
+    baseline: &Sample,
+    target: &Sample,
+) -> Result<Vec<Distance>, Error> {
+    let mut distances = Vec::new();
+    if args.wasserstein {
+        distances.push(distance::with_interval(
+            "wasserstein",
+            &distance::wasserstein,
+            args.iterations,
+            seed,
+            baseline,
+            target,
+            1.0 - args.alpha,
+        )?);
+    }
+    if args.js_divergence {
+        // Bins stay fixed across bootstrap replicates.
+        let bins = distance::Bins::new(baseline, target);
+        let divergence = distance::with_interval(
+            "jensen-shannon",
+            &|a, b| distance::jensen_shannon(a, b, &bins),
+            args.iterations,
+            seed,
+            baseline,
+            target,
+            1.0 - args.alpha,
+        )?;
+        distances.push(Distance {
+            dimensionless: true,
+            ..divergence
+        });
+    }
+    Ok(distances)
+}
+
+// The seed, estimators and metadata of a comparison.
+type Prepared = (Seed, Vec<Box<dyn Estimator>>, RunMetadata);
+
+fn prepare(
+    args: &CompareArgs,
+    baseline: &Sample,
+    target: &Sample,
+    inputs: Vec<InputInfo>,
+) -> Result<Prepared, Error> {
+    let seed = args.seed.unwrap_or_else(rand::random);
+    // The Bayesian bootstrap reweights the samples it estimates on.
+    if args.quantile_method != QuantileMethod::Type7
+        && (args.method == Method::Bayes || baseline.weights.is_some() || target.weights.is_some())
+    {
+        return Err(Error::Usage(
+            "--quantile-method and --discrete are not supported for weighted samples or --method bayes"
+                .to_string(),
+        ));
+    }
+    if baseline.strata.is_some()
+        && (args.method != Method::Bootstrap
+            || args.bootstrap != BootstrapMethod::Iid
+            || args.null != NullModel::Baseline)
+    {
+        return Err(Error::Usage(
+            "--strata-column needs --method bootstrap with iid resampling and --null baseline"
+                .to_string(),
+        ));
+    }
+    let estimators = estimators(args)?;
+    let mut metadata = RunMetadata::new(seed, args.iterations, inputs);
+    metadata.unit = args.unit.clone();
+    metadata.labels = labels(args);
+    metadata.bandwidth = args.bandwidth;
+    if args.capture_env {
+        metadata.environment = Some(environment::capture());
+    }
+    Ok((Seed::new(seed, args.rng), estimators, metadata))
+}
+
+// The --format json output of a comparison, for the embedding API.
+fn comparison_json(
+    args: &CompareArgs,
+    baseline: &Sample,
+    target: &Sample,
+) -> Result<String, Error> {
+    let (seed, estimators, mut metadata) = prepare(args, baseline, target, Vec::new())?;
+    match args.method {
+        Method::Bayes => {
+            let results = bayes::bayesian_bootstrap(
+                args.iterations,
+                seed,
+                baseline,
+                target,
+                &estimators,
+                1.0 - args.alpha,
+            )?;
+            Ok(bayes::results_json(metadata.to_json(), &results))
+        }
+        Method::Jackknife => {
+            let results = jackknife::jackknife_all(baseline, target, &estimators)?;
+            Ok(jackknife::results_json(metadata.to_json(), &results))
+        }
+        Method::Bootstrap => {
+            let analysis = analyze(args, seed, baseline, target, &estimators, &mut Vec::new())?;
+            if let Some(r) = analysis.results.first() {
+                metadata.iterations = r.sim_count;
+            }
+            Ok(output::results_json(
+                &metadata,
+                baseline.len(),
+                target.len(),
+                &analysis.results,
+                analysis.spread.as_ref(),
+                &analysis.distances,
+                analysis.superiority.as_ref(),
+                args.alpha,
+            ))
+        }
+    }
+}
+
+// The bootstrap results of a comparison, for the library API.
+fn comparison_results(
+    args: &CompareArgs,
+    baseline: &Sample,
+    target: &Sample,
+) -> Result<Vec<EstimatorResult>, Error> {
+    if args.method != Method::Bootstrap {
+        return Err(Error::Usage(
+            "only --method bootstrap gives per-estimator results".to_string(),
+        ));
+    }
+    let (seed, estimators, _) = prepare(args, baseline, target, Vec::new())?;
+    let analysis = analyze(args, seed, baseline, target, &estimators, &mut Vec::new())?;
+    Ok(analysis.results)
+}
+
+// How values are shown, from --unit, --display-unit and the precision
+// options.
+fn units(args: &CompareArgs) -> Result<Units, Error> {
+    let mut units = Units::new(args.unit.as_deref(), args.display_unit)?;
+    units.precision = match (args.precision, args.sig_figs) {
+        (Some(decimals), _) => Some(Precision::Decimals(decimals)),
+        (None, Some(digits)) => Some(Precision::Significant(digits)),
+        (None, None) => None,
+    };
+    units.notation = args.notation;
+    units.discrete = args.discrete;
+    Ok(units)
+}
+
+fn labels(args: &CompareArgs) -> Labels {
+    Labels {
+        baseline: args.baseline_label.clone(),
+        target: args.target_label.clone(),
+    }
+}
+
+// Returns whether any estimator regressed.
+fn compare(
+    args: &CompareArgs,
+    baseline: &Sample,
+    target: &Sample,
+    inputs: Vec<InputInfo>,
+    timings: Vec<(&'static str, f64)>,
+) -> Result<bool, Error> {
+    compare_column(args, None, baseline, target, inputs, timings)
+}
+
+// Compares every numeric column the two CSV files share, in the order of
+// the baseline's header, returning whether any regressed.
+fn compare_all_columns(
+    args: &CompareArgs,
+    baseline_filename: &Path,
+    target_filename: &Path,
+    input: &InputArgs,
+) -> Result<bool, Error> {
+    if !matches!(args.format, Format::Text | Format::Json) {
+        return Err(Error::Usage(
+            "--all-columns supports only text and json output".to_string(),
+        ));
+    }
+    if [baseline_filename, target_filename].contains(&Path::new("-")) {
+        return Err(Error::Usage(
+            "--all-columns reads each input once per column and cannot read stdin".to_string(),
+        ));
+    }
+    let target_columns = input::numeric_columns(target_filename, input)?;
+    let columns: Vec<String> = input::numeric_columns(baseline_filename, input)?
+        .into_iter()
+        .filter(|c| target_columns.contains(c))
+        .collect();
+    if columns.is_empty() {
+        return Err(Error::InvalidInput(format!(
+            "{} and {} have no numeric columns in common",
+            baseline_filename.display(),
+            target_filename.display()
+        )));
+    }
+    let mut regressed = false;
+    for (i, column) in columns.iter().enumerate() {
+        // --estimator COLUMN:DIRECTION sets the direction of every estimator
+        // of that column.
+        let mut args = args.clone();
+        for (name, direction) in args.directed.iter() {
+            if name == column {
+                args.direction = Some(*direction);
+            }
+        }
+        args.directed.retain(|(name, _)| !columns.contains(name));
+        let start = Instant::now();
+        let input = InputArgs {
+            column: Some(column.clone()),
+            ..input.clone()
+        };
+        let baseline = input::read_sample(baseline_filename, &input)?;
+        let target = input::read_sample(target_filename, &input)?;
+        let inputs = vec![
+            InputInfo::new("baseline", baseline_filename, baseline.len())?,
+            InputInfo::new("target", target_filename, target.len())?,
+        ];
+        let mut timings = Vec::new();
+        record_timing(&mut timings, "reading", start.elapsed().as_secs_f64());
+        if args.format == Format::Text && !args.quiet && args.template.is_none() {
+            if i > 0 {
+                println!();
+            }
+            println!("##### Column {} #####", column);
+            println!();
+        }
+        regressed |= compare_column(&args, Some(column), &baseline, &target, inputs, timings)?;
+    }
+    Ok(regressed)
+}
+
+// Compares the samples and presents the results, returning whether the
+// caller should exit with status 2 for a regression. `column` names the CSV
+// column compared, with --all-columns.
+fn compare_column(
+    args: &CompareArgs,
+    column: Option<&str>,
+    baseline: &Sample,
+    target: &Sample,
+    inputs: Vec<InputInfo>,
+    mut timings: Vec<(&'static str, f64)>,
+) -> Result<bool, Error> {
+    // A --sequential look tests at its share of alpha, which also sets the
+    // level of the intervals shown.
+    let look = if args.sequential {
+        if args.method != Method::Bootstrap
+            || !matches!(args.format, Format::Text | Format::Json)
+            || args.no_sim
+        {
+            return Err(Error::Usage(
+                "--sequential needs --method bootstrap and text or json output".to_string(),
+            ));
+        }
+        Some(sequential::look(
+            target.len(),
+            args.max_n.expect("required by clap"),
+            args.looks.expect("required by clap"),
+            args.spending,
+            args.alpha,
+        )?)
+    } else {
+        None
+    };
+    let args = &match &look {
+        Some(look) => CompareArgs {
+            alpha: look.alpha,
+            ..args.clone()
+        },
+        None => args.clone(),
+    };
+    let (seed, estimators, mut metadata) = prepare(args, baseline, target, inputs)?;
+    metadata.column = column.map(str::to_string);
+    warn_autocorrelation(args, baseline, target);
+    check_discrete(args, baseline, target)?;
+    let units = units(args)?;
+
+    if args.no_sim && args.format != Format::Text {
+        return Err(Error::Usage(
+            "--no-sim supports only text output".to_string(),
+        ));
+    }
+    if args.template.is_some() && args.method != Method::Bootstrap {
+        return Err(Error::Usage(
+            "--template needs --method bootstrap".to_string(),
+        ));
+    }
+    let template = args.template.as_deref().map(template::load).transpose()?;
+    let text = args.format == Format::Text && !args.quiet && template.is_none();
+
+    if text {
+        let start = Instant::now();
+        println!("=== Summary ===");
+        print!(
+            "{}",
+            summary_table(baseline, target, &estimators, &units, &metadata.labels)?
+                .render(args.layout)
+        );
+        println!();
+        record_timing(&mut timings, "summaries", start.elapsed().as_secs_f64());
+    }
+
+    if !args.percentile_grid.is_empty() {
+        let options = QuantileOptions {
+            estimator: args.quantile_estimator,
+            method: args.quantile_method,
+        };
+        let grid = grid::percentile_grid(baseline, target, &args.percentile_grid, options)?;
+        if let Some(path) = &args.percentile_grid_csv {
+            grid::write_csv(path, &grid)?;
+        }
+        if let Some(path) = &args.percentile_grid_json {
+            grid::write_json(path, &grid)?;
+        }
+        if text && args.percentile_grid_csv.is_none() && args.percentile_grid_json.is_none() {
+            println!("=== Percentile grid ===");
+            let labels = (
+                metadata.labels.baseline.as_str(),
+                metadata.labels.target.as_str(),
+            );
+            print!("{}", grid::table(&grid, &units, labels, args.layout));
+            println!();
+        }
+    }
+
+    if args.modality {
+        let start = Instant::now();
+        let mut rng = seed.analysis_stream("modality");
+        let mut table = Table::new(&["sample", "critical bandwidth", "p-value", "modality"]);
+        for (label, sample) in [
+            (&metadata.labels.baseline, baseline),
+            (&metadata.labels.target, target),
+        ] {
+            // The test needs a density estimate per iteration, so fewer
+            // than the comparison uses.
+            let iterations = args.iterations.min(MODALITY_ITERATIONS);
+            let Some(m) = modality::silverman(sample, iterations, &mut rng) else {
+                table.row(vec![
+                    label.clone(),
+                    String::new(),
+                    String::new(),
+                    "too few distinct values".to_string(),
+                ]);
+                continue;
+            };
+            let multimodal = m.p_value < args.alpha;
+            if multimodal {
+                tracing::warn!(
+                    "the {} looks multimodal (Silverman's test p = {:.4}); a single location such as the median cannot say which mode the values fall in, consider --mode",
+                    label, m.p_value
+                );
+            }
+            table.row(vec![
+                label.clone(),
+                units.format(m.critical_bandwidth),
+                m.p_value.to_string(),
+                if multimodal { "multimodal" } else { "unimodal" }.to_string(),
+            ]);
+        }
+        if text {
+            println!("=== Modality (Silverman's test) ===");
+            print!("{}", table.render(args.layout));
+            println!();
+        }
+        record_timing(&mut timings, "modality", start.elapsed().as_secs_f64());
+    }
+
+    if args.ab_sanity {
+        if args.method != Method::Bootstrap || args.no_sim {
+            return Err(Error::Usage(
+                "--ab-sanity needs --method bootstrap".to_string(),
+            ));
+        }
+        let start = Instant::now();
+        sanity::check(args, seed, baseline, &estimators, &units, text)?;
+        record_timing(&mut timings, "A/A check", start.elapsed().as_secs_f64());
+    }
+
+    // The summary table already shows each estimator's delta.
+    if args.no_sim {
+        return Ok(false);
+    }
+
+    if args.method == Method::Bayes {
+        let results = bayes::bayesian_bootstrap(
+            args.iterations,
+            seed,
+            baseline,
+            target,
+            &estimators,
+            1.0 - args.alpha,
+        )?;
+        match args.format {
+            Format::Text => bayes::print_text(&results, &units, &metadata.labels),
+            Format::Json => println!("{}", bayes::results_json(metadata.to_json(), &results)),
+            _ => {
+                return Err(Error::Usage(
+                    "--method bayes supports only text and json output".to_string(),
+                ))
+            }
+        }
+        return Ok(false);
+    }
+
+    if args.method == Method::Jackknife {
+        let results = jackknife::jackknife_all(baseline, target, &estimators)?;
+        match args.format {
+            Format::Text => jackknife::print_text(&results, &units, &metadata.labels),
+            Format::Json => println!("{}", jackknife::results_json(metadata.to_json(), &results)),
+            _ => {
+                return Err(Error::Usage(
+                    "--method jackknife supports only text and json output".to_string(),
+                ))
+            }
+        }
+        return Ok(false);
+    }
+
+    let Analysis {
+        results,
+        distributions,
+        spread,
+        distances,
+        superiority,
+    } = analyze(args, seed, baseline, target, &estimators, &mut timings)?;
+    if let Some(r) = results.first() {
+        metadata.iterations = r.sim_count;
+    }
+    warn_p_value_floor(args, &results);
+    let decision = look.as_ref().map(|look| look.decision(&results));
+    if let (Some(look), Some(decision)) = (&look, decision) {
+        metadata.sequential = Some(look.to_json(decision));
+    }
+
+    let start = Instant::now();
+    write_outputs(
+        args,
+        &units,
+        &metadata,
+        &baseline.values,
+        &target.values,
+        &results,
+        &distributions,
+    )?;
+    record_timing(&mut timings, "output", start.elapsed().as_secs_f64());
+
+    if let Some(template) = &template {
+        let context = output::results_json(
+            &metadata,
+            baseline.len(),
+            target.len(),
+            &results,
+            spread.as_ref(),
+            &distances,
+            superiority.as_ref(),
+            args.alpha,
+        );
+        print!("{}", template.render_json(&context)?);
+        return Ok(false);
+    }
+
+    match args.format {
+        Format::Text => (),
+        Format::Json => {
+            println!(
+                "{}",
+                output::results_json(
+                    &metadata,
+                    baseline.len(),
+                    target.len(),
+                    &results,
+                    spread.as_ref(),
+                    &distances,
+                    superiority.as_ref(),
+                    args.alpha
+                )
+            );
+            return Ok(false);
+        }
+        Format::OpenMetrics => {
+            print!(
+                "{}",
+                output::results_openmetrics(baseline.len(), target.len(), &results, args.alpha)
+            );
+            return Ok(false);
+        }
+        Format::Junit => {
+            print!("{}", output::results_junit(&results, args.alpha));
+            return Ok(false);
+        }
+        Format::Tap => {
+            print!("{}", output::results_tap(&results, args.alpha));
+            return Ok(false);
+        }
+        Format::Github => {
+            print!(
+                "{}",
+                output::results_github_annotations(&results, args.alpha)
+            );
+            let table =
+                output::results_markdown_table(&results, args.alpha, &units, &metadata.labels);
+            print!("{}", table);
+            if let Some(path) = std::env::var_os("GITHUB_STEP_SUMMARY") {
+                let mut summary = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)?;
+                writeln!(summary, "### numcmp comparison\n")?;
+                write!(summary, "{}", table)?;
+            }
+            return Ok(any_regressed(&results, args.alpha));
+        }
+    }
+
+    if args.quiet {
+        for result in results.iter() {
+            match column {
+                Some(column) => println!(
+                    "{}\t{}\t{}",
+                    column,
+                    result.name,
+                    result.verdict(args.alpha)
+                ),
+                None => println!("{}\t{}", result.name, result.verdict(args.alpha)),
+            }
+        }
+        if let Some(decision) = decision {
+            match column {
+                Some(column) => println!("{}\tsequential\t{}", column, decision),
+                None => println!("sequential\t{}", decision),
+            }
+        }
+        return Ok(any_regressed(&results, args.alpha));
+    }
+
+    let show = |x: f64| units.format(x);
+    println!("=== Comparison ===");
+    let labels = &metadata.labels;
+    let mut table = Table::new(&[
+        "estimator",
+        &labels.baseline,
+        &labels.target,
+        "delta",
+        "change",
+        "p-value",
+        "MC 95% CI",
+        "verdict",
+    ]);
+    for result in results.iter() {
+        let delta = result.target_estimator - result.full_baseline_estimator;
+        let (lo, hi) = result.p_value_interval();
+        table.row(vec![
+            result.name.clone(),
+            show(result.full_baseline_estimator),
+            show(result.target_estimator),
+            show(delta),
+            format_change(result.full_baseline_estimator, result.target_estimator),
+            result.p_value_text(),
+            format!("{:.4}-{:.4}", lo, hi),
+            result.verdict(args.alpha).to_string(),
+        ]);
+    }
+    print!("{}", table.render(args.layout));
+    let floor = results
+        .iter()
+        .map(|r| r.p_value_floor())
+        .fold(0.0, f64::max);
+    println!(
+        "P-values resolve down to {} with {} iterations.",
+        floor, metadata.iterations
+    );
+    if let Some(margin) = args.noise_threshold {
+        let within: Vec<&str> = results
+            .iter()
+            .filter(|r| r.verdict(args.alpha) == Verdict::WithinNoise)
+            .map(|r| r.name.as_str())
+            .collect();
+        if !within.is_empty() {
+            println!(
+                "Significant but within the noise threshold of {}: {}.",
+                margin,
+                within.join(", ")
+            );
+        }
+    }
+    let ties: Vec<String> = results
+        .iter()
+        .filter(|r| r.target_eq_sim_count > 0)
+        .map(|r| format!("{} {}", r.name, r.target_eq_sim_count))
+        .collect();
+    if !ties.is_empty() {
+        println!(
+            "Simulated values tied with the {}: {}; {}.",
+            labels.target,
+            ties.join(", "),
+            match args.ties {
+                Ties::Conservative => "counted as at least as extreme (--ties conservative)",
+                Ties::Split => "counted half (--ties split)",
+                Ties::Anticonservative => "counted as less extreme (--ties anticonservative)",
+            }
+        );
+    }
+    if let Some(spread) = &spread {
+        println!(
+            "Spread (Brown-Forsythe): mean absolute deviation {} to {}, F = {:.4}, p = {:.4}, {}",
+            show(spread.spreads.0),
+            show(spread.spreads.1),
+            spread.f,
+            spread.p_value,
+            spread_verdict(spread, args.alpha)
+        );
+    }
+    if let Some(superiority) = &superiority {
+        println!(
+            "Probability of superiority: P({} > {}) = {:.4}, {}% CI {:.4} to {:.4}",
+            labels.target,
+            labels.baseline,
+            superiority.value,
+            100.0 * (1.0 - args.alpha),
+            superiority.interval.0,
+            superiority.interval.1
+        );
+    }
+
+    if let (Some(look), Some(decision)) = (&look, decision) {
+        println!();
+        println!("=== Sequential (look {} of {}) ===", look.look, look.looks);
+        println!(
+            "{} of at most {} {} values; this look tests at alpha {:.6}, {:.6} of {} spent so far.",
+            look.n, look.max_n, labels.target, look.alpha, look.spent, look.overall
+        );
+        println!("Decision: {}", decision);
+    }
+
+    if let Some(replicates) = args.replicates {
+        println!();
+        replicates::print(
+            args,
+            seed,
+            baseline,
+            target,
+            &estimators,
+            &results,
+            replicates,
+        )?;
+    }
+
+    if !distances.is_empty() {
+        println!();
+        println!("=== Distribution distances ===");
+        let interval = format!("{}% CI", 100.0 * (1.0 - args.alpha));
+        let mut table = Table::new(&["distance", "value", &interval]);
+        for d in distances.iter() {
+            let show = |x| {
+                if d.dimensionless {
+                    units.number(x)
+                } else {
+                    show(x)
+                }
+            };
+            table.row(vec![
+                d.name.to_string(),
+                show(d.value),
+                format!("{} to {}", show(d.interval.0), show(d.interval.1)),
+            ]);
+        }
+        print!("{}", table.render(args.layout));
+    }
+
+    if args.shift_function {
+        println!();
+        println!(
+            "=== Shift function ({}% simultaneous bands) ===",
+            100.0 * (1.0 - args.alpha)
+        );
+        let points =
+            shift::shift_function(args.iterations, seed, baseline, target, 1.0 - args.alpha)?;
+        let mut table = Table::new(&[
+            "quantile",
+            &labels.baseline,
+            &labels.target,
+            "difference",
+            "band",
+            "changed",
+        ]);
+        for p in points.iter() {
+            table.row(vec![
+                p.q.to_string(),
+                show(p.baseline),
+                show(p.target),
+                show(p.difference()),
+                format!("{} to {}", show(p.band.0), show(p.band.1)),
+                if p.changed() { "yes" } else { "no" }.to_string(),
+            ]);
+        }
+        print!("{}", table.render(args.layout));
+        let ranges = shift::changed_ranges(&points);
+        if ranges.is_empty() {
+            println!("No decile changed significantly.");
+        } else {
+            println!("Changed significantly at quantiles {}.", ranges.join(", "));
+        }
+    }
+
+    if args.ecdf_band {
+        println!();
+        let band = ecdfband::ecdf_band(args.iterations, seed, baseline, target, 1.0 - args.alpha)?;
+        println!(
+            "=== ECDF band ({}% simultaneous, {} ± {:.4}) ===",
+            100.0 * (1.0 - args.alpha),
+            labels.baseline,
+            band.half_width
+        );
+        print!("{}", textplot::ecdf_band(&band, labels));
+        let ranges = ecdfband::outside_ranges(&band, show);
+        if ranges.is_empty() {
+            println!("The {} ECDF stays within the band.", labels.target);
+        } else {
+            println!(
+                "The {} ECDF leaves the band at values {}.",
+                labels.target,
+                ranges.join(", ")
+            );
+        }
+    }
+
+    if let Some(method) = args.ci_method {
+        println!();
+        println!(
+            "=== {}% confidence intervals for the difference ({}) ===",
+            100.0 * (1.0 - args.alpha),
+            method
+                .to_possible_value()
+                .expect("no skipped variants")
+                .get_name()
+        );
+        for result in results.iter() {
+            if let Some((lo, hi)) = result.difference_interval {
+                println!(
+                    "{}: {} [{}, {}]",
+                    result.name,
+                    show(result.target_estimator - result.full_baseline_estimator),
+                    show(lo),
+                    show(hi)
+                );
+            }
+        }
+    }
+
+    if args.mde {
+        println!();
+        println!(
+            "=== Minimum detectable effect ({}% power) ===",
+            100.0 * power::MDE_POWER
+        );
+        for result in results.iter() {
+            if let Some(mde) = result.minimum_detectable_effect {
+                let relative = match result.full_baseline_estimator {
+                    0.0 => String::new(),
+                    b => format!(" ({:.2}%)", 100.0 * mde / b.abs()),
+                };
+                println!("{}: {}{}", result.name, show(mde), relative);
+            }
+        }
+    }
+
+    if let Some(margin) = args.equivalence_margin {
+        println!();
+        println!("=== Equivalence (margin {}) ===", margin);
+        for result in results.iter() {
+            if let Some(eq) = &result.equivalence {
+                println!(
+                    "{}: within ±{}, p-values {} (lower) and {} (upper), {}",
+                    result.name,
+                    eq.margin,
+                    eq.p_lower,
+                    eq.p_upper,
+                    if eq.equivalent(args.alpha) {
+                        "equivalent"
+                    } else {
+                        "not shown equivalent"
+                    }
+                );
+            }
+        }
+    }
+
+    if args.normality {
+        println!();
+        print_normality(baseline, target, &metadata.labels);
+    }
+
+    if args.autocorrelation {
+        println!();
+        print_autocorrelation(baseline, target, &metadata.labels);
+    }
+
+    if args.qq {
+        println!();
+        println!(
+            "=== Q-Q plot ({} vs {}) ===",
+            metadata.labels.target, metadata.labels.baseline
+        );
+        print!("{}", textplot::qq(baseline, target, &metadata.labels)?);
+    }
+
+    if args.kde {
+        println!();
+        println!("=== Kernel density estimates ===");
+        print!(
+            "{}",
+            textplot::kde(baseline, target, args.bandwidth, &metadata.labels)
+        );
+    }
+
+    if args.verbose {
+        println!();
+        print_diagnostics(seed.value, &results, &timings);
+    }
+
+    Ok(false)
+}
+
+// Follows one estimator across all recorded runs: each run is compared to
+// its predecessor and to the first run, and a Mann-Kendall test over the
+// whole sequence catches gradual drifts that no single step reveals.
+fn trend(
+    db: &Path,
+    estimator_name: &str,
+    iterations: u64,
+    alpha: f64,
+    seed: u64,
+) -> Result<(), Error> {
+    let estimator = default_estimators(QuantileOptions::default())
+        .into_iter()
+        .find(|e| e.name() == estimator_name)
+        .ok_or_else(|| Error::Usage(format!("unknown estimator {:?}", estimator_name)))?;
+    let estimators = [estimator];
+    let records = store::load(db)?;
+    if records.is_empty() {
+        return Err(Error::InvalidInput(format!(
+            "no records in {}",
+            db.display()
+        )));
+    }
+    let samples = records
+        .iter()
+        .map(|r| r.sample())
+        .collect::<Result<Vec<Sample>, Error>>()?;
+
+    let p_value = |baseline: &Sample, target: &Sample| -> Result<(f64, Verdict), Error> {
+        let resampler = Resampler::new(BootstrapMethod::Iid, None, None, None, baseline)?;
+        let results = simulate(
+            Stopping::Fixed(iterations),
+            None,
+            Seed::new(seed, RngKind::Chacha),
+            &resampler,
+            baseline,
+            target,
+            &estimators,
+            Null::Baseline,
+            None,
+            None,
+            &mut |_, _| Ok(()),
+        )?;
+        Ok((results[0].p_value(), results[0].verdict(alpha)))
+    };
+
+    println!("=== Trend ({}) ===", estimator_name);
+    let mut values = Vec::new();
+    for (i, (record, sample)) in records.iter().zip(samples.iter()).enumerate() {
+        let value = estimators[0].estimate(sample)?;
+        values.push(value);
+        if i == 0 {
+            println!(
+                "{} ({}, n={}): {}",
+                record.label,
+                record.recorded_at,
+                sample.len(),
+                value
+            );
+            continue;
+        }
+        let (p_prev, v_prev) = p_value(&samples[i - 1], sample)?;
+        let (p_first, v_first) = p_value(&samples[0], sample)?;
+        println!(
+            "{} ({}, n={}): {}, vs previous: {} (p={}), vs first: {} (p={})",
+            record.label,
+            record.recorded_at,
+            sample.len(),
+            value,
+            v_prev,
+            p_prev,
+            v_first,
+            p_first
+        );
+    }
+
+    if values.len() >= 3 {
+        let (s, z, p) = stats::mann_kendall(&values);
+        let conclusion = if p >= alpha {
+            "no significant trend"
+        } else if s > 0 {
+            "significant upward drift"
+        } else {
+            "significant downward drift"
+        };
+        println!();
+        println!(
+            "Mann-Kendall: S={}, z={:.3}, p={:.4}: {}",
+            s, z, p, conclusion
+        );
+    }
+
+    Ok(())
+}
+
+pub fn main() {
+    // Exiting skips destructors, so the temporary files are removed first.
+    let code = {
+        let _cleanup = fetch::Cleanup;
+        match run() {
+            Ok(false) => 0,
+            Ok(true) => REGRESSION_EXIT_CODE,
+            Err(e) => {
+                eprintln!("numcmp: {}", e);
+                e.exit_code()
+            }
+        }
+    };
+    std::process::exit(code);
+}
+
+// Runs the command line, returning whether a comparison regressed.
+fn run() -> Result<bool, Error> {
+    let cli = Cli::parse();
+    log::init(cli.log_level);
+
+    match cli.command {
+        Some(Command::Record {
+            db,
+            label,
+            filename,
+            input,
+        }) => {
+            let observations = input::read_observations(&filename, &input)?;
+            let count = observations.values.len();
+            store::record(&db, &label, observations)?;
+            println!("Recorded {} values as {:?}", count, label);
+            Ok(false)
+        }
+        Some(Command::Check {
+            db,
+            against,
+            target_filename,
+            input,
+            compare: args,
+        }) => {
+            let start = Instant::now();
+            let record = store::find(&db, &against)?;
+            let baseline = record.sample()?;
+            let target = input::read_sample(&target_filename, &input)?;
+            let mut timings = Vec::new();
+            record_timing(&mut timings, "reading", start.elapsed().as_secs_f64());
+            let inputs = vec![
+                record.input_info(&db),
+                InputInfo::new("target", &target_filename, target.len())?,
+            ];
+            compare(&args, &baseline, &target, inputs, timings)
+        }
+        Some(Command::Run {
+            runs,
+            warmup,
+            measure,
+            output,
+            against,
+            input,
+            compare: args,
+            command,
+        }) => {
+            let start = Instant::now();
+            let times = runner::time_runs(&command, runs, warmup, measure)?;
+            let text: String = times.iter().map(|t| format!("{}\n", t)).collect();
+            match &output {
+                Some(path) => std::fs::write(path, &text)?,
+                None if against.is_none() => print!("{}", text),
+                None => {}
+            }
+            let Some(baseline_filename) = against else {
+                return Ok(false);
+            };
+            let baseline = input::read_sample(&baseline_filename, &input)?;
+            let target = Sample::new(times);
+            let mut timings = Vec::new();
+            record_timing(&mut timings, "running", start.elapsed().as_secs_f64());
+            let inputs = vec![
+                InputInfo::new("baseline", &baseline_filename, baseline.len())?,
+                InputInfo {
+                    role: "target",
+                    path: command.join(" "),
+                    sha256: sha256::hex_digest(text.as_bytes()),
+                    count: target.len(),
+                },
+            ];
+            compare(&args, &baseline, &target, inputs, timings)
+        }
+        Some(Command::Duel {
+            runs,
+            warmup,
+            measure,
+            compare: args,
+            commands,
+        }) => {
+            let (first, second) = runner::split_commands(&commands)?;
+            let start = Instant::now();
+            let order_seed = args.seed.unwrap_or_else(rand::random);
+            let (first_times, second_times) =
+                runner::duel(first, second, runs, warmup, measure, order_seed)?;
+            let mut timings = Vec::new();
+            record_timing(&mut timings, "running", start.elapsed().as_secs_f64());
+            let info = |role, command: &[String], times: &[f64]| InputInfo {
+                role,
+                path: command.join(" "),
+                sha256: sha256::hex_digest(
+                    times
+                        .iter()
+                        .map(|t| format!("{}\n", t))
+                        .collect::<String>()
+                        .as_bytes(),
+                ),
+                count: times.len(),
+            };
+            let inputs = vec![
+                info("baseline", first, &first_times),
+                info("target", second, &second_times),
+            ];
+            compare(
+                &args,
+                &Sample::new(first_times),
+                &Sample::new(second_times),
+                inputs,
+                timings,
+            )
+        }
+        Some(Command::Tui {
+            baseline_filename,
+            target_filename,
+            input,
+            compare: args,
+        }) => {
+            tui::check_terminal()?;
+            let baseline = input::read_sample(&baseline_filename, &input)?;
+            let target = input::read_sample(&target_filename, &input)?;
+            warn_autocorrelation(&args, &baseline, &target);
+            let results = comparison_results(&args, &baseline, &target)?;
+            tui::run(
+                &baseline,
+                &target,
+                &results,
+                args.alpha,
+                &units(&args)?,
+                &labels(&args),
+            )?;
+            Ok(false)
+        }
+        Some(Command::Follow {
+            baseline_filename,
+            target_filename,
+            planned,
+            look_every,
+            input,
+            compare: args,
+        }) => sequential::follow(
+            &args,
+            &baseline_filename,
+            &target_filename,
+            &input,
+            planned,
+            look_every,
+        ),
+        Some(Command::Merge {
+            filenames,
+            input,
+            output,
+            summaries,
+            alpha,
+        }) => {
+            merge::merge(&filenames, &input, output.as_deref(), summaries, alpha)?;
+            Ok(false)
+        }
+        Some(Command::Groups {
+            filenames,
+            input,
+            iterations,
+            alpha,
+            seed,
+            rng,
+            layout,
+            pairwise,
+            pairwise_csv,
+            pairwise_json,
+        }) => {
+            omnibus::groups(
+                &filenames,
+                &input,
+                iterations,
+                Seed::new(seed.unwrap_or_else(rand::random), rng),
+                alpha,
+                layout,
+                omnibus::PairwiseOutput {
+                    table: pairwise,
+                    csv: pairwise_csv.as_deref(),
+                    json: pairwise_json.as_deref(),
+                },
+            )?;
+            Ok(false)
+        }
+        Some(Command::Changepoint {
+            filename,
+            input,
+            min_segment,
+            iterations,
+            alpha,
+            seed,
+            rng,
+            layout,
+        }) => {
+            changepoint::changepoint(
+                &filename,
+                &input,
+                min_segment,
+                iterations,
+                Seed::new(seed.unwrap_or_else(rand::random), rng),
+                alpha,
+                layout,
+            )?;
+            Ok(false)
+        }
+        Some(Command::Windows {
+            filename,
+            input,
+            window,
+            against,
+            estimator,
+            iterations,
+            alpha,
+            seed,
+            rng,
+            layout,
+        }) => {
+            windows::windows(
+                &filename,
+                &input,
+                windows::WindowOptions {
+                    size: window,
+                    against,
+                    estimator: &estimator,
+                    iterations,
+                    seed: Seed::new(seed.unwrap_or_else(rand::random), rng),
+                    alpha,
+                    layout,
+                },
+            )?;
+            Ok(false)
+        }
+        Some(Command::Power {
+            filename,
+            input,
+            effect,
+            alpha,
+            iterations,
+            seed,
+            size,
+        }) => {
+            let baseline = input::read_sample(&filename, &input)?;
+            power::power(
+                &baseline,
+                effect,
+                alpha,
+                iterations,
+                seed.unwrap_or_else(rand::random),
+                size,
+            )?;
+            Ok(false)
+        }
+        Some(Command::Samplesize {
+            filename,
+            input,
+            effect,
+            power,
+            alpha,
+            iterations,
+            seed,
+            max_size,
+        }) => {
+            let baseline = input::read_sample(&filename, &input)?;
+            power::samplesize(
+                &baseline,
+                effect,
+                alpha,
+                power,
+                iterations,
+                seed.unwrap_or_else(rand::random),
+                max_size,
+            )?;
+            Ok(false)
+        }
+        Some(Command::Selftest {
+            runs,
+            size,
+            compare: args,
+        }) => {
+            selftest::selftest(&args, runs, size)?;
+            Ok(false)
+        }
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(shell, &mut Cli::command(), "numcmp", &mut std::io::stdout());
+            Ok(false)
+        }
+        Some(Command::Mangen) => {
+            clap_mangen::Man::new(Cli::command()).render(&mut std::io::stdout())?;
+            Ok(false)
+        }
+        Some(Command::Diff { old, new, layout }) => diff::diff(&old, &new, layout),
+        Some(Command::Trend {
+            db,
+            estimator,
+            iterations,
+            alpha,
+            seed,
+        }) => {
+            trend(
+                &db,
+                &estimator,
+                iterations,
+                alpha,
+                seed.unwrap_or_else(rand::random),
+            )?;
+            Ok(false)
+        }
+        None => {
+            if let Some(marker) = &cli.split_marker {
+                let start = Instant::now();
+                let source = cli.baseline_filename.unwrap_or_else(|| PathBuf::from("-"));
+                let (baseline_name, target_name) = input::split_input(&source, marker, &cli.input)?;
+                // The parts were decompressed when the input was split.
+                let input = InputArgs {
+                    compression: Compression::None,
+                    ..cli.input.clone()
+                };
+                let baseline = input::read_sample(&baseline_name, &input)?;
+                let target = input::read_sample(&target_name, &input)?;
+                let inputs = vec![
+                    InputInfo::new("baseline", &baseline_name, baseline.len())?,
+                    InputInfo::new("target", &target_name, target.len())?,
+                ];
+                let mut timings = Vec::new();
+                record_timing(&mut timings, "reading", start.elapsed().as_secs_f64());
+                return compare(&cli.compare, &baseline, &target, inputs, timings);
+            }
+            if cli.watch {
+                let paths: Vec<&Path> = cli
+                    .baseline_filename
+                    .iter()
+                    .chain(cli.target_filename.iter())
+                    .map(PathBuf::as_path)
+                    .collect();
+                watch::run(&paths, || compare_files(&cli))?;
+                return Ok(false);
+            }
+            compare_files(&cli)
+        }
+    }
+}
+
+// Reads the samples from the files named on the command line and compares
+// them, returning whether any comparison regressed.
+fn compare_files(cli: &Cli) -> Result<bool, Error> {
+    let start = Instant::now();
+    let baseline_filename = cli.baseline_filename.as_deref().expect("required by clap");
+    if cli.all_columns {
+        let target_filename = cli.target_filename.as_deref().expect("required by clap");
+        return compare_all_columns(&cli.compare, baseline_filename, target_filename, &cli.input);
+    }
+
+    let (baseline, target, inputs) = match (&cli.input.baseline_group, &cli.input.target_group) {
+        (Some(baseline_group), Some(target_group)) => {
+            let (baseline, target) =
+                input::read_grouped(baseline_filename, &cli.input, baseline_group, target_group)?;
+            let inputs = vec![
+                InputInfo::new("baseline", baseline_filename, baseline.values.len())?,
+                InputInfo::new("target", baseline_filename, target.values.len())?,
+            ];
+            (baseline.into_sample()?, target.into_sample()?, inputs)
+        }
+        _ => {
+            let target_filename = cli.target_filename.as_deref().expect("required by clap");
+            let baseline = input::read_sample(baseline_filename, &cli.input)?;
+            let target = input::read_sample(target_filename, &cli.input)?;
+            let inputs = vec![
+                InputInfo::new("baseline", baseline_filename, baseline.len())?,
+                InputInfo::new("target", target_filename, target.len())?,
+            ];
+            (baseline, target, inputs)
+        }
+    };
+    let mut timings = Vec::new();
+    record_timing(&mut timings, "reading", start.elapsed().as_secs_f64());
+    compare_column(&cli.compare, None, &baseline, &target, inputs, timings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compare_args(flags: &[&str]) -> CompareArgs {
+        let args = ["numcmp", "a", "b"].iter().chain(flags);
+        Cli::try_parse_from(args).unwrap().compare
+    }
+
+    // The Bayesian bootstrap reweights the samples, which the quantile
+    // definitions other than type 7 would ignore.
+    #[test]
+    fn bayes_rejects_other_quantile_methods() {
+        let sample = Sample::new(vec![1.0, 2.0, 3.0]);
+        let prepared = |flags: &[&str]| prepare(&compare_args(flags), &sample, &sample, Vec::new());
+        assert!(matches!(
+            prepared(&["--method", "bayes", "--quantile-method", "5"]),
+            Err(Error::Usage(_))
+        ));
+        // --discrete makes type 2 the default.
+        assert!(matches!(
+            prepared(&["--method", "bayes", "--discrete"]),
+            Err(Error::Usage(_))
+        ));
+        assert!(prepared(&["--method", "bayes"]).is_ok());
+    }
+}
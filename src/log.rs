@@ -0,0 +1,73 @@
+// Diagnostics on stderr, kept apart from the analytical output on stdout and
+// filtered by --log-level. They are emitted as tracing events; each is one
+// line: "numcmp: LEVEL: message" followed by key=value fields.
+
+use clap::ValueEnum;
+use std::fmt;
+use tracing::level_filters::LevelFilter;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, ValueEnum)]
+pub enum Level {
+    /// Nothing, not even warnings
+    Off,
+    Error,
+    /// Warnings about the input, such as skipped lines and dropped values
+    Warn,
+    /// The time taken by each phase
+    Info,
+    /// Simulation progress
+    Debug,
+}
+
+impl From<Level> for LevelFilter {
+    fn from(level: Level) -> LevelFilter {
+        match level {
+            Level::Off => LevelFilter::OFF,
+            Level::Error => LevelFilter::ERROR,
+            Level::Warn => LevelFilter::WARN,
+            Level::Info => LevelFilter::INFO,
+            Level::Debug => LevelFilter::DEBUG,
+        }
+    }
+}
+
+// Writes events in numcmp's own line format rather than tracing's default
+// of timestamps and targets.
+struct Format;
+
+impl<S, N> FormatEvent<S, N> for Format
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let name = match *event.metadata().level() {
+            tracing::Level::ERROR => "error",
+            tracing::Level::WARN => "warning",
+            tracing::Level::INFO => "info",
+            _ => "debug",
+        };
+        write!(writer, "numcmp: {}: ", name)?;
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+// Sends events at `level` and above to stderr. Only the first call in a
+// process takes effect.
+pub fn init(level: Level) {
+    let _ = tracing_subscriber::fmt()
+        .with_max_level(LevelFilter::from(level))
+        .with_writer(std::io::stderr)
+        .event_format(Format)
+        .try_init();
+}
@@ -0,0 +1,104 @@
+// Margins for equivalence testing, either relative to the baseline
+// estimator ("2%") or in the units of the data ("0.5").
+
+use crate::estimator::Direction;
+use crate::EstimatorResult;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Margin {
+    Relative(f64),
+    Absolute(f64),
+}
+
+impl Margin {
+    pub fn amount(&self, baseline: f64) -> f64 {
+        match self {
+            Margin::Relative(fraction) => fraction * baseline.abs(),
+            Margin::Absolute(x) => *x,
+        }
+    }
+}
+
+impl std::fmt::Display for Margin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Margin::Relative(fraction) => write!(f, "{}%", 100.0 * fraction),
+            Margin::Absolute(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+pub fn parse_margin(s: &str) -> Result<Margin, String> {
+    let (number, relative) = match s.strip_suffix('%') {
+        Some(number) => (number, true),
+        None => (s, false),
+    };
+    let x: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid margin {:?}, expected e.g. 2% or 0.5", s))?;
+    if !x.is_finite() || x < 0.0 {
+        return Err(format!("margin must be non-negative, got {:?}", s));
+    }
+    Ok(if relative {
+        Margin::Relative(x / 100.0)
+    } else {
+        Margin::Absolute(x)
+    })
+}
+
+// Fraction of simulated values, shifted by `shift`, that are at least as
+// large (or small) as the target estimator.
+fn shifted_fraction(sims: &[f64], shift: f64, target: f64, at_least: bool) -> f64 {
+    let count = sims
+        .iter()
+        .filter(|sim| {
+            if at_least {
+                **sim + shift >= target
+            } else {
+                **sim + shift <= target
+            }
+        })
+        .count();
+    (count as f64) / (sims.len() as f64)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Equivalence {
+    pub margin: f64,
+    // Null hypothesis: the target is lower than baseline - margin.
+    pub p_lower: f64,
+    // Null hypothesis: the target is higher than baseline + margin.
+    pub p_upper: f64,
+}
+
+impl Equivalence {
+    pub fn p_value(&self) -> f64 {
+        self.p_lower.max(self.p_upper)
+    }
+
+    pub fn equivalent(&self, alpha: f64) -> bool {
+        self.p_value() < alpha
+    }
+}
+
+// Two one-sided tests: each null is placed at the edge of the margin by
+// shifting the simulated baseline distribution.
+pub fn tost(result: &EstimatorResult, sims: &[f64], margin: Margin) -> Equivalence {
+    let d = margin.amount(result.full_baseline_estimator);
+    Equivalence {
+        margin: d,
+        p_lower: shifted_fraction(sims, -d, result.target_estimator, true),
+        p_upper: shifted_fraction(sims, d, result.target_estimator, false),
+    }
+}
+
+// One-sided p-value for the target being worse than the baseline by more
+// than the tolerated margin.
+pub fn tolerated_p_value(result: &EstimatorResult, sims: &[f64], margin: Margin) -> f64 {
+    let d = margin.amount(result.full_baseline_estimator);
+    match result.direction {
+        Direction::LowerIsBetter => shifted_fraction(sims, d, result.target_estimator, true),
+        Direction::HigherIsBetter => shifted_fraction(sims, -d, result.target_estimator, false),
+    }
+}
@@ -0,0 +1,82 @@
+// Combining the measurements of several runs into one sample, checking
+// first that the runs look alike.
+
+use crate::input::{self, InputArgs, Observations};
+use crate::stats;
+use crate::units::format_number;
+use crate::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// Writes the values of all files, in order, one per line followed by the
+// weight if the input had weights, so the result reads back the same way.
+fn write_merged(out: &mut dyn Write, runs: &[Observations]) -> std::io::Result<()> {
+    for run in runs {
+        for (i, x) in run.values.iter().enumerate() {
+            match &run.weights {
+                Some(ws) => writeln!(out, "{} {}", x, ws[i])?,
+                None => writeln!(out, "{}", x)?,
+            }
+        }
+    }
+    out.flush()
+}
+
+pub fn merge(
+    filenames: &[PathBuf],
+    input: &InputArgs,
+    output: Option<&Path>,
+    summaries: bool,
+    alpha: f64,
+) -> Result<(), Error> {
+    let runs = filenames
+        .iter()
+        .map(|f| input::read_observations(f, input))
+        .collect::<Result<Vec<Observations>, Error>>()?;
+    let weighted = runs.iter().any(|r| r.weights.is_some());
+    if weighted && runs.iter().any(|r| r.weights.is_none()) {
+        return Err(Error::InvalidInput(
+            "cannot merge weighted and unweighted files".to_string(),
+        ));
+    }
+
+    if summaries {
+        for (filename, run) in filenames.iter().zip(runs.iter()) {
+            let sample = Observations {
+                values: run.values.clone(),
+                weights: run.weights.clone(),
+                strata: None,
+            }
+            .into_sample()?;
+            eprintln!(
+                "{}: n={}, mean={}, median={}",
+                filename.display(),
+                sample.size(),
+                format_number(sample.mean()),
+                format_number(sample.quantile(0.5)?)
+            );
+        }
+    }
+
+    // The rank test ignores weights, so it only checks unweighted runs.
+    if runs.len() > 1 && !weighted {
+        let groups: Vec<&[f64]> = runs.iter().map(|r| r.values.as_slice()).collect();
+        if let Some(test) = stats::kruskal_wallis(&groups) {
+            if test.p_value < alpha {
+                tracing::warn!(
+                    "the runs differ (Kruskal-Wallis H={:.3}, df={}, p={:.4}); merging them may hide a change between runs",
+                    test.h, test.df, test.p_value
+                );
+            }
+        }
+    }
+
+    match output {
+        Some(path) => {
+            write_merged(&mut BufWriter::new(File::create(path)?), &runs)?;
+        }
+        None => write_merged(&mut std::io::stdout().lock(), &runs)?,
+    }
+    Ok(())
+}
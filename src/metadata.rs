@@ -0,0 +1,166 @@
+// Information needed to reproduce a run, embedded in JSON and HTML output.
+
+use crate::environment::Environment;
+use crate::{fetch, json, sha256, Error};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub struct InputInfo {
+    pub role: &'static str,
+    pub path: String,
+    pub sha256: String,
+    pub count: usize,
+}
+
+// What the two samples are called in tables, plots and reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Labels {
+    pub baseline: String,
+    pub target: String,
+}
+
+impl Default for Labels {
+    fn default() -> Labels {
+        Labels {
+            baseline: "baseline".to_string(),
+            target: "target".to_string(),
+        }
+    }
+}
+
+pub struct RunMetadata {
+    pub version: &'static str,
+    pub args: Vec<String>,
+    pub seed: u64,
+    pub iterations: u64,
+    pub inputs: Vec<InputInfo>,
+    pub timestamp: String,
+    pub unit: Option<String>,
+    // The --bandwidth of density plots, if given.
+    pub bandwidth: Option<f64>,
+    pub labels: Labels,
+    // The CSV column compared, with --all-columns.
+    pub column: Option<String>,
+    // The look and decision of --sequential, as JSON.
+    pub sequential: Option<String>,
+    // The machine the run happened on, with --capture-env.
+    pub environment: Option<Environment>,
+}
+
+// Formats seconds since the epoch as an RFC 3339 UTC timestamp.
+fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+
+    // Civil-from-days conversion (Howard Hinnant's algorithm).
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem / 60) % 60,
+        rem % 60
+    )
+}
+
+// There is no clock on wasm32, where runs get the epoch as timestamp.
+pub fn now_timestamp() -> String {
+    if cfg!(target_arch = "wasm32") {
+        return format_timestamp(0);
+    }
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    format_timestamp(secs)
+}
+
+impl InputInfo {
+    pub fn new(role: &'static str, path: &Path, count: usize) -> Result<InputInfo, Error> {
+        Ok(InputInfo {
+            role,
+            path: path.display().to_string(),
+            sha256: sha256::hex_digest(&std::fs::read(fetch::local(path)?)?),
+            count,
+        })
+    }
+}
+
+impl RunMetadata {
+    pub fn new(seed: u64, iterations: u64, inputs: Vec<InputInfo>) -> RunMetadata {
+        RunMetadata {
+            version: env!("CARGO_PKG_VERSION"),
+            args: std::env::args().collect(),
+            seed,
+            iterations,
+            inputs,
+            timestamp: now_timestamp(),
+            unit: None,
+            bandwidth: None,
+            labels: Labels::default(),
+            column: None,
+            sequential: None,
+            environment: None,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let args: Vec<String> = self.args.iter().map(|a| json::string(a)).collect();
+        let inputs: Vec<String> = self
+            .inputs
+            .iter()
+            .map(|input| {
+                json::object(&[
+                    ("role", json::string(input.role)),
+                    ("path", json::string(&input.path)),
+                    ("sha256", json::string(&input.sha256)),
+                    ("count", input.count.to_string()),
+                ])
+            })
+            .collect();
+        let mut fields = vec![
+            ("numcmp_version", json::string(self.version)),
+            ("args", json::array(&args)),
+            ("seed", self.seed.to_string()),
+            ("iterations", self.iterations.to_string()),
+            ("inputs", json::array(&inputs)),
+            ("timestamp", json::string(&self.timestamp)),
+        ];
+        if let Some(unit) = &self.unit {
+            fields.push(("unit", json::string(unit)));
+        }
+        if let Some(bandwidth) = self.bandwidth {
+            fields.push(("bandwidth", json::number(bandwidth)));
+        }
+        if let Some(column) = &self.column {
+            fields.push(("column", json::string(column)));
+        }
+        if let Some(sequential) = &self.sequential {
+            fields.push(("sequential", sequential.clone()));
+        }
+        if let Some(environment) = &self.environment {
+            fields.push(("environment", environment.to_json()));
+        }
+        if self.labels != Labels::default() {
+            fields.push((
+                "labels",
+                json::object(&[
+                    ("baseline", json::string(&self.labels.baseline)),
+                    ("target", json::string(&self.labels.target)),
+                ]),
+            ));
+        }
+        json::object(&fields)
+    }
+}
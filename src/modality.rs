@@ -0,0 +1,139 @@
+// Silverman's bootstrap test for multimodality, for --modality. The
+// critical bandwidth is the smallest with which a Gaussian kernel density
+// estimate of the sample has a single mode; a multimodal sample needs a
+// large one. How often smoothed bootstrap resamples, drawn from that
+// unimodal estimate, still have more than one mode at that bandwidth gives
+// the p-value for unimodality.
+
+use crate::sample::Sample;
+use crate::stats::standard_normal;
+use rand::Rng;
+
+// Points of the grid the densities are evaluated on.
+const GRID: usize = 256;
+
+// Bisection steps for the critical bandwidth.
+const STEPS: usize = 40;
+
+pub struct Modality {
+    pub critical_bandwidth: f64,
+    // The p-value for the hypothesis that the sample is unimodal.
+    pub p_value: f64,
+}
+
+// The weighted values linearly binned onto GRID points from the smallest
+// value to the largest, and the spacing of the points.
+fn binned(values: &[f64], weights: Option<&[f64]>) -> (Vec<f64>, f64) {
+    let lo = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let hi = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let step = (hi - lo) / (GRID - 1) as f64;
+    let mut counts = vec![0.0; GRID];
+    for (i, x) in values.iter().enumerate() {
+        let w = weights.map_or(1.0, |ws| ws[i]);
+        let position = (x - lo) / step;
+        let j = (position.floor() as usize).min(GRID - 2);
+        let fraction = position - j as f64;
+        counts[j] += w * (1.0 - fraction);
+        counts[j + 1] += w * fraction;
+    }
+    (counts, step)
+}
+
+// The number of modes of the density estimate with bandwidth h. All modes
+// of a Gaussian kernel estimate lie between the extreme values, so the grid
+// covers them.
+fn modes(counts: &[f64], step: f64, h: f64) -> usize {
+    let reach = ((4.0 * h / step).ceil() as usize).min(GRID);
+    let kernel: Vec<f64> = (0..=reach)
+        .map(|d| (-0.5 * (d as f64 * step / h).powi(2)).exp())
+        .collect();
+    let density: Vec<f64> = (0..GRID)
+        .map(|j| {
+            let from = j.saturating_sub(reach);
+            let to = (j + reach).min(GRID - 1);
+            (from..=to).map(|k| counts[k] * kernel[j.abs_diff(k)]).sum()
+        })
+        .collect();
+    // Rounding makes ripples where the density is flat.
+    let tolerance = 1e-9 * density.iter().copied().fold(0.0, f64::max);
+    (0..GRID)
+        .filter(|&j| {
+            (j == 0 || density[j] > density[j - 1] + tolerance)
+                && (j == GRID - 1 || density[j] + tolerance >= density[j + 1])
+        })
+        .count()
+}
+
+// The smallest bandwidth, to within a small factor, giving a single mode.
+fn critical_bandwidth(counts: &[f64], step: f64) -> f64 {
+    let (mut lo, mut hi) = (step, step * GRID as f64);
+    if modes(counts, step, lo) <= 1 {
+        return lo;
+    }
+    for _ in 0..STEPS {
+        let mid = (lo * hi).sqrt();
+        if modes(counts, step, mid) <= 1 {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    hi
+}
+
+// None for samples with fewer than three distinct values, which have no
+// shape to test.
+pub fn silverman(sample: &Sample, iterations: u64, rng: &mut impl Rng) -> Option<Modality> {
+    let distinct = sample.values.windows(2).filter(|w| w[0] != w[1]).count() + 1;
+    if distinct < 3 {
+        return None;
+    }
+    let (counts, step) = binned(&sample.values, sample.weights.as_deref());
+    let h = critical_bandwidth(&counts, step);
+
+    // Resamples are drawn from the density estimate with bandwidth h,
+    // rescaled to the sample's variance (Silverman 1981).
+    let mean = sample.mean();
+    let total = sample.total_weight();
+    let variance = sample
+        .values
+        .iter()
+        .enumerate()
+        .map(|(i, x)| sample.weights.as_ref().map_or(1.0, |ws| ws[i]) * (x - mean).powi(2))
+        .sum::<f64>()
+        / total;
+    let scale = 1.0 / (1.0 + h * h / variance).sqrt();
+    let cumulative: Option<Vec<f64>> = sample.weights.as_ref().map(|ws| {
+        ws.iter()
+            .scan(0.0, |sum, w| {
+                *sum += w;
+                Some(*sum)
+            })
+            .collect()
+    });
+    let n = sample.size();
+    let mut resample = Vec::with_capacity(n);
+    let mut multimodal = 0;
+    for _ in 0..iterations {
+        resample.clear();
+        for _ in 0..n {
+            let i = match &cumulative {
+                Some(c) => {
+                    let u = rng.gen::<f64>() * total;
+                    c.partition_point(|w| *w < u).min(c.len() - 1)
+                }
+                None => rng.gen_range(0..sample.len()),
+            };
+            let x = sample.values[i] + h * standard_normal(rng);
+            resample.push(mean + (x - mean) * scale);
+        }
+        let (counts, step) = binned(&resample, None);
+        if modes(&counts, step, h) > 1 {
+            multimodal += 1;
+        }
+    }
+    Some(Modality {
+        critical_bandwidth: h,
+        p_value: multimodal as f64 / iterations as f64,
+    })
+}
@@ -0,0 +1,289 @@
+// Omnibus tests of whether several samples differ at all, to run before
+// looking at any pair of them.
+
+use crate::estimator::{default_estimators, Estimator, QuantileOptions};
+use crate::input::{self, InputArgs};
+use crate::resample::{BootstrapMethod, Resampler};
+use crate::rng::Seed;
+use crate::sample::Sample;
+use crate::table::{Layout, Table};
+use crate::units::format_number;
+use crate::{csv_field, json, simulate, stats, Alternative, Error, Null, Stopping};
+use rand::seq::SliceRandom;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// Kruskal-Wallis H from the rank sums of consecutive groups of the given
+// sizes, without the tie correction, which permutations do not change.
+fn h_statistic(ranks: &[f64], sizes: &[usize]) -> f64 {
+    let n = ranks.len() as f64;
+    let mut start = 0;
+    let mut sum = 0.0;
+    for &size in sizes {
+        let r: f64 = ranks[start..start + size].iter().sum();
+        sum += r * r / size as f64;
+        start += size;
+    }
+    12.0 / (n * (n + 1.0)) * sum - 3.0 * (n + 1.0)
+}
+
+// Fraction of random reassignments of the pooled values to groups of the
+// same sizes giving an H statistic at least as large as the observed one.
+fn permutation_p_value(groups: &[&[f64]], iterations: u64, seed: Seed) -> f64 {
+    let (ranks, _) = stats::pooled_ranks(groups);
+    let sizes: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+    let mut pooled: Vec<f64> = ranks.into_iter().flatten().collect();
+    let observed = h_statistic(&pooled, &sizes);
+    let mut rng = seed.analysis_stream("omnibus");
+    let mut extreme = 0;
+    for _ in 0..iterations {
+        pooled.shuffle(&mut rng);
+        // Allow for rounding in the sums.
+        if h_statistic(&pooled, &sizes) >= observed - 1e-9 * observed.abs() {
+            extreme += 1;
+        }
+    }
+    // Counting the observed statistic as one of the permutations keeps the
+    // p-value above zero, as a permutation test's should be.
+    (extreme + 1) as f64 / (iterations + 1) as f64
+}
+
+// One cell of the pairwise matrix: group `target` against group `baseline`
+// for one estimator.
+struct Pair {
+    estimator: String,
+    baseline: usize,
+    target: usize,
+    baseline_value: f64,
+    target_value: f64,
+    p_value: f64,
+    // Holm-adjusted over all pairs of groups for this estimator.
+    adjusted_p_value: f64,
+}
+
+impl Pair {
+    fn delta(&self) -> f64 {
+        self.target_value - self.baseline_value
+    }
+}
+
+// Two-sided bootstrap comparisons of every pair of groups, each with its own
+// seed derived from `seed`.
+fn pairwise(
+    samples: &[Sample],
+    estimators: &[Box<dyn Estimator>],
+    iterations: u64,
+    seed: Seed,
+) -> Result<Vec<Pair>, Error> {
+    let mut pairs = Vec::new();
+    let mut k = 0;
+    for i in 0..samples.len() {
+        for j in (i + 1)..samples.len() {
+            let resampler = Resampler::new(BootstrapMethod::Iid, None, None, None, &samples[i])?;
+            let results = simulate(
+                Stopping::Fixed(iterations),
+                Some(Alternative::TwoSided),
+                Seed::new(seed.value.wrapping_add(k), seed.kind),
+                &resampler,
+                &samples[i],
+                &samples[j],
+                estimators,
+                Null::Baseline,
+                None,
+                None,
+                &mut |_, _| Ok(()),
+            )?;
+            k += 1;
+            for r in results {
+                pairs.push(Pair {
+                    p_value: r.p_value(),
+                    estimator: r.name,
+                    baseline: i,
+                    target: j,
+                    baseline_value: r.full_baseline_estimator,
+                    target_value: r.target_estimator,
+                    adjusted_p_value: 1.0,
+                });
+            }
+        }
+    }
+    for est in estimators {
+        let indices: Vec<usize> = (0..pairs.len())
+            .filter(|i| pairs[*i].estimator == est.name())
+            .collect();
+        let p_values: Vec<f64> = indices.iter().map(|i| pairs[*i].p_value).collect();
+        for (i, adjusted) in indices.iter().zip(stats::holm(&p_values)) {
+            pairs[*i].adjusted_p_value = adjusted;
+        }
+    }
+    Ok(pairs)
+}
+
+// A matrix per estimator with the difference of the column's group from the
+// row's, and the adjusted p-value.
+fn print_pairwise(
+    names: &[String],
+    pairs: &[Pair],
+    estimators: &[Box<dyn Estimator>],
+    layout: Layout,
+) {
+    for est in estimators {
+        println!(
+            "=== Pairwise: {} (column - row, Holm-adjusted p) ===",
+            est.name()
+        );
+        let mut header = vec![""];
+        header.extend(names.iter().map(|n| n.as_str()));
+        let mut table = Table::new(&header);
+        for (i, name) in names.iter().enumerate() {
+            let mut row = vec![name.clone()];
+            for j in 0..names.len() {
+                let pair = pairs.iter().find(|p| {
+                    p.estimator == est.name()
+                        && ((p.baseline, p.target) == (i, j) || (p.baseline, p.target) == (j, i))
+                });
+                row.push(match pair {
+                    None => "-".to_string(),
+                    Some(p) => {
+                        let delta = if p.baseline == i {
+                            p.delta()
+                        } else {
+                            -p.delta()
+                        };
+                        let sign = if delta > 0.0 { "+" } else { "" };
+                        format!(
+                            "{}{} (p={:.4})",
+                            sign,
+                            format_number(delta),
+                            p.adjusted_p_value
+                        )
+                    }
+                });
+            }
+            table.row(row);
+        }
+        print!("{}", table.render(layout));
+        println!();
+    }
+}
+
+fn write_pairwise_csv(path: &Path, names: &[String], pairs: &[Pair]) -> Result<(), Error> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(
+        out,
+        "estimator,baseline,target,baseline_value,target_value,delta,p_value,adjusted_p_value"
+    )?;
+    for p in pairs {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            csv_field(&p.estimator),
+            csv_field(&names[p.baseline]),
+            csv_field(&names[p.target]),
+            p.baseline_value,
+            p.target_value,
+            p.delta(),
+            p.p_value,
+            p.adjusted_p_value
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn write_pairwise_json(path: &Path, names: &[String], pairs: &[Pair]) -> Result<(), Error> {
+    let items: Vec<String> = pairs
+        .iter()
+        .map(|p| {
+            json::object(&[
+                ("estimator", json::string(&p.estimator)),
+                ("baseline", json::string(&names[p.baseline])),
+                ("target", json::string(&names[p.target])),
+                ("baseline_value", json::number(p.baseline_value)),
+                ("target_value", json::number(p.target_value)),
+                ("delta", json::number(p.delta())),
+                ("p_value", json::number(p.p_value)),
+                ("adjusted_p_value", json::number(p.adjusted_p_value)),
+            ])
+        })
+        .collect();
+    std::fs::write(path, json::array(&items) + "\n")?;
+    Ok(())
+}
+
+// Where to put the pairwise comparisons of `numcmp groups`, if anywhere.
+pub struct PairwiseOutput<'a> {
+    pub table: bool,
+    pub csv: Option<&'a Path>,
+    pub json: Option<&'a Path>,
+}
+
+pub fn groups(
+    filenames: &[PathBuf],
+    input: &InputArgs,
+    iterations: u64,
+    seed: Seed,
+    alpha: f64,
+    layout: Layout,
+    output: PairwiseOutput,
+) -> Result<(), Error> {
+    let samples = filenames
+        .iter()
+        .map(|f| input::read_sample(f, input))
+        .collect::<Result<Vec<Sample>, Error>>()?;
+    if samples.iter().any(|s| s.weights.is_some()) {
+        return Err(Error::Usage(
+            "rank tests do not support weighted samples".to_string(),
+        ));
+    }
+
+    println!("=== Groups ===");
+    let mut table = Table::new(&["group", "n", "mean", "median"]);
+    for (filename, sample) in filenames.iter().zip(samples.iter()) {
+        table.row(vec![
+            filename.display().to_string(),
+            sample.size().to_string(),
+            format_number(sample.mean()),
+            format_number(sample.quantile(0.5)?),
+        ]);
+    }
+    print!("{}", table.render(layout));
+    println!();
+
+    let groups: Vec<&[f64]> = samples.iter().map(|s| s.values.as_slice()).collect();
+    let test = stats::kruskal_wallis(&groups).ok_or_else(|| {
+        Error::InvalidInput("cannot test groups whose values are all equal".to_string())
+    })?;
+    let permutation_p = permutation_p_value(&groups, iterations, seed);
+    println!("=== Omnibus test ===");
+    println!(
+        "Kruskal-Wallis: H={:.3}, df={}, p={:.4} (chi-squared), p={:.4} ({} permutations)",
+        test.h, test.df, test.p_value, permutation_p, iterations
+    );
+    if permutation_p < alpha {
+        println!("The groups differ at alpha={}.", alpha);
+    } else {
+        println!(
+            "No significant difference between the groups at alpha={}.",
+            alpha
+        );
+    }
+
+    if output.table || output.csv.is_some() || output.json.is_some() {
+        let names: Vec<String> = filenames.iter().map(|f| f.display().to_string()).collect();
+        let estimators = default_estimators(QuantileOptions::default());
+        let pairs = pairwise(&samples, &estimators, iterations, seed)?;
+        if output.table {
+            println!();
+            print_pairwise(&names, &pairs, &estimators, layout);
+        }
+        if let Some(path) = output.csv {
+            write_pairwise_csv(path, &names, &pairs)?;
+        }
+        if let Some(path) = output.json {
+            write_pairwise_json(path, &names, &pairs)?;
+        }
+    }
+    Ok(())
+}
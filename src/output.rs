@@ -0,0 +1,395 @@
+// Machine-readable renderings of the comparison results.
+
+use crate::distance::Distance;
+use crate::estimator::Direction;
+use crate::metadata::{Labels, RunMetadata};
+use crate::stats::SpreadTest;
+use crate::svg::escape;
+use crate::units::{format_change, Units};
+use crate::{json, spread_verdict, EstimatorResult, Verdict};
+
+fn sample_json(count: usize, values: &[(&str, f64)]) -> String {
+    let estimators: Vec<(&str, String)> = values
+        .iter()
+        .map(|(name, x)| (*name, json::number(*x)))
+        .collect();
+    json::object(&[
+        ("count", count.to_string()),
+        ("estimators", json::object(&estimators)),
+    ])
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn results_json(
+    metadata: &RunMetadata,
+    baseline_count: usize,
+    target_count: usize,
+    results: &[EstimatorResult],
+    spread: Option<&SpreadTest>,
+    distances: &[Distance],
+    superiority: Option<&Distance>,
+    alpha: f64,
+) -> String {
+    let baseline_values: Vec<(&str, f64)> = results
+        .iter()
+        .map(|r| (r.name.as_str(), r.full_baseline_estimator))
+        .collect();
+    let target_values: Vec<(&str, f64)> = results
+        .iter()
+        .map(|r| (r.name.as_str(), r.target_estimator))
+        .collect();
+    let comparison: Vec<String> = results
+        .iter()
+        .map(|r| {
+            let mut fields = vec![
+                ("estimator", json::string(&r.name)),
+                ("baseline", json::number(r.full_baseline_estimator)),
+                ("target", json::number(r.target_estimator)),
+                (
+                    "delta",
+                    json::number(r.target_estimator - r.full_baseline_estimator),
+                ),
+                ("p_value", json::number(r.p_value())),
+                ("p_value_floor", json::number(r.p_value_floor())),
+                ("p_value_mc_ci_low", json::number(r.p_value_interval().0)),
+                ("p_value_mc_ci_high", json::number(r.p_value_interval().1)),
+                ("verdict", json::string(&r.verdict(alpha).to_string())),
+                (
+                    "better",
+                    json::string(match r.direction {
+                        Direction::LowerIsBetter => "lower",
+                        Direction::HigherIsBetter => "higher",
+                    }),
+                ),
+                ("sim_count", r.sim_count.to_string()),
+                ("target_lt_sim_count", r.target_lt_sim_count.to_string()),
+                ("target_gt_sim_count", r.target_gt_sim_count.to_string()),
+                ("target_eq_sim_count", r.target_eq_sim_count.to_string()),
+            ];
+            if let Some(p) = r.tolerated_p_value {
+                fields.push(("tolerated_p_value", json::number(p)));
+            }
+            if let Some((lo, hi)) = r.difference_interval {
+                fields.push(("delta_ci_low", json::number(lo)));
+                fields.push(("delta_ci_high", json::number(hi)));
+            }
+            if let Some(mde) = r.minimum_detectable_effect {
+                fields.push(("minimum_detectable_effect", json::number(mde)));
+            }
+            if let Some(eq) = &r.equivalence {
+                fields.push((
+                    "equivalence",
+                    json::object(&[
+                        ("margin", json::number(eq.margin)),
+                        ("p_lower", json::number(eq.p_lower)),
+                        ("p_upper", json::number(eq.p_upper)),
+                        ("equivalent", eq.equivalent(alpha).to_string()),
+                    ]),
+                ));
+            }
+            json::object(&fields)
+        })
+        .collect();
+    let mut fields = vec![
+        ("metadata", metadata.to_json()),
+        ("alpha", json::number(alpha)),
+        ("baseline", sample_json(baseline_count, &baseline_values)),
+        ("target", sample_json(target_count, &target_values)),
+        ("comparison", json::array(&comparison)),
+    ];
+    if let Some(spread) = spread {
+        fields.push((
+            "spread",
+            json::object(&[
+                ("test", json::string("brown-forsythe")),
+                ("baseline", json::number(spread.spreads.0)),
+                ("target", json::number(spread.spreads.1)),
+                ("f", json::number(spread.f)),
+                ("p_value", json::number(spread.p_value)),
+                ("verdict", json::string(spread_verdict(spread, alpha))),
+            ]),
+        ));
+    }
+    if !distances.is_empty() {
+        let distances: Vec<String> = distances
+            .iter()
+            .map(|d| {
+                json::object(&[
+                    ("name", json::string(d.name)),
+                    ("value", json::number(d.value)),
+                    ("ci_low", json::number(d.interval.0)),
+                    ("ci_high", json::number(d.interval.1)),
+                ])
+            })
+            .collect();
+        fields.push(("distances", json::array(&distances)));
+    }
+    if let Some(superiority) = superiority {
+        fields.push((
+            "superiority",
+            json::object(&[
+                ("value", json::number(superiority.value)),
+                ("ci_low", json::number(superiority.interval.0)),
+                ("ci_high", json::number(superiority.interval.1)),
+            ]),
+        ));
+    }
+    json::object(&fields)
+}
+
+fn openmetrics_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn openmetrics_value(x: f64) -> String {
+    if x.is_nan() {
+        "NaN".to_string()
+    } else if x == f64::INFINITY {
+        "+Inf".to_string()
+    } else if x == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        x.to_string()
+    }
+}
+
+pub fn results_openmetrics(
+    baseline_count: usize,
+    target_count: usize,
+    results: &[EstimatorResult],
+    alpha: f64,
+) -> String {
+    let mut out = String::new();
+    let mut family = |name: &str, help: &str, samples: Vec<(String, f64)>| {
+        out.push_str(&format!("# TYPE numcmp_{} gauge\n", name));
+        out.push_str(&format!("# HELP numcmp_{} {}\n", name, help));
+        for (labels, value) in samples {
+            out.push_str(&format!(
+                "numcmp_{}{{{}}} {}\n",
+                name,
+                labels,
+                openmetrics_value(value)
+            ));
+        }
+    };
+
+    family(
+        "sample_count",
+        "Number of values in each sample.",
+        vec![
+            ("sample=\"baseline\"".to_string(), baseline_count as f64),
+            ("sample=\"target\"".to_string(), target_count as f64),
+        ],
+    );
+    family(
+        "estimator",
+        "Estimator value for each sample.",
+        results
+            .iter()
+            .flat_map(|r| {
+                let name = openmetrics_label(&r.name);
+                [
+                    (
+                        format!("estimator=\"{}\",sample=\"baseline\"", name),
+                        r.full_baseline_estimator,
+                    ),
+                    (
+                        format!("estimator=\"{}\",sample=\"target\"", name),
+                        r.target_estimator,
+                    ),
+                ]
+            })
+            .collect(),
+    );
+    let per_estimator = |f: &dyn Fn(&EstimatorResult) -> f64| -> Vec<(String, f64)> {
+        results
+            .iter()
+            .map(|r| {
+                (
+                    format!("estimator=\"{}\"", openmetrics_label(&r.name)),
+                    f(r),
+                )
+            })
+            .collect()
+    };
+    family(
+        "delta",
+        "Target estimator minus baseline estimator.",
+        per_estimator(&|r| r.target_estimator - r.full_baseline_estimator),
+    );
+    family(
+        "p_value",
+        "Simulated p-value of the change in each estimator.",
+        per_estimator(&|r| r.p_value()),
+    );
+    family(
+        "regressed",
+        "1 if the estimator regressed significantly, 0 otherwise.",
+        per_estimator(&|r| {
+            if r.verdict(alpha) == Verdict::Regressed {
+                1.0
+            } else {
+                0.0
+            }
+        }),
+    );
+    out.push_str("# EOF\n");
+    out
+}
+
+fn github_escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn github_escape_property(s: &str) -> String {
+    github_escape_data(s)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+// Workflow commands for GitHub Actions: an error for each significant
+// regression, and a warning when an estimator got worse with a p-value
+// close to (within twice) the significance level.
+pub fn results_github_annotations(results: &[EstimatorResult], alpha: f64) -> String {
+    let mut out = String::new();
+    for r in results.iter() {
+        let worse = r.is_worse();
+        let level = match r.verdict(alpha) {
+            Verdict::Regressed => "error",
+            _ if worse && r.p_value() < 2.0 * alpha => "warning",
+            _ => continue,
+        };
+        let message = format!(
+            "{} changed from {} to {} ({}), p={}",
+            r.name,
+            r.full_baseline_estimator,
+            r.target_estimator,
+            format_change(r.full_baseline_estimator, r.target_estimator),
+            r.p_value_text()
+        );
+        out.push_str(&format!(
+            "::{} title={}::{}\n",
+            level,
+            github_escape_property(&format!("numcmp: {} regressed", r.name)),
+            github_escape_data(&message)
+        ));
+    }
+    out
+}
+
+pub fn results_markdown_table(
+    results: &[EstimatorResult],
+    alpha: f64,
+    units: &Units,
+    labels: &Labels,
+) -> String {
+    let mut out = String::new();
+    out.push_str(&format!(
+        "| estimator | {} | {} | change | p-value | verdict |\n",
+        units.label(&labels.baseline).replace('|', "\\|"),
+        units.label(&labels.target).replace('|', "\\|")
+    ));
+    out.push_str("|---|---:|---:|---:|---:|---|\n");
+    for r in results.iter() {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            r.name.replace('|', "\\|"),
+            r.full_baseline_estimator,
+            r.target_estimator,
+            format_change(r.full_baseline_estimator, r.target_estimator),
+            r.p_value_text(),
+            r.verdict(alpha)
+        ));
+    }
+    out
+}
+
+// One test case per estimator, failing when it regressed significantly.
+pub fn results_junit(results: &[EstimatorResult], alpha: f64) -> String {
+    let failures = results
+        .iter()
+        .filter(|r| r.verdict(alpha) == Verdict::Regressed)
+        .count();
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<testsuites name=\"numcmp\" tests=\"{}\" failures=\"{}\">\n",
+        results.len(),
+        failures
+    ));
+    out.push_str(&format!(
+        "  <testsuite name=\"numcmp\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\">\n",
+        results.len(),
+        failures
+    ));
+    for r in results.iter() {
+        let verdict = r.verdict(alpha);
+        let details = format!(
+            "{} changed from {} to {} (delta {}), p={}, verdict {} at alpha={}",
+            r.name,
+            r.full_baseline_estimator,
+            r.target_estimator,
+            r.target_estimator - r.full_baseline_estimator,
+            r.p_value_text(),
+            verdict,
+            alpha
+        );
+        out.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"numcmp\">\n",
+            escape(&r.name)
+        ));
+        if verdict == Verdict::Regressed {
+            out.push_str(&format!(
+                "      <failure message=\"{} regressed\" type=\"regression\">{}</failure>\n",
+                escape(&r.name),
+                escape(&details)
+            ));
+        } else {
+            out.push_str(&format!(
+                "      <system-out>{}</system-out>\n",
+                escape(&details)
+            ));
+        }
+        out.push_str("    </testcase>\n");
+    }
+    out.push_str("  </testsuite>\n");
+    out.push_str("</testsuites>\n");
+    out
+}
+
+// TAP version 13, with the numbers in a YAML diagnostic block per test.
+pub fn results_tap(results: &[EstimatorResult], alpha: f64) -> String {
+    let mut out = String::new();
+    out.push_str("TAP version 13\n");
+    out.push_str(&format!("1..{}\n", results.len()));
+    for (i, r) in results.iter().enumerate() {
+        let verdict = r.verdict(alpha);
+        let status = if verdict == Verdict::Regressed {
+            "not ok"
+        } else {
+            "ok"
+        };
+        out.push_str(&format!(
+            "{} {} - {}\n",
+            status,
+            i + 1,
+            r.name.replace('#', "\\#")
+        ));
+        out.push_str("  ---\n");
+        out.push_str(&format!("  baseline: {}\n", r.full_baseline_estimator));
+        out.push_str(&format!("  target: {}\n", r.target_estimator));
+        out.push_str(&format!(
+            "  delta: {}\n",
+            r.target_estimator - r.full_baseline_estimator
+        ));
+        out.push_str(&format!("  p_value: {}\n", r.p_value()));
+        out.push_str(&format!("  verdict: {}\n", verdict));
+        out.push_str("  ...\n");
+    }
+    out
+}
@@ -0,0 +1,648 @@
+// Reading a numeric column from Parquet files: the footer metadata, encoded
+// with Thrift's compact protocol, and the column's pages in each row group.
+// Pages may be plain or dictionary encoded, and uncompressed or compressed
+// with Snappy or gzip.
+
+use crate::gzip;
+
+// A message describing why the column could not be read.
+type Result<T> = std::result::Result<T, String>;
+
+const MAGIC: &[u8] = b"PAR1";
+
+// Physical types.
+const INT32: i64 = 1;
+const INT64: i64 = 2;
+const FLOAT: i64 = 4;
+const DOUBLE: i64 = 5;
+
+// Field repetitions.
+const OPTIONAL: i64 = 1;
+const REPEATED: i64 = 2;
+
+// Converted types of integers.
+const DECIMAL: i64 = 5;
+const UINT_8: i64 = 11;
+const UINT_64: i64 = 14;
+
+// Page types.
+const DATA_PAGE: i64 = 0;
+const DICTIONARY_PAGE: i64 = 2;
+const DATA_PAGE_V2: i64 = 3;
+
+// Value encodings.
+const PLAIN: i64 = 0;
+const PLAIN_DICTIONARY: i64 = 2;
+const RLE_DICTIONARY: i64 = 8;
+
+// Compression codecs.
+const UNCOMPRESSED: i64 = 0;
+const SNAPPY: i64 = 1;
+const GZIP: i64 = 2;
+const CODECS: [&str; 8] = [
+    "uncompressed",
+    "Snappy",
+    "gzip",
+    "LZO",
+    "Brotli",
+    "LZ4",
+    "zstd",
+    "LZ4",
+];
+
+// Structs nest at most this deep in metadata.
+const MAX_DEPTH: usize = 64;
+
+fn corrupt() -> String {
+    "corrupt Parquet data".to_string()
+}
+
+// A value in Thrift's compact protocol. The metadata is decoded generically
+// and its structs are then picked apart by field id.
+enum Thrift<'a> {
+    Bool(bool),
+    Int(i64),
+    Double,
+    Binary(&'a [u8]),
+    List(Vec<Thrift<'a>>),
+    Struct(Vec<(i16, Thrift<'a>)>),
+}
+
+impl<'a> Thrift<'a> {
+    fn field(&self, id: i16) -> Option<&Thrift<'a>> {
+        match self {
+            Thrift::Struct(fields) => fields.iter().find(|(i, _)| *i == id).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn int(&self, id: i16) -> Option<i64> {
+        match self.field(id)? {
+            Thrift::Int(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    fn required_int(&self, id: i16) -> Result<i64> {
+        self.int(id).ok_or_else(corrupt)
+    }
+
+    fn bool(&self, id: i16) -> Option<bool> {
+        match self.field(id)? {
+            Thrift::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    fn string(&self, id: i16) -> Option<&'a str> {
+        match self.field(id)? {
+            Thrift::Binary(b) => std::str::from_utf8(b).ok(),
+            _ => None,
+        }
+    }
+
+    fn list(&self, id: i16) -> &[Thrift<'a>] {
+        match self.field(id) {
+            Some(Thrift::List(items)) => items,
+            _ => &[],
+        }
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Reader<'a> {
+        Reader { buf, pos: 0 }
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        let b = *self.buf.get(self.pos).ok_or_else(corrupt)?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(n).ok_or_else(corrupt)?;
+        let b = self.buf.get(self.pos..end).ok_or_else(corrupt)?;
+        self.pos = end;
+        Ok(b)
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            value |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(corrupt())
+    }
+
+    fn zigzag(&mut self) -> Result<i64> {
+        let v = self.varint()?;
+        Ok((v >> 1) as i64 ^ -((v & 1) as i64))
+    }
+
+    fn value(&mut self, ty: u8, depth: usize) -> Result<Thrift<'a>> {
+        Ok(match ty {
+            1 | 2 => Thrift::Bool(self.byte()? == 1),
+            3 => Thrift::Int(self.byte()? as i8 as i64),
+            4..=6 => Thrift::Int(self.zigzag()?),
+            7 => {
+                self.bytes(8)?;
+                Thrift::Double
+            }
+            8 => {
+                let n = self.varint()? as usize;
+                Thrift::Binary(self.bytes(n)?)
+            }
+            9 | 10 => {
+                let header = self.byte()?;
+                let n = match header >> 4 {
+                    15 => self.varint()? as usize,
+                    n => n as usize,
+                };
+                // Every element takes at least a byte.
+                if n > self.buf.len() - self.pos {
+                    return Err(corrupt());
+                }
+                let items = (0..n)
+                    .map(|_| self.value(header & 0x0f, depth))
+                    .collect::<Result<Vec<Thrift>>>()?;
+                Thrift::List(items)
+            }
+            11 => {
+                let n = self.varint()? as usize;
+                if n > self.buf.len() - self.pos {
+                    return Err(corrupt());
+                }
+                let types = if n > 0 { self.byte()? } else { 0 };
+                let mut items = Vec::with_capacity(2 * n);
+                for _ in 0..n {
+                    items.push(self.value(types >> 4, depth)?);
+                    items.push(self.value(types & 0x0f, depth)?);
+                }
+                Thrift::List(items)
+            }
+            12 => self.structure(depth + 1)?,
+            _ => return Err(corrupt()),
+        })
+    }
+
+    fn structure(&mut self, depth: usize) -> Result<Thrift<'a>> {
+        if depth > MAX_DEPTH {
+            return Err(corrupt());
+        }
+        let mut fields = Vec::new();
+        let mut id: i16 = 0;
+        loop {
+            let header = self.byte()?;
+            if header == 0 {
+                return Ok(Thrift::Struct(fields));
+            }
+            id = match header >> 4 {
+                0 => self.zigzag()? as i16,
+                delta => id.wrapping_add(delta as i16),
+            };
+            // Booleans are held in the field header itself.
+            let value = match header & 0x0f {
+                1 => Thrift::Bool(true),
+                2 => Thrift::Bool(false),
+                ty => self.value(ty, depth)?,
+            };
+            fields.push((id, value));
+        }
+    }
+}
+
+// Decompresses a raw Snappy block, as Parquet stores them.
+fn snappy(data: &[u8]) -> Result<Vec<u8>> {
+    let mut r = Reader::new(data);
+    let len = r.varint()? as usize;
+    let mut out: Vec<u8> = Vec::new();
+    while r.pos < data.len() {
+        let tag = r.byte()?;
+        let (n, offset) = match tag & 3 {
+            0 => {
+                let n = match (tag >> 2) as usize {
+                    n @ 60.. => r
+                        .bytes(n - 59)?
+                        .iter()
+                        .rev()
+                        .fold(0, |acc, b| acc << 8 | *b as usize),
+                    n => n,
+                };
+                out.extend_from_slice(r.bytes(n + 1)?);
+                if out.len() > len {
+                    return Err(corrupt());
+                }
+                continue;
+            }
+            1 => (
+                4 + ((tag >> 2) & 7) as usize,
+                ((tag as usize >> 5) << 8) | r.byte()? as usize,
+            ),
+            2 => {
+                let b = r.bytes(2)?;
+                (
+                    1 + (tag >> 2) as usize,
+                    u16::from_le_bytes([b[0], b[1]]) as usize,
+                )
+            }
+            _ => {
+                let b = r.bytes(4)?;
+                (
+                    1 + (tag >> 2) as usize,
+                    u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize,
+                )
+            }
+        };
+        if offset == 0 || offset > out.len() || out.len() + n > len {
+            return Err(corrupt());
+        }
+        let start = out.len() - offset;
+        for i in 0..n {
+            out.push(out[start + i]);
+        }
+    }
+    if out.len() != len {
+        return Err(corrupt());
+    }
+    Ok(out)
+}
+
+fn decompress(codec: i64, data: &[u8]) -> Result<Vec<u8>> {
+    match codec {
+        UNCOMPRESSED => Ok(data.to_vec()),
+        SNAPPY => snappy(data),
+        GZIP => gzip::decompress(data),
+        _ => Err(format!(
+            "{} compression is not supported",
+            CODECS.get(codec as usize).unwrap_or(&"unknown")
+        )),
+    }
+}
+
+// Decodes `count` values of the hybrid of run-length encoding and bit
+// packing that Parquet uses for levels and dictionary indices.
+fn hybrid(data: &[u8], bit_width: u32, count: usize) -> Result<Vec<u32>> {
+    if bit_width > 32 {
+        return Err(corrupt());
+    }
+    let mut r = Reader::new(data);
+    let mut out = Vec::new();
+    while out.len() < count {
+        let header = r.varint()? as usize;
+        if header & 1 == 1 {
+            // Groups of eight values packed least significant bit first.
+            let groups = header >> 1;
+            let packed = r.bytes(groups.checked_mul(bit_width as usize).ok_or_else(corrupt)?)?;
+            let n = (groups * 8).min(count - out.len());
+            for i in 0..n {
+                let mut v = 0u32;
+                for b in 0..bit_width as usize {
+                    let bit = i * bit_width as usize + b;
+                    v |= ((packed[bit / 8] >> (bit % 8)) as u32 & 1) << b;
+                }
+                out.push(v);
+            }
+        } else {
+            let bytes = r.bytes((bit_width as usize).div_ceil(8))?;
+            let v = bytes
+                .iter()
+                .rev()
+                .fold(0u32, |acc, b| acc.wrapping_shl(8) | *b as u32);
+            let n = (header >> 1).min(count - out.len());
+            out.extend(std::iter::repeat_n(v, n));
+        }
+    }
+    Ok(out)
+}
+
+// The column being read and how its values are stored.
+struct Column {
+    physical: i64,
+    optional: bool,
+    unsigned: bool,
+    // Decimal columns hold integers to be divided by 10^scale.
+    scale: i32,
+}
+
+impl Column {
+    // Appends the first `n` plainly encoded values of `data`.
+    fn plain(&self, data: &[u8], n: usize, out: &mut Vec<f64>) -> Result<()> {
+        let size = match self.physical {
+            INT32 | FLOAT => 4,
+            _ => 8,
+        };
+        let bytes = data
+            .get(..n.checked_mul(size).ok_or_else(corrupt)?)
+            .ok_or_else(corrupt)?;
+        let divisor = 10f64.powi(self.scale);
+        out.extend(bytes.chunks_exact(size).map(|b| {
+            let x = match (self.physical, self.unsigned) {
+                (INT32, false) => i32::from_le_bytes(b.try_into().unwrap()) as f64,
+                (INT32, true) => u32::from_le_bytes(b.try_into().unwrap()) as f64,
+                (INT64, false) => i64::from_le_bytes(b.try_into().unwrap()) as f64,
+                (INT64, true) => u64::from_le_bytes(b.try_into().unwrap()) as f64,
+                (FLOAT, _) => f32::from_le_bytes(b.try_into().unwrap()) as f64,
+                _ => f64::from_le_bytes(b.try_into().unwrap()),
+            };
+            x / divisor
+        }));
+        Ok(())
+    }
+}
+
+// The top-level column named `name`, from the schema's depth-first list of
+// elements below the root.
+fn find_column(schema: &[Thrift], name: &str) -> Result<Column> {
+    let root = schema.first().ok_or_else(corrupt)?;
+    let mut names = Vec::new();
+    let mut i = 1;
+    for _ in 0..root.int(5).unwrap_or(0) {
+        let element = schema.get(i).ok_or_else(corrupt)?;
+        let element_name = element.string(4).ok_or_else(corrupt)?;
+        if element_name == name {
+            if element.int(5).is_some_and(|n| n > 0) {
+                return Err(format!("column {:?} is nested, not a number", name));
+            }
+            if element.int(3) == Some(REPEATED) {
+                return Err(format!("column {:?} is repeated, not a number", name));
+            }
+            let physical = element.required_int(1)?;
+            if ![INT32, INT64, FLOAT, DOUBLE].contains(&physical) {
+                return Err(format!("column {:?} is not numeric", name));
+            }
+            let converted = element.int(6);
+            // Newer writers give the scale in the logical type instead.
+            let logical_scale = element
+                .field(10)
+                .and_then(|logical| logical.field(5))
+                .and_then(|decimal| decimal.int(1));
+            let scale = match converted {
+                Some(DECIMAL) => element.int(7),
+                _ => logical_scale,
+            };
+            return Ok(Column {
+                physical,
+                optional: element.int(3) == Some(OPTIONAL),
+                unsigned: converted.is_some_and(|c| (UINT_8..=UINT_64).contains(&c)),
+                scale: scale.unwrap_or(0).clamp(0, 38) as i32,
+            });
+        }
+        names.push(element_name);
+        // Skip the element and everything nested in it.
+        let mut pending = 1;
+        while pending > 0 {
+            let element = schema.get(i).ok_or_else(corrupt)?;
+            pending += element.int(5).unwrap_or(0).max(0) - 1;
+            i += 1;
+        }
+    }
+    Err(format!(
+        "no column named {:?}; there are {}",
+        name,
+        names.join(", ")
+    ))
+}
+
+// Reads the values of one column chunk, returning how many were null.
+fn read_chunk(data: &[u8], chunk: &Thrift, column: &Column, out: &mut Vec<f64>) -> Result<usize> {
+    if chunk.string(1).is_some() {
+        return Err("columns in other files are not supported".to_string());
+    }
+    let meta = chunk.field(3).ok_or_else(corrupt)?;
+    let codec = meta.required_int(4)?;
+    let total = meta.required_int(5)?;
+    let mut start = meta.required_int(9)?;
+    if let Some(dictionary) = meta.int(11).filter(|d| *d > 0) {
+        start = start.min(dictionary);
+    }
+    let end = start
+        .checked_add(meta.required_int(7)?)
+        .ok_or_else(corrupt)?;
+    let chunk_data = usize::try_from(start)
+        .ok()
+        .zip(usize::try_from(end).ok())
+        .and_then(|(start, end)| data.get(start..end))
+        .ok_or_else(corrupt)?;
+
+    let mut r = Reader::new(chunk_data);
+    let mut dictionary: Option<Vec<f64>> = None;
+    let mut seen = 0;
+    let mut nulls = 0;
+    while seen < total && r.pos < chunk_data.len() {
+        let header = r.structure(0)?;
+        let size = usize::try_from(header.required_int(3)?).map_err(|_| corrupt())?;
+        let page = r.bytes(size)?;
+        match header.required_int(1)? {
+            DICTIONARY_PAGE => {
+                let info = header.field(7).ok_or_else(corrupt)?;
+                let n = usize::try_from(info.required_int(1)?).map_err(|_| corrupt())?;
+                let mut values = Vec::new();
+                column.plain(&decompress(codec, page)?, n, &mut values)?;
+                dictionary = Some(values);
+            }
+            kind @ (DATA_PAGE | DATA_PAGE_V2) => {
+                let v2 = kind == DATA_PAGE_V2;
+                let info = header.field(if v2 { 8 } else { 5 }).ok_or_else(corrupt)?;
+                let n = usize::try_from(info.required_int(1)?).map_err(|_| corrupt())?;
+                let encoding = info.required_int(if v2 { 4 } else { 2 })?;
+                // Version 2 pages keep their levels uncompressed ahead of the
+                // values, with their lengths in the header.
+                let (levels, values) = if v2 {
+                    let rep = usize::try_from(info.int(6).unwrap_or(0)).map_err(|_| corrupt())?;
+                    let def = usize::try_from(info.int(5).unwrap_or(0)).map_err(|_| corrupt())?;
+                    let levels = page
+                        .get(rep..rep.checked_add(def).ok_or_else(corrupt)?)
+                        .ok_or_else(corrupt)?;
+                    let values = &page[rep + def..];
+                    let values = if info.bool(7).unwrap_or(true) {
+                        decompress(codec, values)?
+                    } else {
+                        values.to_vec()
+                    };
+                    (levels.to_vec(), values)
+                } else {
+                    let page = decompress(codec, page)?;
+                    if !column.optional {
+                        (Vec::new(), page)
+                    } else {
+                        let mut pr = Reader::new(&page);
+                        let len = pr.bytes(4)?;
+                        let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+                        let levels = pr.bytes(len)?.to_vec();
+                        (levels, page[pr.pos..].to_vec())
+                    }
+                };
+                let present = if column.optional {
+                    hybrid(&levels, 1, n)?.iter().filter(|l| **l == 1).count()
+                } else {
+                    n
+                };
+                nulls += n - present;
+                match encoding {
+                    PLAIN => column.plain(&values, present, out)?,
+                    PLAIN_DICTIONARY | RLE_DICTIONARY => {
+                        let dictionary = dictionary
+                            .as_ref()
+                            .ok_or_else(|| "dictionary page missing".to_string())?;
+                        let (&bit_width, indices) = values.split_first().ok_or_else(corrupt)?;
+                        for i in hybrid(indices, bit_width as u32, present)? {
+                            out.push(*dictionary.get(i as usize).ok_or_else(corrupt)?);
+                        }
+                    }
+                    _ => return Err(format!("Parquet encoding {} is not supported", encoding)),
+                }
+                seen += n as i64;
+            }
+            _ => {}
+        }
+    }
+    Ok(nulls)
+}
+
+// Reads the named top-level column of every row group, returning the
+// non-null values and how many were null.
+pub fn read_column(data: &[u8], name: &str) -> Result<(Vec<f64>, usize)> {
+    if data.len() < 12 || !data.starts_with(MAGIC) || !data.ends_with(MAGIC) {
+        return Err("not a Parquet file".to_string());
+    }
+    let footer = &data[data.len() - 8..data.len() - 4];
+    let footer_len = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]) as usize;
+    let footer_start = (data.len() - 8)
+        .checked_sub(footer_len)
+        .filter(|start| *start >= MAGIC.len())
+        .ok_or_else(corrupt)?;
+    let metadata = Reader::new(&data[footer_start..data.len() - 8]).structure(0)?;
+    let column = find_column(metadata.list(2), name)?;
+    let mut values = Vec::new();
+    let mut nulls = 0;
+    for group in metadata.list(4) {
+        let chunk = group
+            .list(1)
+            .iter()
+            .find(|chunk| {
+                let path = chunk.field(3).map_or(&[][..], |meta| meta.list(3));
+                matches!(path, [Thrift::Binary(b)] if *b == name.as_bytes())
+            })
+            .ok_or_else(corrupt)?;
+        nulls += read_chunk(data, chunk, &column, &mut values)?;
+    }
+    Ok((values, nulls))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two row groups of an optional INT64 column n (3, null, 5, 3 and null,
+    // 5) and a DECIMAL(9, 2) INT32 column d (1.50, -0.25, 0.07, 0 and 0.01,
+    // 0.02), dictionary encoded and compressed with Snappy.
+    const DICTIONARY: &[u8] = &[
+        0x50, 0x41, 0x52, 0x31, 0x15, 0x04, 0x15, 0x20, 0x15, 0x24, 0x4c, 0x15, 0x04, 0x15, 0x00,
+        0x00, 0x00, 0x10, 0x3c, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x15, 0x00, 0x15, 0x12, 0x15, 0x16, 0x2c, 0x15, 0x08, 0x15,
+        0x10, 0x15, 0x06, 0x15, 0x06, 0x00, 0x00, 0x09, 0x20, 0x02, 0x00, 0x00, 0x00, 0x03, 0x0d,
+        0x01, 0x03, 0x02, 0x15, 0x04, 0x15, 0x20, 0x15, 0x24, 0x4c, 0x15, 0x08, 0x15, 0x00, 0x00,
+        0x00, 0x10, 0x3c, 0xe7, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00,
+        0x96, 0x00, 0x00, 0x00, 0x15, 0x00, 0x15, 0x08, 0x15, 0x0c, 0x2c, 0x15, 0x08, 0x15, 0x10,
+        0x15, 0x06, 0x15, 0x06, 0x00, 0x00, 0x04, 0x0c, 0x02, 0x03, 0x63, 0x00, 0x15, 0x04, 0x15,
+        0x10, 0x15, 0x14, 0x4c, 0x15, 0x02, 0x15, 0x00, 0x00, 0x00, 0x08, 0x1c, 0x05, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x15, 0x00, 0x15, 0x12, 0x15, 0x16, 0x2c, 0x15, 0x04, 0x15,
+        0x10, 0x15, 0x06, 0x15, 0x06, 0x00, 0x00, 0x09, 0x20, 0x02, 0x00, 0x00, 0x00, 0x03, 0x02,
+        0x01, 0x03, 0x00, 0x15, 0x04, 0x15, 0x10, 0x15, 0x14, 0x4c, 0x15, 0x04, 0x15, 0x00, 0x00,
+        0x00, 0x08, 0x1c, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x15, 0x00, 0x15, 0x06,
+        0x15, 0x0a, 0x2c, 0x15, 0x04, 0x15, 0x10, 0x15, 0x06, 0x15, 0x06, 0x00, 0x00, 0x03, 0x08,
+        0x01, 0x03, 0x02, 0x15, 0x02, 0x19, 0x3c, 0x48, 0x06, 0x73, 0x63, 0x68, 0x65, 0x6d, 0x61,
+        0x15, 0x04, 0x00, 0x15, 0x04, 0x25, 0x02, 0x18, 0x01, 0x6e, 0x00, 0x15, 0x02, 0x25, 0x00,
+        0x18, 0x01, 0x64, 0x25, 0x0a, 0x15, 0x04, 0x15, 0x12, 0x00, 0x16, 0x0c, 0x19, 0x2c, 0x19,
+        0x2c, 0x26, 0x08, 0x1c, 0x15, 0x04, 0x19, 0x15, 0x10, 0x19, 0x18, 0x01, 0x6e, 0x15, 0x02,
+        0x16, 0x08, 0x16, 0x76, 0x16, 0x76, 0x26, 0x46, 0x26, 0x08, 0x00, 0x00, 0x26, 0x7e, 0x1c,
+        0x15, 0x02, 0x19, 0x15, 0x10, 0x19, 0x18, 0x01, 0x64, 0x15, 0x02, 0x16, 0x08, 0x16, 0x6c,
+        0x16, 0x6c, 0x26, 0xbc, 0x01, 0x26, 0x7e, 0x00, 0x00, 0x16, 0x00, 0x16, 0x08, 0x00, 0x19,
+        0x2c, 0x26, 0xea, 0x01, 0x1c, 0x15, 0x04, 0x19, 0x15, 0x10, 0x19, 0x18, 0x01, 0x6e, 0x15,
+        0x02, 0x16, 0x04, 0x16, 0x66, 0x16, 0x66, 0x26, 0x98, 0x02, 0x26, 0xea, 0x01, 0x00, 0x00,
+        0x26, 0xd0, 0x02, 0x1c, 0x15, 0x02, 0x19, 0x15, 0x10, 0x19, 0x18, 0x01, 0x64, 0x15, 0x02,
+        0x16, 0x04, 0x16, 0x5a, 0x16, 0x5a, 0x26, 0xfe, 0x02, 0x26, 0xd0, 0x02, 0x00, 0x00, 0x16,
+        0x00, 0x16, 0x04, 0x00, 0x00, 0xa7, 0x00, 0x00, 0x00, 0x50, 0x41, 0x52, 0x31,
+    ];
+
+    // An optional FLOAT column f (0.5, null, 2) in a version 2 data page,
+    // compressed with gzip.
+    const V2: &[u8] = &[
+        0x50, 0x41, 0x52, 0x31, 0x15, 0x06, 0x15, 0x14, 0x15, 0x3c, 0x5c, 0x15, 0x06, 0x15, 0x02,
+        0x15, 0x06, 0x15, 0x00, 0x15, 0x04, 0x15, 0x00, 0x00, 0x00, 0x03, 0x05, 0x1f, 0x8b, 0x08,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x63, 0x60, 0x60, 0xb0, 0x67, 0x60, 0x60, 0x70,
+        0x00, 0x00, 0xae, 0xb1, 0x8f, 0x30, 0x08, 0x00, 0x00, 0x00, 0x15, 0x02, 0x19, 0x2c, 0x48,
+        0x06, 0x73, 0x63, 0x68, 0x65, 0x6d, 0x61, 0x15, 0x02, 0x00, 0x15, 0x08, 0x25, 0x02, 0x18,
+        0x01, 0x66, 0x00, 0x16, 0x06, 0x19, 0x1c, 0x19, 0x1c, 0x26, 0x08, 0x1c, 0x15, 0x08, 0x19,
+        0x15, 0x00, 0x19, 0x18, 0x01, 0x66, 0x15, 0x04, 0x16, 0x06, 0x16, 0x66, 0x16, 0x66, 0x26,
+        0x08, 0x00, 0x00, 0x16, 0x00, 0x16, 0x06, 0x00, 0x00, 0x3b, 0x00, 0x00, 0x00, 0x50, 0x41,
+        0x52, 0x31,
+    ];
+
+    #[test]
+    fn reads_dictionary_pages() {
+        assert_eq!(
+            read_column(DICTIONARY, "n"),
+            Ok((vec![3.0, 5.0, 3.0, 5.0], 2))
+        );
+        assert_eq!(
+            read_column(DICTIONARY, "d"),
+            Ok((vec![1.5, -0.25, 0.07, 0.0, 0.01, 0.02], 0))
+        );
+    }
+
+    #[test]
+    fn reads_version_2_pages() {
+        assert_eq!(read_column(V2, "f"), Ok((vec![0.5, 2.0], 1)));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert_eq!(
+            read_column(b"x\n1\n2\n", "x"),
+            Err("not a Parquet file".to_string())
+        );
+        assert_eq!(
+            read_column(V2, "g"),
+            Err("no column named \"g\"; there are f".to_string())
+        );
+        // Truncated files keep their footer but lose pages or metadata.
+        for len in 0..DICTIONARY.len() - 8 {
+            let mut data = DICTIONARY[..len].to_vec();
+            data.extend_from_slice(&DICTIONARY[DICTIONARY.len() - 8..]);
+            assert!(read_column(&data, "n").is_err(), "{}", len);
+        }
+    }
+
+    #[test]
+    fn decompresses_snappy() {
+        // A literal, then copies with one, two and four byte offsets.
+        let data = [
+            20, 0x0c, b'a', b'b', b'c', b'd', 0x11, 4, 0x0e, 4, 0, 0x0f, 3, 0, 0, 0,
+        ];
+        assert_eq!(snappy(&data), Ok(b"abcdabcdabcdabcdbcdb".to_vec()));
+        assert!(snappy(&[4, 0x04, b'a', b'b', 0x01, 3]).is_err());
+        assert!(snappy(&[3, 0x0c, b'a', b'b', b'c', b'd']).is_err());
+    }
+
+    #[test]
+    fn decodes_hybrid_runs() {
+        // A run of five 7s, then eight bit packed 3-bit values.
+        let data = [0x0a, 7, 0x03, 0x88, 0xc6, 0xfa];
+        assert_eq!(
+            hybrid(&data, 3, 13),
+            Ok(vec![7, 7, 7, 7, 7, 0, 1, 2, 3, 4, 5, 6, 7])
+        );
+        assert!(hybrid(&data, 3, 14).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+// Writes the comparison plots as individual SVG files.
+
+use crate::kde;
+use crate::metadata::Labels;
+use crate::sample::Sample;
+use crate::svg::{self, Curve, Marker, Series};
+use crate::units::Units;
+use crate::{Error, EstimatorResult};
+use std::path::Path;
+
+pub const HISTOGRAM_BINS: usize = 40;
+
+pub fn sample_series<'a>(
+    baseline: &'a [f64],
+    target: &'a [f64],
+    labels: &'a Labels,
+) -> [Series<'a>; 2] {
+    [
+        Series {
+            label: &labels.baseline,
+            values: baseline,
+        },
+        Series {
+            label: &labels.target,
+            values: target,
+        },
+    ]
+}
+
+// Simulated distribution of an estimator with the observed values marked.
+pub fn bootstrap_histogram(
+    result: &EstimatorResult,
+    sims: &[f64],
+    units: &Units,
+    labels: &Labels,
+) -> String {
+    let series = [Series {
+        label: "simulated",
+        values: sims,
+    }];
+    let markers = [
+        Marker {
+            label: &labels.baseline,
+            x: result.full_baseline_estimator,
+        },
+        Marker {
+            label: &labels.target,
+            x: result.target_estimator,
+        },
+    ];
+    svg::histogram(
+        &units.label(&result.name),
+        &series,
+        &markers,
+        HISTOGRAM_BINS,
+    )
+}
+
+// Kernel density estimates of both samples, with the given bandwidth or
+// Silverman's rule for each.
+pub fn kde_plot(
+    baseline: &[f64],
+    target: &[f64],
+    bandwidth: Option<f64>,
+    units: &Units,
+    labels: &Labels,
+) -> String {
+    let samples = [Sample::new(baseline.to_vec()), Sample::new(target.to_vec())];
+    let bandwidths: Vec<f64> = samples
+        .iter()
+        .map(|s| bandwidth.unwrap_or_else(|| kde::silverman_bandwidth(s)))
+        .collect();
+    let xs = kde::grid(
+        &[(&samples[0], bandwidths[0]), (&samples[1], bandwidths[1])],
+        kde::GRID_POINTS,
+    );
+    let densities: Vec<Vec<f64>> = samples
+        .iter()
+        .zip(bandwidths.iter())
+        .map(|(s, h)| kde::density(s, *h, &xs))
+        .collect();
+    let curves = [
+        Curve {
+            label: &labels.baseline,
+            xs: &xs,
+            ys: &densities[0],
+        },
+        Curve {
+            label: &labels.target,
+            xs: &xs,
+            ys: &densities[1],
+        },
+    ];
+    svg::lines(&units.label("Kernel density estimate"), &curves)
+}
+
+// Estimator names may contain arbitrary characters; keep file names tame.
+pub fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn write_plot_dir(
+    dir: &Path,
+    baseline: &[f64],
+    target: &[f64],
+    results: &[EstimatorResult],
+    distributions: &[Vec<f64>],
+    bandwidth: Option<f64>,
+    units: &Units,
+    labels: &Labels,
+) -> Result<(), Error> {
+    std::fs::create_dir_all(dir)?;
+
+    let samples = sample_series(baseline, target, labels);
+    std::fs::write(
+        dir.join("density.svg"),
+        svg::histogram(&units.label("Density"), &samples, &[], HISTOGRAM_BINS),
+    )?;
+    std::fs::write(
+        dir.join("kde.svg"),
+        kde_plot(baseline, target, bandwidth, units, labels),
+    )?;
+    std::fs::write(
+        dir.join("boxplot.svg"),
+        svg::boxplot(&units.label("Box plot"), &samples),
+    )?;
+
+    for (result, sims) in results.iter().zip(distributions.iter()) {
+        std::fs::write(
+            dir.join(format!("bootstrap-{}.svg", file_stem(&result.name))),
+            bootstrap_histogram(result, sims, units, labels),
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,156 @@
+// Power analysis by simulation. Comparing two samples of the same size, both
+// drawn from the baseline, gives the null distribution of the difference in
+// each estimator; an effect of a given size shifts that distribution.
+
+use crate::estimator::{default_estimators, Estimator, QuantileOptions};
+use crate::margin::Margin;
+use crate::resample::{BootstrapMethod, Resampler};
+use crate::sample::Sample;
+use crate::{get_quantile, Error};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+// Sorted simulated differences target - baseline for each estimator, with
+// n values per side.
+fn null_differences(
+    iterations: u64,
+    seed: u64,
+    baseline: &Sample,
+    n: usize,
+    estimators: &[Box<dyn Estimator>],
+) -> Result<Vec<Vec<f64>>, Error> {
+    let resampler = Resampler::new(BootstrapMethod::Iid, None, None, None, baseline)?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut x = Sample::default();
+    let mut y = Sample::default();
+    let mut diffs = vec![Vec::with_capacity(iterations as usize); estimators.len()];
+    for _ in 0..iterations {
+        resampler.resample(&mut rng, baseline, n, &mut x);
+        resampler.resample(&mut rng, baseline, n, &mut y);
+        x.values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        y.values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for (est, d) in estimators.iter().zip(diffs.iter_mut()) {
+            d.push(est.estimate(&y)? - est.estimate(&x)?);
+        }
+    }
+    for d in diffs.iter_mut() {
+        d.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    }
+    Ok(diffs)
+}
+
+// Probability that a regression of `effect` is flagged by a one-sided test
+// at level alpha.
+fn power_from_differences(diffs: &[f64], effect: f64, alpha: f64) -> Result<f64, Error> {
+    let critical = get_quantile(diffs, 1.0 - alpha)?;
+    let detected = diffs.iter().filter(|d| **d + effect > critical).count();
+    Ok((detected as f64) / (diffs.len() as f64))
+}
+
+pub fn power(
+    baseline: &Sample,
+    effect: Margin,
+    alpha: f64,
+    iterations: u64,
+    seed: u64,
+    size: Option<usize>,
+) -> Result<(), Error> {
+    let estimators = default_estimators(QuantileOptions::default());
+    let n = size.unwrap_or(baseline.size());
+    if n == 0 {
+        return Err(Error::Usage("sample size must be positive".to_string()));
+    }
+    let diffs = null_differences(iterations, seed, baseline, n, &estimators)?;
+    println!(
+        "=== Power (effect {}, alpha {}, n={} per side) ===",
+        effect, alpha, n
+    );
+    for (est, d) in estimators.iter().zip(diffs.iter()) {
+        let amount = effect.amount(est.estimate(baseline)?);
+        println!(
+            "{}: effect {}, power {}",
+            est.name(),
+            amount,
+            power_from_differences(d, amount, alpha)?
+        );
+    }
+    Ok(())
+}
+
+// Smallest size per side reaching the target power, by doubling and then
+// bisecting.
+fn required_size(
+    power_at: impl Fn(usize) -> Result<f64, Error>,
+    target_power: f64,
+    max_size: usize,
+) -> Result<Option<(usize, f64)>, Error> {
+    let mut lo = 1;
+    let mut hi = 2;
+    let mut hi_power = power_at(hi)?;
+    while hi_power < target_power {
+        if hi >= max_size {
+            return Ok(None);
+        }
+        lo = hi;
+        hi = (hi * 2).min(max_size);
+        hi_power = power_at(hi)?;
+    }
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        let p = power_at(mid)?;
+        if p >= target_power {
+            hi = mid;
+            hi_power = p;
+        } else {
+            lo = mid;
+        }
+    }
+    Ok(Some((hi, hi_power)))
+}
+
+pub fn samplesize(
+    baseline: &Sample,
+    effect: Margin,
+    alpha: f64,
+    target_power: f64,
+    iterations: u64,
+    seed: u64,
+    max_size: usize,
+) -> Result<(), Error> {
+    if !(0.0..1.0).contains(&target_power) {
+        return Err(Error::Usage(format!(
+            "power must be in [0, 1), got {}",
+            target_power
+        )));
+    }
+    let estimators = default_estimators(QuantileOptions::default());
+    println!(
+        "=== Sample size (effect {}, alpha {}, power {}) ===",
+        effect, alpha, target_power
+    );
+    for est in estimators.iter() {
+        let amount = effect.amount(est.estimate(baseline)?);
+        // The same seed is used at every size so that the estimated power
+        // varies smoothly with n.
+        let power_at = |n: usize| -> Result<f64, Error> {
+            let diffs = null_differences(iterations, seed, baseline, n, std::slice::from_ref(est))?;
+            power_from_differences(&diffs[0], amount, alpha)
+        };
+        match required_size(power_at, target_power, max_size)? {
+            Some((n, p)) => println!("{}: {} per side (power {})", est.name(), n, p),
+            None => println!("{}: more than {} per side", est.name(), max_size),
+        }
+    }
+    Ok(())
+}
+
+pub const MDE_POWER: f64 = 0.8;
+
+// Smallest regression the comparison would flag with the given power,
+// treating the baseline as fixed as the comparison itself does: the target
+// estimator must exceed the (1 - alpha) quantile of the simulated values.
+pub fn minimum_detectable_effect(sims: &[f64], alpha: f64, power: f64) -> Result<f64, Error> {
+    let mut sorted = sims.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    Ok(get_quantile(&sorted, 1.0 - alpha)? - get_quantile(&sorted, 1.0 - power)?)
+}
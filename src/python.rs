@@ -0,0 +1,78 @@
+// Python bindings, built with --features python and packaged with maturin
+// from python/pyproject.toml:
+//
+//     >>> import numcmp
+//     >>> numcmp.compare([1.0, 2.0, 3.0], [2.0, 3.0, 4.0], iterations=1000, seed=1)
+//
+// Options are the command-line flags of compare with underscores for dashes,
+// e.g. alpha=0.01 or quantile=[0.5, 0.9]. A list repeats its flag, True passes
+// a bare flag, and None or False leaves the flag out.
+
+use crate::api;
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyTuple};
+
+create_exception!(numcmp, NumcmpError, PyException);
+
+fn push_option(args: &mut Vec<String>, flag: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+    if value.is_none() {
+        return Ok(());
+    }
+    if let Ok(set) = value.extract::<bool>() {
+        if set {
+            args.push(flag.to_string());
+        }
+        return Ok(());
+    }
+    args.push(flag.to_string());
+    args.push(value.str()?.to_string());
+    Ok(())
+}
+
+fn option_args(options: Option<&Bound<'_, PyDict>>) -> PyResult<Vec<String>> {
+    let mut args = Vec::new();
+    for (key, value) in options.into_iter().flat_map(|options| options.iter()) {
+        let flag = format!("--{}", key.extract::<String>()?.replace('_', "-"));
+        if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+            for v in value.try_iter()? {
+                push_option(&mut args, &flag, &v?)?;
+            }
+        } else {
+            push_option(&mut args, &flag, &value)?;
+        }
+    }
+    Ok(args)
+}
+
+/// Compares two sequences of numbers as `numcmp compare --format json`
+/// would, returning the parsed results.
+#[pyfunction]
+#[pyo3(signature = (baseline, target, iterations=10000, seed=None, **options))]
+fn compare<'py>(
+    py: Python<'py>,
+    baseline: Vec<f64>,
+    target: Vec<f64>,
+    iterations: u64,
+    seed: Option<u64>,
+    options: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let mut args = vec!["--iterations".to_string(), iterations.to_string()];
+    if let Some(seed) = seed {
+        args.extend(["--seed".to_string(), seed.to_string()]);
+    }
+    args.extend(option_args(options)?);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let json = py
+        .detach(|| api::catch_panic(|| api::compare_json(&baseline, &target, &args)))
+        .map_err(|e| NumcmpError::new_err(e.to_string()))?;
+    py.import("json")?.call_method1("loads", (json,))
+}
+
+#[pymodule]
+fn numcmp(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(compare, m)?)?;
+    m.add("NumcmpError", m.py().get_type::<NumcmpError>())?;
+    Ok(())
+}
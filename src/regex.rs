@@ -0,0 +1,532 @@
+// A small backtracking regular expression matcher, enough for picking
+// numbers out of log lines: literals, '.', classes like [0-9.] and \d \w \s,
+// groups (capturing and (?:...)), alternation, anchors and the quantifiers
+// * + ? {n} {n,} {n,m}, optionally lazy.
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Regex {
+    root: Node,
+    groups: usize,
+}
+
+const DIGIT: &[(char, char)] = &[('0', '9')];
+const WORD: &[(char, char)] = &[('0', '9'), ('A', 'Z'), ('a', 'z'), ('_', '_')];
+const SPACE: &[(char, char)] = &[(' ', ' '), ('\t', '\r')];
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    groups: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn alt(&mut self) -> Result<Node, String> {
+        let mut alts = vec![self.concat()?];
+        while self.eat('|') {
+            alts.push(self.concat()?);
+        }
+        Ok(if alts.len() == 1 {
+            alts.pop().unwrap()
+        } else {
+            Node::Alt(alts)
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let c = self.peek().expect("checked by caller");
+        self.pos += 1;
+        Ok(match c {
+            '(' => {
+                let index = if self.eat('?') {
+                    if !self.eat(':') {
+                        return Err("only (?:...) groups are supported".to_string());
+                    }
+                    None
+                } else {
+                    self.groups += 1;
+                    Some(self.groups)
+                };
+                let inner = self.alt()?;
+                if !self.eat(')') {
+                    return Err("missing ')'".to_string());
+                }
+                Node::Group(Box::new(inner), index)
+            }
+            '[' => self.class()?,
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '\\' => self.escape()?,
+            '*' | '+' | '?' => return Err(format!("nothing to repeat before {:?}", c)),
+            c => Node::Char(c),
+        })
+    }
+
+    fn escape(&mut self) -> Result<Node, String> {
+        let c = self.peek().ok_or("trailing backslash")?;
+        self.pos += 1;
+        let class = |ranges: &[(char, char)], negated| Node::Class {
+            ranges: ranges.to_vec(),
+            negated,
+        };
+        Ok(match c {
+            'd' => class(DIGIT, false),
+            'D' => class(DIGIT, true),
+            'w' => class(WORD, false),
+            'W' => class(WORD, true),
+            's' => class(SPACE, false),
+            'S' => class(SPACE, true),
+            'n' => Node::Char('\n'),
+            't' => Node::Char('\t'),
+            'r' => Node::Char('\r'),
+            c if c.is_ascii_alphanumeric() => return Err(format!("unknown escape \\{}", c)),
+            c => Node::Char(c),
+        })
+    }
+
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.peek().ok_or("missing ']'")?;
+            self.pos += 1;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let lo = if c == '\\' {
+                let e = self.peek().ok_or("missing ']'")?;
+                self.pos += 1;
+                match e {
+                    'd' => {
+                        ranges.extend_from_slice(DIGIT);
+                        continue;
+                    }
+                    'w' => {
+                        ranges.extend_from_slice(WORD);
+                        continue;
+                    }
+                    's' => {
+                        ranges.extend_from_slice(SPACE);
+                        continue;
+                    }
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    e => e,
+                }
+            } else {
+                c
+            };
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|c| *c != ']') {
+                let hi = self.chars[self.pos + 1];
+                self.pos += 2;
+                if hi < lo {
+                    return Err(format!("invalid class range {}-{}", lo, hi));
+                }
+                ranges.push((lo, hi));
+            } else {
+                ranges.push((lo, lo));
+            }
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    // Parses {n}, {n,} or {n,m}; anything else leaves '{' as a literal.
+    fn braces(&mut self) -> Option<(usize, Option<usize>)> {
+        let start = self.pos;
+        self.pos += 1;
+        let bounds = self.number().and_then(|min| {
+            if self.eat('}') {
+                return Some((min, Some(min)));
+            }
+            if !self.eat(',') {
+                return None;
+            }
+            if self.eat('}') {
+                return Some((min, None));
+            }
+            let max = self.number()?;
+            self.eat('}').then_some((min, Some(max)))
+        });
+        if bounds.is_none() {
+            self.pos = start;
+        }
+        bounds
+    }
+
+    fn quantified(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('{') => match self.braces() {
+                Some(bounds) => bounds,
+                None => return Ok(atom),
+            },
+            Some(c) if "*+?".contains(c) => {
+                self.pos += 1;
+                match c {
+                    '*' => (0, None),
+                    '+' => (1, None),
+                    _ => (0, Some(1)),
+                }
+            }
+            _ => return Ok(atom),
+        };
+        if max.is_some_and(|max| max < min) {
+            return Err(format!("invalid repetition {{{},{}}}", min, max.unwrap()));
+        }
+        let greedy = !self.eat('?');
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+            greedy,
+        })
+    }
+}
+
+type Captures = Vec<Option<(usize, usize)>>;
+
+struct Matcher<'a> {
+    text: &'a [char],
+}
+
+impl Matcher<'_> {
+    // Matches `node` at `pos` and calls `k` with the end position; backtracks
+    // into the node whenever `k` fails.
+    fn node(
+        &self,
+        node: &Node,
+        pos: usize,
+        caps: &mut Captures,
+        k: &mut dyn FnMut(usize, &mut Captures) -> bool,
+    ) -> bool {
+        let text = self.text;
+        match node {
+            Node::Char(c) => pos < text.len() && text[pos] == *c && k(pos + 1, caps),
+            Node::Any => pos < text.len() && text[pos] != '\n' && k(pos + 1, caps),
+            Node::Class { ranges, negated } => {
+                pos < text.len()
+                    && ranges
+                        .iter()
+                        .any(|(lo, hi)| (*lo..=*hi).contains(&text[pos]))
+                        != *negated
+                    && k(pos + 1, caps)
+            }
+            Node::Start => pos == 0 && k(pos, caps),
+            Node::End => pos == text.len() && k(pos, caps),
+            Node::Group(inner, None) => self.node(inner, pos, caps, k),
+            Node::Group(inner, Some(index)) => {
+                let index = *index;
+                self.node(inner, pos, caps, &mut |end, caps| {
+                    let saved = caps[index];
+                    caps[index] = Some((pos, end));
+                    if k(end, caps) {
+                        return true;
+                    }
+                    caps[index] = saved;
+                    false
+                })
+            }
+            Node::Concat(nodes) => self.sequence(nodes, pos, caps, k),
+            Node::Alt(alts) => alts.iter().any(|alt| self.node(alt, pos, caps, k)),
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => self.repeat(node, *min, *max, *greedy, 0, pos, caps, k),
+        }
+    }
+
+    fn sequence(
+        &self,
+        nodes: &[Node],
+        pos: usize,
+        caps: &mut Captures,
+        k: &mut dyn FnMut(usize, &mut Captures) -> bool,
+    ) -> bool {
+        match nodes.split_first() {
+            None => k(pos, caps),
+            Some((first, rest)) => self.node(first, pos, caps, &mut |end, caps| {
+                self.sequence(rest, end, caps, k)
+            }),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn repeat(
+        &self,
+        node: &Node,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+        count: usize,
+        pos: usize,
+        caps: &mut Captures,
+        k: &mut dyn FnMut(usize, &mut Captures) -> bool,
+    ) -> bool {
+        let more = |caps: &mut Captures, k: &mut dyn FnMut(usize, &mut Captures) -> bool| {
+            max.is_none_or(|max| count < max)
+                && self.node(node, pos, caps, &mut |end, caps| {
+                    // Once the minimum is reached, empty iterations would
+                    // only loop forever.
+                    (end != pos || count < min)
+                        && self.repeat(node, min, max, greedy, count + 1, end, caps, k)
+                })
+        };
+        if count < min {
+            return more(caps, k);
+        }
+        if !greedy && k(pos, caps) {
+            return true;
+        }
+        more(caps, k) || (greedy && k(pos, caps))
+    }
+}
+
+impl Regex {
+    pub fn new(source: &str) -> Result<Regex, String> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            pos: 0,
+            groups: 0,
+        };
+        let root = parser.alt()?;
+        if parser.pos != parser.chars.len() {
+            return Err("unmatched ')'".to_string());
+        }
+        Ok(Regex {
+            root,
+            groups: parser.groups,
+        })
+    }
+
+    // The number of capturing groups.
+    pub fn groups(&self) -> usize {
+        self.groups
+    }
+
+    // The leftmost match in `text`: the whole match followed by each
+    // capturing group, or None if there is no match.
+    pub fn captures<'t>(&self, text: &'t str) -> Option<Vec<Option<&'t str>>> {
+        let chars: Vec<char> = text.chars().collect();
+        let offsets: Vec<usize> = text
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(text.len()))
+            .collect();
+        let matcher = Matcher { text: &chars };
+        let mut caps = vec![None; self.groups + 1];
+        for start in 0..=chars.len() {
+            let mut found = None;
+            if matcher.node(&self.root, start, &mut caps, &mut |end, caps| {
+                let mut all = caps.clone();
+                all[0] = Some((start, end));
+                found = Some(all);
+                true
+            }) {
+                return found.map(|all| {
+                    all.into_iter()
+                        .map(|span| span.map(|(a, b)| &text[offsets[a]..offsets[b]]))
+                        .collect()
+                });
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The whole leftmost match.
+    fn find<'t>(pattern: &str, text: &'t str) -> Option<&'t str> {
+        Regex::new(pattern)
+            .unwrap()
+            .captures(text)
+            .map(|c| c[0].unwrap())
+    }
+
+    fn groups<'t>(pattern: &str, text: &'t str) -> Vec<Option<&'t str>> {
+        Regex::new(pattern).unwrap().captures(text).unwrap()[1..].to_vec()
+    }
+
+    fn error(pattern: &str) -> String {
+        Regex::new(pattern).unwrap_err()
+    }
+
+    #[test]
+    fn literals_and_any() {
+        assert_eq!(find("abc", "xxabcxx"), Some("abc"));
+        assert_eq!(find("abc", "ab"), None);
+        assert_eq!(find("a.c", "abc a\nc"), Some("abc"));
+        assert_eq!(find("a.c", "a\nc"), None);
+        assert_eq!(find(r"1\.5", "105 1.5"), Some("1.5"));
+        assert_eq!(find("", "abc"), Some(""));
+        assert_eq!(find("µs", "12µs"), Some("µs"));
+    }
+
+    #[test]
+    fn classes() {
+        assert_eq!(find("[0-9.]+", "took 12.5ms"), Some("12.5"));
+        assert_eq!(find("[^ ]+", "  word  "), Some("word"));
+        assert_eq!(find("[a-]+", "x-a-b"), Some("-a-"));
+        assert_eq!(find("[]a]+", "x]a]"), Some("]a]"));
+        assert_eq!(find(r"[\d,]+", "n=1,024;"), Some("1,024"));
+        assert_eq!(find(r"\d+", "abc 42 7"), Some("42"));
+        assert_eq!(find(r"\D+", "42abc7"), Some("abc"));
+        assert_eq!(find(r"\w+", "-- foo_1 --"), Some("foo_1"));
+        assert_eq!(find(r"\W+", "foo -- bar"), Some(" -- "));
+        assert_eq!(find(r"\s+", "a \t\nb"), Some(" \t\n"));
+        assert_eq!(find(r"\S+", "  ab  "), Some("ab"));
+        assert_eq!(find(r"\t", "a\tb"), Some("\t"));
+    }
+
+    #[test]
+    fn anchors() {
+        assert_eq!(find("^a", "ba"), None);
+        assert_eq!(find("^b", "ba"), Some("b"));
+        assert_eq!(find("a$", "ab"), None);
+        assert_eq!(find("b$", "ab"), Some("b"));
+        assert_eq!(find(r"^\d+$", "123"), Some("123"));
+        assert_eq!(find(r"^\d+$", "123x"), None);
+        assert_eq!(find("^$", ""), Some(""));
+    }
+
+    #[test]
+    fn alternation() {
+        assert_eq!(find("cat|dog", "hotdog"), Some("dog"));
+        // The leftmost match wins, then the first alternative.
+        assert_eq!(find("b|ab", "ab"), Some("ab"));
+        assert_eq!(find("a|ab", "ab"), Some("a"));
+        assert_eq!(find("x(?:a|b)+y", "xababy"), Some("xababy"));
+        assert_eq!(find("a|", "b"), Some(""));
+        assert_eq!(find("^(?:ms|s)$", "s"), Some("s"));
+    }
+
+    #[test]
+    fn repetition() {
+        assert_eq!(find("ab*", "abbbc"), Some("abbb"));
+        assert_eq!(find("ab+", "ac ab"), Some("ab"));
+        assert_eq!(find("ab?c", "ac abc"), Some("ac"));
+        assert_eq!(find("a{2}", "a aa"), Some("aa"));
+        assert_eq!(find("a{2,}", "aaaa"), Some("aaaa"));
+        assert_eq!(find("a{1,2}", "aaaa"), Some("aa"));
+        assert_eq!(find("a{0}b", "ab"), Some("b"));
+        // Lazy quantifiers take as little as the rest of the pattern allows.
+        assert_eq!(find("a+?", "aaa"), Some("a"));
+        assert_eq!(find("<.*?>", "<a><b>"), Some("<a>"));
+        assert_eq!(find("<.*>", "<a><b>"), Some("<a><b>"));
+        assert_eq!(find("a{2,}?", "aaaa"), Some("aa"));
+        // Backtracking gives characters back to the rest of the pattern.
+        assert_eq!(find(r"\d+5", "12345"), Some("12345"));
+        assert_eq!(find(r"^(?:a|ab)c$", "abc"), Some("abc"));
+        // Braces that are not a repetition are literal.
+        assert_eq!(find("a{x}", "a{x}"), Some("a{x}"));
+        assert_eq!(find("a{1", "a{1"), Some("a{1"));
+        // Repeating something that can match empty terminates.
+        assert_eq!(find("(?:a*)*b", "aaab"), Some("aaab"));
+        assert_eq!(find("(?:a?)+", "aa"), Some("aa"));
+        assert_eq!(find("(?:a*)*b", "aaaa"), None);
+    }
+
+    #[test]
+    fn captures() {
+        let re = Regex::new(r"(\w+)=(\d+)(?:ms)?").unwrap();
+        assert_eq!(re.groups(), 2);
+        assert_eq!(
+            re.captures("x time=125ms"),
+            Some(vec![Some("time=125ms"), Some("time"), Some("125")])
+        );
+        assert_eq!(re.captures("nothing here"), None);
+        // A group that takes no part in the match captures nothing.
+        assert_eq!(groups("(a)|(b)", "b"), vec![None, Some("b")]);
+        // A repeated group captures its last iteration.
+        assert_eq!(groups(r"(?:(\d),?)+", "1,2,3"), vec![Some("3")]);
+        // Groups number by their opening parenthesis.
+        assert_eq!(
+            groups("((a)(b))", "ab"),
+            vec![Some("ab"), Some("a"), Some("b")]
+        );
+        // Captures are reported after backtracking out of a failed attempt.
+        assert_eq!(groups(r"(\d+)(\d)$", "1234"), vec![Some("123"), Some("4")]);
+        assert_eq!(groups("(é+)", "aéé"), vec![Some("éé")]);
+        assert_eq!(groups("(x*)", "abc"), vec![Some("")]);
+    }
+
+    #[test]
+    fn malformed_patterns() {
+        assert_eq!(error("(ab"), "missing ')'");
+        assert_eq!(error("ab)"), "unmatched ')'");
+        assert_eq!(error("[ab"), "missing ']'");
+        assert_eq!(error("[a\\"), "missing ']'");
+        assert_eq!(error("[z-a]"), "invalid class range z-a");
+        assert_eq!(error("*a"), "nothing to repeat before '*'");
+        assert_eq!(error("a|+"), "nothing to repeat before '+'");
+        assert_eq!(error("a**"), "nothing to repeat before '*'");
+        assert_eq!(error("a{3,1}"), "invalid repetition {3,1}");
+        assert_eq!(error("(?=a)"), "only (?:...) groups are supported");
+        assert_eq!(error("a\\"), "trailing backslash");
+        assert_eq!(error(r"\q"), "unknown escape \\q");
+    }
+}
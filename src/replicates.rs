@@ -0,0 +1,79 @@
+// Repeats the simulation with other seeds for --replicates, showing how much
+// the p-values and verdicts owe to Monte Carlo randomness rather than to the
+// data.
+
+use crate::estimator::Estimator;
+use crate::rng::Seed;
+use crate::sample::Sample;
+use crate::table::Table;
+use crate::{get_quantile, run_simulation, CompareArgs, Error, EstimatorResult, Verdict};
+
+// Replicate i uses the seed plus i, so that any of them can be rerun alone
+// with --seed. `results` are those of the first, which used the seed itself.
+pub fn print(
+    args: &CompareArgs,
+    seed: Seed,
+    baseline: &Sample,
+    target: &Sample,
+    estimators: &[Box<dyn Estimator>],
+    results: &[EstimatorResult],
+    replicates: usize,
+) -> Result<(), Error> {
+    // The p-values and verdicts of each estimator across the replicates.
+    let mut p_values: Vec<Vec<f64>> = results.iter().map(|r| vec![r.p_value()]).collect();
+    let mut verdicts: Vec<Vec<Verdict>> = results
+        .iter()
+        .map(|r| vec![r.verdict(args.alpha)])
+        .collect();
+    for i in 1..replicates as u64 {
+        let replicate = Seed::new(seed.value.wrapping_add(i), seed.kind);
+        let (results, _) = run_simulation(args, replicate, baseline, target, estimators)?;
+        for (k, result) in results.iter().enumerate() {
+            p_values[k].push(result.p_value());
+            verdicts[k].push(result.verdict(args.alpha));
+        }
+    }
+
+    println!(
+        "=== Replicates ({} seeds, {} to {}) ===",
+        replicates,
+        seed.value,
+        seed.value.wrapping_add(replicates as u64 - 1)
+    );
+    let mut table = Table::new(&["estimator", "p min", "p median", "p max", "verdicts"]);
+    let mut unstable = 0;
+    for ((result, ps), vs) in results.iter().zip(p_values.iter_mut()).zip(&verdicts) {
+        ps.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let counts: Vec<String> = [
+            Verdict::Regressed,
+            Verdict::Improved,
+            Verdict::WithinNoise,
+            Verdict::Unchanged,
+        ]
+        .iter()
+        .filter_map(|verdict| {
+            let n = vs.iter().filter(|v| *v == verdict).count();
+            (n > 0).then(|| format!("{} {}", verdict, n))
+        })
+        .collect();
+        if counts.len() > 1 {
+            unstable += 1;
+        }
+        table.row(vec![
+            result.name.clone(),
+            ps[0].to_string(),
+            get_quantile(ps, 0.5)?.to_string(),
+            ps[ps.len() - 1].to_string(),
+            counts.join(", "),
+        ]);
+    }
+    print!("{}", table.render(args.layout));
+    match unstable {
+        0 => println!("Every replicate gives the same verdicts."),
+        n => println!(
+            "Verdicts differ between replicates for {} estimator(s); more --iterations would settle them.",
+            n
+        ),
+    }
+    Ok(())
+}
@@ -0,0 +1,193 @@
+// Standalone HTML report with inline SVG plots.
+
+use crate::metadata::{Labels, RunMetadata};
+use crate::plots::{bootstrap_histogram, kde_plot, sample_series, HISTOGRAM_BINS};
+use crate::svg::{self, escape};
+use crate::units::{format_change, Units};
+use crate::{Error, EstimatorResult};
+use std::fmt::Write as _;
+use std::path::Path;
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 1.5em; }
+th, td { border: 1px solid #ccc; padding: 4px 10px; text-align: right; }
+th:first-child, td:first-child { text-align: left; }
+.regressed { color: #d62728; font-weight: bold; }
+.improved { color: #2ca02c; font-weight: bold; }
+.plots svg { margin: 0 1em 1em 0; }";
+
+fn summary_table(
+    out: &mut String,
+    baseline: &[f64],
+    target: &[f64],
+    results: &[EstimatorResult],
+    units: &Units,
+    labels: &Labels,
+) {
+    let _ = writeln!(out, "<h2>Summary</h2>");
+    let _ = writeln!(out, "<table>");
+    let _ = writeln!(
+        out,
+        "<tr><th></th><th>{}</th><th>{}</th><th>delta</th></tr>",
+        escape(&units.label(&labels.baseline)),
+        escape(&units.label(&labels.target))
+    );
+    let _ = writeln!(
+        out,
+        "<tr><td>Count</td><td>{}</td><td>{}</td><td>{:+}</td></tr>",
+        baseline.len(),
+        target.len(),
+        target.len() as i64 - baseline.len() as i64
+    );
+    for result in results.iter() {
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&result.name),
+            result.full_baseline_estimator,
+            result.target_estimator,
+            result.target_estimator - result.full_baseline_estimator
+        );
+    }
+    let _ = writeln!(out, "</table>");
+}
+
+fn comparison_table(
+    out: &mut String,
+    results: &[EstimatorResult],
+    alpha: f64,
+    units: &Units,
+    labels: &Labels,
+) {
+    let _ = writeln!(out, "<h2>Comparison</h2>");
+    let _ = writeln!(out, "<table>");
+    let _ = writeln!(
+        out,
+        "<tr><th>estimator</th><th>{}</th><th>{}</th><th>{}</th><th>change</th><th>p-value</th><th>verdict</th></tr>",
+        escape(&units.label(&labels.baseline)),
+        escape(&units.label(&labels.target)),
+        escape(&units.label("delta"))
+    );
+    for result in results.iter() {
+        let delta = result.target_estimator - result.full_baseline_estimator;
+        let verdict = result.verdict(alpha);
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"{}\">{}</td></tr>",
+            escape(&result.name),
+            result.full_baseline_estimator,
+            result.target_estimator,
+            delta,
+            format_change(result.full_baseline_estimator, result.target_estimator),
+            escape(&result.p_value_text()),
+            verdict,
+            verdict
+        );
+    }
+    let _ = writeln!(out, "</table>");
+    let _ = writeln!(
+        out,
+        "<p>Significance level: {}. p-values are the fraction of simulated baseline estimators at least as extreme as the target's.</p>",
+        alpha
+    );
+}
+
+fn metadata_table(out: &mut String, metadata: &RunMetadata) {
+    let _ = writeln!(out, "<h2>Run metadata</h2>");
+    let _ = writeln!(out, "<table>");
+    let mut rows = vec![
+        ("numcmp version", metadata.version.to_string()),
+        ("Command line", metadata.args.join(" ")),
+        ("Seed", metadata.seed.to_string()),
+        ("Iterations", metadata.iterations.to_string()),
+        ("Timestamp", metadata.timestamp.clone()),
+    ];
+    if let Some(unit) = &metadata.unit {
+        rows.push(("Unit", unit.clone()));
+    }
+    if let Some(bandwidth) = metadata.bandwidth {
+        rows.push(("KDE bandwidth", bandwidth.to_string()));
+    }
+    if let Some(environment) = &metadata.environment {
+        rows.extend(environment.rows());
+    }
+    for (key, value) in rows.iter() {
+        let _ = writeln!(out, "<tr><td>{}</td><td>{}</td></tr>", key, escape(value));
+    }
+    for input in metadata.inputs.iter() {
+        let _ = writeln!(
+            out,
+            "<tr><td>Input ({})</td><td>{} ({} values, sha256 {})</td></tr>",
+            input.role,
+            escape(&input.path),
+            input.count,
+            input.sha256
+        );
+    }
+    let _ = writeln!(out, "</table>");
+}
+
+pub fn write_html(
+    path: &Path,
+    metadata: &RunMetadata,
+    baseline: &[f64],
+    target: &[f64],
+    results: &[EstimatorResult],
+    distributions: &[Vec<f64>],
+    alpha: f64,
+) -> Result<(), Error> {
+    let units = &Units::new(metadata.unit.as_deref(), None)?;
+    let mut out = String::new();
+    let _ = writeln!(out, "<!DOCTYPE html>");
+    let _ = writeln!(out, "<html><head><meta charset=\"utf-8\">");
+    let _ = writeln!(out, "<title>numcmp report</title>");
+    let _ = writeln!(out, "<style>{}</style>", STYLE);
+    let _ = writeln!(out, "</head><body>");
+    let labels = &metadata.labels;
+    if *labels == Labels::default() {
+        let _ = writeln!(out, "<h1>numcmp report</h1>");
+    } else {
+        let _ = writeln!(
+            out,
+            "<h1>numcmp report: {} vs {}</h1>",
+            escape(&labels.baseline),
+            escape(&labels.target)
+        );
+    }
+
+    summary_table(&mut out, baseline, target, results, units, labels);
+    comparison_table(&mut out, results, alpha, units, labels);
+
+    let samples = sample_series(baseline, target, labels);
+    let _ = writeln!(out, "<h2>Distributions</h2>");
+    let _ = writeln!(out, "<div class=\"plots\">");
+    out.push_str(&svg::histogram(
+        &units.label("Histogram"),
+        &samples,
+        &[],
+        HISTOGRAM_BINS,
+    ));
+    out.push_str(&kde_plot(
+        baseline,
+        target,
+        metadata.bandwidth,
+        units,
+        labels,
+    ));
+    out.push_str(&svg::boxplot(&units.label("Box plot"), &samples));
+    out.push_str(&svg::ecdf(&units.label("ECDF"), &samples));
+    let _ = writeln!(out, "</div>");
+
+    let _ = writeln!(out, "<h2>Bootstrap distributions</h2>");
+    let _ = writeln!(out, "<div class=\"plots\">");
+    for (result, sims) in results.iter().zip(distributions.iter()) {
+        out.push_str(&bootstrap_histogram(result, sims, units, labels));
+    }
+    let _ = writeln!(out, "</div>");
+
+    metadata_table(&mut out, metadata);
+    let _ = writeln!(out, "</body></html>");
+
+    std::fs::write(path, out)?;
+    Ok(())
+}
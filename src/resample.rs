@@ -0,0 +1,315 @@
+// Strategies for drawing simulated samples from the baseline.
+
+use crate::sample::Sample;
+use crate::stats;
+use crate::Error;
+use clap::ValueEnum;
+use rand::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum BootstrapMethod {
+    /// Independent draws with replacement
+    Iid,
+    /// Moving-block bootstrap over the values in input order
+    Block,
+    /// Stationary bootstrap: blocks of geometrically distributed length
+    Stationary,
+    /// m-out-of-n subsampling without replacement, rescaled to the target size
+    Subsample,
+}
+
+pub enum Resampler {
+    Iid {
+        // Cumulative weights, for weighted samples.
+        cumulative_weights: Option<Vec<f64>>,
+    },
+    // Iid draws from a baseline whose weights are whole counts, taken as
+    // multinomial counts of its distinct values instead of one by one.
+    Counts {
+        probabilities: Vec<f64>,
+    },
+    Block {
+        block_size: usize,
+    },
+    Stationary {
+        mean_block_size: f64,
+    },
+    Subsample {
+        m: usize,
+    },
+    // Iid draws within each stratum: the positions of the stratum's values
+    // in the baseline and how many values to draw from them.
+    Stratified {
+        strata: Vec<(Vec<usize>, usize)>,
+    },
+}
+
+// Draws from Binomial(n, p): by inversion when few successes or failures
+// are expected, and from the normal approximation otherwise.
+fn binomial(rng: &mut impl Rng, n: usize, p: f64) -> usize {
+    if p <= 0.0 || n == 0 {
+        return 0;
+    }
+    if p >= 1.0 {
+        return n;
+    }
+    if p > 0.5 {
+        return n - binomial(rng, n, 1.0 - p);
+    }
+    let mean = n as f64 * p;
+    if mean < 30.0 {
+        let ratio = p / (1.0 - p);
+        let mut pmf = (1.0 - p).powf(n as f64);
+        let mut cdf = pmf;
+        let u: f64 = rng.gen();
+        let mut k = 0;
+        while u > cdf && k < n {
+            pmf *= ratio * ((n - k) as f64) / ((k + 1) as f64);
+            cdf += pmf;
+            k += 1;
+        }
+        return k;
+    }
+    let z = stats::standard_normal(rng);
+    (mean + z * (mean * (1.0 - p)).sqrt())
+        .round()
+        .clamp(0.0, n as f64) as usize
+}
+
+// Splits n draws among the baseline's values as a multinomial sample,
+// pushing the values drawn at least once to `out` and returning their counts.
+fn draw_counts(
+    rng: &mut impl Rng,
+    baseline: &Sample,
+    probabilities: &[f64],
+    n: usize,
+    out: &mut Vec<f64>,
+) -> Vec<f64> {
+    let mut counts = Vec::with_capacity(probabilities.len());
+    let mut remaining = n;
+    let mut rest = 1.0;
+    for (x, p) in baseline.values.iter().zip(probabilities.iter()) {
+        let count = binomial(rng, remaining, (p / rest).min(1.0));
+        remaining -= count;
+        rest -= p;
+        if count > 0 {
+            out.push(*x);
+            counts.push(count as f64);
+        }
+    }
+    if remaining > 0 {
+        // Rounding left the last few draws unassigned.
+        out.push(baseline.values[baseline.len() - 1]);
+        counts.push(remaining as f64);
+        if out.len() > 1 && out[out.len() - 2] == out[out.len() - 1] {
+            out.pop();
+            let extra = counts.pop().expect("pushed above");
+            *counts.last_mut().expect("more than one") += extra;
+        }
+    }
+    counts
+}
+
+// A common rule of thumb for the block length of the moving-block bootstrap.
+fn default_block_size(n: usize) -> usize {
+    ((n as f64).cbrt().round() as usize).max(1)
+}
+
+impl Resampler {
+    pub fn new(
+        method: BootstrapMethod,
+        block_size: Option<usize>,
+        mean_block_size: Option<f64>,
+        subsample_size: Option<usize>,
+        baseline: &Sample,
+    ) -> Result<Resampler, Error> {
+        if method != BootstrapMethod::Iid && baseline.weights.is_some() {
+            return Err(Error::Usage(format!(
+                "weighted samples are not supported by the {} bootstrap",
+                method
+                    .to_possible_value()
+                    .expect("no skipped variants")
+                    .get_name()
+            )));
+        }
+        match method {
+            BootstrapMethod::Iid
+                if baseline
+                    .weights
+                    .as_ref()
+                    .is_some_and(|ws| ws.iter().all(|w| w.fract() == 0.0)) =>
+            {
+                let total = baseline.total_weight();
+                let ws = baseline.weights.as_ref().expect("checked above");
+                Ok(Resampler::Counts {
+                    probabilities: ws.iter().map(|w| w / total).collect(),
+                })
+            }
+            BootstrapMethod::Iid => {
+                // Weighted baselines are resampled with probability
+                // proportional to weight; the resamples themselves are then
+                // unweighted.
+                let cumulative_weights = baseline.weights.as_ref().map(|ws| {
+                    ws.iter()
+                        .scan(0.0, |acc, w| {
+                            *acc += w;
+                            Some(*acc)
+                        })
+                        .collect()
+                });
+                Ok(Resampler::Iid { cumulative_weights })
+            }
+            BootstrapMethod::Block => {
+                let block_size = block_size.unwrap_or_else(|| default_block_size(baseline.len()));
+                if block_size == 0 {
+                    return Err(Error::Usage("block size must be positive".to_string()));
+                }
+                Ok(Resampler::Block {
+                    block_size: block_size.min(baseline.len()),
+                })
+            }
+            BootstrapMethod::Stationary => {
+                let mean_block_size =
+                    mean_block_size.unwrap_or_else(|| default_block_size(baseline.len()) as f64);
+                if mean_block_size.is_nan() || mean_block_size < 1.0 {
+                    return Err(Error::Usage(
+                        "mean block size must be at least 1".to_string(),
+                    ));
+                }
+                Ok(Resampler::Stationary { mean_block_size })
+            }
+            BootstrapMethod::Subsample => {
+                let n = baseline.len();
+                let m = subsample_size
+                    .unwrap_or_else(|| ((n as f64).powf(2.0 / 3.0).round() as usize).max(1));
+                if m == 0 || m > n {
+                    return Err(Error::Usage(format!(
+                        "subsample size must be between 1 and the baseline size {}",
+                        n
+                    )));
+                }
+                Ok(Resampler::Subsample { m })
+            }
+        }
+    }
+
+    // Draws from each stratum of `baseline` as many values as `composition`
+    // has in that stratum, so resamples keep the composition's mix of strata.
+    pub fn stratified(baseline: &Sample, composition: &Sample) -> Result<Resampler, Error> {
+        let (Some(from), Some(counts)) = (&baseline.strata, &composition.strata) else {
+            return Err(Error::Usage(
+                "stratified resampling needs --strata-column".to_string(),
+            ));
+        };
+        let mut names: Vec<&String> = counts.iter().collect();
+        names.sort();
+        names.dedup();
+        let mut strata = Vec::with_capacity(names.len());
+        for name in names {
+            let positions: Vec<usize> = (0..from.len()).filter(|i| from[*i] == *name).collect();
+            if positions.is_empty() {
+                return Err(Error::InvalidInput(format!(
+                    "stratum {:?} has no baseline values",
+                    name
+                )));
+            }
+            let count = counts.iter().filter(|s| *s == name).count();
+            strata.push((positions, count));
+        }
+        Ok(Resampler::Stratified { strata })
+    }
+
+    // Whether resamples are independent draws with replacement, as the
+    // bootstrap confidence intervals want.
+    pub fn is_iid(&self) -> bool {
+        matches!(self, Resampler::Iid { .. } | Resampler::Counts { .. })
+    }
+
+    // Maps an estimator computed on a resample to the scale of a sample of
+    // size n. Subsample estimators spread like 1/sqrt(m) around the baseline
+    // estimator, so their deviation is shrunk by sqrt(m/n).
+    pub fn rescale(&self, sim: f64, baseline_estimator: f64, n: usize) -> f64 {
+        match self {
+            Resampler::Subsample { m } => {
+                baseline_estimator + ((*m as f64) / (n as f64)).sqrt() * (sim - baseline_estimator)
+            }
+            _ => sim,
+        }
+    }
+
+    // Fills `out` with n values drawn from the baseline, unsorted and
+    // unweighted, except that counted baselines give sorted values weighted
+    // by their counts. The subsampling bootstrap always draws m values, and
+    // stratified resampling the size of the sample it was composed from.
+    pub fn resample(&self, rng: &mut impl Rng, baseline: &Sample, n: usize, sample: &mut Sample) {
+        sample.weights = None;
+        let out = &mut sample.values;
+        out.clear();
+        match self {
+            Resampler::Counts { probabilities } => {
+                sample.weights = Some(draw_counts(rng, baseline, probabilities, n, out));
+            }
+            Resampler::Iid {
+                cumulative_weights: Some(cw),
+            } => {
+                let total = cw[cw.len() - 1];
+                for _ in 0..n {
+                    let u = rng.gen_range(0.0..total);
+                    let item = cw.partition_point(|c| *c <= u).min(cw.len() - 1);
+                    out.push(baseline.values[item]);
+                }
+            }
+            Resampler::Iid {
+                cumulative_weights: None,
+            } => {
+                for _ in 0..n {
+                    let item = rng.gen_range(0..baseline.len());
+                    out.push(baseline.values[item]);
+                }
+            }
+            Resampler::Block { block_size } => {
+                let series = &baseline.input_order;
+                let starts = series.len() - block_size + 1;
+                while out.len() < n {
+                    let start = rng.gen_range(0..starts);
+                    let take = (*block_size).min(n - out.len());
+                    out.extend_from_slice(&series[start..start + take]);
+                }
+            }
+            Resampler::Stationary { mean_block_size } => {
+                // Politis & Romano: each step either continues the current
+                // block (wrapping around) or, with probability 1/mean, jumps
+                // to a new random position.
+                let series = &baseline.input_order;
+                let restart = 1.0 / mean_block_size;
+                let mut i = rng.gen_range(0..series.len());
+                for _ in 0..n {
+                    out.push(series[i]);
+                    i = if rng.gen::<f64>() < restart {
+                        rng.gen_range(0..series.len())
+                    } else {
+                        (i + 1) % series.len()
+                    };
+                }
+            }
+            Resampler::Subsample { m } => {
+                // Partial Fisher-Yates shuffle: the first m values form a
+                // draw without replacement.
+                out.extend_from_slice(&baseline.values);
+                for i in 0..*m {
+                    let j = rng.gen_range(i..out.len());
+                    out.swap(i, j);
+                }
+                out.truncate(*m);
+            }
+            Resampler::Stratified { strata } => {
+                for (positions, count) in strata.iter() {
+                    for _ in 0..*count {
+                        let item = positions[rng.gen_range(0..positions.len())];
+                        out.push(baseline.values[item]);
+                    }
+                }
+            }
+        }
+    }
+}
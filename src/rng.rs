@@ -0,0 +1,159 @@
+// Random number generators for the simulations. Iterations are drawn in
+// chunks, each from its own stream derived from the seed and the chunk
+// number, so that a seeded run gives the same results however the chunks
+// are scheduled.
+
+use clap::ValueEnum;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+// Iterations per random stream.
+pub const CHUNK_SIZE: u64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum RngKind {
+    /// ChaCha with 12 rounds, the rand crate's standard generator
+    Chacha,
+    /// PCG XSL RR 128/64 (PCG64), faster but not cryptographically strong
+    Pcg,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Seed {
+    pub value: u64,
+    pub kind: RngKind,
+}
+
+impl Seed {
+    pub fn new(value: u64, kind: RngKind) -> Seed {
+        Seed { value, kind }
+    }
+
+    // The generator for one chunk of iterations. Stream 0 of ChaCha is the
+    // same as rand's StdRng seeded with the value.
+    pub fn stream(&self, chunk: u64) -> SimRng {
+        match self.kind {
+            RngKind::Chacha => {
+                let mut rng = ChaCha12Rng::seed_from_u64(self.value);
+                rng.set_stream(chunk);
+                SimRng::Chacha(Box::new(rng))
+            }
+            RngKind::Pcg => SimRng::Pcg(Pcg64::new(self.value, chunk)),
+        }
+    }
+
+    // The generator for an analysis other than the main simulation, e.g.
+    // the Bayesian bootstrap, on a stream derived from its name so that its
+    // draws are independent of every chunk's.
+    pub fn analysis_stream(&self, name: &str) -> SimRng {
+        let parts: Vec<u64> = name.bytes().map(u64::from).collect();
+        self.stream(derive_stream(&parts))
+    }
+}
+
+pub enum SimRng {
+    Chacha(Box<ChaCha12Rng>),
+    Pcg(Pcg64),
+}
+
+impl RngCore for SimRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            SimRng::Chacha(rng) => rng.next_u32(),
+            SimRng::Pcg(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            SimRng::Chacha(rng) => rng.next_u64(),
+            SimRng::Pcg(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            SimRng::Chacha(rng) => rng.fill_bytes(dest),
+            SimRng::Pcg(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+// SplitMix64, for spreading a 64-bit seed over the PCG state.
+fn splitmix64(x: &mut u64) -> u64 {
+    *x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *x;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// A stream number derived from several numbers, for work that is not
+// numbered by chunks, e.g. the segments of a recursive search.
+pub fn derive_stream(parts: &[u64]) -> u64 {
+    let mut x = 0;
+    for part in parts {
+        x = splitmix64(&mut x) ^ part;
+    }
+    splitmix64(&mut x)
+}
+
+// O'Neill's PCG64: a 128-bit LCG whose increment selects the stream, with
+// the XSL RR output function. Same constants as the rand_pcg crate.
+pub struct Pcg64 {
+    state: u128,
+    increment: u128,
+}
+
+const PCG_MULTIPLIER: u128 = 0x2360_ED05_1FC6_5DA4_4385_DF64_9FCC_F645;
+
+impl Pcg64 {
+    pub fn new(seed: u64, stream: u64) -> Pcg64 {
+        let mut mix = seed;
+        let state = ((splitmix64(&mut mix) as u128) << 64) | splitmix64(&mut mix) as u128;
+        let mut pcg = Pcg64 {
+            state,
+            increment: ((stream as u128) << 1) | 1,
+        };
+        pcg.state = pcg.state.wrapping_add(pcg.increment);
+        pcg.step();
+        pcg
+    }
+
+    fn step(&mut self) {
+        self.state = self
+            .state
+            .wrapping_mul(PCG_MULTIPLIER)
+            .wrapping_add(self.increment);
+    }
+}
+
+impl RngCore for Pcg64 {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let state = self.state;
+        self.step();
+        let rotation = (state >> 122) as u32;
+        (((state >> 64) as u64) ^ (state as u64)).rotate_right(rotation)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
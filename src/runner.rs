@@ -0,0 +1,138 @@
+// Collecting a sample by running a command repeatedly and timing each run,
+// for `numcmp run`.
+
+use crate::Error;
+use clap::ValueEnum;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::process::{Command, Stdio};
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Measure {
+    /// Elapsed wall-clock time
+    Wall,
+    /// CPU time spent in user mode
+    User,
+    /// CPU time spent in the kernel
+    Sys,
+    /// User and kernel CPU time together
+    Cpu,
+}
+
+// CPU time (user, sys) in microseconds used by the children waited for so
+// far. Kept in whole microseconds so that differences are exact.
+#[cfg(unix)]
+fn children_cpu_time() -> (i64, i64) {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    // SAFETY: getrusage only writes the structure passed to it.
+    unsafe { libc::getrusage(libc::RUSAGE_CHILDREN, &mut usage) };
+    // The widths of time_t and suseconds_t differ between platforms.
+    #[allow(clippy::unnecessary_cast)]
+    let micros = |t: libc::timeval| t.tv_sec as i64 * 1_000_000 + t.tv_usec as i64;
+    (micros(usage.ru_utime), micros(usage.ru_stime))
+}
+
+#[cfg(not(unix))]
+fn children_cpu_time() -> (i64, i64) {
+    (0, 0)
+}
+
+// Runs the command once, returning the time taken. Its output is discarded
+// so that it does not mix with numcmp's; errors still go to stderr.
+fn time_once(command: &[String], measure: Measure) -> Result<f64, Error> {
+    let (user_before, sys_before) = children_cpu_time();
+    let start = Instant::now();
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .status()
+        .map_err(|e| {
+            Error::Io(std::io::Error::new(
+                e.kind(),
+                format!("cannot run {}: {}", command[0], e),
+            ))
+        })?;
+    let wall = start.elapsed().as_secs_f64();
+    let (user_after, sys_after) = children_cpu_time();
+    if !status.success() {
+        return Err(Error::Io(std::io::Error::other(format!(
+            "{} failed: {}",
+            command[0], status
+        ))));
+    }
+    let (user, sys) = (user_after - user_before, sys_after - sys_before);
+    Ok(match measure {
+        Measure::Wall => wall,
+        Measure::User => user as f64 / 1e6,
+        Measure::Sys => sys as f64 / 1e6,
+        Measure::Cpu => (user + sys) as f64 / 1e6,
+    })
+}
+
+// Times `runs` runs of the command after `warmup` untimed ones, in seconds.
+pub fn time_runs(
+    command: &[String],
+    runs: usize,
+    warmup: usize,
+    measure: Measure,
+) -> Result<Vec<f64>, Error> {
+    if cfg!(not(unix)) && measure != Measure::Wall {
+        return Err(Error::Usage(
+            "only --measure wall is supported on this platform".to_string(),
+        ));
+    }
+    for _ in 0..warmup {
+        time_once(command, measure)?;
+    }
+    let mut times = Vec::with_capacity(runs);
+    for run in 0..runs {
+        let time = time_once(command, measure)?;
+        tracing::debug!(run = run + 1, seconds = time, "run finished");
+        times.push(time);
+    }
+    Ok(times)
+}
+
+// Times two commands in alternation, `runs` times each, in a random order
+// within each round so that drift such as heating or cache warming does
+// not favour either. Returns the times of the first and the second.
+pub fn duel(
+    first: &[String],
+    second: &[String],
+    runs: usize,
+    warmup: usize,
+    measure: Measure,
+    seed: u64,
+) -> Result<(Vec<f64>, Vec<f64>), Error> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..warmup {
+        time_once(first, measure)?;
+        time_once(second, measure)?;
+    }
+    let (mut first_times, mut second_times) = (Vec::new(), Vec::new());
+    for round in 0..runs {
+        let first_goes_first = rng.gen::<bool>();
+        for i in 0..2 {
+            if (i == 0) == first_goes_first {
+                first_times.push(time_once(first, measure)?);
+            } else {
+                second_times.push(time_once(second, measure)?);
+            }
+        }
+        tracing::debug!(round = round + 1, "round finished");
+    }
+    Ok((first_times, second_times))
+}
+
+// Splits the words after the first -- at the second, into two commands.
+pub fn split_commands(words: &[String]) -> Result<(&[String], &[String]), Error> {
+    match words.iter().position(|w| w == "--") {
+        Some(i) if i > 0 && i + 1 < words.len() => Ok((&words[..i], &words[i + 1..])),
+        _ => Err(Error::Usage(
+            "numcmp duel needs two commands: numcmp duel [OPTIONS] -- BASELINE_CMD -- TARGET_CMD"
+                .to_string(),
+        )),
+    }
+}
@@ -0,0 +1,140 @@
+// A sample of numbers, kept sorted, with optional per-value weights.
+
+use crate::{get_quantile, Error};
+
+#[derive(Debug, Clone, Default)]
+pub struct Sample {
+    // Sorted in ascending order.
+    pub values: Vec<f64>,
+    // Same length as values when present; all weights are positive.
+    pub weights: Option<Vec<f64>>,
+    // The values in the order they were read, for order-aware resampling.
+    pub input_order: Vec<f64>,
+    // The stratum of each value in `values`, with --strata-column.
+    pub strata: Option<Vec<String>>,
+}
+
+impl Sample {
+    pub fn new(values: Vec<f64>) -> Sample {
+        let input_order = values.clone();
+        let mut values = values;
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        Sample {
+            values,
+            weights: None,
+            input_order,
+            strata: None,
+        }
+    }
+
+    pub fn stratified(values: Vec<f64>, strata: Vec<String>) -> Sample {
+        let input_order = values.clone();
+        let mut pairs: Vec<(f64, String)> = values.into_iter().zip(strata).collect();
+        pairs.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let (values, strata) = pairs.into_iter().unzip();
+        Sample {
+            values,
+            weights: None,
+            input_order,
+            strata: Some(strata),
+        }
+    }
+
+    // Values with zero weight carry no information and are dropped.
+    pub fn weighted(mut pairs: Vec<(f64, f64)>) -> Result<Sample, Error> {
+        if let Some((_, w)) = pairs.iter().find(|(_, w)| !w.is_finite() || *w < 0.0) {
+            return Err(Error::InvalidInput(format!("invalid weight {}", w)));
+        }
+        // Zero weights drop their values, which must not drop them all.
+        if !pairs.is_empty() && pairs.iter().all(|(_, w)| *w == 0.0) {
+            return Err(Error::InvalidInput(
+                "the weights add up to zero".to_string(),
+            ));
+        }
+        pairs.retain(|(_, w)| *w > 0.0);
+        let input_order = pairs.iter().map(|(x, _)| *x).collect();
+        pairs.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        Ok(Sample {
+            values: pairs.iter().map(|(x, _)| *x).collect(),
+            weights: Some(pairs.iter().map(|(_, w)| *w).collect()),
+            input_order,
+            strata: None,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    // The size of a simulated sample standing in for this one: with integer
+    // weights, which count repeated values, their total.
+    pub fn size(&self) -> usize {
+        match &self.weights {
+            Some(ws) if ws.iter().all(|w| w.fract() == 0.0) => ws.iter().sum::<f64>() as usize,
+            _ => self.len(),
+        }
+    }
+
+    pub fn total_weight(&self) -> f64 {
+        match &self.weights {
+            Some(ws) => ws.iter().sum(),
+            None => self.values.len() as f64,
+        }
+    }
+
+    pub fn mean(&self) -> f64 {
+        match &self.weights {
+            Some(ws) => {
+                let sum: f64 = self.values.iter().zip(ws.iter()).map(|(x, w)| x * w).sum();
+                sum / self.total_weight()
+            }
+            None => self.values.iter().sum::<f64>() / (self.values.len() as f64),
+        }
+    }
+
+    pub fn quantile(&self, q: f64) -> Result<f64, Error> {
+        match &self.weights {
+            Some(ws) => weighted_quantile(&self.values, ws, q),
+            None => get_quantile(&self.values, q),
+        }
+    }
+}
+
+// Linear interpolation between values placed at the midpoints of their
+// weight, scaled so the first and last values sit at 0 and 1. With equal
+// weights this reduces to the unweighted definition.
+fn weighted_quantile(sorted_numbers: &[f64], weights: &[f64], q: f64) -> Result<f64, Error> {
+    if sorted_numbers.is_empty() {
+        return Err(Error::EmptySample(
+            "cannot take a quantile of no values".to_string(),
+        ));
+    }
+    if !(0.0..=1.0).contains(&q) {
+        return Err(Error::InvalidQuantile(q));
+    }
+    let n = sorted_numbers.len();
+    if n == 1 {
+        return Ok(sorted_numbers[0]);
+    }
+
+    let total: f64 = weights.iter().sum();
+    let lo = weights[0] / 2.0;
+    let span = total - weights[0] / 2.0 - weights[n - 1] / 2.0;
+    let target = lo + q * span;
+
+    let mut cumulative = 0.0;
+    let mut prev_pos = lo;
+    for i in 0..n {
+        let pos = cumulative + weights[i] / 2.0;
+        if pos >= target {
+            if i == 0 || pos == prev_pos {
+                return Ok(sorted_numbers[i]);
+            }
+            let t = (target - prev_pos) / (pos - prev_pos);
+            return Ok(sorted_numbers[i - 1] * (1.0 - t) + sorted_numbers[i] * t);
+        }
+        cumulative += weights[i];
+        prev_pos = pos;
+    }
+    Ok(sorted_numbers[n - 1])
+}
@@ -0,0 +1,109 @@
+// The A/A check of --ab-sanity: the baseline is split at random into two
+// halves, which come from the same distribution by construction, and
+// compared as if they were baseline and target. A significant difference
+// between them, after a Holm correction across the estimators since the
+// check asks whether any differ, means the p-values of the real comparison
+// cannot be taken at face value either.
+
+use crate::estimator::Estimator;
+use crate::rng::Seed;
+use crate::sample::Sample;
+use crate::table::Table;
+use crate::units::Units;
+use crate::{run_simulation, stats, CompareArgs, Error};
+use rand::seq::SliceRandom;
+
+// Splits the sample at random into two halves, keeping the input order
+// within each.
+fn split(sample: &Sample, seed: Seed) -> Result<(Sample, Sample), Error> {
+    let mut rng = seed.analysis_stream("a/a split");
+    let mut indices: Vec<usize> = (0..sample.len()).collect();
+    indices.shuffle(&mut rng);
+    let (a, b) = indices.split_at(sample.len() / 2);
+    let half = |chosen: &[usize]| -> Result<Sample, Error> {
+        let mut chosen = chosen.to_vec();
+        chosen.sort_unstable();
+        match (&sample.weights, &sample.strata) {
+            (Some(ws), _) => {
+                Sample::weighted(chosen.iter().map(|i| (sample.values[*i], ws[*i])).collect())
+            }
+            (None, Some(strata)) => Ok(Sample::stratified(
+                chosen.iter().map(|i| sample.values[*i]).collect(),
+                chosen.iter().map(|i| strata[*i].clone()).collect(),
+            )),
+            (None, None) => Ok(Sample::new(
+                chosen.iter().map(|i| sample.input_order[*i]).collect(),
+            )),
+        }
+    };
+    Ok((half(a)?, half(b)?))
+}
+
+// Runs the check, printing its results when `text`, and warns if the
+// halves differ.
+pub fn check(
+    args: &CompareArgs,
+    seed: Seed,
+    baseline: &Sample,
+    estimators: &[Box<dyn Estimator>],
+    units: &Units,
+    text: bool,
+) -> Result<(), Error> {
+    if baseline.len() < 4 {
+        return Err(Error::InvalidInput(format!(
+            "--ab-sanity needs at least 4 {} values to split, got {}",
+            args.baseline_label,
+            baseline.len()
+        )));
+    }
+    let (a, b) = split(baseline, seed)?;
+    let (results, _) = run_simulation(args, seed, &a, &b, estimators)?;
+    let p_values: Vec<f64> = results.iter().map(|r| r.p_value()).collect();
+    let adjusted = stats::holm(&p_values);
+    let changed: Vec<&str> = results
+        .iter()
+        .zip(adjusted.iter())
+        .filter(|(_, p)| **p < args.alpha)
+        .map(|(r, _)| r.name.as_str())
+        .collect();
+    if text {
+        println!(
+            "=== A/A sanity check ({} split into halves of {} and {}) ===",
+            args.baseline_label,
+            a.len(),
+            b.len()
+        );
+        let mut table = Table::new(&[
+            "estimator",
+            "first half",
+            "second half",
+            "p-value",
+            "Holm p-value",
+            "differs",
+        ]);
+        for (result, p) in results.iter().zip(adjusted.iter()) {
+            table.row(vec![
+                result.name.clone(),
+                units.format(result.full_baseline_estimator),
+                units.format(result.target_estimator),
+                result.p_value_text(),
+                format!("{:.4}", p),
+                if *p < args.alpha { "yes" } else { "no" }.to_string(),
+            ]);
+        }
+        print!("{}", table.render(args.layout));
+        if changed.is_empty() {
+            println!("No estimator differs between the halves.");
+        }
+        println!();
+    }
+    if !changed.is_empty() {
+        tracing::warn!(
+            "two random halves of the {} differ significantly in {}; the data or settings are suspect (e.g. drift or autocorrelation in the {}, or too few --iterations), so treat the comparison with caution",
+            args.baseline_label,
+            changed.join(", "),
+            args.baseline_label
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,157 @@
+// Calibration check of the comparison itself: pairs of samples drawn from
+// the same known distribution are compared many times, and the rate of
+// reported changes and the coverage of the difference intervals are set
+// against the nominal significance and confidence levels.
+
+use crate::ci::CiMethod;
+use crate::rng::Seed;
+use crate::sample::Sample;
+use crate::stats::standard_normal;
+use crate::table::Table;
+use crate::{analyze, prepare, CompareArgs, Error, Format, Method, Verdict};
+use rand::Rng;
+
+// Distributions with different shapes: symmetric, skewed, heavy-tailed and
+// bounded.
+const DISTRIBUTIONS: [&str; 4] = ["normal", "exponential", "lognormal", "uniform"];
+
+fn draw(rng: &mut impl Rng, distribution: &str, n: usize) -> Sample {
+    let values = (0..n)
+        .map(|_| match distribution {
+            "normal" => 100.0 + 10.0 * standard_normal(rng),
+            "exponential" => -(1.0 - rng.gen::<f64>()).ln(),
+            "lognormal" => standard_normal(rng).exp(),
+            _ => rng.gen::<f64>(),
+        })
+        .collect();
+    Sample::new(values)
+}
+
+// Counts for one estimator over the runs of one distribution.
+#[derive(Default, Clone)]
+struct Tally {
+    changed: u64,
+    covered: u64,
+}
+
+// A rate and whether it is within three binomial standard errors of the
+// nominal rate.
+fn rate(count: u64, runs: u64, nominal: f64) -> (String, bool) {
+    let observed = count as f64 / runs as f64;
+    let se = (nominal * (1.0 - nominal) / runs as f64).sqrt();
+    (
+        format!("{:.1}%", 100.0 * observed),
+        (observed - nominal).abs() <= 3.0 * se,
+    )
+}
+
+pub fn selftest(args: &CompareArgs, runs: u64, size: usize) -> Result<(), Error> {
+    if args.method != Method::Bootstrap {
+        return Err(Error::Usage(
+            "numcmp selftest supports only --method bootstrap".to_string(),
+        ));
+    }
+    if args.format != Format::Text {
+        return Err(Error::Usage(
+            "numcmp selftest supports only text output".to_string(),
+        ));
+    }
+    if size < 2 {
+        return Err(Error::Usage(
+            "numcmp selftest needs --size of at least 2".to_string(),
+        ));
+    }
+    // Every run has its own seed, so nothing would be read back from the
+    // cache.
+    let mut args = args.clone();
+    args.no_cache = true;
+    let ci_method = *args.ci_method.get_or_insert(CiMethod::Percentile);
+    let seed = Seed::new(args.seed.unwrap_or_else(rand::random), args.rng);
+
+    println!(
+        "=== Selftest ({} runs of n={} per side, alpha {}, {:?} intervals) ===",
+        runs, size, args.alpha, ci_method
+    );
+    let level = 1.0 - args.alpha;
+    let mut table = Table::new(&[
+        "distribution",
+        "estimator",
+        "false positives",
+        "coverage",
+        "calibrated",
+    ]);
+    let mut miscalibrated = 0;
+    for (d, distribution) in DISTRIBUTIONS.iter().enumerate() {
+        let mut names = Vec::new();
+        let mut tallies: Vec<Tally> = Vec::new();
+        for run in 0..runs {
+            // The samples and the seed of each run come from their own
+            // stream, so runs do not depend on each other.
+            let mut rng = seed.stream(d as u64 * runs + run);
+            let baseline = draw(&mut rng, distribution, size);
+            let target = draw(&mut rng, distribution, size);
+            args.seed = Some(rng.gen());
+            let (run_seed, estimators, _) = prepare(&args, &baseline, &target, Vec::new())?;
+            let analysis = analyze(
+                &args,
+                run_seed,
+                &baseline,
+                &target,
+                &estimators,
+                &mut Vec::new(),
+            )?;
+            if tallies.is_empty() {
+                names = analysis.results.iter().map(|r| r.name.clone()).collect();
+                tallies = vec![Tally::default(); names.len()];
+            }
+            for (result, tally) in analysis.results.iter().zip(tallies.iter_mut()) {
+                if !matches!(result.verdict(args.alpha), Verdict::Unchanged) {
+                    tally.changed += 1;
+                }
+                // The true difference is zero.
+                if let Some((lo, hi)) = result.difference_interval {
+                    if lo <= 0.0 && 0.0 <= hi {
+                        tally.covered += 1;
+                    }
+                }
+            }
+            tracing::debug!(
+                distribution = %distribution,
+                run = run + 1,
+                "selftest run finished"
+            );
+        }
+        for (name, tally) in names.iter().zip(tallies.iter()) {
+            let (false_positives, fp_ok) = rate(tally.changed, runs, args.alpha);
+            let (coverage, coverage_ok) = rate(tally.covered, runs, level);
+            if !(fp_ok && coverage_ok) {
+                miscalibrated += 1;
+            }
+            table.row(vec![
+                distribution.to_string(),
+                name.clone(),
+                false_positives,
+                coverage,
+                if fp_ok && coverage_ok { "yes" } else { "no" }.to_string(),
+            ]);
+        }
+    }
+    print!("{}", table.render(args.layout));
+    println!(
+        "Nominal: {:.1}% false positives, {:.1}% coverage; rates more than three standard errors away are not calibrated.",
+        100.0 * args.alpha,
+        100.0 * level
+    );
+    if miscalibrated > 0 {
+        println!(
+            "{} estimator(s) outside the expected range; small samples and extreme quantiles are the usual cause.",
+            miscalibrated
+        );
+    }
+    if args.alternative.is_none() {
+        println!(
+            "Without --alternative, p-values are one-sided in the direction each estimator moved, so about twice alpha of unchanged comparisons are flagged."
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+// Sequential comparisons, where the target is compared again as it grows
+// and each look tests only at the alpha that an alpha-spending function
+// releases by then, so that looking often does not inflate the
+// false-positive rate: `numcmp follow`, which watches a growing target file,
+// and --sequential, which makes one look of a group-sequential design for a
+// script that collects the target in batches.
+
+use crate::input::{self, InputArgs};
+use crate::watch;
+use crate::{comparison_results, CompareArgs, Error, EstimatorResult, Verdict};
+use crate::{json, stats};
+use clap::ValueEnum;
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Spending {
+    /// Lan-DeMets O'Brien-Fleming type: little alpha early, most of it at the end
+    ObrienFleming,
+    /// Lan-DeMets Pocock type: alpha spread about evenly over the looks
+    Pocock,
+}
+
+impl Spending {
+    // The alpha spent once a fraction t of the planned values is in.
+    fn spent(self, alpha: f64, t: f64) -> f64 {
+        let t = t.min(1.0);
+        match self {
+            Spending::ObrienFleming => {
+                let z = stats::normal_quantile(1.0 - alpha / 2.0);
+                2.0 * (1.0 - stats::normal_cdf(z / t.sqrt()))
+            }
+            Spending::Pocock => alpha * (1.0 + (std::f64::consts::E - 1.0) * t).ln(),
+        }
+    }
+}
+
+// Compares the target with the baseline whenever it has grown by `every`
+// values, until an estimator changes or the target reaches `planned`
+// values, returning whether it regressed. Each look tests at the alpha
+// spent since the previous one, so by the union bound the chance of any
+// look finding a change that is not there stays within alpha.
+pub fn follow(
+    args: &CompareArgs,
+    baseline_filename: &Path,
+    target_filename: &Path,
+    input: &InputArgs,
+    planned: usize,
+    every: usize,
+) -> Result<bool, Error> {
+    if args.sequential {
+        return Err(Error::Usage(
+            "numcmp follow chooses its own looks and does not take --sequential".to_string(),
+        ));
+    }
+    if [baseline_filename, target_filename].contains(&Path::new("-")) {
+        return Err(Error::Usage(
+            "numcmp follow rereads its inputs and cannot read stdin".to_string(),
+        ));
+    }
+    // The same seed at every look, so that only the new values change the
+    // results.
+    let mut args = args.clone();
+    args.seed.get_or_insert_with(rand::random);
+    let baseline = input::read_sample(baseline_filename, input)?;
+    let paths = [target_filename];
+    let mut spent = 0.0;
+    let mut looks = 0;
+    // The number of target values at the last look.
+    let mut seen = 0;
+    loop {
+        let stamps = watch::stamps(&paths);
+        let target = match input::read_sample(target_filename, input) {
+            Ok(target) => target,
+            // Until the first values are written.
+            Err(Error::Open { .. } | Error::EmptySample(_)) => {
+                watch::wait(&paths, stamps);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let n = target.len();
+        if n < seen {
+            return Err(Error::InvalidInput(format!(
+                "{}: the target shrank from {} to {} values",
+                target_filename.display(),
+                seen,
+                n
+            )));
+        }
+        if n >= seen + every || (n >= planned && n > seen) {
+            looks += 1;
+            seen = n;
+            let total = args.spending.spent(args.alpha, n as f64 / planned as f64);
+            let level = total - spent;
+            spent = total;
+            let results = comparison_results(&args, &baseline, &target)?;
+            let changed: Vec<(String, Verdict, String)> = results
+                .iter()
+                .map(|r| (r.name.clone(), r.verdict(level), r.p_value_text()))
+                .filter(|(_, verdict, _)| {
+                    !matches!(verdict, Verdict::Unchanged | Verdict::WithinNoise)
+                })
+                .collect();
+            let outcome = if changed.is_empty() {
+                "no change".to_string()
+            } else {
+                changed
+                    .iter()
+                    .map(|(name, verdict, p)| format!("{} {} (p {})", name, verdict, p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            println!(
+                "Look {}: {} of {} target values, testing at {:.6} ({:.6} of {} spent): {}",
+                looks, n, planned, level, spent, args.alpha, outcome
+            );
+            std::io::stdout().flush()?;
+            if !changed.is_empty() {
+                return Ok(changed.iter().any(|(_, v, _)| *v == Verdict::Regressed));
+            }
+            if n >= planned {
+                println!("No change after {} target values.", n);
+                return Ok(false);
+            }
+        }
+        watch::wait(&paths, stamps);
+    }
+}
+
+// One look of a group-sequential design with equally spaced looks.
+pub struct Look {
+    pub look: usize,
+    pub looks: usize,
+    pub n: usize,
+    pub max_n: usize,
+    // The alpha this look tests at, that spent by it and earlier looks, and
+    // that of the whole design.
+    pub alpha: f64,
+    pub spent: f64,
+    pub overall: f64,
+}
+
+// The look that a target of n values makes: the first batch of
+// max_n / looks values is look 1, and so on.
+pub fn look(
+    n: usize,
+    max_n: usize,
+    looks: usize,
+    spending: Spending,
+    alpha: f64,
+) -> Result<Look, Error> {
+    if looks > max_n {
+        return Err(Error::Usage(format!(
+            "--looks {} is more than --max-n {}",
+            looks, max_n
+        )));
+    }
+    if n > max_n {
+        tracing::warn!(
+            "the target has {} values, more than --max-n {}; this is the last look",
+            n,
+            max_n
+        );
+    }
+    let batch = max_n as f64 / looks as f64;
+    let look = ((n as f64 / batch).ceil() as usize).clamp(1, looks);
+    let spent = spending.spent(alpha, look as f64 / looks as f64);
+    let before = match look {
+        1 => 0.0,
+        _ => spending.spent(alpha, (look - 1) as f64 / looks as f64),
+    };
+    Ok(Look {
+        look,
+        looks,
+        n,
+        max_n,
+        alpha: spent - before,
+        spent,
+        overall: alpha,
+    })
+}
+
+impl Look {
+    // What the driving script should do: stop at the first change, or at
+    // the last look, and otherwise collect another batch.
+    pub fn decision(&self, results: &[EstimatorResult]) -> &'static str {
+        let verdicts: Vec<Verdict> = results.iter().map(|r| r.verdict(self.alpha)).collect();
+        if verdicts.contains(&Verdict::Regressed) {
+            "stop-regressed"
+        } else if verdicts.contains(&Verdict::Improved) {
+            "stop-improved"
+        } else if self.look == self.looks {
+            "stop-unchanged"
+        } else {
+            "continue"
+        }
+    }
+
+    pub fn to_json(&self, decision: &str) -> String {
+        json::object(&[
+            ("look", self.look.to_string()),
+            ("looks", self.looks.to_string()),
+            ("n", self.n.to_string()),
+            ("max_n", self.max_n.to_string()),
+            ("alpha", json::number(self.alpha)),
+            ("alpha_spent", json::number(self.spent)),
+            ("decision", json::string(decision)),
+        ])
+    }
+}
@@ -0,0 +1,121 @@
+// Doksum's shift function: the difference target - baseline at each decile,
+// with simultaneous bootstrap confidence bands, showing which parts of the
+// distribution moved.
+
+use crate::ci;
+use crate::resample::{BootstrapMethod, Resampler};
+use crate::rng::Seed;
+use crate::sample::Sample;
+use crate::{get_quantile, Error};
+
+pub const DECILES: [f64; 9] = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9];
+
+pub struct ShiftPoint {
+    pub q: f64,
+    pub baseline: f64,
+    pub target: f64,
+    pub band: (f64, f64),
+}
+
+impl ShiftPoint {
+    pub fn difference(&self) -> f64 {
+        self.target - self.baseline
+    }
+
+    pub fn changed(&self) -> bool {
+        self.band.0 > 0.0 || self.band.1 < 0.0
+    }
+}
+
+fn differences(baseline: &Sample, target: &Sample) -> Result<Vec<f64>, Error> {
+    DECILES
+        .iter()
+        .map(|q| Ok(target.quantile(*q)? - baseline.quantile(*q)?))
+        .collect()
+}
+
+// Bands from the bootstrap distribution of the largest standardized
+// deviation across deciles, so that all of them hold together with the
+// given confidence.
+pub fn shift_function(
+    iterations: u64,
+    seed: Seed,
+    baseline: &Sample,
+    target: &Sample,
+    level: f64,
+) -> Result<Vec<ShiftPoint>, Error> {
+    let baseline_resampler = Resampler::new(BootstrapMethod::Iid, None, None, None, baseline)?;
+    let target_resampler = Resampler::new(BootstrapMethod::Iid, None, None, None, target)?;
+    let mut rng = seed.analysis_stream("shift function");
+    let mut b = Sample::default();
+    let mut t = Sample::default();
+
+    let observed = differences(baseline, target)?;
+    let mut replicates = Vec::new();
+    for _ in 0..iterations {
+        ci::draw(&mut rng, &baseline_resampler, baseline, &mut b);
+        ci::draw(&mut rng, &target_resampler, target, &mut t);
+        replicates.push(differences(&b, &t)?);
+    }
+    let n = replicates.len() as f64;
+    let std_errors: Vec<f64> = (0..DECILES.len())
+        .map(|k| {
+            let mean = replicates.iter().map(|r| r[k]).sum::<f64>() / n;
+            (replicates
+                .iter()
+                .map(|r| (r[k] - mean).powi(2))
+                .sum::<f64>()
+                / (n - 1.0))
+                .sqrt()
+        })
+        .collect();
+    let mut maxima: Vec<f64> = replicates
+        .iter()
+        .map(|r| {
+            (0..DECILES.len())
+                .filter(|k| std_errors[*k] > 0.0)
+                .map(|k| (r[k] - observed[k]).abs() / std_errors[k])
+                .fold(0.0, f64::max)
+        })
+        .collect();
+    maxima.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let critical = get_quantile(&maxima, level)?;
+
+    DECILES
+        .iter()
+        .enumerate()
+        .map(|(k, q)| {
+            let margin = critical * std_errors[k];
+            Ok(ShiftPoint {
+                q: *q,
+                baseline: baseline.quantile(*q)?,
+                target: target.quantile(*q)?,
+                band: (observed[k] - margin, observed[k] + margin),
+            })
+        })
+        .collect()
+}
+
+// Runs of adjacent deciles that changed, e.g. "0.7-0.9 (up)".
+pub fn changed_ranges(points: &[ShiftPoint]) -> Vec<String> {
+    let mut ranges = Vec::new();
+    for run in points.chunk_by(|a, b| {
+        a.changed() == b.changed() && (a.difference() > 0.0) == (b.difference() > 0.0)
+    }) {
+        let (first, last) = (&run[0], &run[run.len() - 1]);
+        if !first.changed() {
+            continue;
+        }
+        let direction = if first.difference() > 0.0 {
+            "up"
+        } else {
+            "down"
+        };
+        ranges.push(if run.len() == 1 {
+            format!("{} ({})", first.q, direction)
+        } else {
+            format!("{}-{} ({})", first.q, last.q, direction)
+        });
+    }
+    ranges
+}
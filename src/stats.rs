@@ -0,0 +1,433 @@
+// General statistical helper functions.
+
+use rand::Rng;
+
+// Complementary error function, with fractional error below 1.2e-7
+// (Numerical Recipes, erfcc).
+pub fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807
+                            + t * (-1.13520398
+                                + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    let r = t * poly.exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+pub fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / std::f64::consts::SQRT_2)
+}
+
+// Inverse of normal_cdf, by bisection, for p strictly between 0 and 1.
+pub fn normal_quantile(p: f64) -> f64 {
+    let (mut lo, mut hi) = (-40.0, 40.0);
+    for _ in 0..100 {
+        let mid = (lo + hi) / 2.0;
+        if normal_cdf(mid) < p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+// A draw from the standard normal distribution, by Box-Muller.
+pub fn standard_normal(rng: &mut impl Rng) -> f64 {
+    let (u1, u2): (f64, f64) = (1.0 - rng.gen::<f64>(), rng.gen());
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+// Mann-Kendall test for a monotonic trend in a sequence. Returns the S
+// statistic, its normal-approximation z-score, and the two-sided p-value.
+pub fn mann_kendall(xs: &[f64]) -> (i64, f64, f64) {
+    let n = xs.len();
+    let mut s: i64 = 0;
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if xs[j] > xs[i] {
+                s += 1;
+            } else if xs[j] < xs[i] {
+                s -= 1;
+            }
+        }
+    }
+
+    let nf = n as f64;
+    let mut var = nf * (nf - 1.0) * (2.0 * nf + 5.0);
+    let mut sorted = xs.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    for group in sorted.chunk_by(|a, b| a == b) {
+        let t = group.len() as f64;
+        var -= t * (t - 1.0) * (2.0 * t + 5.0);
+    }
+    var /= 18.0;
+
+    let z = if var <= 0.0 || s == 0 {
+        0.0
+    } else if s > 0 {
+        ((s - 1) as f64) / var.sqrt()
+    } else {
+        ((s + 1) as f64) / var.sqrt()
+    };
+    (s, z, 2.0 * (1.0 - normal_cdf(z.abs())))
+}
+
+// Wilson score interval for a binomial proportion of successes out of n
+// trials, for the normal quantile z (1.96 for 95%).
+pub fn wilson_interval(successes: f64, n: f64, z: f64) -> (f64, f64) {
+    if n <= 0.0 {
+        return (0.0, 1.0);
+    }
+    let p = successes / n;
+    let z2 = z * z;
+    let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let half = z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+    ((center - half).max(0.0), (center + half).min(1.0))
+}
+
+// Anderson-Darling test of normality with the mean and variance estimated
+// from the data. Returns the small-sample adjusted A*² statistic and its
+// approximate p-value (D'Agostino & Stephens, 1986), or None for fewer than
+// 8 values or no spread.
+pub fn anderson_darling(sorted: &[f64]) -> Option<(f64, f64)> {
+    let n = sorted.len();
+    if n < 8 {
+        return None;
+    }
+    let nf = n as f64;
+    let mean = sorted.iter().sum::<f64>() / nf;
+    let var = sorted.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (nf - 1.0);
+    if var <= 0.0 {
+        return None;
+    }
+    let sd = var.sqrt();
+    let mut sum = 0.0;
+    for i in 0..n {
+        let lo = normal_cdf((sorted[i] - mean) / sd).max(f64::MIN_POSITIVE);
+        let hi = normal_cdf(-(sorted[n - 1 - i] - mean) / sd).max(f64::MIN_POSITIVE);
+        sum += (2.0 * (i as f64) + 1.0) * (lo.ln() + hi.ln());
+    }
+    let a2 = -nf - sum / nf;
+    let a = a2 * (1.0 + 0.75 / nf + 2.25 / (nf * nf));
+    let p = if a >= 0.6 {
+        (1.2937 - 5.709 * a + 0.0186 * a * a).exp()
+    } else if a >= 0.34 {
+        (0.9177 - 4.279 * a - 1.38 * a * a).exp()
+    } else if a >= 0.2 {
+        1.0 - (-8.318 + 42.796 * a - 59.938 * a * a).exp()
+    } else {
+        1.0 - (-13.436 + 101.14 * a - 223.73 * a * a).exp()
+    };
+    Some((a, p.clamp(0.0, 1.0)))
+}
+
+// Natural logarithm of the gamma function (Lanczos approximation).
+pub fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut ser = 1.000000000190015;
+    for (i, c) in COEFFICIENTS.iter().enumerate() {
+        ser += c / (x + 1.0 + i as f64);
+    }
+    -tmp + (2.5066282746310005 * ser / x).ln()
+}
+
+// Continued fraction for the incomplete beta function (Numerical Recipes,
+// betacf).
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..=300 {
+        let m = m as f64;
+        let aa = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 + aa * d;
+        d = if d.abs() < TINY { 1.0 / TINY } else { 1.0 / d };
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        h *= d * c;
+        let aa = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 + aa * d;
+        d = if d.abs() < TINY { 1.0 / TINY } else { 1.0 / d };
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    h
+}
+
+// Regularized incomplete beta function I_x(a, b).
+pub fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+// Upper tail probability of the F distribution with (d1, d2) degrees of
+// freedom.
+pub fn f_survival(f: f64, d1: f64, d2: f64) -> f64 {
+    if f <= 0.0 {
+        return 1.0;
+    }
+    incomplete_beta(d2 / 2.0, d1 / 2.0, d2 / (d2 + d1 * f))
+}
+
+fn sorted_median(sorted: &[f64]) -> f64 {
+    let n = sorted.len();
+    if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+    }
+}
+
+pub struct SpreadTest {
+    // Mean absolute deviation from the median of each sample.
+    pub spreads: (f64, f64),
+    // F statistic with 1 and n - 2 degrees of freedom.
+    pub f: f64,
+    pub p_value: f64,
+}
+
+// Brown-Forsythe test for equal spread in two samples: a one-way ANOVA on
+// the absolute deviations from each sample's median. None if there is too
+// little data or no spread at all.
+pub fn brown_forsythe(a_sorted: &[f64], b_sorted: &[f64]) -> Option<SpreadTest> {
+    if a_sorted.len() < 2 || b_sorted.len() < 2 {
+        return None;
+    }
+    let deviations = |xs: &[f64]| -> Vec<f64> {
+        let median = sorted_median(xs);
+        xs.iter().map(|x| (x - median).abs()).collect()
+    };
+    let groups = [deviations(a_sorted), deviations(b_sorted)];
+    let total: f64 = groups.iter().flatten().sum();
+    let n = (a_sorted.len() + b_sorted.len()) as f64;
+    let grand_mean = total / n;
+    let mut between = 0.0;
+    let mut within = 0.0;
+    let mut means = [0.0; 2];
+    for (group, mean) in groups.iter().zip(means.iter_mut()) {
+        let k = group.len() as f64;
+        *mean = group.iter().sum::<f64>() / k;
+        between += k * (*mean - grand_mean) * (*mean - grand_mean);
+        within += group.iter().map(|z| (z - *mean) * (z - *mean)).sum::<f64>();
+    }
+    if within <= 0.0 {
+        return None;
+    }
+    let f = between / (within / (n - 2.0));
+    Some(SpreadTest {
+        spreads: (means[0], means[1]),
+        f,
+        p_value: f_survival(f, 1.0, n - 2.0),
+    })
+}
+
+// Regularized upper incomplete gamma function Q(a, x), by its series below
+// a + 1 and its continued fraction above (Numerical Recipes, gammq).
+pub fn incomplete_gamma_upper(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let front = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        for n in 1..=500 {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term.abs() < sum.abs() * 1e-14 {
+                break;
+            }
+        }
+        return (1.0 - front * sum).clamp(0.0, 1.0);
+    }
+    const TINY: f64 = 1e-300;
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / TINY;
+    let mut d = 1.0 / b;
+    let mut h = d;
+    for i in 1..=500 {
+        let an = -(i as f64) * (i as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = b + an / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < 1e-14 {
+            break;
+        }
+    }
+    (front * h).clamp(0.0, 1.0)
+}
+
+// Upper tail probability of the chi-squared distribution.
+pub fn chi_squared_survival(x: f64, df: f64) -> f64 {
+    incomplete_gamma_upper(df / 2.0, x / 2.0)
+}
+
+// Ranks of the values of all groups pooled, with ties given their average
+// rank, in the same layout as the groups; and the tie correction
+// sum(t^3 - t) over groups of t tied values.
+pub fn pooled_ranks(groups: &[&[f64]]) -> (Vec<Vec<f64>>, f64) {
+    let mut pooled: Vec<(f64, usize, usize)> = groups
+        .iter()
+        .enumerate()
+        .flat_map(|(g, xs)| xs.iter().enumerate().map(move |(i, x)| (*x, g, i)))
+        .collect();
+    pooled.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    let mut ranks: Vec<Vec<f64>> = groups.iter().map(|xs| vec![0.0; xs.len()]).collect();
+    let mut ties = 0.0;
+    let mut start = 0;
+    while start < pooled.len() {
+        let mut end = start + 1;
+        while end < pooled.len() && pooled[end].0 == pooled[start].0 {
+            end += 1;
+        }
+        let rank = (start + end + 1) as f64 / 2.0;
+        for &(_, g, i) in &pooled[start..end] {
+            ranks[g][i] = rank;
+        }
+        let t = (end - start) as f64;
+        ties += t * t * t - t;
+        start = end;
+    }
+    (ranks, ties)
+}
+
+pub struct KruskalWallis {
+    // Tie-corrected H statistic, approximately chi-squared with
+    // groups - 1 degrees of freedom.
+    pub h: f64,
+    pub df: usize,
+    pub p_value: f64,
+}
+
+// Kruskal-Wallis test of whether several groups come from the same
+// distribution. None if there are fewer than two non-empty groups or all
+// values are equal.
+pub fn kruskal_wallis(groups: &[&[f64]]) -> Option<KruskalWallis> {
+    let groups: Vec<&[f64]> = groups.iter().copied().filter(|g| !g.is_empty()).collect();
+    if groups.len() < 2 {
+        return None;
+    }
+    let n = groups.iter().map(|g| g.len()).sum::<usize>() as f64;
+    let (ranks, ties) = pooled_ranks(&groups);
+    let correction = 1.0 - ties / (n * n * n - n);
+    if correction <= 0.0 {
+        return None;
+    }
+    let sum: f64 = ranks
+        .iter()
+        .map(|r| r.iter().sum::<f64>().powi(2) / r.len() as f64)
+        .sum();
+    let h = (12.0 / (n * (n + 1.0)) * sum - 3.0 * (n + 1.0)) / correction;
+    let df = groups.len() - 1;
+    Some(KruskalWallis {
+        h,
+        df,
+        p_value: chi_squared_survival(h, df as f64),
+    })
+}
+
+// Holm-Bonferroni adjusted p-values, controlling the family-wise error rate
+// across all of `p_values`.
+pub fn holm(p_values: &[f64]) -> Vec<f64> {
+    let m = p_values.len();
+    let mut order: Vec<usize> = (0..m).collect();
+    order.sort_by(|a, b| p_values[*a].partial_cmp(&p_values[*b]).unwrap());
+    let mut adjusted = vec![0.0; m];
+    let mut running = 0.0f64;
+    for (rank, &i) in order.iter().enumerate() {
+        running = running.max(((m - rank) as f64 * p_values[i]).min(1.0));
+        adjusted[i] = running;
+    }
+    adjusted
+}
+
+// Sample autocorrelation of values in the order they were measured, at the
+// given lag; zero for values without spread.
+pub fn autocorrelation(xs: &[f64], lag: usize) -> f64 {
+    let n = xs.len();
+    if lag >= n {
+        return 0.0;
+    }
+    let mean = xs.iter().sum::<f64>() / n as f64;
+    let variance: f64 = xs.iter().map(|x| (x - mean) * (x - mean)).sum();
+    if variance <= 0.0 {
+        return 0.0;
+    }
+    let covariance: f64 = xs
+        .iter()
+        .zip(xs[lag..].iter())
+        .map(|(a, b)| (a - mean) * (b - mean))
+        .sum();
+    covariance / variance
+}
+
+// Longest lag summed for the effective sample size.
+const MAX_ESS_LAG: usize = 100;
+
+// Effective sample size n / (1 + 2 sum rho_k), summing autocorrelations from
+// lag 1 while they stay positive. At most n.
+pub fn effective_sample_size(xs: &[f64]) -> f64 {
+    let n = xs.len();
+    let mut sum = 0.0;
+    for lag in 1..n.min(MAX_ESS_LAG + 1) {
+        let rho = autocorrelation(xs, lag);
+        if rho <= 0.0 {
+            break;
+        }
+        sum += rho;
+    }
+    n as f64 / (1.0 + 2.0 * sum)
+}
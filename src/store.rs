@@ -0,0 +1,225 @@
+// A results database of labelled samples: an SQLite database with one row
+// per record of label, timestamp and the raw values as text. Weighted values
+// are written as value:weight. The database is accessed through the sqlite3
+// command-line shell, as zstd input is decompressed by the zstd command.
+
+use crate::input::{Line, NumberFormat, Observations};
+use crate::json::{self, Value};
+use crate::metadata::{self, InputInfo};
+use crate::sample::Sample;
+use crate::{sha256, Error};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS records (
+    label TEXT PRIMARY KEY,
+    recorded_at TEXT NOT NULL,
+    observations TEXT NOT NULL
+);";
+
+// Runs SQL statements against the database, returning the rows of the last
+// query.
+fn sqlite(db: &Path, sql: &str) -> Result<Vec<Value>, Error> {
+    let cannot_run = |e: std::io::Error| {
+        Error::open(
+            db,
+            std::io::Error::new(e.kind(), format!("cannot run sqlite3: {}", e)),
+        )
+    };
+    let mut child = Command::new("sqlite3")
+        .args(["-batch", "-bail", "-json", "--"])
+        .arg(db)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(cannot_run)?;
+    let mut stdin = child.stdin.take().expect("piped");
+    stdin.write_all(sql.as_bytes()).map_err(cannot_run)?;
+    drop(stdin);
+    let output = child.wait_with_output().map_err(cannot_run)?;
+    if !output.status.success() {
+        return Err(Error::InvalidInput(format!(
+            "{}: sqlite3 failed: {}",
+            db.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    match json::parse(&text) {
+        Ok(Value::Array(rows)) => Ok(rows),
+        _ => Err(Error::InvalidInput(format!(
+            "{}: unexpected output from sqlite3",
+            db.display()
+        ))),
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+pub struct Record {
+    pub label: String,
+    pub recorded_at: String,
+    pub observations: Observations,
+}
+
+impl Record {
+    fn values_text(&self) -> String {
+        let obs = &self.observations;
+        let values: Vec<String> = match &obs.weights {
+            Some(ws) => obs
+                .values
+                .iter()
+                .zip(ws.iter())
+                .map(|(x, w)| format!("{}:{}", x, w))
+                .collect(),
+            None => obs.values.iter().map(|x| x.to_string()).collect(),
+        };
+        values.join(" ")
+    }
+
+    // The line of text that identifies the record in metadata.
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}",
+            self.label,
+            self.recorded_at,
+            self.values_text()
+        )
+    }
+
+    fn parse(db: &Path, number: usize, row: &Value) -> Result<Record, Error> {
+        let field = |name: &str| match row.get_path(name) {
+            Some(Value::String(s)) => Ok(s.as_str()),
+            _ => Err(Error::InvalidInput(format!(
+                "malformed results store record {} in {}",
+                number,
+                db.display()
+            ))),
+        };
+        let (label, recorded_at, values) = (
+            field("label")?,
+            field("recorded_at")?,
+            field("observations")?,
+        );
+        let line = Line {
+            path: db,
+            number,
+            text: values,
+            format: NumberFormat::default(),
+        };
+        let mut xs = Vec::new();
+        let mut ws = Vec::new();
+        for token in values.split_whitespace() {
+            match token.split_once(':') {
+                Some((x, w)) => {
+                    xs.push(line.parse(x)?);
+                    ws.push(line.parse(w)?);
+                }
+                None => xs.push(line.parse(token)?),
+            }
+        }
+        if !ws.is_empty() && ws.len() != xs.len() {
+            return Err(Error::InvalidInput(format!(
+                "inconsistent weights in results store record {} in {}",
+                number,
+                db.display()
+            )));
+        }
+        Ok(Record {
+            label: label.to_string(),
+            recorded_at: recorded_at.to_string(),
+            observations: Observations {
+                values: xs,
+                weights: if ws.is_empty() { None } else { Some(ws) },
+                strata: None,
+            },
+        })
+    }
+
+    pub fn input_info(&self, db: &Path) -> InputInfo {
+        InputInfo {
+            role: "baseline",
+            path: format!("{}#{}", db.display(), self.label),
+            sha256: sha256::hex_digest(self.to_line().as_bytes()),
+            count: self.observations.values.len(),
+        }
+    }
+
+    pub fn sample(&self) -> Result<Sample, Error> {
+        Observations {
+            values: self.observations.values.clone(),
+            weights: self.observations.weights.clone(),
+            strata: None,
+        }
+        .into_sample()
+    }
+}
+
+pub fn load(db: &Path) -> Result<Vec<Record>, Error> {
+    // sqlite3 would create a missing database.
+    std::fs::metadata(db).map_err(|e| Error::open(db, e))?;
+    let rows = sqlite(
+        db,
+        "SELECT label, recorded_at, observations FROM records ORDER BY rowid;",
+    )?;
+    rows.iter()
+        .enumerate()
+        .map(|(i, row)| Record::parse(db, i + 1, row))
+        .collect()
+}
+
+pub fn find(db: &Path, label: &str) -> Result<Record, Error> {
+    load(db)?
+        .into_iter()
+        .find(|r| r.label == label)
+        .ok_or_else(|| {
+            Error::Usage(format!(
+                "no record labelled {:?} in {}",
+                label,
+                db.display()
+            ))
+        })
+}
+
+pub fn record(db: &Path, label: &str, observations: Observations) -> Result<(), Error> {
+    if label.is_empty() || label.contains(['\t', '\n', '\r']) {
+        return Err(Error::Usage(format!("invalid label {:?}", label)));
+    }
+    if observations.values.is_empty() {
+        return Err(Error::EmptySample(
+            "refusing to record an empty sample".to_string(),
+        ));
+    }
+
+    if db.exists() && load(db)?.iter().any(|r| r.label == label) {
+        return Err(Error::Usage(format!(
+            "label {:?} is already recorded in {}",
+            label,
+            db.display()
+        )));
+    }
+
+    let record = Record {
+        label: label.to_string(),
+        recorded_at: metadata::now_timestamp(),
+        observations,
+    };
+    sqlite(
+        db,
+        &format!(
+            "{}\nINSERT INTO records VALUES ({}, {}, {});",
+            SCHEMA,
+            quote(&record.label),
+            quote(&record.recorded_at),
+            quote(&record.values_text())
+        ),
+    )?;
+    Ok(())
+}
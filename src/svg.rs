@@ -0,0 +1,452 @@
+// Small self-contained SVG plotting, so reports need no external tooling.
+
+use std::fmt::Write;
+
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 320.0;
+const MARGIN_LEFT: f64 = 60.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const MARGIN_TOP: f64 = 30.0;
+const MARGIN_BOTTOM: f64 = 40.0;
+const TICKS: usize = 5;
+
+pub const COLORS: [&str; 6] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b",
+];
+
+pub struct Series<'a> {
+    pub label: &'a str,
+    pub values: &'a [f64],
+}
+
+pub struct Marker<'a> {
+    pub label: &'a str,
+    pub x: f64,
+}
+
+pub struct Curve<'a> {
+    pub label: &'a str,
+    pub xs: &'a [f64],
+    pub ys: &'a [f64],
+}
+
+struct Frame {
+    x0: f64,
+    x1: f64,
+    y0: f64,
+    y1: f64,
+}
+
+impl Frame {
+    fn new(x0: f64, x1: f64, y0: f64, y1: f64) -> Frame {
+        // Degenerate ranges (e.g. constant samples) still need some width.
+        let (x0, x1) = if x1 > x0 {
+            (x0, x1)
+        } else {
+            (x0 - 0.5, x1 + 0.5)
+        };
+        let (y0, y1) = if y1 > y0 { (y0, y1) } else { (y0, y0 + 1.0) };
+        Frame { x0, x1, y0, y1 }
+    }
+
+    fn px(&self, x: f64) -> f64 {
+        MARGIN_LEFT + (x - self.x0) / (self.x1 - self.x0) * (WIDTH - MARGIN_LEFT - MARGIN_RIGHT)
+    }
+
+    fn py(&self, y: f64) -> f64 {
+        HEIGHT
+            - MARGIN_BOTTOM
+            - (y - self.y0) / (self.y1 - self.y0) * (HEIGHT - MARGIN_TOP - MARGIN_BOTTOM)
+    }
+}
+
+pub fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn format_tick(x: f64) -> String {
+    let s = format!("{:.4}", x);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+fn value_range(series: &[Series]) -> Option<(f64, f64)> {
+    let mut lo = f64::INFINITY;
+    let mut hi = f64::NEG_INFINITY;
+    for s in series.iter() {
+        for x in s.values.iter().filter(|x| x.is_finite()) {
+            lo = lo.min(*x);
+            hi = hi.max(*x);
+        }
+    }
+    if lo <= hi {
+        Some((lo, hi))
+    } else {
+        None
+    }
+}
+
+fn begin(out: &mut String, title: &str, frame: &Frame) {
+    begin_frame(out, title, frame, true);
+}
+
+fn begin_without_y_axis(out: &mut String, title: &str, frame: &Frame) {
+    begin_frame(out, title, frame, false);
+}
+
+fn begin_frame(out: &mut String, title: &str, frame: &Frame, y_axis: bool) {
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="11">"#,
+        w = WIDTH,
+        h = HEIGHT
+    );
+    let _ = writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    let _ = writeln!(
+        out,
+        r#"<text x="{}" y="18" text-anchor="middle" font-size="13">{}</text>"#,
+        WIDTH / 2.0,
+        escape(title)
+    );
+    let _ = writeln!(
+        out,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="black"/>"#,
+        MARGIN_LEFT,
+        MARGIN_TOP,
+        WIDTH - MARGIN_LEFT - MARGIN_RIGHT,
+        HEIGHT - MARGIN_TOP - MARGIN_BOTTOM
+    );
+    for i in 0..=TICKS {
+        let t = (i as f64) / (TICKS as f64);
+        let x = frame.x0 + t * (frame.x1 - frame.x0);
+        let y = frame.y0 + t * (frame.y1 - frame.y0);
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+            frame.px(x),
+            HEIGHT - MARGIN_BOTTOM + 15.0,
+            format_tick(x)
+        );
+        if !y_axis {
+            continue;
+        }
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"#,
+            MARGIN_LEFT - 5.0,
+            frame.py(y) + 4.0,
+            format_tick(y)
+        );
+    }
+}
+
+fn legend(out: &mut String, labels: &[(&str, &str)]) {
+    for (i, (label, color)) in labels.iter().enumerate() {
+        let y = MARGIN_TOP + 15.0 + 15.0 * (i as f64);
+        let x = WIDTH - MARGIN_RIGHT - 120.0;
+        let _ = writeln!(
+            out,
+            r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="2"/>"#,
+            x,
+            y - 4.0,
+            x + 15.0,
+            y - 4.0,
+            color
+        );
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}">{}</text>"#,
+            x + 20.0,
+            y,
+            escape(label)
+        );
+    }
+}
+
+fn end(out: &mut String) {
+    let _ = writeln!(out, "</svg>");
+}
+
+fn histogram_densities(values: &[f64], lo: f64, width: f64, bins: usize) -> Vec<f64> {
+    let mut counts = vec![0usize; bins];
+    let mut n = 0usize;
+    for x in values.iter().filter(|x| x.is_finite()) {
+        let i = (((x - lo) / width) as usize).min(bins - 1);
+        counts[i] += 1;
+        n += 1;
+    }
+    counts
+        .into_iter()
+        .map(|c| (c as f64) / ((n.max(1) as f64) * width))
+        .collect()
+}
+
+// Overlaid density-normalized histograms on a shared set of bins, with
+// optional vertical marker lines.
+pub fn histogram(title: &str, series: &[Series], markers: &[Marker], bins: usize) -> String {
+    let mut out = String::new();
+    let (mut lo, mut hi) = value_range(series).unwrap_or((0.0, 1.0));
+    for m in markers.iter().filter(|m| m.x.is_finite()) {
+        lo = lo.min(m.x);
+        hi = hi.max(m.x);
+    }
+    if hi <= lo {
+        lo -= 0.5;
+        hi += 0.5;
+    }
+    let width = (hi - lo) / (bins as f64);
+    let densities: Vec<Vec<f64>> = series
+        .iter()
+        .map(|s| histogram_densities(s.values, lo, width, bins))
+        .collect();
+    let ymax = densities
+        .iter()
+        .flat_map(|d| d.iter())
+        .fold(0.0f64, |a, b| a.max(*b));
+    let frame = Frame::new(lo, hi, 0.0, ymax * 1.05);
+
+    begin(&mut out, title, &frame);
+    for (si, d) in densities.iter().enumerate() {
+        let color = COLORS[si % COLORS.len()];
+        for (i, density) in d.iter().enumerate() {
+            if *density <= 0.0 {
+                continue;
+            }
+            let x0 = frame.px(lo + width * (i as f64));
+            let x1 = frame.px(lo + width * ((i + 1) as f64));
+            let y = frame.py(*density);
+            let _ = writeln!(
+                out,
+                r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}" fill-opacity="0.35" stroke="{}" stroke-width="0.5"/>"#,
+                x0,
+                y,
+                (x1 - x0).max(0.5),
+                frame.py(0.0) - y,
+                color,
+                color
+            );
+        }
+    }
+    let mut labels: Vec<(&str, &str)> = series
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.label, COLORS[i % COLORS.len()]))
+        .collect();
+    for (mi, m) in markers.iter().enumerate() {
+        let color = COLORS[(series.len() + mi) % COLORS.len()];
+        let x = frame.px(m.x);
+        let _ = writeln!(
+            out,
+            r#"<line x1="{:.1}" y1="{}" x2="{:.1}" y2="{}" stroke="{}" stroke-width="2" stroke-dasharray="4,3"/>"#,
+            x,
+            MARGIN_TOP,
+            x,
+            HEIGHT - MARGIN_BOTTOM,
+            color
+        );
+        labels.push((m.label, color));
+    }
+    legend(&mut out, &labels);
+    end(&mut out);
+    out
+}
+
+// Empirical cumulative distribution functions drawn as step lines.
+pub fn ecdf(title: &str, series: &[Series]) -> String {
+    let mut out = String::new();
+    let (lo, hi) = value_range(series).unwrap_or((0.0, 1.0));
+    let frame = Frame::new(lo, hi, 0.0, 1.0);
+
+    begin(&mut out, title, &frame);
+    for (si, s) in series.iter().enumerate() {
+        let mut sorted: Vec<f64> = s.values.iter().copied().filter(|x| x.is_finite()).collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        if sorted.is_empty() {
+            continue;
+        }
+        let n = sorted.len() as f64;
+        let mut points = format!("{:.1},{:.1}", frame.px(frame.x0), frame.py(0.0));
+        for (i, x) in sorted.iter().enumerate() {
+            let _ = write!(
+                points,
+                " {:.1},{:.1} {:.1},{:.1}",
+                frame.px(*x),
+                frame.py((i as f64) / n),
+                frame.px(*x),
+                frame.py(((i + 1) as f64) / n)
+            );
+        }
+        let _ = write!(points, " {:.1},{:.1}", frame.px(frame.x1), frame.py(1.0));
+        let _ = writeln!(
+            out,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"/>"#,
+            points,
+            COLORS[si % COLORS.len()]
+        );
+    }
+    let labels: Vec<(&str, &str)> = series
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.label, COLORS[i % COLORS.len()]))
+        .collect();
+    legend(&mut out, &labels);
+    end(&mut out);
+    out
+}
+
+// Curves such as density estimates drawn as lines over a shared x range.
+pub fn lines(title: &str, curves: &[Curve]) -> String {
+    let mut out = String::new();
+    let points = || curves.iter().flat_map(|c| c.xs.iter().zip(c.ys.iter()));
+    let (lo, hi) = points().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (x, _)| {
+        (lo.min(*x), hi.max(*x))
+    });
+    let ymax = points().fold(0.0f64, |a, (_, y)| a.max(*y));
+    let (lo, hi) = if lo.is_finite() { (lo, hi) } else { (0.0, 1.0) };
+    let frame = Frame::new(lo, hi, 0.0, ymax * 1.05);
+
+    begin(&mut out, title, &frame);
+    for (ci, curve) in curves.iter().enumerate() {
+        let points: Vec<String> = curve
+            .xs
+            .iter()
+            .zip(curve.ys.iter())
+            .map(|(x, y)| format!("{:.1},{:.1}", frame.px(*x), frame.py(*y)))
+            .collect();
+        let _ = writeln!(
+            out,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"/>"#,
+            points.join(" "),
+            COLORS[ci % COLORS.len()]
+        );
+    }
+    let labels: Vec<(&str, &str)> = curves
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (c.label, COLORS[i % COLORS.len()]))
+        .collect();
+    legend(&mut out, &labels);
+    end(&mut out);
+    out
+}
+
+struct BoxStats {
+    whisker_lo: f64,
+    q1: f64,
+    median: f64,
+    q3: f64,
+    whisker_hi: f64,
+    outliers: Vec<f64>,
+}
+
+// Tukey box plot statistics: whiskers extend to the most extreme points
+// within 1.5 IQR of the quartiles.
+fn box_stats(sorted: &[f64]) -> Option<BoxStats> {
+    let q1 = crate::get_quantile(sorted, 0.25).ok()?;
+    let median = crate::get_quantile(sorted, 0.5).ok()?;
+    let q3 = crate::get_quantile(sorted, 0.75).ok()?;
+    let iqr = q3 - q1;
+    let (fence_lo, fence_hi) = (q1 - 1.5 * iqr, q3 + 1.5 * iqr);
+    let inside = sorted.iter().filter(|x| **x >= fence_lo && **x <= fence_hi);
+    let whisker_lo = inside.clone().fold(q1, |a, b| a.min(*b));
+    let whisker_hi = inside.fold(q3, |a, b| a.max(*b));
+    let outliers = sorted
+        .iter()
+        .copied()
+        .filter(|x| *x < fence_lo || *x > fence_hi)
+        .collect();
+    Some(BoxStats {
+        whisker_lo,
+        q1,
+        median,
+        q3,
+        whisker_hi,
+        outliers,
+    })
+}
+
+// Horizontal box plots, one row per series, on a shared value axis.
+pub fn boxplot(title: &str, series: &[Series]) -> String {
+    let mut out = String::new();
+    let (lo, hi) = value_range(series).unwrap_or((0.0, 1.0));
+    let frame = Frame::new(lo, hi, 0.0, 1.0);
+    let rows = series.len().max(1) as f64;
+    let row_height = (HEIGHT - MARGIN_TOP - MARGIN_BOTTOM) / rows;
+
+    begin_without_y_axis(&mut out, title, &frame);
+    for (si, s) in series.iter().enumerate() {
+        let mut sorted: Vec<f64> = s.values.iter().copied().filter(|x| x.is_finite()).collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let stats = match box_stats(&sorted) {
+            Some(stats) => stats,
+            None => continue,
+        };
+        let color = COLORS[si % COLORS.len()];
+        let cy = MARGIN_TOP + row_height * ((si as f64) + 0.5);
+        let half = (row_height * 0.25).min(30.0);
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"#,
+            MARGIN_LEFT - 5.0,
+            cy + 4.0,
+            escape(s.label)
+        );
+        let _ = writeln!(
+            out,
+            r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}"/>"#,
+            frame.px(stats.whisker_lo),
+            cy,
+            frame.px(stats.whisker_hi),
+            cy,
+            color
+        );
+        for x in [stats.whisker_lo, stats.whisker_hi] {
+            let _ = writeln!(
+                out,
+                r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}"/>"#,
+                frame.px(x),
+                cy - half / 2.0,
+                frame.px(x),
+                cy + half / 2.0,
+                color
+            );
+        }
+        let _ = writeln!(
+            out,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}" fill-opacity="0.35" stroke="{}"/>"#,
+            frame.px(stats.q1),
+            cy - half,
+            (frame.px(stats.q3) - frame.px(stats.q1)).max(0.5),
+            2.0 * half,
+            color,
+            color
+        );
+        let _ = writeln!(
+            out,
+            r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="2"/>"#,
+            frame.px(stats.median),
+            cy - half,
+            frame.px(stats.median),
+            cy + half,
+            color
+        );
+        for x in stats.outliers.iter() {
+            let _ = writeln!(
+                out,
+                r#"<circle cx="{:.1}" cy="{:.1}" r="2" fill="none" stroke="{}"/>"#,
+                frame.px(*x),
+                cy,
+                color
+            );
+        }
+    }
+    end(&mut out);
+    out
+}
@@ -0,0 +1,126 @@
+// Plain-text tables for the human-readable output: aligned columns on a
+// wide enough terminal, one line per row on a narrow one, and tab-separated
+// values when piped.
+
+use clap::ValueEnum;
+use std::io::IsTerminal;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Layout {
+    /// Aligned tables on a terminal, tab-separated values otherwise
+    Auto,
+    /// Aligned tables, or one line per row if they do not fit the terminal
+    Table,
+    /// Tab-separated values
+    Plain,
+}
+
+// The width of the terminal on stdout, if it is one.
+fn terminal_width() -> Option<usize> {
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
+    }
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    #[cfg(unix)]
+    {
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        // SAFETY: TIOCGWINSZ only writes a winsize into the pointer.
+        let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+        if ok && size.ws_col > 0 {
+            return Some(size.ws_col as usize);
+        }
+    }
+    None
+}
+
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+const COLUMN_GAP: &str = "  ";
+
+impl Table {
+    pub fn new(header: &[&str]) -> Table {
+        Table {
+            header: header.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    pub fn render(&self, layout: Layout) -> String {
+        match layout {
+            Layout::Auto if !std::io::stdout().is_terminal() => self.render_plain(),
+            Layout::Plain => self.render_plain(),
+            Layout::Auto | Layout::Table => {
+                let aligned = self.render_aligned();
+                let width = aligned.lines().map(|l| l.chars().count()).max();
+                match terminal_width() {
+                    Some(columns) if width > Some(columns) => self.render_compact(),
+                    _ => aligned,
+                }
+            }
+        }
+    }
+
+    fn render_plain(&self) -> String {
+        let mut out = String::new();
+        for row in std::iter::once(&self.header).chain(self.rows.iter()) {
+            out.push_str(&row.join("\t"));
+            out.push('\n');
+        }
+        out
+    }
+
+    // Each row on its own line as "name: header value, header value".
+    fn render_compact(&self) -> String {
+        let mut out = String::new();
+        for row in self.rows.iter() {
+            let fields: Vec<String> = self.header[1..]
+                .iter()
+                .zip(row[1..].iter())
+                .map(|(h, cell)| format!("{} {}", h, cell))
+                .collect();
+            out.push_str(&format!("{}: {}\n", row[0], fields.join(", ")));
+        }
+        out
+    }
+
+    // The first column holds names and is left-aligned; the others hold
+    // numbers and are right-aligned.
+    fn render_aligned(&self) -> String {
+        let mut widths: Vec<usize> = self.header.iter().map(|h| h.chars().count()).collect();
+        for row in self.rows.iter() {
+            for (i, cell) in row.iter().enumerate() {
+                if i >= widths.len() {
+                    widths.push(0);
+                }
+                widths[i] = widths[i].max(cell.chars().count());
+            }
+        }
+        let mut out = String::new();
+        for row in std::iter::once(&self.header).chain(self.rows.iter()) {
+            let mut line = String::new();
+            for (i, cell) in row.iter().enumerate() {
+                let pad = " ".repeat(widths[i] - cell.chars().count());
+                if i == 0 {
+                    line.push_str(cell);
+                    line.push_str(&pad);
+                } else {
+                    line.push_str(COLUMN_GAP);
+                    line.push_str(&pad);
+                    line.push_str(cell);
+                }
+            }
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+}
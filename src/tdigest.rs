@@ -0,0 +1,93 @@
+// A merging t-digest (Dunning and Ertl) for --sketch tdigest: values are
+// summarized as centroids, small in the tails and larger in the middle, so
+// quantiles stay accurate where they matter most while memory is bounded by
+// the compression however many values are read.
+
+pub struct TDigest {
+    compression: f64,
+    // Centroids as (mean, weight), ordered by mean.
+    centroids: Vec<(f64, f64)>,
+    // Values not merged into the centroids yet.
+    buffer: Vec<(f64, f64)>,
+    // The extremes, which are kept exactly.
+    min: f64,
+    max: f64,
+}
+
+impl TDigest {
+    pub fn new(compression: f64) -> TDigest {
+        TDigest {
+            compression,
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    pub fn record(&mut self, x: f64, weight: f64) {
+        self.buffer.push((x, weight));
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+        if self.buffer.len() as f64 >= 10.0 * self.compression {
+            self.merge();
+        }
+    }
+
+    // The k1 scale function: centroids may span at most one unit of k.
+    fn k(&self, q: f64) -> f64 {
+        self.compression / (2.0 * std::f64::consts::PI) * (2.0 * q - 1.0).asin()
+    }
+
+    fn q(&self, k: f64) -> f64 {
+        let k = k.min(self.compression / 4.0);
+        ((2.0 * std::f64::consts::PI * k / self.compression).sin() + 1.0) / 2.0
+    }
+
+    fn merge(&mut self) {
+        let mut points = std::mem::take(&mut self.buffer);
+        points.append(&mut self.centroids);
+        points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let total: f64 = points.iter().map(|p| p.1).sum();
+        let mut points = points.into_iter();
+        let Some(mut current) = points.next() else {
+            return;
+        };
+        // The weight before the current centroid, and the most it may reach.
+        let mut before = 0.0;
+        let mut limit = total * self.q(self.k(0.0) + 1.0);
+        for (x, w) in points {
+            if before + current.1 + w <= limit {
+                let merged = current.1 + w;
+                current.0 += (x - current.0) * w / merged;
+                current.1 = merged;
+            } else {
+                before += current.1;
+                self.centroids.push(current);
+                limit = total * self.q(self.k(before / total) + 1.0);
+                current = (x, w);
+            }
+        }
+        self.centroids.push(current);
+    }
+
+    // The centroids as weighted values, with the extremes split off the
+    // outermost centroids as values of weight one, so that the minimum and
+    // maximum are exact and the mean is unchanged.
+    pub fn centroids(mut self) -> Vec<(f64, f64)> {
+        self.merge();
+        let mut centroids = self.centroids;
+        if let Some(first) = centroids.first_mut().filter(|c| c.1 > 1.0) {
+            *first = (
+                (first.0 * first.1 - self.min) / (first.1 - 1.0),
+                first.1 - 1.0,
+            );
+            centroids.insert(0, (self.min, 1.0));
+        }
+        if let Some(last) = centroids.last_mut().filter(|c| c.1 > 1.0) {
+            *last = ((last.0 * last.1 - self.max) / (last.1 - 1.0), last.1 - 1.0);
+            centroids.push((self.max, 1.0));
+        }
+        centroids
+    }
+}
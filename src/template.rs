@@ -0,0 +1,356 @@
+// Rendering results through user-provided templates in a subset of the
+// Handlebars syntax: {{path}}, {{#each}}, {{#if}}, {{#unless}}, {{#eq}} with
+// {{else}}, comments, and the {{fixed path digits}} helper. Values are
+// inserted as they are, without HTML escaping, since templates are mostly
+// for chat messages and wiki markup.
+
+use crate::json::Value;
+use crate::Error;
+use std::path::Path;
+
+// A message describing what is wrong with the template.
+type Result<T> = std::result::Result<T, String>;
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    // A value or helper call: the words inside the braces.
+    Expression(Vec<String>),
+    Block {
+        helper: String,
+        args: Vec<String>,
+        body: Vec<Node>,
+        inverse: Vec<Node>,
+    },
+}
+
+// The words of a tag; double-quoted strings stay together, with quotes.
+fn words(tag: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut rest = tag.trim();
+    while !rest.is_empty() {
+        let end = match rest.strip_prefix('"') {
+            Some(quoted) => quoted
+                .find('"')
+                .map(|i| i + 2)
+                .ok_or_else(|| format!("unterminated string in {{{{{}}}}}", tag))?,
+            None => rest.find(char::is_whitespace).unwrap_or(rest.len()),
+        };
+        words.push(rest[..end].to_string());
+        rest = rest[end..].trim_start();
+    }
+    Ok(words)
+}
+
+enum Tag {
+    Open(String, Vec<String>),
+    Else,
+    Close(String),
+    Expression(Vec<String>),
+}
+
+fn tag(content: &str) -> Result<Tag> {
+    let content = content.trim();
+    if let Some(open) = content.strip_prefix('#') {
+        let mut words = words(open)?;
+        if words.is_empty() {
+            return Err("block without a helper name".to_string());
+        }
+        let helper = words.remove(0);
+        if !["each", "if", "unless", "eq"].contains(&helper.as_str()) {
+            return Err(format!("unknown block helper {:?}", helper));
+        }
+        return Ok(Tag::Open(helper, words));
+    }
+    if let Some(close) = content.strip_prefix('/') {
+        return Ok(Tag::Close(close.trim().to_string()));
+    }
+    if content == "else" {
+        return Ok(Tag::Else);
+    }
+    let words = words(content)?;
+    match words.first().map(String::as_str) {
+        None => Err("empty {{}}".to_string()),
+        Some("fixed") if words.len() == 3 => Ok(Tag::Expression(words)),
+        Some(helper) if words.len() > 1 => Err(format!("unknown helper {:?}", helper)),
+        _ => Ok(Tag::Expression(words)),
+    }
+}
+
+// Splits the template into text and tags. Block tags alone on their line
+// take the line with them, so that they do not leave blank lines behind.
+fn tokens(template: &str) -> Result<Vec<(String, Option<Tag>)>> {
+    let mut tokens = Vec::new();
+    let mut rest = template;
+    // Whether the text before the next tag starts at the start of a line.
+    let mut line_start = true;
+    while let Some(start) = rest.find("{{") {
+        let mut text = rest[..start].to_string();
+        let after = &rest[start + 2..];
+        let (content, end) = if let Some(comment) = after.strip_prefix("!--") {
+            let end = comment.find("--}}").ok_or("unterminated {{!-- comment")?;
+            (None, start + 5 + end + 4)
+        } else {
+            let end = after.find("}}").ok_or("unterminated {{")?;
+            if after.starts_with('!') {
+                (None, start + 2 + end + 2)
+            } else {
+                (Some(&after[..end]), start + 2 + end + 2)
+            }
+        };
+        let tag = content.map(tag).transpose()?;
+        let mut next = &rest[end..];
+        let indent = text.rfind('\n').map_or(0, |i| i + 1);
+        let alone = !matches!(tag, Some(Tag::Expression(_)))
+            && text[indent..].trim().is_empty()
+            && (indent > 0 || line_start)
+            && next[..next.find('\n').unwrap_or(next.len())]
+                .trim()
+                .is_empty();
+        line_start = alone;
+        if alone {
+            text.truncate(indent);
+            next = next.find('\n').map_or("", |i| &next[i + 1..]);
+        }
+        tokens.push((text, tag));
+        rest = next;
+    }
+    tokens.push((rest.to_string(), None));
+    Ok(tokens)
+}
+
+// Builds the tree of nodes up to the closing tag of `open`, if any.
+fn nodes(
+    tokens: &mut std::vec::IntoIter<(String, Option<Tag>)>,
+    open: Option<&str>,
+) -> Result<(Vec<Node>, Vec<Node>)> {
+    let mut body = Vec::new();
+    let mut inverse = Vec::new();
+    let mut in_inverse = false;
+    while let Some((text, tag)) = tokens.next() {
+        let current = if in_inverse { &mut inverse } else { &mut body };
+        if !text.is_empty() {
+            current.push(Node::Text(text));
+        }
+        match tag {
+            None => {}
+            Some(Tag::Expression(words)) => current.push(Node::Expression(words)),
+            Some(Tag::Open(helper, args)) => {
+                let (block_body, block_inverse) = nodes(tokens, Some(&helper))?;
+                current.push(Node::Block {
+                    helper,
+                    args,
+                    body: block_body,
+                    inverse: block_inverse,
+                });
+            }
+            Some(Tag::Else) if open.is_some() && !in_inverse => in_inverse = true,
+            Some(Tag::Else) => return Err("{{else}} outside a block".to_string()),
+            Some(Tag::Close(name)) => {
+                return match open {
+                    Some(open) if open == name => Ok((body, inverse)),
+                    Some(open) => Err(format!("{{{{/{}}}}} closes {{{{#{}}}}}", name, open)),
+                    None => Err(format!("{{{{/{}}}}} without a matching block", name)),
+                };
+            }
+        }
+    }
+    match open {
+        Some(open) => Err(format!("{{{{#{}}}}} is not closed", open)),
+        None => Ok((body, inverse)),
+    }
+}
+
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+pub fn parse(template: &str) -> Result<Template> {
+    let (nodes, _) = nodes(&mut tokens(template)?.into_iter(), None)?;
+    Ok(Template { nodes })
+}
+
+// One level of context: the value, and where it is within an {{#each}}.
+struct Frame<'a> {
+    value: &'a Value,
+    index: Option<(usize, usize)>,
+    key: Option<&'a str>,
+}
+
+fn truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) | Some(Value::Bool(false)) => false,
+        Some(Value::Number(x)) => *x != 0.0,
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(Value::Array(items)) => !items.is_empty(),
+        _ => true,
+    }
+}
+
+fn text(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::Bool(b)) => b.to_string(),
+        Some(Value::Number(x)) => x.to_string(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|v| text(Some(v)))
+            .collect::<Vec<_>>()
+            .join(","),
+        Some(Value::Object(_)) => "[object]".to_string(),
+    }
+}
+
+// Looks up `path` in the innermost frame, or in outer ones for each "../"
+// prefix and in the outermost for "@root.".
+fn lookup<'a>(frames: &[Frame<'a>], path: &str) -> Option<&'a Value> {
+    let mut depth = frames.len() - 1;
+    let mut path = path;
+    if let Some(rest) = path.strip_prefix("@root") {
+        depth = 0;
+        path = rest.strip_prefix('.').unwrap_or(rest);
+    }
+    while let Some(rest) = path.strip_prefix("../") {
+        depth = depth.saturating_sub(1);
+        path = rest;
+    }
+    let frame = &frames[depth];
+    let path = match path.strip_prefix("this") {
+        Some(rest) if rest.is_empty() || rest.starts_with('.') => {
+            rest.strip_prefix('.').unwrap_or(rest)
+        }
+        _ => path,
+    };
+    if path.is_empty() {
+        return Some(frame.value);
+    }
+    frame.value.get_path(path)
+}
+
+// The value of a helper argument: a string literal, a number, a variable
+// from the {{#each}} being iterated over, or a path.
+fn argument<'a>(frames: &[Frame<'a>], word: &str) -> Option<Value> {
+    if let Some(s) = word.strip_prefix('"').and_then(|w| w.strip_suffix('"')) {
+        return Some(Value::String(s.to_string()));
+    }
+    if let Ok(x) = word.parse::<f64>() {
+        return Some(Value::Number(x));
+    }
+    let frame = frames.last()?;
+    match word {
+        "@index" => frame.index.map(|(i, _)| Value::Number(i as f64)),
+        "@first" => frame.index.map(|(i, _)| Value::Bool(i == 0)),
+        "@last" => frame.index.map(|(i, n)| Value::Bool(i + 1 == n)),
+        "@key" => frame.key.map(|k| Value::String(k.to_string())),
+        _ => lookup(frames, word).cloned(),
+    }
+}
+
+fn expression(frames: &[Frame], words: &[String]) -> Result<String> {
+    match words {
+        [path] => Ok(text(argument(frames, path).as_ref())),
+        [helper, path, digits] if helper == "fixed" => {
+            let digits: usize = digits
+                .parse()
+                .map_err(|_| format!("{{{{fixed}}}} needs a number of digits, not {}", digits))?;
+            Ok(match argument(frames, path) {
+                Some(Value::Number(x)) => format!("{:.*}", digits, x),
+                value => text(value.as_ref()),
+            })
+        }
+        [helper, ..] => Err(format!("unknown helper {:?}", helper)),
+        [] => unreachable!("tags have at least one word"),
+    }
+}
+
+fn render_nodes<'a>(nodes: &[Node], frames: &mut Vec<Frame<'a>>, out: &mut String) -> Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Expression(words) => out.push_str(&expression(frames, words)?),
+            Node::Block {
+                helper,
+                args,
+                body,
+                inverse,
+            } => {
+                let arg = |i: usize| -> Result<Option<Value>> {
+                    let word = args
+                        .get(i)
+                        .ok_or_else(|| format!("{{{{#{}}}}} needs an argument", helper))?;
+                    Ok(argument(frames, word))
+                };
+                let condition = match helper.as_str() {
+                    "if" => truthy(arg(0)?.as_ref()),
+                    "unless" => !truthy(arg(0)?.as_ref()),
+                    "eq" => arg(0)? == arg(1)?,
+                    "each" => {
+                        let path = args.first().ok_or("{{#each}} needs an argument")?;
+                        let Some(value) = lookup(frames, path) else {
+                            render_nodes(inverse, frames, out)?;
+                            continue;
+                        };
+                        let items: Vec<(Option<&str>, &Value)> = match value {
+                            Value::Array(items) => items.iter().map(|v| (None, v)).collect(),
+                            Value::Object(fields) => {
+                                fields.iter().map(|(k, v)| (Some(k.as_str()), v)).collect()
+                            }
+                            _ => Vec::new(),
+                        };
+                        if items.is_empty() {
+                            render_nodes(inverse, frames, out)?;
+                        }
+                        for (i, (key, value)) in items.iter().enumerate() {
+                            frames.push(Frame {
+                                value,
+                                index: Some((i, items.len())),
+                                key: *key,
+                            });
+                            let rendered = render_nodes(body, frames, out);
+                            frames.pop();
+                            rendered?;
+                        }
+                        continue;
+                    }
+                    _ => return Err(format!("unknown block helper {:?}", helper)),
+                };
+                if condition {
+                    render_nodes(body, frames, out)?;
+                } else {
+                    render_nodes(inverse, frames, out)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Template {
+    pub fn render(&self, context: &Value) -> Result<String> {
+        let mut out = String::new();
+        let mut frames = vec![Frame {
+            value: context,
+            index: None,
+            key: None,
+        }];
+        render_nodes(&self.nodes, &mut frames, &mut out)?;
+        Ok(out)
+    }
+}
+
+// Reads and parses the template in the file at `path`.
+pub fn load(path: &Path) -> std::result::Result<Template, Error> {
+    let template = std::fs::read_to_string(path).map_err(|e| Error::open(path, e))?;
+    parse(&template).map_err(|msg| Error::Usage(format!("{}: {}", path.display(), msg)))
+}
+
+impl Template {
+    // Renders the template with a JSON document, such as the --format json
+    // output, as its context.
+    pub fn render_json(&self, context: &str) -> std::result::Result<String, Error> {
+        let context = crate::json::parse(context).map_err(Error::InvalidInput)?;
+        self.render(&context)
+            .map_err(|msg| Error::Usage(format!("template: {}", msg)))
+    }
+}
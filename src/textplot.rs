@@ -0,0 +1,273 @@
+// Plots drawn with characters, for the terminal.
+
+use crate::ecdfband::EcdfBand;
+use crate::kde;
+use crate::metadata::Labels;
+use crate::sample::Sample;
+use crate::units::format_number;
+use crate::Error;
+
+const WIDTH: usize = 60;
+const HEIGHT: usize = 20;
+
+// A character grid with the value range of each axis.
+struct Canvas {
+    cells: Vec<Vec<char>>,
+    x: (f64, f64),
+    y: (f64, f64),
+}
+
+impl Canvas {
+    fn new(x: (f64, f64), y: (f64, f64)) -> Canvas {
+        Canvas {
+            cells: vec![vec![' '; WIDTH]; HEIGHT],
+            x,
+            y,
+        }
+    }
+
+    fn cell(&self, x: f64, y: f64) -> Option<(usize, usize)> {
+        let fx = if self.x.1 > self.x.0 {
+            (x - self.x.0) / (self.x.1 - self.x.0)
+        } else {
+            0.5
+        };
+        let fy = if self.y.1 > self.y.0 {
+            (y - self.y.0) / (self.y.1 - self.y.0)
+        } else {
+            0.5
+        };
+        if !(0.0..=1.0).contains(&fx) || !(0.0..=1.0).contains(&fy) {
+            return None;
+        }
+        let col = ((fx * (WIDTH - 1) as f64).round() as usize).min(WIDTH - 1);
+        let row = HEIGHT - 1 - ((fy * (HEIGHT - 1) as f64).round() as usize).min(HEIGHT - 1);
+        Some((row, col))
+    }
+
+    // Marks a point; `overwrite` decides whether it replaces what is there.
+    fn plot(&mut self, x: f64, y: f64, c: char, overwrite: bool) {
+        if let Some((row, col)) = self.cell(x, y) {
+            if overwrite || self.cells[row][col] == ' ' {
+                self.cells[row][col] = c;
+            }
+        }
+    }
+
+    fn render(&self, x_label: &str, y_label: &str) -> String {
+        let top = format_number(self.y.1);
+        let bottom = format_number(self.y.0);
+        let margin = top.len().max(bottom.len());
+        let mut out = format!("{}\n", y_label);
+        for (i, row) in self.cells.iter().enumerate() {
+            let label = match i {
+                0 => &top,
+                i if i == HEIGHT - 1 => &bottom,
+                _ => "",
+            };
+            let line: String = row.iter().collect();
+            out.push_str(&format!("{:>margin$} |{}\n", label, line.trim_end()));
+        }
+        out.push_str(&format!("{:>margin$} +{}\n", "", "-".repeat(WIDTH)));
+        let left = format_number(self.x.0);
+        let right = format_number(self.x.1);
+        let gap = (WIDTH + 1).saturating_sub(left.len() + right.len());
+        out.push_str(&format!(
+            "{:>margin$}  {}{}{}\n",
+            "",
+            left,
+            " ".repeat(gap),
+            right
+        ));
+        let label = format!("{:>margin$}  {:^WIDTH$}", "", x_label);
+        out.push_str(label.trim_end());
+        out.push('\n');
+        out
+    }
+}
+
+// Quantiles of the target against the same quantiles of the baseline, with
+// the line y = x for reference: points above it are where the target is
+// higher. Both axes share one range.
+pub fn qq(baseline: &Sample, target: &Sample, labels: &Labels) -> Result<String, Error> {
+    const POINTS: usize = 100;
+    let lo = baseline.quantile(0.0)?.min(target.quantile(0.0)?);
+    let hi = baseline.quantile(1.0)?.max(target.quantile(1.0)?);
+    let mut canvas = Canvas::new((lo, hi), (lo, hi));
+    for col in 0..WIDTH {
+        let x = lo + (hi - lo) * (col as f64) / ((WIDTH - 1) as f64);
+        canvas.plot(x, x, '.', false);
+    }
+    for i in 0..POINTS {
+        let q = (i as f64 + 0.5) / (POINTS as f64);
+        canvas.plot(baseline.quantile(q)?, target.quantile(q)?, '*', true);
+    }
+    Ok(canvas.render(
+        &format!("{} quantiles", labels.baseline),
+        &format!("{} quantiles", labels.target),
+    ))
+}
+
+// Kernel density estimates of both samples on shared axes: 'b' marks the
+// baseline, 't' the target and '#' where the two curves meet.
+pub fn kde(baseline: &Sample, target: &Sample, bandwidth: Option<f64>, labels: &Labels) -> String {
+    let bandwidths = [
+        bandwidth.unwrap_or_else(|| kde::silverman_bandwidth(baseline)),
+        bandwidth.unwrap_or_else(|| kde::silverman_bandwidth(target)),
+    ];
+    let xs = kde::grid(&[(baseline, bandwidths[0]), (target, bandwidths[1])], WIDTH);
+    if xs.is_empty() {
+        return String::new();
+    }
+    let densities = [
+        kde::density(baseline, bandwidths[0], &xs),
+        kde::density(target, bandwidths[1], &xs),
+    ];
+    let ymax = densities
+        .iter()
+        .flat_map(|d| d.iter())
+        .fold(0.0f64, |a, b| a.max(*b));
+    let mut canvas = Canvas::new((xs[0], xs[WIDTH - 1]), (0.0, ymax));
+    for (density, c) in densities.iter().zip(['b', 't']) {
+        for (x, y) in xs.iter().zip(density.iter()) {
+            if let Some((row, col)) = canvas.cell(*x, *y) {
+                let cell = &mut canvas.cells[row][col];
+                *cell = if *cell == ' ' || *cell == c { c } else { '#' };
+            }
+        }
+    }
+    let mut out = canvas.render("value", "density");
+    out.push_str(&format!(
+        "b = {} (bandwidth {}), t = {} (bandwidth {})\n",
+        labels.baseline,
+        format_number(bandwidths[0]),
+        labels.target,
+        format_number(bandwidths[1])
+    ));
+    out
+}
+
+// The total weight of the values of a sample for which `keep` holds.
+fn weight_where(sample: &Sample, keep: impl Fn(f64) -> bool) -> f64 {
+    match &sample.weights {
+        Some(ws) => sample
+            .values
+            .iter()
+            .zip(ws.iter())
+            .filter(|(x, _)| keep(**x))
+            .map(|(_, w)| w)
+            .sum(),
+        None => sample.values.iter().filter(|x| keep(**x)).count() as f64,
+    }
+}
+
+// The shared value range of both samples.
+fn value_range(baseline: &Sample, target: &Sample) -> Result<(f64, f64), Error> {
+    Ok((
+        baseline.quantile(0.0)?.min(target.quantile(0.0)?),
+        baseline.quantile(1.0)?.max(target.quantile(1.0)?),
+    ))
+}
+
+// Marks the curves of both samples, one point per column: 'b' for the
+// baseline, 't' for the target and '#' where they meet. With `fill`, each
+// column is filled down to the axis, giving bars.
+fn overlay(canvas: &mut Canvas, curves: [&[(f64, f64)]; 2], fill: bool) {
+    for (curve, c) in curves.iter().zip(['b', 't']) {
+        for (x, y) in curve.iter() {
+            let Some((top, col)) = canvas.cell(*x, *y) else {
+                continue;
+            };
+            if fill && *y <= 0.0 {
+                continue;
+            }
+            let bottom = if fill { HEIGHT - 1 } else { top };
+            for row in top..=bottom {
+                let cell = &mut canvas.cells[row][col];
+                *cell = if *cell == ' ' || *cell == c { c } else { '#' };
+            }
+        }
+    }
+}
+
+// The key to the marks made by overlay().
+fn legend(labels: &Labels) -> String {
+    format!("b = {}, t = {}, # = both\n", labels.baseline, labels.target)
+}
+
+// Histograms of both samples on shared axes, as the fraction of each
+// sample's weight in each of the equal-width bins that span the columns.
+pub fn histogram(baseline: &Sample, target: &Sample, labels: &Labels) -> Result<String, Error> {
+    let (lo, hi) = value_range(baseline, target)?;
+    let width = (hi - lo) / WIDTH as f64;
+    let bins = |sample: &Sample| -> Vec<(f64, f64)> {
+        let total = sample.total_weight();
+        (0..WIDTH)
+            .map(|i| {
+                let start = lo + width * i as f64;
+                let last = i == WIDTH - 1;
+                let weight = weight_where(sample, |x| {
+                    x >= start && (x < start + width || (last && x <= hi))
+                });
+                (start + width / 2.0, weight / total)
+            })
+            .collect()
+    };
+    let curves = [bins(baseline), bins(target)];
+    let ymax = curves
+        .iter()
+        .flat_map(|c| c.iter().map(|p| p.1))
+        .fold(0.0f64, f64::max);
+    let mut canvas = Canvas::new((lo + width / 2.0, hi - width / 2.0), (0.0, ymax));
+    overlay(&mut canvas, [&curves[0], &curves[1]], true);
+    let mut out = canvas.render("value", "fraction of values");
+    out.push_str(&legend(labels));
+    Ok(out)
+}
+
+// Empirical cumulative distribution functions of both samples.
+pub fn ecdf(baseline: &Sample, target: &Sample, labels: &Labels) -> Result<String, Error> {
+    let (lo, hi) = value_range(baseline, target)?;
+    let cdf = |sample: &Sample| -> Vec<(f64, f64)> {
+        let total = sample.total_weight();
+        (0..WIDTH)
+            .map(|i| {
+                let x = lo + (hi - lo) * i as f64 / (WIDTH - 1) as f64;
+                (x, weight_where(sample, |v| v <= x) / total)
+            })
+            .collect()
+    };
+    let curves = [cdf(baseline), cdf(target)];
+    let mut canvas = Canvas::new((lo, hi), (0.0, 1.0));
+    overlay(&mut canvas, [&curves[0], &curves[1]], false);
+    let mut out = canvas.render("value", "fraction of values at most x");
+    out.push_str(&legend(labels));
+    Ok(out)
+}
+
+// The baseline ECDF with its simultaneous band, marked with '.', and the
+// target ECDF, with '!' where it leaves the band.
+pub fn ecdf_band(band: &EcdfBand, labels: &Labels) -> String {
+    let (lo, hi) = (band.xs[0], band.xs[band.xs.len() - 1]);
+    let mut canvas = Canvas::new((lo, hi), (0.0, 1.0));
+    for col in 0..WIDTH {
+        let x = lo + (hi - lo) * col as f64 / (WIDTH - 1) as f64;
+        // The ECDFs are steps that change only at the values in xs.
+        let i = band.xs.partition_point(|v| *v <= x).max(1) - 1;
+        let (band_lo, band_hi) = band.band(i);
+        canvas.plot(x, band_lo, '.', true);
+        canvas.plot(x, band_hi, '.', true);
+        canvas.plot(x, band.baseline[i], 'b', true);
+        let mark = match band.outside(i) {
+            Some(_) => '!',
+            None => 't',
+        };
+        canvas.plot(x, band.target[i], mark, true);
+    }
+    let mut out = canvas.render("value", "fraction of values at most x");
+    out.push_str(&format!(
+        "b = {}, . = its band, t = {}, ! = {} outside the band\n",
+        labels.baseline, labels.target, labels.target
+    ));
+    out
+}
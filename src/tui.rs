@@ -0,0 +1,274 @@
+// An interactive terminal view of a comparison, with tabs for the
+// summaries, overlaid histograms and ECDFs, and the comparison table. The
+// significance level can be changed and estimators toggled without
+// simulating again.
+
+use crate::metadata::Labels;
+use crate::sample::Sample;
+use crate::table::{Layout, Table};
+use crate::textplot;
+use crate::units::{format_change, Units};
+use crate::{Error, EstimatorResult, Verdict};
+use std::io::{IsTerminal, Read, Write};
+
+const TABS: [&str; 4] = ["Summary", "Histogram", "ECDF", "Comparison"];
+
+// Significance levels offered by + and -, besides --alpha.
+const ALPHAS: [f64; 6] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.2];
+
+enum Key {
+    Left,
+    Right,
+    Up,
+    Down,
+    Char(char),
+    Quit,
+}
+
+fn parse_key(bytes: &[u8]) -> Option<Key> {
+    match bytes {
+        // Escape on its own, or Ctrl-C.
+        [27] | [3] => Some(Key::Quit),
+        [27, b'[', b'A'] => Some(Key::Up),
+        [27, b'[', b'B'] => Some(Key::Down),
+        [27, b'[', b'C'] | [b'\t'] => Some(Key::Right),
+        [27, b'[', b'D'] => Some(Key::Left),
+        [c] => Some(Key::Char(*c as char)),
+        _ => None,
+    }
+}
+
+struct View<'a> {
+    baseline: &'a Sample,
+    target: &'a Sample,
+    results: &'a [EstimatorResult],
+    units: &'a Units,
+    labels: &'a Labels,
+    alphas: Vec<f64>,
+    alpha: usize,
+    tab: usize,
+    selected: usize,
+    enabled: Vec<bool>,
+}
+
+impl View<'_> {
+    fn alpha(&self) -> f64 {
+        self.alphas[self.alpha]
+    }
+
+    // Handles a key, returning false to quit.
+    fn key(&mut self, key: Key) -> bool {
+        let n = self.results.len();
+        match key {
+            Key::Quit | Key::Char('q') => return false,
+            Key::Right => self.tab = (self.tab + 1) % TABS.len(),
+            Key::Left => self.tab = (self.tab + TABS.len() - 1) % TABS.len(),
+            Key::Char(c @ '1'..='4') => self.tab = c as usize - '1' as usize,
+            Key::Up | Key::Char('k') => self.selected = self.selected.saturating_sub(1),
+            Key::Down | Key::Char('j') => self.selected = (self.selected + 1).min(n - 1),
+            Key::Char(' ') => self.enabled[self.selected] ^= true,
+            Key::Char('+') => self.alpha = (self.alpha + 1).min(self.alphas.len() - 1),
+            Key::Char('-') => self.alpha = self.alpha.saturating_sub(1),
+            Key::Char(_) => {}
+        }
+        true
+    }
+
+    fn summary(&self) -> String {
+        let mut table = Table::new(&["", &self.labels.baseline, &self.labels.target, "delta"]);
+        table.row(vec![
+            "Count".to_string(),
+            self.baseline.len().to_string(),
+            self.target.len().to_string(),
+            format!(
+                "{:+}",
+                self.target.len() as i64 - self.baseline.len() as i64
+            ),
+        ]);
+        for (result, _) in self.results.iter().zip(&self.enabled).filter(|(_, e)| **e) {
+            table.row(vec![
+                result.name.clone(),
+                self.units.format(result.full_baseline_estimator),
+                self.units.format(result.target_estimator),
+                self.units
+                    .format(result.target_estimator - result.full_baseline_estimator),
+            ]);
+        }
+        table.render(Layout::Table)
+    }
+
+    fn comparison(&self) -> String {
+        let mut table = Table::new(&[
+            "",
+            "estimator",
+            &self.labels.baseline,
+            &self.labels.target,
+            "change",
+            "p-value",
+            "verdict",
+        ]);
+        for (i, (result, enabled)) in self.results.iter().zip(&self.enabled).enumerate() {
+            let mark = match (i == self.selected, enabled) {
+                (true, true) => "> [x]",
+                (true, false) => "> [ ]",
+                (false, true) => "  [x]",
+                (false, false) => "  [ ]",
+            };
+            table.row(vec![
+                mark.to_string(),
+                result.name.clone(),
+                self.units.format(result.full_baseline_estimator),
+                self.units.format(result.target_estimator),
+                format_change(result.full_baseline_estimator, result.target_estimator),
+                match result.p_value() {
+                    0.0 => result.p_value_text(),
+                    p => format!("{:.4}", p),
+                },
+                if *enabled {
+                    result.verdict(self.alpha()).to_string()
+                } else {
+                    "-".to_string()
+                },
+            ]);
+        }
+        let regressed = self
+            .results
+            .iter()
+            .zip(&self.enabled)
+            .filter(|(r, e)| **e && matches!(r.verdict(self.alpha()), Verdict::Regressed))
+            .count();
+        format!(
+            "{}\n{} of the selected estimators regressed at alpha = {}\n",
+            table.render(Layout::Table),
+            regressed,
+            self.alpha()
+        )
+    }
+
+    fn render(&self) -> Result<String, Error> {
+        let mut out = String::new();
+        for (i, name) in TABS.iter().enumerate() {
+            if i == self.tab {
+                out.push_str(&format!("\x1b[7m {} {} \x1b[0m ", i + 1, name));
+            } else {
+                out.push_str(&format!(" {} {}  ", i + 1, name));
+            }
+        }
+        out.push_str("\n\n");
+        out.push_str(&match self.tab {
+            0 => self.summary(),
+            1 => textplot::histogram(self.baseline, self.target, self.labels)?,
+            2 => textplot::ecdf(self.baseline, self.target, self.labels)?,
+            _ => self.comparison(),
+        });
+        out.push_str(&format!(
+            "\n\x1b[2mtabs: \u{2190} \u{2192} or 1-4   estimators: \u{2191} \u{2193}, space toggles   alpha = {}: + -   quit: q\x1b[0m\n",
+            self.alpha()
+        ));
+        Ok(out)
+    }
+}
+
+// The terminal in raw mode on the alternate screen, restored when dropped.
+#[cfg(unix)]
+struct RawTerminal {
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl RawTerminal {
+    fn enter() -> Result<RawTerminal, Error> {
+        let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+        // SAFETY: tcgetattr and tcsetattr only read and write the termios
+        // structure passed to them.
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut termios) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let original = termios;
+        unsafe { libc::cfmakeraw(&mut termios) };
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        print!("\x1b[?1049h\x1b[?25l");
+        Ok(RawTerminal { original })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = std::io::stdout().flush();
+        // SAFETY: restores the settings read in enter().
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+}
+
+// Fails unless stdin and stdout are a terminal, before any work is done.
+pub fn check_terminal() -> Result<(), Error> {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return Err(Error::Usage(
+            "numcmp tui needs a terminal on stdin and stdout".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn run(
+    baseline: &Sample,
+    target: &Sample,
+    results: &[EstimatorResult],
+    alpha: f64,
+    units: &Units,
+    labels: &Labels,
+) -> Result<(), Error> {
+    let mut alphas = ALPHAS.to_vec();
+    alphas.push(alpha);
+    alphas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    alphas.dedup();
+    let mut view = View {
+        baseline,
+        target,
+        results,
+        units,
+        labels,
+        alpha: alphas
+            .iter()
+            .position(|a| *a == alpha)
+            .expect("added above"),
+        alphas,
+        tab: 0,
+        selected: 0,
+        enabled: vec![true; results.len()],
+    };
+    interact(&mut view)
+}
+
+#[cfg(unix)]
+fn interact(view: &mut View) -> Result<(), Error> {
+    let _terminal = RawTerminal::enter()?;
+    let mut stdout = std::io::stdout();
+    let mut buf = [0u8; 16];
+    loop {
+        // Raw mode does not turn newlines into carriage return and newline.
+        let frame = view.render()?.replace('\n', "\r\n");
+        write!(stdout, "\x1b[H\x1b[2J{}", frame)?;
+        stdout.flush()?;
+        let n = std::io::stdin().read(&mut buf)?;
+        if n == 0 {
+            return Ok(());
+        }
+        if let Some(key) = parse_key(&buf[..n]) {
+            if !view.key(key) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn interact(_view: &mut View) -> Result<(), Error> {
+    Err(Error::Usage(
+        "numcmp tui is only supported on Unix terminals".to_string(),
+    ))
+}
@@ -0,0 +1,243 @@
+// Labelling values with their unit, and presenting timings in a
+// human-friendly unit.
+
+use crate::Error;
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum DisplayUnit {
+    /// Pick a unit for each value by its magnitude
+    Auto,
+    Ns,
+    Us,
+    Ms,
+    S,
+}
+
+const UNITS: &[(DisplayUnit, &str, f64)] = &[
+    (DisplayUnit::Ns, "ns", 1e-9),
+    (DisplayUnit::Us, "µs", 1e-6),
+    (DisplayUnit::Ms, "ms", 1e-3),
+    (DisplayUnit::S, "s", 1.0),
+];
+
+// Digits printed in text output, from --precision or --sig-figs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Precision {
+    Decimals(usize),
+    Significant(usize),
+}
+
+fn format_fixed(x: f64, precision: Precision) -> String {
+    if !x.is_finite() {
+        return x.to_string();
+    }
+    let decimals = match precision {
+        Precision::Decimals(decimals) => decimals,
+        Precision::Significant(_) if x == 0.0 => 0,
+        Precision::Significant(digits) => {
+            (digits as i32 - 1 - x.abs().log10().floor() as i32).max(0) as usize
+        }
+    };
+    format!("{:.*}", decimals, x)
+}
+
+// Formats a value given in seconds with `format` applied to the number in
+// the chosen unit, e.g. "1.24 ms".
+fn format_duration(seconds: f64, unit: DisplayUnit, format: impl Fn(f64) -> String) -> String {
+    let (_, name, scale) = match unit {
+        DisplayUnit::Auto if seconds == 0.0 || !seconds.is_finite() => UNITS[UNITS.len() - 1],
+        DisplayUnit::Auto => *UNITS
+            .iter()
+            .rev()
+            .find(|(_, _, scale)| seconds.abs() >= *scale)
+            .unwrap_or(&UNITS[0]),
+        unit => *UNITS
+            .iter()
+            .find(|(u, _, _)| *u == unit)
+            .expect("all units listed"),
+    };
+    format!("{} {}", format(seconds / scale), name)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum Notation {
+    /// Plain decimal numbers
+    #[default]
+    Fixed,
+    /// One digit before the point and a power of ten, e.g. 1.234e-5
+    Scientific,
+    /// Powers of ten that are multiples of three, e.g. 12.34e-6
+    Engineering,
+}
+
+// Formats x with the given number of significant digits as a mantissa and
+// a power of ten, which is a multiple of three for engineering notation.
+fn format_exponential(x: f64, significant: usize, engineering: bool, trim: bool) -> String {
+    if !x.is_finite() || x == 0.0 {
+        return x.to_string();
+    }
+    let s = format!("{:.*e}", significant.saturating_sub(1), x);
+    let (mantissa, exponent) = s.split_once('e').expect("formatted with {:e}");
+    let mut exponent: i32 = exponent.parse().expect("formatted with {:e}");
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let mut digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let mut point = 1;
+    if engineering {
+        let shift = exponent.rem_euclid(3);
+        point += shift as usize;
+        exponent -= shift;
+        while digits.len() < point {
+            digits.push('0');
+        }
+    }
+    let (whole, fraction) = digits.split_at(point);
+    let fraction = if trim {
+        fraction.trim_end_matches('0')
+    } else {
+        fraction
+    };
+    if fraction.is_empty() {
+        format!("{}{}e{}", sign, whole, exponent)
+    } else {
+        format!("{}{}.{}e{}", sign, whole, fraction, exponent)
+    }
+}
+
+// Six significant digits keep table columns narrow while hiding
+// floating-point noise such as 28.549999999999997.
+const SIGNIFICANT_DIGITS: i32 = 6;
+
+pub fn format_number(x: f64) -> String {
+    if !x.is_finite() || x == 0.0 {
+        return x.to_string();
+    }
+    let decimals = (SIGNIFICANT_DIGITS - 1 - x.abs().log10().floor() as i32).max(0) as usize;
+    let s = format!("{:.*}", decimals, x);
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        s
+    }
+}
+
+// The relative change from baseline to target, e.g. "+2.50%", or "n/a"
+// when the baseline is zero and there is nothing to be relative to.
+pub fn format_change(baseline: f64, target: f64) -> String {
+    if baseline == 0.0 {
+        return "n/a".to_string();
+    }
+    format!("{:+.2}%", 100.0 * (target - baseline) / baseline)
+}
+
+// Whole numbers as integers and anything else, such as a mean or a
+// midpoint, to one decimal place.
+fn format_discrete(x: f64) -> String {
+    let s = format!("{:.1}", x);
+    match s.strip_suffix(".0") {
+        Some(whole) if whole != "-0" => whole.to_string(),
+        Some(_) => "0".to_string(),
+        None => s,
+    }
+}
+
+// How values are labelled in tables, plots and reports.
+#[derive(Debug, Clone, Default)]
+pub struct Units {
+    // The unit of the input values (--unit), purely presentational.
+    pub unit: Option<String>,
+    display: Option<DisplayUnit>,
+    // Seconds per input unit, for --display-unit.
+    seconds: f64,
+    pub precision: Option<Precision>,
+    pub notation: Notation,
+    // For --discrete: without a precision, at most one decimal place.
+    pub discrete: bool,
+}
+
+impl Units {
+    pub fn new(unit: Option<&str>, display: Option<DisplayUnit>) -> Result<Units, Error> {
+        let seconds = match (unit, display) {
+            (Some(unit), Some(_)) => match unit {
+                "min" => 60.0,
+                "us" => 1e-6,
+                unit => UNITS
+                    .iter()
+                    .find(|(_, name, _)| *name == unit)
+                    .map(|(_, _, scale)| *scale)
+                    .ok_or_else(|| {
+                        Error::Usage(format!(
+                            "--display-unit needs a time unit such as ms or s, not {:?}",
+                            unit
+                        ))
+                    })?,
+            },
+            _ => 1.0,
+        };
+        Ok(Units {
+            unit: unit.map(|u| u.to_string()),
+            display,
+            seconds,
+            precision: None,
+            notation: Notation::Fixed,
+            discrete: false,
+        })
+    }
+
+    // Formats a value for text output: as a duration with --display-unit,
+    // otherwise as the number followed by the unit, if any.
+    pub fn format(&self, x: f64) -> String {
+        match (self.display, &self.unit) {
+            (Some(display), _) => {
+                format_duration(x * self.seconds, display, |x| match self.notation {
+                    Notation::Fixed => {
+                        let precision = self.precision.unwrap_or(Precision::Significant(3));
+                        if x == 0.0 && self.precision.is_none() {
+                            x.to_string()
+                        } else {
+                            format_fixed(x, precision)
+                        }
+                    }
+                    _ => self.number(x),
+                })
+            }
+            (None, Some(unit)) => format!("{} {}", self.number(x), unit),
+            (None, None) => self.number(x),
+        }
+    }
+
+    // Formats a number that is not in the unit of the input values.
+    pub fn number(&self, x: f64) -> String {
+        let engineering = self.notation == Notation::Engineering;
+        match (self.notation, self.precision) {
+            (Notation::Fixed, Some(precision)) => format_fixed(x, precision),
+            (Notation::Fixed, None) if self.discrete => format_discrete(x),
+            (Notation::Fixed, None) => format_number(x),
+            (_, None) => format_exponential(x, SIGNIFICANT_DIGITS as usize, engineering, true),
+            (_, Some(Precision::Significant(digits))) => {
+                format_exponential(x, digits, engineering, false)
+            }
+            (_, Some(Precision::Decimals(decimals))) => {
+                // Digits before the point: one, or up to three in
+                // engineering notation.
+                let whole = if engineering && x != 0.0 && x.is_finite() {
+                    1 + (x.abs().log10().floor() as i32).rem_euclid(3) as usize
+                } else {
+                    1
+                };
+                format_exponential(x, whole + decimals, engineering, false)
+            }
+        }
+    }
+
+    // A heading or plot title for raw values, e.g. "baseline (ms)".
+    pub fn label(&self, title: &str) -> String {
+        match &self.unit {
+            Some(unit) => format!("{} ({})", title, unit),
+            None => title.to_string(),
+        }
+    }
+}
@@ -0,0 +1,1351 @@
+// Estimator plugins compiled to WebAssembly, run by a small interpreter for
+// the core (MVP) instruction set plus sign extension, saturating truncation
+// and bulk memory copy/fill.
+//
+// Plugin ABI: the module exports a memory named "memory" and a function
+// `estimate(ptr: i32, len: i32) -> f64`. The sample's values are written to
+// memory as `len` little-endian f64s, sorted ascending, starting at `ptr`.
+// Modules may not import anything.
+
+use crate::estimator::Estimator;
+use crate::sample::Sample;
+use crate::Error;
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+use std::rc::Rc;
+
+const PAGE_SIZE: usize = 65536;
+const MAX_PAGES: u32 = 65536;
+const MAX_CALL_DEPTH: usize = 1000;
+
+const I32: u8 = 0x7f;
+const F64: u8 = 0x7c;
+
+fn trap(msg: &str) -> Error {
+    Error::Plugin(format!("trap: {}", msg))
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::Plugin(format!("invalid wasm module: {}", msg))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, Error> {
+        let b = *self
+            .bytes
+            .get(self.pos)
+            .ok_or_else(|| invalid("unexpected end"))?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.pos + n > self.bytes.len() {
+            return Err(invalid("unexpected end"));
+        }
+        let s = &self.bytes[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(s)
+    }
+
+    fn leb(&mut self, bits: u32, signed: bool) -> Result<u64, Error> {
+        let mut result: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let b = self.byte()?;
+            if shift >= 64 {
+                return Err(invalid("integer too long"));
+            }
+            result |= ((b & 0x7f) as u64) << shift;
+            shift += 7;
+            if b & 0x80 == 0 {
+                if signed && shift < 64 && b & 0x40 != 0 {
+                    result |= !0u64 << shift;
+                }
+                break;
+            }
+        }
+        if bits < 64 && !signed {
+            result &= (1u64 << bits) - 1;
+        }
+        Ok(result)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(self.leb(32, false)? as u32)
+    }
+
+    fn name(&mut self) -> Result<String, Error> {
+        let n = self.u32()? as usize;
+        String::from_utf8(self.bytes(n)?.to_vec()).map_err(|_| invalid("name is not UTF-8"))
+    }
+}
+
+struct FuncType {
+    params: Vec<u8>,
+    results: Vec<u8>,
+}
+
+// Number of values a block takes and leaves on the stack.
+#[derive(Clone, Copy)]
+struct BlockType {
+    params: usize,
+    results: usize,
+}
+
+enum Instr {
+    Unreachable,
+    Nop,
+    Block {
+        ty: BlockType,
+        end: usize,
+    },
+    Loop {
+        ty: BlockType,
+    },
+    If {
+        ty: BlockType,
+        else_at: Option<usize>,
+        end: usize,
+    },
+    Else {
+        end: usize,
+    },
+    End,
+    Br(u32),
+    BrIf(u32),
+    BrTable(Vec<u32>, u32),
+    Return,
+    Call(u32),
+    Drop,
+    Select,
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+    Load(u8, u32),
+    Store(u8, u32),
+    MemorySize,
+    MemoryGrow,
+    Const(u64),
+    Numeric(u8),
+    TruncSat(u32),
+    MemoryCopy,
+    MemoryFill,
+}
+
+struct Func {
+    ty: u32,
+    locals: Vec<u8>,
+    code: Vec<Instr>,
+}
+
+fn block_type(r: &mut Reader, types: &[FuncType]) -> Result<BlockType, Error> {
+    let b = r
+        .bytes
+        .get(r.pos)
+        .copied()
+        .ok_or_else(|| invalid("unexpected end"))?;
+    if b == 0x40 {
+        r.pos += 1;
+        return Ok(BlockType {
+            params: 0,
+            results: 0,
+        });
+    }
+    if matches!(b, 0x7c..=0x7f) {
+        r.pos += 1;
+        return Ok(BlockType {
+            params: 0,
+            results: 1,
+        });
+    }
+    let idx = r.leb(33, true)? as usize;
+    let ty = types.get(idx).ok_or_else(|| invalid("bad block type"))?;
+    Ok(BlockType {
+        params: ty.params.len(),
+        results: ty.results.len(),
+    })
+}
+
+fn decode_body(r: &mut Reader, types: &[FuncType]) -> Result<Vec<Instr>, Error> {
+    let mut code = Vec::new();
+    // Open blocks: index of the block instruction and of its else, if any.
+    let mut open: Vec<(usize, Option<usize>)> = Vec::new();
+    loop {
+        let op = r.byte()?;
+        let instr = match op {
+            0x00 => Instr::Unreachable,
+            0x01 => Instr::Nop,
+            0x02..=0x04 => {
+                let ty = block_type(r, types)?;
+                open.push((code.len(), None));
+                match op {
+                    0x02 => Instr::Block { ty, end: 0 },
+                    0x03 => Instr::Loop { ty },
+                    _ => Instr::If {
+                        ty,
+                        else_at: None,
+                        end: 0,
+                    },
+                }
+            }
+            0x05 => {
+                let top = open.last_mut().ok_or_else(|| invalid("else outside if"))?;
+                top.1 = Some(code.len());
+                Instr::Else { end: 0 }
+            }
+            0x0b => {
+                let end = code.len();
+                match open.pop() {
+                    None => {
+                        code.push(Instr::End);
+                        return Ok(code);
+                    }
+                    Some((start, else_at)) => {
+                        match &mut code[start] {
+                            Instr::Block { end: e, .. } => *e = end,
+                            Instr::If {
+                                end: e,
+                                else_at: el,
+                                ..
+                            } => {
+                                *e = end;
+                                *el = else_at;
+                            }
+                            _ => (),
+                        }
+                        if let Some(i) = else_at {
+                            code[i] = Instr::Else { end };
+                        }
+                    }
+                }
+                Instr::End
+            }
+            0x0c => Instr::Br(r.u32()?),
+            0x0d => Instr::BrIf(r.u32()?),
+            0x0e => {
+                let n = r.u32()?;
+                let targets = (0..n)
+                    .map(|_| r.u32())
+                    .collect::<Result<Vec<u32>, Error>>()?;
+                Instr::BrTable(targets, r.u32()?)
+            }
+            0x0f => Instr::Return,
+            0x10 => Instr::Call(r.u32()?),
+            0x1a => Instr::Drop,
+            0x1b => Instr::Select,
+            0x1c => {
+                let n = r.u32()?;
+                r.bytes(n as usize)?;
+                Instr::Select
+            }
+            0x20 => Instr::LocalGet(r.u32()?),
+            0x21 => Instr::LocalSet(r.u32()?),
+            0x22 => Instr::LocalTee(r.u32()?),
+            0x23 => Instr::GlobalGet(r.u32()?),
+            0x24 => Instr::GlobalSet(r.u32()?),
+            0x28..=0x3e => {
+                r.u32()?;
+                let offset = r.u32()?;
+                if op <= 0x35 {
+                    Instr::Load(op, offset)
+                } else {
+                    Instr::Store(op, offset)
+                }
+            }
+            0x3f => {
+                r.byte()?;
+                Instr::MemorySize
+            }
+            0x40 => {
+                r.byte()?;
+                Instr::MemoryGrow
+            }
+            0x41 => Instr::Const(r.leb(32, true)? as u32 as u64),
+            0x42 => Instr::Const(r.leb(64, true)?),
+            0x43 => {
+                let b = r.bytes(4)?;
+                Instr::Const(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as u64)
+            }
+            0x44 => {
+                let b = r.bytes(8)?;
+                let mut a = [0u8; 8];
+                a.copy_from_slice(b);
+                Instr::Const(u64::from_le_bytes(a))
+            }
+            0x45..=0xc4 => Instr::Numeric(op),
+            0xfc => match r.u32()? {
+                n @ 0..=7 => Instr::TruncSat(n),
+                10 => {
+                    r.bytes(2)?;
+                    Instr::MemoryCopy
+                }
+                11 => {
+                    r.byte()?;
+                    Instr::MemoryFill
+                }
+                n => return Err(invalid(&format!("unsupported instruction 0xfc {}", n))),
+            },
+            _ => return Err(invalid(&format!("unsupported instruction 0x{:02x}", op))),
+        };
+        code.push(instr);
+    }
+}
+
+// Evaluates a constant initializer expression.
+fn const_expr(r: &mut Reader) -> Result<u64, Error> {
+    let value = match r.byte()? {
+        0x41 => r.leb(32, true)? as u32 as u64,
+        0x42 => r.leb(64, true)?,
+        0x43 => {
+            let b = r.bytes(4)?;
+            u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as u64
+        }
+        0x44 => {
+            let mut a = [0u8; 8];
+            a.copy_from_slice(r.bytes(8)?);
+            u64::from_le_bytes(a)
+        }
+        _ => return Err(invalid("unsupported constant expression")),
+    };
+    if r.byte()? != 0x0b {
+        return Err(invalid("unsupported constant expression"));
+    }
+    Ok(value)
+}
+
+struct Label {
+    arity: usize,
+    target: usize,
+    height: usize,
+}
+
+pub struct Instance {
+    types: Vec<FuncType>,
+    funcs: Rc<Vec<Func>>,
+    globals: Vec<u64>,
+    memory: Vec<u8>,
+    memory_max: u32,
+    exports: Vec<(String, u8, u32)>,
+    depth: usize,
+}
+
+fn f32v(v: u64) -> f32 {
+    f32::from_bits(v as u32)
+}
+
+fn f64v(v: u64) -> f64 {
+    f64::from_bits(v)
+}
+
+fn from_f32(x: f32) -> u64 {
+    x.to_bits() as u64
+}
+
+fn from_bool(b: bool) -> u64 {
+    b as u64
+}
+
+// NaN-propagating min/max that order -0 below +0, as wasm requires.
+fn fmin(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        f64::NAN
+    } else if a == b {
+        if a.is_sign_negative() {
+            a
+        } else {
+            b
+        }
+    } else {
+        a.min(b)
+    }
+}
+
+fn fmax(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        f64::NAN
+    } else if a == b {
+        if a.is_sign_positive() {
+            a
+        } else {
+            b
+        }
+    } else {
+        a.max(b)
+    }
+}
+
+// Truncates toward zero, trapping if the result does not fit in [lo, hi].
+fn trunc(x: f64, lo: f64, hi: f64) -> Result<f64, Error> {
+    if x.is_nan() {
+        return Err(trap("invalid conversion to integer"));
+    }
+    let t = x.trunc();
+    if t < lo || t > hi {
+        return Err(trap("integer overflow"));
+    }
+    Ok(t)
+}
+
+impl Instance {
+    pub fn load(bytes: &[u8]) -> Result<Instance, Error> {
+        let mut r = Reader { bytes, pos: 0 };
+        if r.bytes(4)? != b"\0asm" || r.bytes(4)? != [1, 0, 0, 0] {
+            return Err(invalid("not a wasm binary (version 1)"));
+        }
+        let mut types = Vec::new();
+        let mut func_types = Vec::new();
+        let mut funcs = Vec::new();
+        let mut globals = Vec::new();
+        let mut memory_pages = 0;
+        let mut memory_max = MAX_PAGES;
+        let mut exports = Vec::new();
+        let mut data: Vec<(usize, &[u8])> = Vec::new();
+        let mut start = None;
+
+        while r.pos < bytes.len() {
+            let id = r.byte()?;
+            let size = r.u32()? as usize;
+            let mut s = Reader {
+                bytes: r.bytes(size)?,
+                pos: 0,
+            };
+            match id {
+                1 => {
+                    for _ in 0..s.u32()? {
+                        if s.byte()? != 0x60 {
+                            return Err(invalid("bad function type"));
+                        }
+                        let n = s.u32()? as usize;
+                        let params = s.bytes(n)?.to_vec();
+                        let n = s.u32()? as usize;
+                        let results = s.bytes(n)?.to_vec();
+                        types.push(FuncType { params, results });
+                    }
+                }
+                2 if s.u32()? > 0 => {
+                    return Err(invalid("plugins may not import anything"));
+                }
+                3 => {
+                    for _ in 0..s.u32()? {
+                        func_types.push(s.u32()?);
+                    }
+                }
+                5 if s.u32()? > 0 => {
+                    let flags = s.byte()?;
+                    memory_pages = s.u32()?;
+                    if flags & 1 != 0 {
+                        memory_max = s.u32()?;
+                    }
+                }
+                6 => {
+                    for _ in 0..s.u32()? {
+                        s.bytes(2)?;
+                        globals.push(const_expr(&mut s)?);
+                    }
+                }
+                7 => {
+                    for _ in 0..s.u32()? {
+                        let name = s.name()?;
+                        let kind = s.byte()?;
+                        exports.push((name, kind, s.u32()?));
+                    }
+                }
+                8 => start = Some(s.u32()?),
+                10 => {
+                    if s.u32()? as usize != func_types.len() {
+                        return Err(invalid("function and code sections disagree"));
+                    }
+                    for (i, ty) in func_types.iter().enumerate() {
+                        let _size = s.u32()?;
+                        let mut locals = types
+                            .get(*ty as usize)
+                            .ok_or_else(|| invalid("bad type index"))?
+                            .params
+                            .clone();
+                        for _ in 0..s.u32()? {
+                            let n = s.u32()? as usize;
+                            let t = s.byte()?;
+                            if locals.len() + n > 50000 {
+                                return Err(invalid("too many locals"));
+                            }
+                            locals.extend(std::iter::repeat_n(t, n));
+                        }
+                        let code = decode_body(&mut s, &types)?;
+                        funcs.push(Func {
+                            ty: func_types[i],
+                            locals,
+                            code,
+                        });
+                    }
+                }
+                11 => {
+                    for _ in 0..s.u32()? {
+                        match s.u32()? {
+                            0 => {
+                                let offset = const_expr(&mut s)? as u32 as usize;
+                                let n = s.u32()? as usize;
+                                data.push((offset, s.bytes(n)?));
+                            }
+                            1 => {
+                                let n = s.u32()? as usize;
+                                s.bytes(n)?;
+                            }
+                            _ => return Err(invalid("unsupported data segment")),
+                        }
+                    }
+                }
+                // Custom sections, tables, elements and data counts are not
+                // needed without call_indirect and memory.init.
+                _ => (),
+            }
+        }
+
+        if funcs.len() != func_types.len() {
+            return Err(invalid("function and code sections disagree"));
+        }
+        let mut memory = vec![0u8; memory_pages as usize * PAGE_SIZE];
+        for (offset, bytes) in data {
+            memory
+                .get_mut(offset..offset + bytes.len())
+                .ok_or_else(|| invalid("data segment out of bounds"))?
+                .copy_from_slice(bytes);
+        }
+        let mut instance = Instance {
+            types,
+            funcs: Rc::new(funcs),
+            globals,
+            memory,
+            memory_max,
+            exports,
+            depth: 0,
+        };
+        if let Some(f) = start {
+            instance.call(f, Vec::new())?;
+        }
+        Ok(instance)
+    }
+
+    fn export(&self, name: &str, kind: u8) -> Option<u32> {
+        self.exports
+            .iter()
+            .find(|(n, k, _)| n == name && *k == kind)
+            .map(|(_, _, i)| *i)
+    }
+
+    fn func_type(&self, func: u32) -> Result<&FuncType, Error> {
+        let f = self
+            .funcs
+            .get(func as usize)
+            .ok_or_else(|| invalid("bad function index"))?;
+        self.types
+            .get(f.ty as usize)
+            .ok_or_else(|| invalid("bad type index"))
+    }
+
+    fn address(&self, base: u64, offset: u32, size: usize) -> Result<usize, Error> {
+        let addr = (base as u32) as usize + offset as usize;
+        if addr + size > self.memory.len() {
+            return Err(trap("out of bounds memory access"));
+        }
+        Ok(addr)
+    }
+
+    fn grow(&mut self, pages: u32) -> Option<u32> {
+        let old = (self.memory.len() / PAGE_SIZE) as u32;
+        if old as u64 + pages as u64 > self.memory_max.min(MAX_PAGES) as u64 {
+            return None;
+        }
+        self.memory.resize((old + pages) as usize * PAGE_SIZE, 0);
+        Some(old)
+    }
+
+    pub fn call(&mut self, func: u32, args: Vec<u64>) -> Result<Vec<u64>, Error> {
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(trap("call stack exhausted"));
+        }
+        self.depth += 1;
+        let result = self.run(func, args);
+        self.depth -= 1;
+        result
+    }
+
+    fn run(&mut self, func: u32, args: Vec<u64>) -> Result<Vec<u64>, Error> {
+        let funcs = Rc::clone(&self.funcs);
+        let f = &funcs[func as usize];
+        let results = self.func_type(func)?.results.len();
+        let mut locals = args;
+        locals.resize(f.locals.len(), 0);
+        let mut stack: Vec<u64> = Vec::new();
+        let mut labels = vec![Label {
+            arity: results,
+            target: f.code.len(),
+            height: 0,
+        }];
+        let mut pc = 0;
+
+        macro_rules! pop {
+            () => {
+                stack.pop().ok_or_else(|| trap("stack underflow"))?
+            };
+        }
+
+        while pc < f.code.len() {
+            let mut next = pc + 1;
+            match &f.code[pc] {
+                Instr::Unreachable => return Err(trap("unreachable")),
+                Instr::Nop => (),
+                Instr::Block { ty, end } => labels.push(Label {
+                    arity: ty.results,
+                    target: end + 1,
+                    height: stack.len().saturating_sub(ty.params),
+                }),
+                Instr::Loop { ty } => labels.push(Label {
+                    arity: ty.params,
+                    target: pc,
+                    height: stack.len().saturating_sub(ty.params),
+                }),
+                Instr::If { ty, else_at, end } => {
+                    let cond = pop!() as u32;
+                    labels.push(Label {
+                        arity: ty.results,
+                        target: end + 1,
+                        height: stack.len().saturating_sub(ty.params),
+                    });
+                    if cond == 0 {
+                        next = match else_at {
+                            Some(e) => e + 1,
+                            None => *end,
+                        };
+                    }
+                }
+                Instr::Else { end } => {
+                    labels.pop();
+                    next = end + 1;
+                }
+                Instr::End => {
+                    labels.pop();
+                }
+                Instr::Br(depth) => next = branch(&mut stack, &mut labels, *depth)?,
+                Instr::BrIf(depth) => {
+                    if pop!() as u32 != 0 {
+                        next = branch(&mut stack, &mut labels, *depth)?;
+                    }
+                }
+                Instr::BrTable(targets, default) => {
+                    let i = pop!() as u32 as usize;
+                    let depth = *targets.get(i).unwrap_or(default);
+                    next = branch(&mut stack, &mut labels, depth)?;
+                }
+                Instr::Return => {
+                    let depth = (labels.len() - 1) as u32;
+                    next = branch(&mut stack, &mut labels, depth)?;
+                }
+                Instr::Call(callee) => {
+                    let n = self.func_type(*callee)?.params.len();
+                    if stack.len() < n {
+                        return Err(trap("stack underflow"));
+                    }
+                    let args = stack.split_off(stack.len() - n);
+                    let results = self.call(*callee, args)?;
+                    stack.extend(results);
+                }
+                Instr::Drop => {
+                    pop!();
+                }
+                Instr::Select => {
+                    let cond = pop!() as u32;
+                    let b = pop!();
+                    let a = pop!();
+                    stack.push(if cond != 0 { a } else { b });
+                }
+                Instr::LocalGet(i) => stack.push(*slot(&mut locals, *i)?),
+                Instr::LocalSet(i) => *slot(&mut locals, *i)? = pop!(),
+                Instr::LocalTee(i) => {
+                    *slot(&mut locals, *i)? =
+                        *stack.last().ok_or_else(|| trap("stack underflow"))?
+                }
+                Instr::GlobalGet(i) => stack.push(*slot(&mut self.globals, *i)?),
+                Instr::GlobalSet(i) => *slot(&mut self.globals, *i)? = pop!(),
+                Instr::Load(op, offset) => {
+                    let base = pop!();
+                    let size = match op {
+                        0x29 | 0x2b => 8,
+                        0x28 | 0x2a | 0x34 | 0x35 => 4,
+                        0x2e | 0x2f | 0x32 | 0x33 => 2,
+                        _ => 1,
+                    };
+                    let addr = self.address(base, *offset, size)?;
+                    let mut raw = [0u8; 8];
+                    raw[..size].copy_from_slice(&self.memory[addr..addr + size]);
+                    let v = u64::from_le_bytes(raw);
+                    let bits = 8 * size as u32;
+                    let sign_extended = ((v << (64 - bits)) as i64 >> (64 - bits)) as u64;
+                    stack.push(match op {
+                        0x2c | 0x2e => sign_extended as u32 as u64,
+                        0x30 | 0x32 | 0x34 => sign_extended,
+                        _ => v,
+                    });
+                }
+                Instr::Store(op, offset) => {
+                    let v = pop!();
+                    let base = pop!();
+                    let size = match op {
+                        0x37 | 0x39 => 8,
+                        0x36 | 0x38 | 0x3e => 4,
+                        0x3b | 0x3d => 2,
+                        _ => 1,
+                    };
+                    let addr = self.address(base, *offset, size)?;
+                    self.memory[addr..addr + size].copy_from_slice(&v.to_le_bytes()[..size]);
+                }
+                Instr::MemorySize => stack.push((self.memory.len() / PAGE_SIZE) as u64),
+                Instr::MemoryGrow => {
+                    let pages = pop!() as u32;
+                    stack.push(self.grow(pages).unwrap_or(u32::MAX) as u64);
+                }
+                Instr::Const(v) => stack.push(*v),
+                Instr::Numeric(op) => numeric(*op, &mut stack)?,
+                Instr::TruncSat(n) => {
+                    let v = pop!();
+                    let x = if n % 4 < 2 { f32v(v) as f64 } else { f64v(v) };
+                    stack.push(match n {
+                        0 | 2 => x as i32 as u32 as u64,
+                        1 | 3 => x as u32 as u64,
+                        4 | 6 => x as i64 as u64,
+                        _ => x as u64,
+                    });
+                }
+                Instr::MemoryCopy => {
+                    let n = pop!() as u32 as usize;
+                    let src = pop!();
+                    let dst = pop!();
+                    let src = self.address(src, 0, n)?;
+                    let dst = self.address(dst, 0, n)?;
+                    self.memory.copy_within(src..src + n, dst);
+                }
+                Instr::MemoryFill => {
+                    let n = pop!() as u32 as usize;
+                    let value = pop!() as u8;
+                    let dst = pop!();
+                    let dst = self.address(dst, 0, n)?;
+                    self.memory[dst..dst + n].fill(value);
+                }
+            }
+            pc = next;
+        }
+        if stack.len() < results {
+            return Err(trap("stack underflow"));
+        }
+        Ok(stack.split_off(stack.len() - results))
+    }
+}
+
+fn slot(values: &mut [u64], i: u32) -> Result<&mut u64, Error> {
+    values
+        .get_mut(i as usize)
+        .ok_or_else(|| trap("bad local or global index"))
+}
+
+// Unwinds to the label `depth` levels out and returns where to continue.
+fn branch(stack: &mut Vec<u64>, labels: &mut Vec<Label>, depth: u32) -> Result<usize, Error> {
+    let i = labels
+        .len()
+        .checked_sub(1 + depth as usize)
+        .ok_or_else(|| trap("bad branch depth"))?;
+    let label = &labels[i];
+    if stack.len() < label.arity {
+        return Err(trap("stack underflow"));
+    }
+    let values = stack.split_off(stack.len() - label.arity);
+    stack.truncate(label.height);
+    stack.extend(values);
+    let target = label.target;
+    // Branching to a loop re-enters it, which pushes its label again.
+    labels.truncate(i);
+    Ok(target)
+}
+
+fn numeric(op: u8, stack: &mut Vec<u64>) -> Result<(), Error> {
+    macro_rules! pop {
+        () => {
+            stack.pop().ok_or_else(|| trap("stack underflow"))?
+        };
+    }
+    let v = match op {
+        // Comparisons and tests.
+        0x45 => from_bool(pop!() as u32 == 0),
+        0x50 => from_bool(pop!() == 0),
+        0x46..=0x4f => {
+            let b = pop!() as u32;
+            let a = pop!() as u32;
+            let (sa, sb) = (a as i32, b as i32);
+            from_bool(match op {
+                0x46 => a == b,
+                0x47 => a != b,
+                0x48 => sa < sb,
+                0x49 => a < b,
+                0x4a => sa > sb,
+                0x4b => a > b,
+                0x4c => sa <= sb,
+                0x4d => a <= b,
+                0x4e => sa >= sb,
+                _ => a >= b,
+            })
+        }
+        0x51..=0x5a => {
+            let b = pop!();
+            let a = pop!();
+            let (sa, sb) = (a as i64, b as i64);
+            from_bool(match op {
+                0x51 => a == b,
+                0x52 => a != b,
+                0x53 => sa < sb,
+                0x54 => a < b,
+                0x55 => sa > sb,
+                0x56 => a > b,
+                0x57 => sa <= sb,
+                0x58 => a <= b,
+                0x59 => sa >= sb,
+                _ => a >= b,
+            })
+        }
+        0x5b..=0x66 => {
+            let b = pop!();
+            let a = pop!();
+            let (a, b, op) = if op <= 0x60 {
+                (f32v(a) as f64, f32v(b) as f64, op - 0x5b)
+            } else {
+                (f64v(a), f64v(b), op - 0x61)
+            };
+            from_bool(match op {
+                0 => a == b,
+                1 => a != b,
+                2 => a < b,
+                3 => a > b,
+                4 => a <= b,
+                _ => a >= b,
+            })
+        }
+        // i32 arithmetic.
+        0x67 => (pop!() as u32).leading_zeros() as u64,
+        0x68 => (pop!() as u32).trailing_zeros() as u64,
+        0x69 => (pop!() as u32).count_ones() as u64,
+        0x6a..=0x78 => {
+            let b = pop!() as u32;
+            let a = pop!() as u32;
+            let (sa, sb) = (a as i32, b as i32);
+            let r = match op {
+                0x6a => a.wrapping_add(b),
+                0x6b => a.wrapping_sub(b),
+                0x6c => a.wrapping_mul(b),
+                0x6d => {
+                    if sb == 0 {
+                        return Err(trap("integer divide by zero"));
+                    }
+                    sa.checked_div(sb).ok_or_else(|| trap("integer overflow"))? as u32
+                }
+                0x6e => a
+                    .checked_div(b)
+                    .ok_or_else(|| trap("integer divide by zero"))?,
+                0x6f => {
+                    if sb == 0 {
+                        return Err(trap("integer divide by zero"));
+                    }
+                    sa.wrapping_rem(sb) as u32
+                }
+                0x70 => a
+                    .checked_rem(b)
+                    .ok_or_else(|| trap("integer divide by zero"))?,
+                0x71 => a & b,
+                0x72 => a | b,
+                0x73 => a ^ b,
+                0x74 => a.wrapping_shl(b),
+                0x75 => sa.wrapping_shr(b) as u32,
+                0x76 => a.wrapping_shr(b),
+                0x77 => a.rotate_left(b % 32),
+                _ => a.rotate_right(b % 32),
+            };
+            r as u64
+        }
+        // i64 arithmetic.
+        0x79 => pop!().leading_zeros() as u64,
+        0x7a => pop!().trailing_zeros() as u64,
+        0x7b => pop!().count_ones() as u64,
+        0x7c..=0x8a => {
+            let b = pop!();
+            let a = pop!();
+            let (sa, sb) = (a as i64, b as i64);
+            match op {
+                0x7c => a.wrapping_add(b),
+                0x7d => a.wrapping_sub(b),
+                0x7e => a.wrapping_mul(b),
+                0x7f => {
+                    if sb == 0 {
+                        return Err(trap("integer divide by zero"));
+                    }
+                    sa.checked_div(sb).ok_or_else(|| trap("integer overflow"))? as u64
+                }
+                0x80 => a
+                    .checked_div(b)
+                    .ok_or_else(|| trap("integer divide by zero"))?,
+                0x81 => {
+                    if sb == 0 {
+                        return Err(trap("integer divide by zero"));
+                    }
+                    sa.wrapping_rem(sb) as u64
+                }
+                0x82 => a
+                    .checked_rem(b)
+                    .ok_or_else(|| trap("integer divide by zero"))?,
+                0x83 => a & b,
+                0x84 => a | b,
+                0x85 => a ^ b,
+                0x86 => a.wrapping_shl(b as u32),
+                0x87 => sa.wrapping_shr(b as u32) as u64,
+                0x88 => a.wrapping_shr(b as u32),
+                0x89 => a.rotate_left((b % 64) as u32),
+                _ => a.rotate_right((b % 64) as u32),
+            }
+        }
+        // f32 arithmetic.
+        0x8b..=0x91 => {
+            let a = f32v(pop!());
+            from_f32(match op {
+                0x8b => a.abs(),
+                0x8c => -a,
+                0x8d => a.ceil(),
+                0x8e => a.floor(),
+                0x8f => a.trunc(),
+                0x90 => a.round_ties_even(),
+                _ => a.sqrt(),
+            })
+        }
+        0x92..=0x98 => {
+            let b = f32v(pop!());
+            let a = f32v(pop!());
+            from_f32(match op {
+                0x92 => a + b,
+                0x93 => a - b,
+                0x94 => a * b,
+                0x95 => a / b,
+                0x96 => fmin(a as f64, b as f64) as f32,
+                0x97 => fmax(a as f64, b as f64) as f32,
+                _ => a.copysign(b),
+            })
+        }
+        // f64 arithmetic.
+        0x99..=0x9f => {
+            let a = f64v(pop!());
+            (match op {
+                0x99 => a.abs(),
+                0x9a => -a,
+                0x9b => a.ceil(),
+                0x9c => a.floor(),
+                0x9d => a.trunc(),
+                0x9e => a.round_ties_even(),
+                _ => a.sqrt(),
+            })
+            .to_bits()
+        }
+        0xa0..=0xa6 => {
+            let b = f64v(pop!());
+            let a = f64v(pop!());
+            (match op {
+                0xa0 => a + b,
+                0xa1 => a - b,
+                0xa2 => a * b,
+                0xa3 => a / b,
+                0xa4 => fmin(a, b),
+                0xa5 => fmax(a, b),
+                _ => a.copysign(b),
+            })
+            .to_bits()
+        }
+        // Conversions.
+        0xa7 => pop!() as u32 as u64,
+        0xa8 => trunc(f32v(pop!()) as f64, i32::MIN as f64, i32::MAX as f64)? as i32 as u32 as u64,
+        0xa9 => trunc(f32v(pop!()) as f64, 0.0, u32::MAX as f64)? as u32 as u64,
+        0xaa => trunc(f64v(pop!()), i32::MIN as f64, i32::MAX as f64)? as i32 as u32 as u64,
+        0xab => trunc(f64v(pop!()), 0.0, u32::MAX as f64)? as u32 as u64,
+        0xac => pop!() as u32 as i32 as i64 as u64,
+        0xad => pop!() as u32 as u64,
+        // 2^63 and 2^64 are exact as floats; the upper bounds are exclusive.
+        0xae | 0xb0 => {
+            let v = pop!();
+            let x = if op == 0xae { f32v(v) as f64 } else { f64v(v) };
+            let t = trunc(x, i64::MIN as f64, f64::INFINITY)?;
+            if t >= 9223372036854775808.0 {
+                return Err(trap("integer overflow"));
+            }
+            t as i64 as u64
+        }
+        0xaf | 0xb1 => {
+            let v = pop!();
+            let x = if op == 0xaf { f32v(v) as f64 } else { f64v(v) };
+            let t = trunc(x, 0.0, f64::INFINITY)?;
+            if t >= 18446744073709551616.0 {
+                return Err(trap("integer overflow"));
+            }
+            t as u64
+        }
+        0xb2 => from_f32(pop!() as u32 as i32 as f32),
+        0xb3 => from_f32(pop!() as u32 as f32),
+        0xb4 => from_f32(pop!() as i64 as f32),
+        0xb5 => from_f32(pop!() as f32),
+        0xb6 => from_f32(f64v(pop!()) as f32),
+        0xb7 => (pop!() as u32 as i32 as f64).to_bits(),
+        0xb8 => (pop!() as u32 as f64).to_bits(),
+        0xb9 => (pop!() as i64 as f64).to_bits(),
+        0xba => (pop!() as f64).to_bits(),
+        0xbb => (f32v(pop!()) as f64).to_bits(),
+        // Reinterpretations keep the bits.
+        0xbc..=0xbf => pop!(),
+        // Sign extension.
+        0xc0 => pop!() as u8 as i8 as i32 as u32 as u64,
+        0xc1 => pop!() as u16 as i16 as i32 as u32 as u64,
+        0xc2 => pop!() as u8 as i8 as i64 as u64,
+        0xc3 => pop!() as u16 as i16 as i64 as u64,
+        _ => pop!() as u32 as i32 as i64 as u64,
+    };
+    stack.push(v);
+    Ok(())
+}
+
+pub struct Plugin {
+    name: String,
+    instance: RefCell<Instance>,
+    estimate: u32,
+    // Offset and capacity in bytes of the region holding the values.
+    buffer: Cell<(usize, usize)>,
+}
+
+impl Plugin {
+    pub fn load(path: &Path) -> Result<Plugin, Error> {
+        let bytes = std::fs::read(path).map_err(|e| Error::open(path, e))?;
+        let instance = Instance::load(&bytes)
+            .map_err(|e| Error::Plugin(format!("{}: {}", path.display(), e)))?;
+        if instance.export("memory", 2).is_none() {
+            return Err(Error::Plugin(format!(
+                "{}: plugin does not export its memory",
+                path.display()
+            )));
+        }
+        let estimate = instance.export("estimate", 0).ok_or_else(|| {
+            Error::Plugin(format!(
+                "{}: plugin does not export an estimate function",
+                path.display()
+            ))
+        })?;
+        let ty = instance.func_type(estimate)?;
+        if ty.params != [I32, I32] || ty.results != [F64] {
+            return Err(Error::Plugin(format!(
+                "{}: estimate must have type (i32, i32) -> f64",
+                path.display()
+            )));
+        }
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "plugin".to_string());
+        Ok(Plugin {
+            name,
+            instance: RefCell::new(instance),
+            estimate,
+            buffer: Cell::new((0, 0)),
+        })
+    }
+}
+
+impl Estimator for Plugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    // The values are placed in pages grown at the end of the plugin's
+    // memory, which are reused by later calls.
+    fn estimate(&self, xs: &Sample) -> Result<f64, Error> {
+        if xs.weights.is_some() {
+            return Err(Error::Plugin(format!(
+                "plugin {} does not support weighted samples",
+                self.name
+            )));
+        }
+        let mut instance = self.instance.borrow_mut();
+        let needed = xs.len() * 8;
+        let (mut ptr, capacity) = self.buffer.get();
+        if capacity < needed {
+            let pages = needed.div_ceil(PAGE_SIZE) as u32;
+            ptr = instance.grow(pages).ok_or_else(|| trap("out of memory"))? as usize * PAGE_SIZE;
+            self.buffer.set((ptr, pages as usize * PAGE_SIZE));
+        }
+        for (i, x) in xs.values.iter().enumerate() {
+            instance.memory[ptr + 8 * i..ptr + 8 * i + 8].copy_from_slice(&x.to_le_bytes());
+        }
+        let result = instance.call(self.estimate, vec![ptr as u64, xs.len() as u64])?;
+        Ok(f64v(result[0]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leb(mut n: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        loop {
+            let b = (n & 0x7f) as u8;
+            n >>= 7;
+            if n == 0 {
+                out.push(b);
+                return out;
+            }
+            out.push(b | 0x80);
+        }
+    }
+
+    fn section(id: u8, count: usize, items: &[u8]) -> Vec<u8> {
+        let mut contents = leb(count);
+        contents.extend_from_slice(items);
+        let mut out = vec![id];
+        out.extend(leb(contents.len()));
+        out.extend(contents);
+        out
+    }
+
+    fn name(s: &str) -> Vec<u8> {
+        let mut out = leb(s.len());
+        out.extend_from_slice(s.as_bytes());
+        out
+    }
+
+    // Params, results, locals and body of a function.
+    type Code<'a> = (&'a [u8], &'a [u8], &'a [u8], &'a [u8]);
+
+    // A module of the functions, each with its own type, one page of memory
+    // and the given exports.
+    fn module(funcs: &[Code], exports: &[(&str, u8, u8)]) -> Vec<u8> {
+        let mut out = b"\0asm\x01\0\0\0".to_vec();
+        let mut types = Vec::new();
+        let mut code = Vec::new();
+        for (params, results, locals, body) in funcs {
+            types.push(0x60);
+            types.extend(leb(params.len()));
+            types.extend_from_slice(params);
+            types.extend(leb(results.len()));
+            types.extend_from_slice(results);
+            let mut func = leb(locals.len());
+            for ty in locals.iter() {
+                func.extend([1, *ty]);
+            }
+            func.extend_from_slice(body);
+            code.extend(leb(func.len()));
+            code.extend(func);
+        }
+        let indices: Vec<u8> = (0..funcs.len() as u8).collect();
+        let mut export = Vec::new();
+        for (n, kind, index) in exports {
+            export.extend(name(n));
+            export.extend([*kind, *index]);
+        }
+        out.extend(section(1, funcs.len(), &types));
+        out.extend(section(3, funcs.len(), &indices));
+        out.extend(section(5, 1, &[0, 1]));
+        out.extend(section(7, exports.len(), &export));
+        out.extend(section(10, funcs.len(), &code));
+        out
+    }
+
+    // Runs a function of no arguments as the only function of a module.
+    fn run(results: &[u8], body: &[u8]) -> Result<Vec<u64>, Error> {
+        Instance::load(&module(&[(&[], results, &[], body)], &[]))?.call(0, vec![])
+    }
+
+    fn message<T>(result: Result<T, Error>) -> String {
+        match result {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    // Sums the f64 values at (ptr, len), the estimate function of a plugin.
+    const SUM: &[u8] = &[
+        0x02, 0x40, // block
+        0x03, 0x40, // loop
+        0x20, 1, 0x45, 0x0d, 1, // br_if len == 0 out of the block
+        0x20, 2, 0x20, 0, 0x2b, 3, 0, 0xa0, 0x21, 2, // sum += f64.load(ptr)
+        0x20, 0, 0x41, 8, 0x6a, 0x21, 0, // ptr += 8
+        0x20, 1, 0x41, 1, 0x6b, 0x21, 1, // len -= 1
+        0x0c, 0, // br to the loop
+        0x0b, 0x0b, // end, end
+        0x20, 2, 0x0b,
+    ];
+
+    #[test]
+    fn rejects_other_binaries() {
+        assert!(message(Instance::load(b"\x7fELF\x02\x01\x01\0")).contains("not a wasm binary"));
+        assert!(message(Instance::load(b"\0asm\x02\0\0\0")).contains("not a wasm binary"));
+        assert!(message(Instance::load(b"\0as")).contains("unexpected end"));
+    }
+
+    #[test]
+    fn rejects_truncated_sections() {
+        let bytes = module(&[(&[], &[I32], &[], &[0x41, 7, 0x0b])], &[]);
+        // A prefix ending between sections is a smaller valid module.
+        for n in 0..bytes.len() {
+            let _ = Instance::load(&bytes[..n]);
+        }
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(message(Instance::load(truncated)).contains("unexpected end"));
+        assert!(Instance::load(&bytes).is_ok());
+    }
+
+    #[test]
+    fn rejects_imports() {
+        let mut bytes = b"\0asm\x01\0\0\0".to_vec();
+        let mut import = name("env");
+        import.extend(name("f"));
+        import.extend([0, 0]);
+        bytes.extend(section(2, 1, &import));
+        assert!(message(Instance::load(&bytes)).contains("may not import"));
+    }
+
+    #[test]
+    fn rejects_mismatched_code() {
+        let mut bytes = module(&[(&[], &[], &[], &[0x0b])], &[]);
+        // Declare a second function without a body for it.
+        let funcs = bytes.windows(4).position(|w| w == [3, 2, 1, 0]).unwrap();
+        bytes.splice(funcs..funcs + 4, [3, 3, 2, 0, 0]);
+        assert!(message(Instance::load(&bytes)).contains("disagree"));
+    }
+
+    #[test]
+    fn rejects_unsupported_instructions() {
+        // 0xd2 is ref.func, from the reference types proposal.
+        let bytes = module(&[(&[], &[], &[], &[0xd2, 0, 0x1a, 0x0b])], &[]);
+        assert!(message(Instance::load(&bytes)).contains("unsupported instruction 0xd2"));
+    }
+
+    #[test]
+    fn evaluates_arithmetic() {
+        // (7 - 2) * 3
+        let body = [0x41, 7, 0x41, 2, 0x6b, 0x41, 3, 0x6c, 0x0b];
+        assert_eq!(run(&[I32], &body).unwrap(), vec![15]);
+        // -1 as a signed LEB128 constant.
+        assert_eq!(
+            run(&[I32], &[0x41, 0x7f, 0x0b]).unwrap(),
+            vec![u32::MAX as u64]
+        );
+        // 1.5 + 2.25
+        let mut body = vec![0x44];
+        body.extend(1.5f64.to_le_bytes());
+        body.push(0x44);
+        body.extend(2.25f64.to_le_bytes());
+        body.extend([0xa0, 0x0b]);
+        assert_eq!(f64v(run(&[F64], &body).unwrap()[0]), 3.75);
+    }
+
+    #[test]
+    fn branches_and_calls() {
+        // if 0 then 1 else 2
+        let body = [0x41, 0, 0x04, I32, 0x41, 1, 0x05, 0x41, 2, 0x0b, 0x0b];
+        assert_eq!(run(&[I32], &body).unwrap(), vec![2]);
+        // The first function doubles its argument, the second calls it on 21.
+        let double: &[u8] = &[0x20, 0, 0x20, 0, 0x6a, 0x0b];
+        let caller: &[u8] = &[0x41, 21, 0x10, 0, 0x0b];
+        let bytes = module(
+            &[(&[I32], &[I32], &[], double), (&[], &[I32], &[], caller)],
+            &[],
+        );
+        assert_eq!(
+            Instance::load(&bytes).unwrap().call(1, vec![]).unwrap(),
+            vec![42]
+        );
+    }
+
+    #[test]
+    fn traps() {
+        assert_eq!(
+            message(run(&[], &[0x00, 0x0b])),
+            "plugin error: trap: unreachable"
+        );
+        assert!(message(run(&[I32], &[0x6a, 0x0b])).contains("trap: stack underflow"));
+        assert!(message(run(&[I32], &[0x41, 1, 0x6a, 0x0b])).contains("trap: stack underflow"));
+        let load = [0x41, 0x80, 0x80, 0x04, 0x28, 2, 0, 0x0b];
+        assert!(message(run(&[I32], &load)).contains("trap: out of bounds memory access"));
+        let store = [0x41, 0xfe, 0xff, 0x03, 0x41, 1, 0x36, 2, 0, 0x0b];
+        assert!(message(run(&[], &store)).contains("trap: out of bounds memory access"));
+        let divide = [0x41, 1, 0x41, 0, 0x6d, 0x0b];
+        assert!(message(run(&[I32], &divide)).contains("trap: integer divide by zero"));
+        // Unoptimized interpreter frames are large, so give the recursion
+        // more than the default test thread stack.
+        let recurse = std::thread::Builder::new()
+            .stack_size(64 << 20)
+            .spawn(|| message(run(&[], &[0x10, 0, 0x0b])))
+            .unwrap();
+        assert!(recurse
+            .join()
+            .unwrap()
+            .contains("trap: call stack exhausted"));
+    }
+
+    #[test]
+    fn memory_persists_between_calls() {
+        let store: &[u8] = &[0x41, 16, 0x41, 0xe3, 0, 0x36, 2, 0, 0x0b];
+        let load: &[u8] = &[0x41, 16, 0x28, 2, 0, 0x0b];
+        let bytes = module(&[(&[], &[], &[], store), (&[], &[I32], &[], load)], &[]);
+        let mut instance = Instance::load(&bytes).unwrap();
+        assert_eq!(instance.call(1, vec![]).unwrap(), vec![0]);
+        instance.call(0, vec![]).unwrap();
+        assert_eq!(instance.call(1, vec![]).unwrap(), vec![99]);
+    }
+
+    fn plugin(file: &str, bytes: &[u8]) -> Result<Plugin, Error> {
+        let path =
+            std::env::temp_dir().join(format!("numcmp-test-{}-{}", std::process::id(), file));
+        std::fs::write(&path, bytes).unwrap();
+        let plugin = Plugin::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        plugin
+    }
+
+    #[test]
+    fn runs_a_plugin() {
+        let exports = [("memory", 2, 0), ("estimate", 0, 0)];
+        let bytes = module(&[(&[I32, I32], &[F64], &[F64], SUM)], &exports);
+        let plugin = plugin("sum.wasm", &bytes).unwrap();
+        assert_eq!(
+            plugin.name(),
+            format!("numcmp-test-{}-sum", std::process::id())
+        );
+        assert_eq!(
+            plugin.estimate(&Sample::new(vec![1.0, 2.5, 4.0])).unwrap(),
+            7.5
+        );
+        // A larger sample than the first buffer grows the memory again.
+        let values: Vec<f64> = (1..=10000).map(|i| i as f64).collect();
+        assert_eq!(plugin.estimate(&Sample::new(values)).unwrap(), 50005000.0);
+        assert_eq!(plugin.estimate(&Sample::new(vec![])).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn validates_plugins() {
+        let func = (&[I32, I32][..], &[F64][..], &[F64][..], SUM);
+        let no_memory = module(&[func], &[("estimate", 0, 0)]);
+        assert!(message(plugin("a.wasm", &no_memory)).contains("does not export its memory"));
+        let no_estimate = module(&[func], &[("memory", 2, 0), ("estimator", 0, 0)]);
+        assert!(message(plugin("b.wasm", &no_estimate)).contains("does not export an estimate"));
+        let wrong_type = module(
+            &[(&[I32], &[F64], &[F64], SUM)],
+            &[("memory", 2, 0), ("estimate", 0, 0)],
+        );
+        assert!(message(plugin("c.wasm", &wrong_type)).contains("(i32, i32) -> f64"));
+    }
+}
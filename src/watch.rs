@@ -0,0 +1,77 @@
+// --watch: reruns the comparison whenever an input file changes, to follow
+// a benchmark that is still appending its results. The files are polled,
+// since the standard library has no portable change notification.
+
+use crate::{fetch, Error};
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+const POLL: Duration = Duration::from_millis(500);
+
+pub type Stamps = Vec<Option<(u64, SystemTime)>>;
+
+// The size and modification time of each file, or None while it is missing.
+pub fn stamps(paths: &[&Path]) -> Stamps {
+    paths
+        .iter()
+        .map(|path| {
+            let metadata = std::fs::metadata(path).ok()?;
+            Some((metadata.len(), metadata.modified().ok()?))
+        })
+        .collect()
+}
+
+// Runs `compare` and then again after every change, until interrupted.
+// Errors, such as a file caught half-written, are shown and watching goes
+// on, as does it after a regression.
+pub fn run(paths: &[&Path], mut compare: impl FnMut() -> Result<bool, Error>) -> Result<(), Error> {
+    for path in paths {
+        if *path == Path::new("-") || fetch::is_url(path) {
+            return Err(Error::Usage(format!(
+                "--watch needs local files, not {}",
+                path.display()
+            )));
+        }
+    }
+    let names: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+    let clear = std::io::stdout().is_terminal();
+    loop {
+        let last = stamps(paths);
+        if clear {
+            print!("\x1b[H\x1b[2J");
+        }
+        if let Err(e) = compare() {
+            eprintln!("numcmp: {}", e);
+        }
+        println!();
+        println!(
+            "Watching {} for changes; press Ctrl-C to stop.",
+            names.join(" and ")
+        );
+        std::io::stdout().flush()?;
+        wait(paths, last);
+    }
+}
+
+// Waits for the files to change from `last`, then until they have stayed
+// the same for a whole poll, so that a write in progress is not read half
+// done.
+pub fn wait(paths: &[&Path], mut last: Stamps) {
+    loop {
+        std::thread::sleep(POLL);
+        let now = stamps(paths);
+        if now != last {
+            last = now;
+            break;
+        }
+    }
+    loop {
+        std::thread::sleep(POLL);
+        let now = stamps(paths);
+        if now == last {
+            break;
+        }
+        last = now;
+    }
+}
@@ -0,0 +1,90 @@
+// Exports for running numcmp as a WebAssembly module in a browser, used
+// through js/numcmp.js. Arrays and strings are passed as pointers into the
+// module's memory, allocated with numcmp_alloc; the result of the last call
+// stays in the module until the next one.
+
+use crate::api;
+use std::alloc::{alloc, dealloc, Layout};
+use std::sync::Mutex;
+
+// Aligned for f64 arrays.
+const ALIGN: usize = 8;
+
+static RESULT: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+extern "C" {
+    // Imported from the host: fills the buffer with random bytes, for
+    // seeding runs without --seed.
+    fn numcmp_random(ptr: *mut u8, len: usize);
+}
+
+fn random_bytes(buf: &mut [u8]) -> Result<(), getrandom::Error> {
+    // SAFETY: the host writes exactly len bytes at ptr.
+    unsafe { numcmp_random(buf.as_mut_ptr(), buf.len()) };
+    Ok(())
+}
+
+getrandom::register_custom_getrandom!(random_bytes);
+
+fn layout(len: usize) -> Layout {
+    Layout::from_size_align(len.max(1), ALIGN).expect("valid layout")
+}
+
+#[no_mangle]
+pub extern "C" fn numcmp_alloc(len: usize) -> *mut u8 {
+    // SAFETY: the layout has a non-zero size.
+    unsafe { alloc(layout(len)) }
+}
+
+/// # Safety
+/// `ptr` must come from `numcmp_alloc(len)` and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn numcmp_free(ptr: *mut u8, len: usize) {
+    dealloc(ptr, layout(len))
+}
+
+/// # Safety
+/// The pointers must point to `baseline_len` and `target_len` f64 values and
+/// `options_len` bytes of UTF-8 options separated by NUL characters.
+#[no_mangle]
+pub unsafe extern "C" fn numcmp_compare(
+    baseline: *const f64,
+    baseline_len: usize,
+    target: *const f64,
+    target_len: usize,
+    options: *const u8,
+    options_len: usize,
+) -> i32 {
+    let slice = |ptr: *const f64, len: usize| match len {
+        0 => &[][..],
+        len => std::slice::from_raw_parts(ptr, len),
+    };
+    let options = match options_len {
+        0 => "",
+        len => std::str::from_utf8(std::slice::from_raw_parts(options, len)).unwrap_or(""),
+    };
+    let options: Vec<&str> = options.split('\0').filter(|o| !o.is_empty()).collect();
+    let (status, output) = match api::catch_panic(|| {
+        api::compare_json(
+            slice(baseline, baseline_len),
+            slice(target, target_len),
+            &options,
+        )
+    }) {
+        Ok(json) => (0, json),
+        Err(e) => (e.exit_code(), e.to_string()),
+    };
+    *RESULT.lock().unwrap() = output.into_bytes();
+    status
+}
+
+// The JSON output, or the error message, of the last comparison.
+#[no_mangle]
+pub extern "C" fn numcmp_result_ptr() -> *const u8 {
+    RESULT.lock().unwrap().as_ptr()
+}
+
+#[no_mangle]
+pub extern "C" fn numcmp_result_len() -> usize {
+    RESULT.lock().unwrap().len()
+}
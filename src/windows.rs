@@ -0,0 +1,110 @@
+// Comparing consecutive windows of one series of measurements against the
+// first window or the one before, to spot warmup or drift within a run.
+
+use crate::estimator::{default_estimators, QuantileOptions};
+use crate::input::{self, InputArgs};
+use crate::resample::{BootstrapMethod, Resampler};
+use crate::rng::Seed;
+use crate::sample::Sample;
+use crate::table::{Layout, Table};
+use crate::units::format_number;
+use crate::{simulate, Error, Null, Stopping};
+use clap::ValueEnum;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Against {
+    /// Compare every window against the first
+    First,
+    /// Compare every window against the one before it
+    Previous,
+}
+
+// Settings of `numcmp windows` beyond its input.
+pub struct WindowOptions<'a> {
+    pub size: usize,
+    pub against: Against,
+    pub estimator: &'a str,
+    pub iterations: u64,
+    pub seed: Seed,
+    pub alpha: f64,
+    pub layout: Layout,
+}
+
+pub fn windows(filename: &Path, input: &InputArgs, options: WindowOptions) -> Result<(), Error> {
+    let estimator = default_estimators(QuantileOptions::default())
+        .into_iter()
+        .find(|e| e.name() == options.estimator)
+        .ok_or_else(|| Error::Usage(format!("unknown estimator {:?}", options.estimator)))?;
+    let estimators = [estimator];
+    let sample = input::read_sample(filename, input)?;
+    if sample.weights.is_some() {
+        return Err(Error::Usage(
+            "windows need a series of unweighted values".to_string(),
+        ));
+    }
+    // A short last window is dropped rather than compared on little data.
+    let windows: Vec<Sample> = sample
+        .input_order
+        .chunks_exact(options.size)
+        .map(|xs| Sample::new(xs.to_vec()))
+        .collect();
+    if windows.len() < 2 {
+        return Err(Error::InvalidInput(format!(
+            "{} values make fewer than two windows of {}",
+            sample.len(),
+            options.size
+        )));
+    }
+
+    println!(
+        "=== Windows of {} ({}, against the {} window) ===",
+        options.size,
+        options.estimator,
+        match options.against {
+            Against::First => "first",
+            Against::Previous => "previous",
+        }
+    );
+    let mut table = Table::new(&["values", options.estimator, "delta", "p-value", "verdict"]);
+    table.row(vec![
+        format!("1-{}", options.size),
+        format_number(estimators[0].estimate(&windows[0])?),
+        String::new(),
+        String::new(),
+        String::new(),
+    ]);
+    for (i, window) in windows.iter().enumerate().skip(1) {
+        let baseline = match options.against {
+            Against::First => &windows[0],
+            Against::Previous => &windows[i - 1],
+        };
+        let resampler = Resampler::new(BootstrapMethod::Iid, None, None, None, baseline)?;
+        let results = simulate(
+            Stopping::Fixed(options.iterations),
+            None,
+            Seed::new(options.seed.value.wrapping_add(i as u64), options.seed.kind),
+            &resampler,
+            baseline,
+            window,
+            &estimators,
+            Null::Baseline,
+            None,
+            None,
+            &mut |_, _| Ok(()),
+        )?;
+        let r = &results[0];
+        table.row(vec![
+            format!("{}-{}", i * options.size + 1, (i + 1) * options.size),
+            format_number(r.target_estimator),
+            format_number(r.target_estimator - r.full_baseline_estimator),
+            match r.p_value() {
+                0.0 => r.p_value_text(),
+                p => format!("{:.4}", p),
+            },
+            r.verdict(options.alpha).to_string(),
+        ]);
+    }
+    print!("{}", table.render(options.layout));
+    Ok(())
+}